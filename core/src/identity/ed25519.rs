@@ -0,0 +1,202 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Ed25519 keys.
+
+use ed25519_dalek as ed25519;
+use identity::error::DecodingError;
+use rand;
+use sha2::Sha512;
+use std::{fmt, sync::Arc};
+
+/// Length of a secret key, in bytes.
+const SECRET_KEY_LENGTH: usize = 32;
+
+/// An Ed25519 keypair.
+#[derive(Clone)]
+pub struct Keypair(Arc<ed25519::Keypair>);
+
+impl Keypair {
+    /// Generates a new Ed25519 keypair.
+    pub fn generate() -> Keypair {
+        let mut rng = rand::thread_rng();
+        Keypair(Arc::new(ed25519::Keypair::generate::<Sha512, _>(&mut rng)))
+    }
+
+    /// Decodes a keypair from its raw bytes.
+    ///
+    /// The bytes are either the 32 bytes secret key defined in RFC 8032, or these 32 bytes
+    /// followed by the 32 bytes of the public key, which is the format used by the other
+    /// implementations of libp2p. In the latter case, the public key must match the secret key.
+    pub fn decode(bytes: &[u8]) -> Result<Keypair, DecodingError> {
+        let (secret, expected_public) = match bytes.len() {
+            32 => (bytes, None),
+            64 => (&bytes[..SECRET_KEY_LENGTH], Some(&bytes[SECRET_KEY_LENGTH..])),
+            _ => return Err(DecodingError::new("Ed25519 keypair must be 32 or 64 bytes long")),
+        };
+
+        let secret = ed25519::SecretKey::from_bytes(secret)
+            .map_err(|e| DecodingError::new(format!("Ed25519 secret key: {}", e)))?;
+        let public = ed25519::PublicKey::from_secret::<Sha512>(&secret);
+        if let Some(expected_public) = expected_public {
+            if public.as_bytes()[..] != expected_public[..] {
+                return Err(DecodingError::new("Ed25519 public key doesn't match the secret key"));
+            }
+        }
+
+        Ok(Keypair(Arc::new(ed25519::Keypair { secret, public })))
+    }
+
+    /// Decodes a keypair from the PKCS#8 format of RFC 8410.
+    ///
+    /// Both the version 1 of the format, produced by OpenSSL, and the version 2, which also
+    /// contains the public key and is produced by ring, are supported.
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Keypair, DecodingError> {
+        // `PrivateKeyInfo` with a version of 0, the Ed25519 algorithm identifier, and the
+        // `OCTET STRING` containing the `OCTET STRING` of the secret key.
+        const V1_PREFIX: &[u8] = &[
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        // Same as above with a version of 1, followed by the `[1]` public key bit string.
+        const V2_PREFIX: &[u8] = &[
+            0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        const V2_PUBLIC_PREFIX: &[u8] = &[0xa1, 0x23, 0x03, 0x21, 0x00];
+
+        if pkcs8.len() == V1_PREFIX.len() + SECRET_KEY_LENGTH && pkcs8.starts_with(V1_PREFIX) {
+            return Keypair::decode(&pkcs8[V1_PREFIX.len()..]);
+        }
+
+        let secret_end = V2_PREFIX.len() + SECRET_KEY_LENGTH;
+        if pkcs8.len() == secret_end + V2_PUBLIC_PREFIX.len() + 32
+            && pkcs8.starts_with(V2_PREFIX)
+            && pkcs8[secret_end..].starts_with(V2_PUBLIC_PREFIX)
+        {
+            let mut raw = pkcs8[V2_PREFIX.len() .. secret_end].to_vec();
+            raw.extend_from_slice(&pkcs8[secret_end + V2_PUBLIC_PREFIX.len() ..]);
+            return Keypair::decode(&raw);
+        }
+
+        Err(DecodingError::new("Unsupported PKCS#8 encoding of Ed25519 keypair"))
+    }
+
+    /// Encodes the keypair as the 32 bytes of the secret key followed by the 32 bytes of the
+    /// public key.
+    pub fn encode(&self) -> [u8; 64] {
+        self.0.to_bytes()
+    }
+
+    /// Returns the 32 bytes of the public key.
+    pub fn public(&self) -> Vec<u8> {
+        self.0.public.as_bytes().to_vec()
+    }
+
+    /// Signs a message with the secret key of this keypair.
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        self.0.sign::<Sha512>(msg).to_bytes().to_vec()
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keypair").field("public", &self.0.public).finish()
+    }
+}
+
+/// Verifies the Ed25519 signature of a message with the 32 bytes of a public key.
+pub fn verify(public: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    let public = ed25519::PublicKey::from_bytes(public);
+    let sig = ed25519::Signature::from_bytes(sig);
+    match (public, sig) {
+        (Ok(public), Ok(sig)) => public.verify::<Sha512>(msg, &sig).is_ok(),
+        _ => {
+            debug!("the Ed25519 signature or public key are in the wrong format");
+            false
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, Keypair};
+
+    // Test vector 1 of RFC 8032.
+    const SECRET: &[u8] = &[
+        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
+        0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
+        0x7f, 0x60,
+    ];
+    const PUBLIC: &[u8] = &[
+        0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64, 0x07,
+        0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68, 0xf7, 0x07,
+        0x51, 0x1a,
+    ];
+    const SIGNATURE: &[u8] = &[
+        0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e, 0x82,
+        0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65, 0x22, 0x49,
+        0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e, 0x39, 0x70, 0x1c,
+        0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24, 0x65, 0x51, 0x41, 0x43,
+        0x8e, 0x7a, 0x10, 0x0b,
+    ];
+
+    #[test]
+    fn rfc8032_vector() {
+        let keypair = Keypair::decode(SECRET).unwrap();
+        assert_eq!(keypair.public(), PUBLIC);
+        assert_eq!(keypair.sign(b""), SIGNATURE);
+        assert!(verify(PUBLIC, b"", SIGNATURE));
+        assert!(!verify(PUBLIC, b"hello", SIGNATURE));
+    }
+
+    #[test]
+    fn encode_then_decode() {
+        let keypair = Keypair::generate();
+        let encoded = keypair.encode();
+        assert_eq!(Keypair::decode(&encoded).unwrap().public(), keypair.public());
+
+        let mut wrong = encoded;
+        wrong[40] ^= 1;
+        assert!(Keypair::decode(&wrong).is_err());
+    }
+
+    #[test]
+    fn from_pkcs8() {
+        let mut v1 = vec![
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        v1.extend_from_slice(SECRET);
+        assert_eq!(Keypair::from_pkcs8(&v1).unwrap().public(), PUBLIC);
+
+        let mut v2 = vec![
+            0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        v2.extend_from_slice(SECRET);
+        v2.extend_from_slice(&[0xa1, 0x23, 0x03, 0x21, 0x00]);
+        v2.extend_from_slice(PUBLIC);
+        assert_eq!(Keypair::from_pkcs8(&v2).unwrap().public(), PUBLIC);
+
+        v1[9] ^= 1;
+        assert!(Keypair::from_pkcs8(&v1).is_err());
+    }
+}
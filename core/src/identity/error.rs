@@ -0,0 +1,63 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Errors of the `identity` module.
+
+use std::{error::Error, fmt};
+
+/// An error during decoding of key material.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodingError {
+    msg: String,
+}
+
+impl DecodingError {
+    pub(crate) fn new<S: ToString>(msg: S) -> Self {
+        DecodingError { msg: msg.to_string() }
+    }
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Key decoding error: {}", self.msg)
+    }
+}
+
+impl Error for DecodingError {}
+
+/// An error during signing of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningError {
+    msg: String,
+}
+
+impl SigningError {
+    pub(crate) fn new<S: ToString>(msg: S) -> Self {
+        SigningError { msg: msg.to_string() }
+    }
+}
+
+impl fmt::Display for SigningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Key signing error: {}", self.msg)
+    }
+}
+
+impl Error for SigningError {}
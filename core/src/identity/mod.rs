@@ -0,0 +1,208 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Keypairs of the node identity, used to sign data on behalf of the local node.
+//!
+//! A `Keypair` wraps one of the supported key types. Its public key is the `PublicKey` sent to
+//! remotes, from which they derive our `PeerId` and with which they verify our signatures.
+//!
+//! Keypairs can be encoded with the same protobuf format as the other implementations of libp2p,
+//! and saved to or loaded from a file with `Keypair::save` and `Keypair::load`.
+//!
+//! RSA support requires the `rsa` feature and secp256k1 support requires the `secp256k1`
+//! feature. Ed25519 is always available.
+
+pub mod ed25519;
+pub mod error;
+#[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+pub mod rsa;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+
+pub use self::error::{DecodingError, SigningError};
+pub use public_key::PublicKey;
+
+use keys_proto;
+use protobuf::{self, Message};
+use std::{fs, io, path::Path};
+use std::io::{Read, Write};
+
+/// Identity keypair of a node.
+#[derive(Debug, Clone)]
+pub enum Keypair {
+    /// An Ed25519 keypair.
+    Ed25519(ed25519::Keypair),
+    /// An RSA keypair.
+    #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+    Rsa(rsa::Keypair),
+    /// A Secp256k1 keypair.
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(secp256k1::Keypair),
+}
+
+impl Keypair {
+    /// Generates a new Ed25519 keypair.
+    pub fn generate_ed25519() -> Keypair {
+        Keypair::Ed25519(ed25519::Keypair::generate())
+    }
+
+    /// Generates a new Secp256k1 keypair.
+    #[cfg(feature = "secp256k1")]
+    pub fn generate_secp256k1() -> Keypair {
+        Keypair::Secp256k1(secp256k1::Keypair::generate())
+    }
+
+    /// Decodes an RSA keypair from a DER-encoded PKCS#8 `PrivateKeyInfo`.
+    #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+    pub fn rsa_from_pkcs8(pkcs8: &[u8]) -> Result<Keypair, DecodingError> {
+        rsa::Keypair::from_pkcs8(pkcs8).map(Keypair::Rsa)
+    }
+
+    /// Signs a message with the secret key of this keypair.
+    ///
+    /// The signature can be checked by the remote with `PublicKey::verify`.
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        match self {
+            Keypair::Ed25519(pair) => Ok(pair.sign(msg)),
+            #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+            Keypair::Rsa(pair) => pair.sign(msg),
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(pair) => pair.sign(msg),
+        }
+    }
+
+    /// Returns the public key of this keypair.
+    pub fn public(&self) -> PublicKey {
+        match self {
+            Keypair::Ed25519(pair) => PublicKey::Ed25519(pair.public()),
+            #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+            Keypair::Rsa(pair) => PublicKey::Rsa(pair.public()),
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(pair) => PublicKey::Secp256k1(pair.public()),
+        }
+    }
+
+    /// Encodes the keypair as a protobuf `PrivateKey` message.
+    ///
+    /// The data is the PKCS#1 `RSAPrivateKey` for RSA, the secret key followed by the public key
+    /// for Ed25519, and the secret key for Secp256k1.
+    pub fn to_protobuf_encoding(&self) -> Vec<u8> {
+        let mut private_key = keys_proto::PrivateKey::new();
+        match self {
+            Keypair::Ed25519(pair) => {
+                private_key.set_Type(keys_proto::KeyType::Ed25519);
+                private_key.set_Data(pair.encode().to_vec());
+            },
+            #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+            Keypair::Rsa(pair) => {
+                private_key.set_Type(keys_proto::KeyType::RSA);
+                private_key.set_Data(pair.encode().to_vec());
+            },
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(pair) => {
+                private_key.set_Type(keys_proto::KeyType::Secp256k1);
+                private_key.set_Data(pair.encode());
+            },
+        };
+
+        private_key
+            .write_to_bytes()
+            .expect("protobuf writing should always be valid")
+    }
+
+    /// Decodes a keypair from a protobuf `PrivateKey` message.
+    pub fn from_protobuf_encoding(bytes: &[u8]) -> Result<Keypair, DecodingError> {
+        let mut private_key = protobuf::parse_from_bytes::<keys_proto::PrivateKey>(bytes)
+            .map_err(|e| DecodingError::new(format!("Protobuf: {}", e)))?;
+        let data = private_key.take_Data();
+
+        match private_key.get_Type() {
+            keys_proto::KeyType::Ed25519 => ed25519::Keypair::decode(&data).map(Keypair::Ed25519),
+            #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+            keys_proto::KeyType::RSA => rsa::Keypair::from_der(&data).map(Keypair::Rsa),
+            #[cfg(not(all(feature = "rsa", not(target_os = "emscripten"))))]
+            keys_proto::KeyType::RSA => Err(DecodingError::new("RSA support is disabled")),
+            #[cfg(feature = "secp256k1")]
+            keys_proto::KeyType::Secp256k1 =>
+                secp256k1::Keypair::decode(&data).map(Keypair::Secp256k1),
+            #[cfg(not(feature = "secp256k1"))]
+            keys_proto::KeyType::Secp256k1 =>
+                Err(DecodingError::new("Secp256k1 support is disabled")),
+        }
+    }
+
+    /// Writes the protobuf encoding of the keypair to a file, replacing it if it exists.
+    ///
+    /// On Unix, the file is only readable and writable by its owner.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(path)?;
+        file.write_all(&self.to_protobuf_encoding())?;
+        file.sync_all()
+    }
+
+    /// Reads a keypair from a file previously written with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Keypair> {
+        let mut bytes = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+        Keypair::from_protobuf_encoding(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::random;
+    use std::{env, fs};
+    use super::Keypair;
+
+    #[test]
+    fn keypair_protobuf_roundtrip() {
+        let keypair = Keypair::generate_ed25519();
+        let decoded = Keypair::from_protobuf_encoding(&keypair.to_protobuf_encoding()).unwrap();
+        assert_eq!(decoded.public(), keypair.public());
+        assert!(Keypair::from_protobuf_encoding(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn sign_then_verify() {
+        let keypair = Keypair::generate_ed25519();
+        let signature = keypair.sign(b"hello world").unwrap();
+        assert!(keypair.public().verify(b"hello world", &signature));
+        assert!(!keypair.public().verify(b"hello world!", &signature));
+    }
+
+    #[test]
+    fn save_then_load() {
+        let path = env::temp_dir().join(format!("libp2p-identity-{}.key", random::<u64>()));
+        let keypair = Keypair::generate_ed25519();
+        keypair.save(&path).unwrap();
+        let loaded = Keypair::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().public(), keypair.public());
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! RSA keys.
+
+use identity::error::{DecodingError, SigningError};
+use ring::rand::SystemRandom;
+use ring::signature::{self, KeyPair, RsaKeyPair, RSA_PKCS1_SHA256, RSA_PKCS1_2048_8192_SHA256};
+use std::{fmt, sync::Arc};
+
+/// DER encoding of the `AlgorithmIdentifier` of `rsaEncryption`. The first two bytes are the
+/// tag and length of the `SEQUENCE`.
+const RSA_ALGORITHM_ID: &[u8] = &[
+    0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, 0x05, 0x00,
+];
+
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_SEQUENCE: u8 = 0x30;
+
+/// An RSA keypair.
+///
+/// > **Note**: RSA keys can't be generated, only loaded.
+#[derive(Clone)]
+pub struct Keypair {
+    inner: Arc<RsaKeyPair>,
+    /// DER encoding of the `RSAPrivateKey` (PKCS#1) of the keypair.
+    der: Vec<u8>,
+}
+
+impl Keypair {
+    /// Decodes a keypair from a DER-encoded PKCS#8 `PrivateKeyInfo`.
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Keypair, DecodingError> {
+        let der = pkcs1_from_pkcs8(pkcs8)
+            .ok_or_else(|| DecodingError::new("RSA PKCS#8 PrivateKeyInfo"))?;
+        Keypair::from_der(der)
+    }
+
+    /// Decodes a keypair from a DER-encoded PKCS#1 `RSAPrivateKey`.
+    pub fn from_der(der: &[u8]) -> Result<Keypair, DecodingError> {
+        let inner = RsaKeyPair::from_der(der)
+            .map_err(|e| DecodingError::new(format!("RSA PKCS#1 RSAPrivateKey: {}", e)))?;
+        Ok(Keypair { inner: Arc::new(inner), der: der.to_vec() })
+    }
+
+    /// Encodes the keypair as a DER-encoded PKCS#1 `RSAPrivateKey`.
+    pub fn encode(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Returns the public key, as a DER-encoded X.509 `SubjectPublicKeyInfo`.
+    pub fn public(&self) -> Vec<u8> {
+        let pkcs1 = self.inner.public_key().as_ref();
+        let mut bit_string = Vec::with_capacity(pkcs1.len() + 1);
+        // Number of unused bits in the last byte of the bit string.
+        bit_string.push(0);
+        bit_string.extend_from_slice(pkcs1);
+
+        let mut content = RSA_ALGORITHM_ID.to_vec();
+        der_write(DER_BIT_STRING, &bit_string, &mut content);
+        let mut spki = Vec::new();
+        der_write(DER_SEQUENCE, &content, &mut spki);
+        spki
+    }
+
+    /// Signs a message with the secret key of this keypair, using PKCS#1 v1.5 padding and
+    /// SHA-256.
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        let mut signature = vec![0; self.inner.public_modulus_len()];
+        let rng = SystemRandom::new();
+        match self.inner.sign(&RSA_PKCS1_SHA256, &rng, msg, &mut signature) {
+            Ok(()) => Ok(signature),
+            Err(_) => Err(SigningError::new("RSA")),
+        }
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keypair").field("public", &self.inner.public_key()).finish()
+    }
+}
+
+/// Verifies the RSA signature of a message with a DER-encoded X.509 `SubjectPublicKeyInfo`.
+pub fn verify(public: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    let pkcs1 = match pkcs1_from_spki(public) {
+        Some(pkcs1) => pkcs1,
+        None => {
+            debug!("the RSA public key isn't a valid SubjectPublicKeyInfo");
+            return false;
+        },
+    };
+
+    signature::UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, pkcs1)
+        .verify(msg, sig)
+        .is_ok()
+}
+
+/// Extracts the `RSAPrivateKey` from a PKCS#8 `PrivateKeyInfo`.
+fn pkcs1_from_pkcs8(pkcs8: &[u8]) -> Option<&[u8]> {
+    let content = der_read_all(DER_SEQUENCE, pkcs8)?;
+    let (_version, rest) = der_read(DER_INTEGER, content)?;
+    let (algorithm, rest) = der_read(DER_SEQUENCE, rest)?;
+    if algorithm != &RSA_ALGORITHM_ID[2..] {
+        return None;
+    }
+    // Optional attributes may follow the private key.
+    let (private_key, _) = der_read(DER_OCTET_STRING, rest)?;
+    Some(private_key)
+}
+
+/// Extracts the `RSAPublicKey` from a `SubjectPublicKeyInfo`.
+fn pkcs1_from_spki(spki: &[u8]) -> Option<&[u8]> {
+    let content = der_read_all(DER_SEQUENCE, spki)?;
+    let (algorithm, rest) = der_read(DER_SEQUENCE, content)?;
+    if algorithm != &RSA_ALGORITHM_ID[2..] {
+        return None;
+    }
+    let bit_string = der_read_all(DER_BIT_STRING, rest)?;
+    match bit_string.split_first() {
+        Some((0, public_key)) => Some(public_key),
+        _ => None,
+    }
+}
+
+/// Reads a DER element with the given tag, and returns its content and the rest of the input.
+fn der_read(tag: u8, input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&found_tag, rest) = input.split_first()?;
+    if found_tag != tag {
+        return None;
+    }
+
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let num_bytes = (first & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || rest.len() < num_bytes {
+            return None;
+        }
+        let len = rest[..num_bytes].iter().fold(0, |len, &b| (len << 8) | b as usize);
+        (len, &rest[num_bytes..])
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+    Some((&rest[..len], &rest[len..]))
+}
+
+/// Same as `der_read`, but the element must span the whole input.
+fn der_read_all(tag: u8, input: &[u8]) -> Option<&[u8]> {
+    match der_read(tag, input)? {
+        (content, []) => Some(content),
+        _ => None,
+    }
+}
+
+/// Writes a DER element at the end of `output`.
+fn der_write(tag: u8, content: &[u8], output: &mut Vec<u8>) {
+    output.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        output.push(len as u8);
+    } else {
+        let len_bytes = [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+        let skip = len_bytes.iter().take_while(|&&b| b == 0).count();
+        output.push(0x80 | (len_bytes.len() - skip) as u8);
+        output.extend_from_slice(&len_bytes[skip..]);
+    }
+    output.extend_from_slice(content);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{der_read_all, der_write, pkcs1_from_spki, DER_SEQUENCE};
+
+    #[test]
+    fn der_write_then_read() {
+        for &len in &[0, 5, 127, 128, 255, 256, 70000] {
+            let content = vec![0x42; len];
+            let mut encoded = Vec::new();
+            der_write(DER_SEQUENCE, &content, &mut encoded);
+            assert_eq!(der_read_all(DER_SEQUENCE, &encoded), Some(&content[..]));
+        }
+    }
+
+    #[test]
+    fn invalid_spki() {
+        assert!(pkcs1_from_spki(&[]).is_none());
+        assert!(pkcs1_from_spki(&[0x30, 0x03, 0x02, 0x01, 0x00]).is_none());
+    }
+}
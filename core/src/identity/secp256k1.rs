@@ -0,0 +1,124 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Secp256k1 keys.
+
+use identity::error::{DecodingError, SigningError};
+use rand::{self, Rng};
+use secp256k1::{constants::SECRET_KEY_SIZE, key, Message, Secp256k1, Signature};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// A Secp256k1 keypair.
+#[derive(Clone)]
+pub struct Keypair {
+    secret: key::SecretKey,
+}
+
+impl Keypair {
+    /// Generates a new Secp256k1 keypair.
+    pub fn generate() -> Keypair {
+        let secp = Secp256k1::without_caps();
+        let mut rng = rand::thread_rng();
+        loop {
+            // Almost all 32 bytes values are valid secret keys.
+            let mut bytes = [0; SECRET_KEY_SIZE];
+            rng.fill(&mut bytes[..]);
+            if let Ok(secret) = key::SecretKey::from_slice(&secp, &bytes) {
+                return Keypair { secret };
+            }
+        }
+    }
+
+    /// Decodes a keypair from the 32 bytes of its secret key.
+    pub fn decode(bytes: &[u8]) -> Result<Keypair, DecodingError> {
+        let secp = Secp256k1::without_caps();
+        let secret = key::SecretKey::from_slice(&secp, bytes)
+            .map_err(|e| DecodingError::new(format!("Secp256k1 secret key: {}", e)))?;
+        Ok(Keypair { secret })
+    }
+
+    /// Encodes the keypair as the 32 bytes of its secret key.
+    pub fn encode(&self) -> Vec<u8> {
+        self.secret[..].to_vec()
+    }
+
+    /// Returns the 33 bytes of the compressed public key.
+    pub fn public(&self) -> Vec<u8> {
+        let secp = Secp256k1::signing_only();
+        key::PublicKey::from_secret_key(&secp, &self.secret).serialize().to_vec()
+    }
+
+    /// Signs the SHA-256 hash of a message with the secret key of this keypair, and returns the
+    /// DER-encoded signature.
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        let digest = Sha256::digest(msg);
+        let message = Message::from_slice(digest.as_ref())
+            .map_err(|e| SigningError::new(format!("Secp256k1 message: {}", e)))?;
+        let secp = Secp256k1::signing_only();
+        Ok(secp.sign(&message, &self.secret).serialize_der(&secp))
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keypair").field("public", &self.public()).finish()
+    }
+}
+
+/// Verifies the DER-encoded Secp256k1 signature of the SHA-256 hash of a message with the
+/// 33 bytes of a compressed public key.
+pub fn verify(public: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    let digest = Sha256::digest(msg);
+    let message = Message::from_slice(digest.as_ref())
+        .expect("digest output length matches secp256k1 input length");
+    let secp = Secp256k1::verification_only();
+    let sig = Signature::from_der(&secp, sig);
+    let public = key::PublicKey::from_slice(&secp, public);
+    match (sig, public) {
+        (Ok(sig), Ok(public)) => secp.verify(&message, &sig, &public).is_ok(),
+        _ => {
+            debug!("the Secp256k1 signature or public key are in the wrong format");
+            false
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, Keypair};
+
+    #[test]
+    fn sign_then_verify() {
+        let keypair = Keypair::generate();
+        let signature = keypair.sign(b"hello world").unwrap();
+        assert_eq!(keypair.public().len(), 33);
+        assert!(verify(&keypair.public(), b"hello world", &signature));
+        assert!(!verify(&keypair.public(), b"hello", &signature));
+    }
+
+    #[test]
+    fn encode_then_decode() {
+        let keypair = Keypair::generate();
+        let decoded = Keypair::decode(&keypair.encode()).unwrap();
+        assert_eq!(decoded.public(), keypair.public());
+        assert!(Keypair::decode(&[0; 32]).is_err());
+    }
+}
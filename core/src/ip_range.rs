@@ -0,0 +1,180 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use multiaddr::Protocol;
+use std::{fmt, net::IpAddr};
+use Multiaddr;
+
+/// Range of IP addresses, in the CIDR notation.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    /// First address of the range. All the bits after `prefix_len` are zero.
+    network: IpAddr,
+    /// Number of bits of the prefix.
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Builds the range of addresses that share the first `prefix_len` bits of `addr`.
+    ///
+    /// Returns `None` if `prefix_len` is larger than the length of the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<IpRange> {
+        let network = match addr {
+            IpAddr::V4(addr) => {
+                if prefix_len > 32 {
+                    return None;
+                }
+                IpAddr::V4((u32::from(addr) & v4_mask(prefix_len)).into())
+            },
+            IpAddr::V6(addr) => {
+                if prefix_len > 128 {
+                    return None;
+                }
+                IpAddr::V6((u128::from(addr) & v6_mask(prefix_len)).into())
+            },
+        };
+
+        Some(IpRange { network, prefix_len })
+    }
+
+    /// Builds the range of addresses that share the first `prefix_len` bits of the IP address
+    /// found in `addr`.
+    ///
+    /// Returns `None` if `addr` doesn't contain an IP address or if `prefix_len` is too large.
+    #[inline]
+    pub fn from_multiaddr(addr: &Multiaddr, prefix_len: u8) -> Option<IpRange> {
+        ip_of_multiaddr(addr).and_then(|ip| IpRange::new(ip, prefix_len))
+    }
+
+    /// Builds a range that contains only the given address.
+    #[inline]
+    pub fn single(addr: IpAddr) -> IpRange {
+        let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+        IpRange { network: addr, prefix_len }
+    }
+
+    /// Returns the first address of the range.
+    #[inline]
+    pub fn network(&self) -> &IpAddr {
+        &self.network
+    }
+
+    /// Returns the number of bits of the prefix.
+    #[inline]
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns true if the range contains the given IP address.
+    pub fn contains_ip(&self, addr: &IpAddr) -> bool {
+        match (&self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                u32::from(*addr) & v4_mask(self.prefix_len) == u32::from(*network)
+            },
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                u128::from(*addr) & v6_mask(self.prefix_len) == u128::from(*network)
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns true if the IP address found in `addr` is part of the range.
+    ///
+    /// Always returns false if `addr` doesn't contain an IP address.
+    #[inline]
+    pub fn contains(&self, addr: &Multiaddr) -> bool {
+        ip_of_multiaddr(addr).map(|ip| self.contains_ip(&ip)).unwrap_or(false)
+    }
+}
+
+impl fmt::Debug for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IpRange({}/{})", self.network, self.prefix_len)
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Returns the first IP address found in the multiaddress, if any.
+fn ip_of_multiaddr(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().filter_map(|proto| match proto {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    }).next()
+}
+
+#[inline]
+fn v4_mask(prefix_len: u8) -> u32 {
+    if prefix_len == 0 { 0 } else { !0 << (32 - u32::from(prefix_len)) }
+}
+
+#[inline]
+fn v6_mask(prefix_len: u8) -> u128 {
+    if prefix_len == 0 { 0 } else { !0 << (128 - u32::from(prefix_len)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IpRange;
+    use Multiaddr;
+
+    #[test]
+    fn ipv4_range() {
+        let range = IpRange::new("10.1.2.3".parse().unwrap(), 16).unwrap();
+        assert_eq!(range.to_string(), "10.1.0.0/16");
+        assert!(range.contains_ip(&"10.1.200.4".parse().unwrap()));
+        assert!(!range.contains_ip(&"10.2.0.1".parse().unwrap()));
+        assert!(!range.contains_ip(&"::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn ipv6_range() {
+        let range = IpRange::new("2001:db8::1".parse().unwrap(), 32).unwrap();
+        assert!(range.contains_ip(&"2001:db8:ffff::2".parse().unwrap()));
+        assert!(!range.contains_ip(&"2001:db9::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn prefix_too_large() {
+        assert!(IpRange::new("127.0.0.1".parse().unwrap(), 33).is_none());
+        assert!(IpRange::new("::1".parse().unwrap(), 129).is_none());
+    }
+
+    #[test]
+    fn from_multiaddr() {
+        let addr: Multiaddr = "/ip4/192.168.0.5/tcp/30333".parse().unwrap();
+        let range = IpRange::from_multiaddr(&addr, 24).unwrap();
+        assert!(range.contains(&"/ip4/192.168.0.200/tcp/1".parse().unwrap()));
+        assert!(!range.contains(&"/ip4/192.168.1.1/tcp/1".parse().unwrap()));
+        assert!(!range.contains(&"/memory".parse().unwrap()));
+        assert!(IpRange::from_multiaddr(&"/memory".parse().unwrap(), 8).is_none());
+    }
+
+    #[test]
+    fn zero_prefix_contains_everything() {
+        let range = IpRange::new("1.2.3.4".parse().unwrap(), 0).unwrap();
+        assert!(range.contains_ip(&"255.255.255.255".parse().unwrap()));
+    }
+}
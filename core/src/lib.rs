@@ -55,6 +55,7 @@
 
 extern crate bs58;
 extern crate bytes;
+extern crate ed25519_dalek;
 extern crate fnv;
 #[macro_use]
 extern crate futures;
@@ -66,15 +67,19 @@ extern crate parking_lot;
 extern crate protobuf;
 #[macro_use]
 extern crate quick_error;
+extern crate rand;
+#[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+extern crate ring;
 extern crate rw_stream_sink;
+#[cfg(feature = "secp256k1")]
+extern crate secp256k1;
+extern crate sha2;
 extern crate smallvec;
 extern crate tokio_executor;
 extern crate tokio_io;
 extern crate tokio_timer;
 extern crate void;
 
-#[cfg(test)]
-extern crate rand;
 #[cfg(test)]
 extern crate tokio;
 #[cfg(test)]
@@ -88,6 +93,7 @@ extern crate tokio_mock_task;
 /// Multi-address re-export.
 pub extern crate multiaddr;
 
+mod ip_range;
 mod keys_proto;
 mod peer_id;
 mod public_key;
@@ -96,6 +102,7 @@ mod public_key;
 mod tests;
 
 pub mod either;
+pub mod identity;
 pub mod muxing;
 pub mod nodes;
 pub mod protocols_handler;
@@ -104,6 +111,7 @@ pub mod topology;
 pub mod transport;
 pub mod upgrade;
 
+pub use self::ip_range::IpRange;
 pub use self::multiaddr::Multiaddr;
 pub use self::muxing::StreamMuxer;
 pub use self::peer_id::PeerId;
@@ -0,0 +1,672 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Dialing of a peer through multiple addresses.
+//!
+//! All the addresses of a peer are attempted within a single `DialAttempt` future, which dials
+//! up to a configurable number of them concurrently and resolves as soon as one of them reaches
+//! the expected peer. The other dials in progress are then dropped.
+
+use crate::{
+    Multiaddr, PeerId,
+    multiaddr::Protocol,
+    nodes::raw_swarm::{DialConfig, RawSwarmReachError},
+    transport::Transport
+};
+use futures::prelude::*;
+use parking_lot::Mutex;
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    sync::Arc,
+    time::{Duration, Instant}
+};
+use tokio_timer::Delay;
+
+/// Addresses of a dialing attempt, shared between the `RawSwarm` and the task that performs the
+/// dialing.
+#[derive(Debug)]
+pub(crate) struct DialAddresses {
+    /// Addresses currently being dialed.
+    pub in_progress: Vec<Multiaddr>,
+    /// Addresses to dial next, in order.
+    pub pending: Vec<Multiaddr>,
+    /// Address to report in the events. This is the address that reached the peer if the attempt
+    /// succeeded, and the last address that failed otherwise.
+    pub reported: Multiaddr,
+    /// Addresses that failed to reach the peer with the error that happened, in the order in
+    /// which they failed.
+    pub failed: Vec<(Multiaddr, RawSwarmReachError)>,
+    /// True once the attempt has succeeded or failed. No address is dialed afterwards.
+    pub finished: bool,
+}
+
+impl DialAddresses {
+    /// Builds the addresses of an attempt that will dial `addrs` in order.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `addrs` is empty.
+    pub fn new(addrs: Vec<Multiaddr>) -> Arc<Mutex<DialAddresses>> {
+        let reported = addrs.first().expect("a dialing attempt needs at least one address").clone();
+        Arc::new(Mutex::new(DialAddresses {
+            in_progress: Vec::new(),
+            pending: addrs,
+            reported,
+            failed: Vec::new(),
+            finished: false,
+        }))
+    }
+
+    /// Returns the number of addresses that are being dialed or that haven't been dialed yet.
+    pub fn remaining(&self) -> usize {
+        self.in_progress.len() + self.pending.len()
+    }
+
+    /// Adds an address to dial after the ones that are already pending.
+    ///
+    /// Doesn't do anything if this address is already being dialed or pending. Returns false if
+    /// the attempt is already finished, in which case the address will never be dialed.
+    pub fn append(&mut self, addr: Multiaddr) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        if !self.in_progress.contains(&addr) && !self.pending.contains(&addr) {
+            self.pending.push(addr);
+        }
+        true
+    }
+}
+
+/// Sorts addresses in the order in which they should be dialed.
+///
+/// The address through which we last reached the peer comes first, then the direct addresses,
+/// then the relayed ones. Within each of these groups, IPv6 and IPv4 addresses alternate,
+/// starting with IPv6, so that a broken IPv6 connectivity doesn't delay the IPv4 attempts too
+/// much. Duplicates are removed.
+pub(crate) fn rank_addresses(addrs: Vec<Multiaddr>, last_successful: Option<&Multiaddr>) -> Vec<Multiaddr> {
+    let mut ranked = Vec::with_capacity(addrs.len());
+    let mut direct = Vec::new();
+    let mut relayed = Vec::new();
+
+    for addr in addrs {
+        if ranked.contains(&addr) || direct.contains(&addr) || relayed.contains(&addr) {
+            continue;
+        }
+
+        if Some(&addr) == last_successful {
+            ranked.push(addr);
+        } else if is_relayed(&addr) {
+            relayed.push(addr);
+        } else {
+            direct.push(addr);
+        }
+    }
+
+    ranked.extend(interleave_families(direct));
+    ranked.extend(interleave_families(relayed));
+    ranked
+}
+
+/// Returns true if the address goes through a relay.
+fn is_relayed(addr: &Multiaddr) -> bool {
+    addr.iter().any(|p| match p {
+        Protocol::P2pCircuit => true,
+        _ => false,
+    })
+}
+
+/// Returns true if the address is an IPv6 address.
+fn is_ipv6(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(Protocol::Ip6(_)) | Some(Protocol::Dns6(_)) => true,
+        _ => false,
+    }
+}
+
+/// Alternates between IPv6 and other addresses, starting with IPv6, while preserving the order
+/// within each family.
+fn interleave_families(addrs: Vec<Multiaddr>) -> Vec<Multiaddr> {
+    let mut out = Vec::with_capacity(addrs.len());
+    let (v6, others): (Vec<_>, Vec<_>) = addrs.into_iter().partition(|a| is_ipv6(a));
+    let mut v6 = v6.into_iter();
+    let mut others = others.into_iter();
+
+    loop {
+        match (v6.next(), others.next()) {
+            (None, None) => break,
+            (a, b) => {
+                out.extend(a);
+                out.extend(b);
+            }
+        }
+    }
+
+    out
+}
+
+/// Future that dials the addresses of a peer until one of them succeeds.
+pub(crate) struct DialAttempt<TTrans>
+where
+    TTrans: Transport,
+{
+    /// Transport used to dial.
+    transport: TTrans,
+    /// Peer we expect to reach.
+    peer_id: PeerId,
+    /// Addresses to dial, shared with the `RawSwarm`.
+    addrs: Arc<Mutex<DialAddresses>>,
+    /// Dials in progress, with the address being dialed.
+    dials: Vec<(Multiaddr, TTrans::Dial)>,
+    /// Maximum number of dials in progress at the same time.
+    concurrency: usize,
+    /// Minimum delay between the start of two dials, if some are in progress.
+    attempt_delay: Duration,
+    /// Fires when we are allowed to start a new dial while others are in progress.
+    next_start: Option<Delay>,
+    /// Fires when the whole attempt must be abandoned.
+    deadline: Option<Delay>,
+    /// Error of the last dial that failed.
+    last_error: Option<RawSwarmReachError>,
+}
+
+impl<TTrans> DialAttempt<TTrans>
+where
+    TTrans: Transport,
+{
+    /// Starts dialing `peer_id` through the addresses in `addrs`.
+    pub fn new(transport: TTrans, peer_id: PeerId, addrs: Arc<Mutex<DialAddresses>>, config: &DialConfig) -> Self {
+        DialAttempt {
+            transport,
+            peer_id,
+            addrs,
+            dials: Vec::new(),
+            concurrency: config.concurrency,
+            attempt_delay: config.attempt_delay,
+            next_start: None,
+            deadline: config.deadline.map(|d| Delay::new(Instant::now() + d)),
+            last_error: None,
+        }
+    }
+
+    /// Records the failure of the dial to `addr`.
+    fn dial_failed(&mut self, addr: Multiaddr, error: RawSwarmReachError) {
+        debug!("Failed to reach {:?} through {}: {}", self.peer_id, addr, error);
+        // The error is both kept with the address and returned if this is the last address.
+        let last_error = match error {
+            RawSwarmReachError::Transport(ref err) => {
+                RawSwarmReachError::Transport(IoError::new(err.kind(), err.to_string()))
+            },
+            RawSwarmReachError::PeerIdMismatch { ref obtained } => {
+                RawSwarmReachError::PeerIdMismatch { obtained: obtained.clone() }
+            },
+        };
+        {
+            let mut addrs = self.addrs.lock();
+            addrs.in_progress.retain(|a| a != &addr);
+            addrs.failed.push((addr.clone(), error));
+            addrs.reported = addr;
+        }
+        self.last_error = Some(last_error);
+        // Don't wait before trying the next address.
+        self.next_start = None;
+    }
+
+    /// Returns true if the staggering delay allows starting a new dial.
+    fn can_start_dial(&mut self) -> bool {
+        if self.dials.is_empty() {
+            return true;
+        }
+
+        match self.next_start.as_mut().map(|d| d.poll()) {
+            Some(Ok(Async::NotReady)) => false,
+            Some(Ok(Async::Ready(()))) | None => true,
+            Some(Err(err)) => {
+                debug!("Timer error while staggering dials: {}", err);
+                true
+            },
+        }
+    }
+
+    /// Implementation of `poll`, which doesn't mark the attempt as finished.
+    fn poll_inner<TMuxer>(&mut self) -> Poll<(PeerId, TMuxer), RawSwarmReachError>
+    where
+        TTrans: Transport<Output = (PeerId, TMuxer)> + Clone,
+    {
+        let deadline_reached = match self.deadline.as_mut().map(|d| d.poll()) {
+            Some(Ok(Async::Ready(()))) => true,
+            Some(Err(err)) => {
+                debug!("Timer error while waiting for the dial deadline: {}", err);
+                true
+            },
+            Some(Ok(Async::NotReady)) | None => false,
+        };
+        if deadline_reached {
+            let msg = "deadline reached while dialing";
+            return Err(RawSwarmReachError::Transport(IoError::new(IoErrorKind::TimedOut, msg)));
+        }
+
+        loop {
+            // Poll the dials in progress.
+            let mut n = 0;
+            while n < self.dials.len() {
+                match self.dials[n].1.poll() {
+                    Ok(Async::NotReady) => n += 1,
+                    Ok(Async::Ready((peer_id, muxer))) => {
+                        let (addr, _) = self.dials.swap_remove(n);
+                        if peer_id != self.peer_id {
+                            self.dial_failed(addr, RawSwarmReachError::PeerIdMismatch { obtained: peer_id });
+                            continue;
+                        }
+
+                        {
+                            let mut addrs = self.addrs.lock();
+                            addrs.in_progress.clear();
+                            addrs.reported = addr;
+                        }
+                        // Cancel the other dials.
+                        self.dials.clear();
+                        return Ok(Async::Ready((peer_id, muxer)));
+                    },
+                    Err(err) => {
+                        let (addr, _) = self.dials.swap_remove(n);
+                        self.dial_failed(addr, RawSwarmReachError::Transport(err));
+                    },
+                }
+            }
+
+            // Start new dials if we can.
+            let mut started = false;
+            while self.dials.len() < self.concurrency && self.can_start_dial() {
+                let addr = {
+                    let mut addrs = self.addrs.lock();
+                    if addrs.pending.is_empty() {
+                        break;
+                    }
+                    let addr = addrs.pending.remove(0);
+                    addrs.in_progress.push(addr.clone());
+                    addr
+                };
+
+                match self.transport.clone().dial(addr.clone()) {
+                    Ok(dial) => {
+                        self.dials.push((addr, dial));
+                        if self.concurrency > 1 {
+                            self.next_start = Some(Delay::new(Instant::now() + self.attempt_delay));
+                        }
+                        started = true;
+                    },
+                    Err((_, addr)) => {
+                        let msg = format!("unsupported multiaddr {}", addr);
+                        let error = RawSwarmReachError::Transport(IoError::new(IoErrorKind::Other, msg));
+                        self.dial_failed(addr, error);
+                    },
+                }
+            }
+
+            // Newly-started dials must be polled at least once.
+            if started {
+                continue;
+            }
+
+            if self.dials.is_empty() {
+                return Err(self.last_error.take().unwrap_or_else(|| {
+                    let msg = "no address to dial";
+                    RawSwarmReachError::Transport(IoError::new(IoErrorKind::Other, msg))
+                }));
+            }
+
+            return Ok(Async::NotReady);
+        }
+    }
+}
+
+impl<TTrans, TMuxer> Future for DialAttempt<TTrans>
+where
+    TTrans: Transport<Output = (PeerId, TMuxer)> + Clone,
+{
+    type Item = (PeerId, TMuxer);
+    type Error = RawSwarmReachError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let result = self.poll_inner();
+        match result {
+            Ok(Async::NotReady) => {},
+            Ok(Async::Ready(_)) | Err(_) => self.addrs.lock().finished = true,
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future::{self, FutureResult}, stream};
+    use std::{collections::HashMap, io};
+    use tokio::runtime::current_thread::Runtime;
+
+    fn addrs(list: &[&str]) -> Vec<Multiaddr> {
+        list.iter().map(|a| a.parse().expect("bad multiaddr")).collect()
+    }
+
+    /// What happens when dialing an address of a `TestTransport`.
+    #[derive(Debug, Clone)]
+    enum Outcome {
+        /// Reaches the given peer after the given delay.
+        Reach(PeerId, Duration),
+        /// Fails after the given delay.
+        Fail(Duration),
+        /// Never finishes.
+        Hang,
+    }
+
+    /// Something that happened to a dial of a `TestTransport`.
+    #[derive(Debug, Clone, PartialEq)]
+    enum DialEvent {
+        Started(Multiaddr, Instant),
+        Dropped(Multiaddr),
+    }
+
+    /// Transport whose dials behave according to a script.
+    #[derive(Clone)]
+    struct TestTransport {
+        outcomes: Arc<HashMap<Multiaddr, Outcome>>,
+        events: Arc<Mutex<Vec<DialEvent>>>,
+    }
+
+    impl TestTransport {
+        fn new(outcomes: Vec<(Multiaddr, Outcome)>) -> Self {
+            TestTransport {
+                outcomes: Arc::new(outcomes.into_iter().collect()),
+                events: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        /// Returns the addresses that have been dialed, with the time at which they were dialed.
+        fn started(&self) -> Vec<(Multiaddr, Instant)> {
+            self.events.lock().iter().filter_map(|ev| match ev {
+                DialEvent::Started(addr, at) => Some((addr.clone(), *at)),
+                DialEvent::Dropped(_) => None,
+            }).collect()
+        }
+
+        /// Returns the addresses whose dial has been dropped before finishing.
+        fn dropped(&self) -> Vec<Multiaddr> {
+            self.events.lock().iter().filter_map(|ev| match ev {
+                DialEvent::Dropped(addr) => Some(addr.clone()),
+                DialEvent::Started(..) => None,
+            }).collect()
+        }
+    }
+
+    struct TestDial {
+        addr: Multiaddr,
+        outcome: Outcome,
+        delay: Option<Delay>,
+        finished: bool,
+        events: Arc<Mutex<Vec<DialEvent>>>,
+    }
+
+    impl Future for TestDial {
+        type Item = (PeerId, ());
+        type Error = io::Error;
+
+        fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+            match self.delay {
+                Some(ref mut delay) => {
+                    try_ready!(delay.poll().map_err(|err| io::Error::new(io::ErrorKind::Other, err)));
+                },
+                None => return Ok(Async::NotReady),
+            }
+
+            self.finished = true;
+            match self.outcome {
+                Outcome::Reach(ref peer_id, _) => Ok(Async::Ready((peer_id.clone(), ()))),
+                Outcome::Fail(_) => Err(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")),
+                Outcome::Hang => unreachable!("hanging dials have no delay"),
+            }
+        }
+    }
+
+    impl Drop for TestDial {
+        fn drop(&mut self) {
+            if !self.finished {
+                self.events.lock().push(DialEvent::Dropped(self.addr.clone()));
+            }
+        }
+    }
+
+    impl Transport for TestTransport {
+        type Output = (PeerId, ());
+        type Listener = stream::Empty<(Self::ListenerUpgrade, Multiaddr), io::Error>;
+        type ListenerUpgrade = FutureResult<Self::Output, io::Error>;
+        type Dial = TestDial;
+
+        fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+            Err((self, addr))
+        }
+
+        fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+            let outcome = match self.outcomes.get(&addr) {
+                Some(outcome) => outcome.clone(),
+                None => return Err((self, addr)),
+            };
+
+            let now = Instant::now();
+            self.events.lock().push(DialEvent::Started(addr.clone(), now));
+            let delay = match outcome {
+                Outcome::Reach(_, delay) | Outcome::Fail(delay) => Some(Delay::new(now + delay)),
+                Outcome::Hang => None,
+            };
+
+            Ok(TestDial {
+                addr,
+                outcome,
+                delay,
+                finished: false,
+                events: self.events.clone(),
+            })
+        }
+
+        fn nat_traversal(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
+            None
+        }
+    }
+
+    /// Runs a `DialAttempt` to `peer_id` through `list` until it finishes.
+    fn run(transport: &TestTransport, peer_id: &PeerId, list: &[Multiaddr], config: DialConfig)
+        -> (Result<(PeerId, ()), RawSwarmReachError>, Arc<Mutex<DialAddresses>>)
+    {
+        let addrs = DialAddresses::new(list.to_vec());
+        let mut rt = Runtime::new().unwrap();
+        let result = rt.block_on(future::lazy({
+            let transport = transport.clone();
+            let peer_id = peer_id.clone();
+            let addrs = addrs.clone();
+            move || DialAttempt::new(transport, peer_id, addrs, &config)
+        }));
+        (result, addrs)
+    }
+
+    #[test]
+    fn dials_up_to_concurrency_addresses_at_once() {
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2", "/ip4/127.0.0.1/tcp/3"]);
+        let transport = TestTransport::new(list.iter().map(|a| (a.clone(), Outcome::Hang)).collect());
+        let config = DialConfig::default()
+            .concurrency(2)
+            .attempt_delay(Duration::from_millis(0))
+            .deadline(Duration::from_millis(100));
+
+        let (result, addrs) = run(&transport, &PeerId::random(), &list, config);
+        assert!(result.is_err());
+        let started = transport.started().into_iter().map(|(a, _)| a).collect::<Vec<_>>();
+        assert_eq!(started, vec![list[0].clone(), list[1].clone()]);
+        assert_eq!(addrs.lock().pending, vec![list[2].clone()]);
+    }
+
+    #[test]
+    fn staggers_concurrent_dials() {
+        let peer_id = PeerId::random();
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2", "/ip4/127.0.0.1/tcp/3"]);
+        let transport = TestTransport::new(vec![
+            (list[0].clone(), Outcome::Hang),
+            (list[1].clone(), Outcome::Hang),
+            (list[2].clone(), Outcome::Reach(peer_id.clone(), Duration::from_millis(0))),
+        ]);
+        let config = DialConfig::default()
+            .concurrency(3)
+            .attempt_delay(Duration::from_millis(100));
+
+        let (result, _) = run(&transport, &peer_id, &list, config);
+        assert_eq!(result.unwrap().0, peer_id);
+        let started = transport.started();
+        assert_eq!(started.len(), 3);
+        assert!(started[1].1 - started[0].1 >= Duration::from_millis(100));
+        assert!(started[2].1 - started[1].1 >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn failed_dial_starts_next_address_immediately() {
+        let peer_id = PeerId::random();
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2", "/ip4/127.0.0.1/tcp/3"]);
+        let transport = TestTransport::new(vec![
+            (list[0].clone(), Outcome::Hang),
+            (list[1].clone(), Outcome::Fail(Duration::from_millis(0))),
+            (list[2].clone(), Outcome::Reach(peer_id.clone(), Duration::from_millis(0))),
+        ]);
+        let config = DialConfig::default()
+            .concurrency(3)
+            .attempt_delay(Duration::from_millis(500));
+
+        let (result, addrs) = run(&transport, &peer_id, &list, config);
+        assert_eq!(result.unwrap().0, peer_id);
+        let started = transport.started();
+        assert_eq!(started.len(), 3);
+        assert!(started[2].1 - started[1].1 < Duration::from_millis(250));
+        let addrs = addrs.lock();
+        assert_eq!(addrs.failed.iter().map(|(a, _)| a.clone()).collect::<Vec<_>>(), vec![list[1].clone()]);
+        assert!(addrs.finished);
+        assert_eq!(addrs.reported, list[2]);
+    }
+
+    #[test]
+    fn other_dials_are_cancelled_on_success() {
+        let peer_id = PeerId::random();
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2", "/ip4/127.0.0.1/tcp/3"]);
+        let transport = TestTransport::new(vec![
+            (list[0].clone(), Outcome::Hang),
+            (list[1].clone(), Outcome::Reach(peer_id.clone(), Duration::from_millis(50))),
+            (list[2].clone(), Outcome::Hang),
+        ]);
+        let config = DialConfig::default()
+            .concurrency(3)
+            .attempt_delay(Duration::from_millis(0));
+
+        let (result, addrs) = run(&transport, &peer_id, &list, config);
+        assert_eq!(result.unwrap().0, peer_id);
+        let mut dropped = transport.dropped();
+        dropped.sort_by(|a, b| a.to_string().cmp(&b.to_string()));
+        assert_eq!(dropped, vec![list[0].clone(), list[2].clone()]);
+        let addrs = addrs.lock();
+        assert!(addrs.in_progress.is_empty());
+        assert_eq!(addrs.reported, list[1]);
+    }
+
+    #[test]
+    fn wrong_peer_id_is_a_failure() {
+        let peer_id = PeerId::random();
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2"]);
+        let transport = TestTransport::new(vec![
+            (list[0].clone(), Outcome::Reach(PeerId::random(), Duration::from_millis(0))),
+            (list[1].clone(), Outcome::Reach(peer_id.clone(), Duration::from_millis(0))),
+        ]);
+
+        let (result, addrs) = run(&transport, &peer_id, &list, DialConfig::default());
+        assert_eq!(result.unwrap().0, peer_id);
+        match addrs.lock().failed.as_slice() {
+            [(addr, RawSwarmReachError::PeerIdMismatch { .. })] => assert_eq!(addr, &list[0]),
+            other => panic!("unexpected failures: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deadline_aborts_the_attempt() {
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1", "/ip4/127.0.0.1/tcp/2"]);
+        let transport = TestTransport::new(list.iter().map(|a| (a.clone(), Outcome::Hang)).collect());
+        let config = DialConfig::default().deadline(Duration::from_millis(100));
+
+        let start = Instant::now();
+        let (result, addrs) = run(&transport, &PeerId::random(), &list, config);
+        assert!(Instant::now() - start >= Duration::from_millis(100));
+        match result {
+            Err(RawSwarmReachError::Transport(ref err)) => assert_eq!(err.kind(), IoErrorKind::TimedOut),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(transport.dropped(), vec![list[0].clone()]);
+        assert_eq!(addrs.lock().remaining(), 2);
+    }
+
+    #[test]
+    fn append_after_end_of_attempt_is_refused() {
+        let list = addrs(&["/ip4/127.0.0.1/tcp/1"]);
+        let transport = TestTransport::new(vec![(list[0].clone(), Outcome::Fail(Duration::from_millis(0)))]);
+
+        let (result, addrs) = run(&transport, &PeerId::random(), &list, DialConfig::default());
+        assert!(result.is_err());
+        let mut addrs = addrs.lock();
+        assert!(!addrs.append("/ip4/127.0.0.1/tcp/2".parse().unwrap()));
+        assert!(addrs.pending.is_empty());
+    }
+
+    #[test]
+    fn ranks_last_successful_then_direct_then_relayed() {
+        let input = addrs(&[
+            "/ip4/1.2.3.4/tcp/1/p2p-circuit",
+            "/ip4/1.2.3.4/tcp/2",
+            "/ip4/1.2.3.4/tcp/3",
+        ]);
+        let last = input[2].clone();
+        let ranked = rank_addresses(input.clone(), Some(&last));
+        assert_eq!(ranked, vec![input[2].clone(), input[1].clone(), input[0].clone()]);
+    }
+
+    #[test]
+    fn interleaves_ip_families() {
+        let input = addrs(&[
+            "/ip4/1.2.3.4/tcp/1",
+            "/ip4/1.2.3.4/tcp/2",
+            "/ip6/::1/tcp/3",
+            "/ip6/::1/tcp/4",
+            "/ip6/::1/tcp/5",
+        ]);
+        let ranked = rank_addresses(input.clone(), None);
+        assert_eq!(ranked, vec![
+            input[2].clone(), input[0].clone(),
+            input[3].clone(), input[1].clone(),
+            input[4].clone(),
+        ]);
+    }
+
+    #[test]
+    fn removes_duplicates() {
+        let input = addrs(&["/ip4/1.2.3.4/tcp/1", "/ip4/1.2.3.4/tcp/1"]);
+        assert_eq!(rank_addresses(input, None).len(), 1);
+    }
+}
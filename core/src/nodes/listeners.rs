@@ -311,6 +311,11 @@ mod tests {
                         }
                     }
                 }
+                ListenerState::UpgradeError => {
+                    let addr = l.address.clone();
+                    let upgrade = future::err(io::Error::new(io::ErrorKind::Other, "upgrade error"));
+                    Box::new(stream::once::<_, io::Error>(Ok((upgrade, addr))))
+                }
             };
     }
 
@@ -19,6 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 pub mod collection;
+mod dial;
 pub mod handled_node;
 pub mod handled_node_tasks;
 pub mod listeners;
@@ -27,4 +28,4 @@ pub mod raw_swarm;
 
 pub use self::node::Substream;
 pub use self::handled_node::{NodeHandlerEvent, NodeHandlerEndpoint};
-pub use self::raw_swarm::{ConnectedPoint, Peer, RawSwarm, RawSwarmEvent};
+pub use self::raw_swarm::{ConnectedPoint, ConnectionLimits, DialConfig, IncomingConnectionPolicy, Peer, RawSwarm, RawSwarmEvent};
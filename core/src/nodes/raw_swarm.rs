@@ -22,6 +22,7 @@ use crate::muxing::StreamMuxer;
 use crate::{
     Endpoint, Multiaddr, PeerId,
     nodes::{
+        dial::{self, DialAddresses, DialAttempt},
         collection::{
             CollectionEvent,
             CollectionNodeAccept,
@@ -41,11 +42,15 @@ use crate::{
 };
 use fnv::FnvHashMap;
 use futures::{prelude::*, future};
+use parking_lot::Mutex;
 use std::{
     collections::hash_map::{Entry, OccupiedEntry},
     error,
     fmt,
-    io::{Error as IoError, ErrorKind as IoErrorKind}
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    mem,
+    sync::Arc,
+    time::Duration
 };
 
 /// Implementation of `Stream` that handles the nodes.
@@ -63,6 +68,148 @@ where
     /// The reach attempts of the swarm.
     /// This needs to be a separate struct in order to handle multiple mutable borrows issues.
     reach_attempts: ReachAttempts,
+
+    /// Limits on the number of connections.
+    limits: ConnectionLimits,
+
+    /// How to dial the addresses of a peer.
+    dial_config: DialConfig,
+
+    /// Policy consulted for each incoming connection, if any.
+    incoming_policy: Option<BoxedIncomingPolicy>,
+}
+
+/// Limits on the number of connections handled by a `RawSwarm`.
+///
+/// All the limits are disabled by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    max_established: Option<usize>,
+    max_established_per_peer: Option<usize>,
+    max_pending_incoming: Option<usize>,
+    max_pending_outgoing: Option<usize>,
+}
+
+impl ConnectionLimits {
+    /// Sets the maximum number of nodes we can be connected to at the same time.
+    ///
+    /// Incoming connections are refused and dialing attempts fail immediately while this limit
+    /// is reached.
+    pub fn max_established(mut self, value: usize) -> Self {
+        self.max_established = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of connections to the same peer, including the one that is being
+    /// negotiated.
+    ///
+    /// Since the `RawSwarm` keeps at most one connection per peer, a value of `1` means that a new
+    /// connection to a peer we are already connected to is refused instead of replacing the
+    /// existing one. Any value greater than `1` has the same effect as no limit: the new
+    /// connection replaces the existing one.
+    pub fn max_established_per_peer(mut self, value: usize) -> Self {
+        self.max_established_per_peer = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of incoming connections that are in the process of being
+    /// negotiated.
+    pub fn max_pending_incoming(mut self, value: usize) -> Self {
+        self.max_pending_incoming = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of outgoing connections that are in the process of being
+    /// negotiated.
+    pub fn max_pending_outgoing(mut self, value: usize) -> Self {
+        self.max_pending_outgoing = Some(value);
+        self
+    }
+
+    /// Returns true if having `current` elements means that `limit` is reached.
+    #[inline]
+    fn is_reached(limit: Option<usize>, current: usize) -> bool {
+        limit.map(|limit| current >= limit).unwrap_or(false)
+    }
+}
+
+/// Configuration of how the `RawSwarm` dials the addresses of a peer.
+///
+/// The addresses are first ranked: the address through which we last reached the peer comes
+/// first, then the direct addresses, then the relayed ones, alternating between IPv6 and IPv4.
+/// They are then dialed in this order, with up to `concurrency` dials in progress at the same
+/// time. As soon as one of them reaches the peer, the others are cancelled.
+///
+/// By default, the addresses are dialed one by one and there is no deadline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialConfig {
+    pub(crate) concurrency: usize,
+    pub(crate) attempt_delay: Duration,
+    pub(crate) deadline: Option<Duration>,
+}
+
+impl DialConfig {
+    /// Sets the maximum number of addresses of the same peer that are dialed at the same time.
+    ///
+    /// A value of `0` is treated as `1`.
+    pub fn concurrency(mut self, value: usize) -> Self {
+        self.concurrency = value.max(1);
+        self
+    }
+
+    /// Sets the delay to wait before dialing another address of a peer while a dial is still in
+    /// progress. A dial that fails doesn't wait for this delay.
+    ///
+    /// Only relevant if the concurrency is greater than one. Defaults to 250ms, as recommended
+    /// by the "Happy Eyeballs" algorithm.
+    pub fn attempt_delay(mut self, value: Duration) -> Self {
+        self.attempt_delay = value;
+        self
+    }
+
+    /// Sets the maximum duration of an attempt to reach a peer, all addresses included.
+    pub fn deadline(mut self, value: Duration) -> Self {
+        self.deadline = Some(value);
+        self
+    }
+}
+
+impl Default for DialConfig {
+    fn default() -> Self {
+        DialConfig {
+            concurrency: 1,
+            attempt_delay: Duration::from_millis(250),
+            deadline: None,
+        }
+    }
+}
+
+/// Policy consulted by the `RawSwarm` whenever a new connection arrives on a listener, before
+/// it is negotiated.
+///
+/// This is implemented on closures of type `FnMut(&Multiaddr, &Multiaddr) -> bool`.
+pub trait IncomingConnectionPolicy {
+    /// Returns `true` if the connection received on `listen_addr`, whose remote can be reached
+    /// back at `send_back_addr`, should be accepted. Returns `false` if it should be refused.
+    fn accept(&mut self, listen_addr: &Multiaddr, send_back_addr: &Multiaddr) -> bool;
+}
+
+impl<TFn> IncomingConnectionPolicy for TFn
+where TFn: FnMut(&Multiaddr, &Multiaddr) -> bool
+{
+    #[inline]
+    fn accept(&mut self, listen_addr: &Multiaddr, send_back_addr: &Multiaddr) -> bool {
+        self(listen_addr, send_back_addr)
+    }
+}
+
+/// Boxed `IncomingConnectionPolicy`, so that it can be stored in the `RawSwarm`.
+struct BoxedIncomingPolicy(Box<dyn IncomingConnectionPolicy + Send>);
+
+impl fmt::Debug for BoxedIncomingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_tuple("IncomingConnectionPolicy").finish()
+    }
 }
 
 #[derive(Debug)]
@@ -79,6 +226,105 @@ struct ReachAttempts {
 
     /// For each peer ID we're connected to, contains the endpoint we're connected to.
     connected_points: FnvHashMap<PeerId, ConnectedPoint>,
+
+    /// For each peer we have successfully dialed, the address through which we reached it.
+    /// Contains at most `MAX_LAST_DIALED_ADDRS` entries.
+    last_dialed_addrs: FnvHashMap<PeerId, Multiaddr>,
+}
+
+/// Maximum number of entries in `ReachAttempts::last_dialed_addrs`.
+const MAX_LAST_DIALED_ADDRS: usize = 1024;
+
+impl ReachAttempts {
+    /// Remembers the address through which we have reached a peer, so that it is dialed first
+    /// the next time.
+    ///
+    /// If we already remember too many addresses, the ones of the peers we aren't connected to
+    /// are forgotten first.
+    fn remember_dialed_addr(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        if self.last_dialed_addrs.len() >= MAX_LAST_DIALED_ADDRS && !self.last_dialed_addrs.contains_key(&peer_id) {
+            let connected_points = &self.connected_points;
+            self.last_dialed_addrs.retain(|peer_id, _| connected_points.contains_key(peer_id));
+            if self.last_dialed_addrs.len() >= MAX_LAST_DIALED_ADDRS {
+                return;
+            }
+        }
+
+        self.last_dialed_addrs.insert(peer_id, addr);
+    }
+
+    /// Returns the number of incoming connections that are being negotiated.
+    fn num_pending_incoming(&self) -> usize {
+        self.other_reach_attempts
+            .iter()
+            .filter(|&(_, endpoint)| endpoint.is_listener())
+            .count()
+    }
+
+    /// Returns the number of outgoing connections that are being negotiated.
+    fn num_pending_outgoing(&self) -> usize {
+        let unknown_peer = self.other_reach_attempts
+            .iter()
+            .filter(|&(_, endpoint)| endpoint.is_dialer())
+            .count();
+        self.out_reach_attempts.len() + unknown_peer
+    }
+
+    /// If the limits don't allow opening a new outgoing connection, returns the error to report.
+    fn check_outgoing_limits(&self, limits: &ConnectionLimits) -> Option<RawSwarmReachError> {
+        if ConnectionLimits::is_reached(limits.max_pending_outgoing, self.num_pending_outgoing()) {
+            let msg = "maximum number of pending outgoing connections reached";
+            return Some(RawSwarmReachError::Transport(IoError::new(IoErrorKind::Other, msg)));
+        }
+
+        if ConnectionLimits::is_reached(limits.max_established, self.connected_points.len()) {
+            let msg = "maximum number of established connections reached";
+            return Some(RawSwarmReachError::Transport(IoError::new(IoErrorKind::Other, msg)));
+        }
+
+        None
+    }
+
+    /// If the limits or the policy don't allow accepting a new incoming connection, returns the
+    /// error to report.
+    fn check_incoming_limits(
+        &self,
+        limits: &ConnectionLimits,
+        policy: Option<&mut BoxedIncomingPolicy>,
+        listen_addr: &Multiaddr,
+        send_back_addr: &Multiaddr
+    ) -> Option<IoError> {
+        if ConnectionLimits::is_reached(limits.max_pending_incoming, self.num_pending_incoming()) {
+            let msg = "maximum number of pending incoming connections reached";
+            return Some(IoError::new(IoErrorKind::PermissionDenied, msg));
+        }
+
+        if ConnectionLimits::is_reached(limits.max_established, self.connected_points.len()) {
+            let msg = "maximum number of established connections reached";
+            return Some(IoError::new(IoErrorKind::PermissionDenied, msg));
+        }
+
+        if let Some(policy) = policy {
+            if !policy.0.accept(listen_addr, send_back_addr) {
+                let msg = "incoming connection refused by policy";
+                return Some(IoError::new(IoErrorKind::PermissionDenied, msg));
+            }
+        }
+
+        None
+    }
+
+    /// Returns true if accepting a new connection to a node would go over the limits.
+    ///
+    /// `would_replace` must be true if we already have a connection to this node.
+    fn node_limit_reached(&self, limits: &ConnectionLimits, would_replace: bool) -> bool {
+        if would_replace {
+            // The existing connection plus the new one.
+            ConnectionLimits::is_reached(limits.max_established_per_peer, 1)
+        } else {
+            ConnectionLimits::is_reached(limits.max_established, self.connected_points.len())
+        }
+    }
 }
 
 /// Attempt to reach a peer.
@@ -86,10 +332,8 @@ struct ReachAttempts {
 struct OutReachAttempt {
     /// Identifier for the reach attempt.
     id: ReachAttemptId,
-    /// Multiaddr currently being attempted.
-    cur_attempted: Multiaddr,
-    /// Multiaddresses to attempt if the current one fails.
-    next_attempts: Vec<Multiaddr>,
+    /// Addresses of the attempt, shared with the task that performs it.
+    addrs: Arc<Mutex<DialAddresses>>,
 }
 
 /// Event that can happen on the `RawSwarm`.
@@ -166,18 +410,26 @@ where
 
     /// Failed to reach a peer that we were trying to dial.
     DialError {
-        /// Returns the number of multiaddresses that still need to be attempted. If this is
-        /// non-zero, then there's still a chance we can connect to this node. If this is zero,
-        /// then we have definitely failed.
+        /// Number of multiaddresses that were not attempted, or whose dial was abandoned, before
+        /// the attempt ended. This is non-zero if the attempt was aborted, for example because
+        /// of the deadline or of the connection limits.
+        ///
+        /// The attempt is over in all cases; a new one must be started to try these addresses.
         remain_addrs_attempt: usize,
 
         /// Id of the peer we were trying to dial.
         peer_id: PeerId,
 
-        /// The multiaddr we failed to reach.
+        /// The multiaddr we failed to reach. If multiple addresses were attempted, this is the
+        /// last one that failed.
         multiaddr: Multiaddr,
 
-        /// The error that happened.
+        /// All the addresses that failed to reach the peer during the attempt, with the error
+        /// that happened for each of them, in the order in which they failed.
+        failed_addrs: Vec<(Multiaddr, RawSwarmReachError)>,
+
+        /// The error that happened. If `multiaddr` isn't part of `failed_addrs`, this is the
+        /// reason why the attempt was aborted.
         error: RawSwarmReachError,
     },
 
@@ -193,6 +445,22 @@ where
         handler: THandler,
     },
 
+    /// We reached a node by dialing an address with `dial()`, but refused the connection because
+    /// accepting it would go over the connection limits.
+    ///
+    /// Contrary to `UnknownPeerDialError`, the handler passed to `dial()` can't be returned, as
+    /// it has already been moved to the task of the node.
+    UnknownPeerConnectionRefused {
+        /// The multiaddr we dialed.
+        multiaddr: Multiaddr,
+
+        /// Id of the node we reached.
+        peer_id: PeerId,
+
+        /// The error that happened.
+        error: IoError,
+    },
+
     /// A node produced a custom event.
     NodeEvent {
         /// Id of the node that produced the event.
@@ -255,11 +523,12 @@ where
                     .field("error", error)
                     .finish()
             }
-            RawSwarmEvent::DialError { ref remain_addrs_attempt, ref peer_id, ref multiaddr, ref error } => {
+            RawSwarmEvent::DialError { ref remain_addrs_attempt, ref peer_id, ref multiaddr, ref failed_addrs, ref error } => {
                 f.debug_struct("DialError")
                     .field("remain_addrs_attempt", remain_addrs_attempt)
                     .field("peer_id", peer_id)
                     .field("multiaddr", multiaddr)
+                    .field("failed_addrs", failed_addrs)
                     .field("error", error)
                     .finish()
             }
@@ -269,6 +538,13 @@ where
                     .field("error", error)
                     .finish()
             }
+            RawSwarmEvent::UnknownPeerConnectionRefused { ref multiaddr, ref peer_id, ref error } => {
+                f.debug_struct("UnknownPeerConnectionRefused")
+                    .field("multiaddr", multiaddr)
+                    .field("peer_id", peer_id)
+                    .field("error", error)
+                    .finish()
+            }
             RawSwarmEvent::NodeEvent { ref peer_id, ref event } => {
                 f.debug_struct("NodeEvent")
                     .field("peer_id", peer_id)
@@ -421,6 +697,16 @@ impl From<ConnectedPoint> for Endpoint {
 }
 
 impl ConnectedPoint {
+    /// Returns the address of the remote. This is the address that was dialed if we are
+    /// `Dialer`, and the address used to send back data if we are `Listener`.
+    #[inline]
+    pub fn remote_addr(&self) -> &Multiaddr {
+        match *self {
+            ConnectedPoint::Dialer { ref address } => address,
+            ConnectedPoint::Listener { ref send_back_addr, .. } => send_back_addr,
+        }
+    }
+
     /// Turns the `ConnectedPoint` into the corresponding `Endpoint`.
     #[inline]
     pub fn to_endpoint(&self) -> Endpoint {
@@ -461,6 +747,11 @@ where
     /// Creates a new node events stream.
     #[inline]
     pub fn new(transport: TTrans, local_peer_id: PeerId) -> Self {
+        Self::with_limits(transport, local_peer_id, ConnectionLimits::default())
+    }
+
+    /// Creates a new node events stream that enforces the given connection limits.
+    pub fn with_limits(transport: TTrans, local_peer_id: PeerId, limits: ConnectionLimits) -> Self {
         // TODO: with_capacity?
         RawSwarm {
             listeners: ListenersStream::new(transport),
@@ -470,10 +761,42 @@ where
                 out_reach_attempts: Default::default(),
                 other_reach_attempts: Vec::new(),
                 connected_points: Default::default(),
+                last_dialed_addrs: Default::default(),
             },
+            limits,
+            dial_config: DialConfig::default(),
+            incoming_policy: None,
         }
     }
 
+    /// Returns the connection limits enforced by this `RawSwarm`.
+    #[inline]
+    pub fn limits(&self) -> &ConnectionLimits {
+        &self.limits
+    }
+
+    /// Returns the configuration of how the addresses of a peer are dialed.
+    #[inline]
+    pub fn dial_config(&self) -> &DialConfig {
+        &self.dial_config
+    }
+
+    /// Sets how the addresses of a peer are dialed. Only applies to the attempts started
+    /// afterwards.
+    #[inline]
+    pub fn set_dial_config(&mut self, config: DialConfig) {
+        self.dial_config = config;
+    }
+
+    /// Sets the policy to consult whenever a new connection arrives on a listener.
+    ///
+    /// Connections refused by the policy produce an `IncomingConnectionError` event.
+    pub fn set_incoming_connection_policy<TPolicy>(&mut self, policy: TPolicy)
+    where TPolicy: IncomingConnectionPolicy + Send + 'static
+    {
+        self.incoming_policy = Some(BoxedIncomingPolicy(Box::new(policy)));
+    }
+
     /// Returns the transport passed when building this object.
     #[inline]
     pub fn transport(&self) -> &TTrans {
@@ -532,13 +855,19 @@ where
         TInEvent: Send + 'static,
         TOutEvent: Send + 'static,
     {
-        let future = match self.transport().clone().dial(addr.clone()) {
-            Ok(fut) => fut,
-            Err((_, addr)) => return Err(addr),
+        // If the limits are reached, the attempt is reported as failed in an
+        // `UnknownPeerDialError` event.
+        let reach_id = if let Some(err) = self.reach_attempts.check_outgoing_limits(&self.limits) {
+            self.active_nodes.add_reach_attempt(future::err(err), handler)
+        } else {
+            let future = match self.transport().clone().dial(addr.clone()) {
+                Ok(fut) => fut,
+                Err((_, addr)) => return Err(addr),
+            };
+            self.active_nodes.add_reach_attempt(future.map_err(RawSwarmReachError::Transport), handler)
         };
 
         let connected_point = ConnectedPoint::Dialer { address: addr };
-        let reach_id = self.active_nodes.add_reach_attempt(future.map_err(RawSwarmReachError::Transport), handler);
         self.reach_attempts.other_reach_attempts.push((reach_id, connected_point));
         Ok(())
     }
@@ -552,10 +881,26 @@ where
     // a lot of API changes
     #[inline]
     pub fn num_incoming_negotiated(&self) -> usize {
-        self.reach_attempts.other_reach_attempts
-            .iter()
-            .filter(|&(_, endpoint)| endpoint.is_listener())
-            .count()
+        self.reach_attempts.num_pending_incoming()
+    }
+
+    /// Returns the number of outgoing connections that are currently in the process of being
+    /// negotiated.
+    #[inline]
+    pub fn num_outgoing_negotiated(&self) -> usize {
+        self.reach_attempts.num_pending_outgoing()
+    }
+
+    /// Returns the number of nodes we are connected to.
+    #[inline]
+    pub fn num_connected(&self) -> usize {
+        self.reach_attempts.connected_points.len()
+    }
+
+    /// Returns the list of nodes we are connected to, with the endpoint of each connection.
+    #[inline]
+    pub fn connections(&self) -> impl Iterator<Item = (&PeerId, &ConnectedPoint)> {
+        self.reach_attempts.connected_points.iter()
     }
 
     /// Sends an event to all nodes.
@@ -586,13 +931,11 @@ where
 
         if self.reach_attempts.out_reach_attempts.get_mut(&peer_id).is_some() {
             debug_assert!(!self.reach_attempts.connected_points.contains_key(&peer_id));
-            return Peer::PendingConnect(PeerPendingConnect {
-                attempt: match self.reach_attempts.out_reach_attempts.entry(peer_id.clone()) {
-                    Entry::Occupied(e) => e,
-                    Entry::Vacant(_) => panic!("we checked for Some just above"),
-                },
-                active_nodes: &mut self.active_nodes,
-            });
+            let attempt = match self.reach_attempts.out_reach_attempts.entry(peer_id.clone()) {
+                Entry::Occupied(e) => e,
+                Entry::Vacant(_) => panic!("we checked for Some just above"),
+            };
+            return Peer::PendingConnect(PeerPendingConnect::new(attempt, &mut self.active_nodes));
         }
 
         debug_assert!(!self.reach_attempts.connected_points.contains_key(&peer_id));
@@ -602,14 +945,13 @@ where
         })
     }
 
-    /// Starts dialing out a multiaddress. `rest` is the list of multiaddresses to attempt if
-    /// `first` fails.
+    /// Starts dialing out the given multiaddresses, which must not be empty.
     ///
     /// It is a logic error to call this method if we already have an outgoing attempt to the
     /// given peer.
-    fn start_dial_out(&mut self, peer_id: PeerId, handler: THandler, first: Multiaddr, rest: Vec<Multiaddr>)
+    fn start_dial_out(&mut self, peer_id: PeerId, handler: THandler, addrs: Vec<Multiaddr>)
     where
-        TTrans: Transport<Output = (PeerId, TMuxer)>,
+        TTrans: Transport<Output = (PeerId, TMuxer)> + Send + 'static,
         TTrans::Dial: Send + 'static,
         TMuxer: StreamMuxer + Send + Sync + 'static,
         TMuxer::OutboundSubstream: Send,
@@ -617,34 +959,25 @@ where
         TInEvent: Send + 'static,
         TOutEvent: Send + 'static,
     {
-        let reach_id = match self.transport().clone().dial(first.clone()) {
-            Ok(fut) => {
-                let expected_peer_id = peer_id.clone();
-                let fut = fut
-                    .map_err(RawSwarmReachError::Transport)
-                    .and_then(move |(actual_peer_id, muxer)| {
-                        if actual_peer_id == expected_peer_id {
-                            Ok((actual_peer_id, muxer))
-                        } else {
-                            Err(RawSwarmReachError::PeerIdMismatch { obtained: actual_peer_id })
-                        }
-                    });
-                self.active_nodes.add_reach_attempt(fut, handler)
-            },
-            Err((_, addr)) => {
-                // TODO: better error reporting
-                let msg = format!("unsupported multiaddr {}", addr);
-                let fut = future::err(RawSwarmReachError::Transport(IoError::new(IoErrorKind::Other, msg)));
-                self.active_nodes.add_reach_attempt(fut, handler)
-            },
+        let addrs = dial::rank_addresses(addrs, self.reach_attempts.last_dialed_addrs.get(&peer_id));
+
+        // If the limits are reached, the attempt fails immediately and we don't try any address.
+        // The addresses are left pending, so that they are reported as not attempted.
+        let (reach_id, addrs) = if let Some(err) = self.reach_attempts.check_outgoing_limits(&self.limits) {
+            let addrs = DialAddresses::new(addrs);
+            (self.active_nodes.add_reach_attempt(future::err(err), handler), addrs)
+        } else {
+            let addrs = DialAddresses::new(addrs);
+            let transport = self.transport().clone();
+            let fut = DialAttempt::new(transport, peer_id.clone(), addrs.clone(), &self.dial_config);
+            (self.active_nodes.add_reach_attempt(fut, handler), addrs)
         };
 
         let former = self.reach_attempts.out_reach_attempts.insert(
             peer_id,
             OutReachAttempt {
                 id: reach_id,
-                cur_attempted: first,
-                next_attempts: rest,
+                addrs,
             },
         );
 
@@ -670,6 +1003,22 @@ where
         match self.listeners.poll() {
             Async::NotReady => (),
             Async::Ready(ListenersEvent::Incoming { upgrade, listen_addr, send_back_addr }) => {
+                let refusal = self.reach_attempts.check_incoming_limits(
+                    &self.limits,
+                    self.incoming_policy.as_mut(),
+                    &listen_addr,
+                    &send_back_addr
+                );
+                if let Some(error) = refusal {
+                    // Dropping the upgrade closes the connection.
+                    drop(upgrade);
+                    return Async::Ready(RawSwarmEvent::IncomingConnectionError {
+                        listen_addr,
+                        send_back_addr,
+                        error,
+                    });
+                }
+
                 let event = IncomingConnectionEvent {
                     upgrade,
                     listen_addr,
@@ -693,7 +1042,7 @@ where
         match self.active_nodes.poll() {
             Async::NotReady => return Async::NotReady,
             Async::Ready(CollectionEvent::NodeReached(reach_event)) => {
-                let (a, e) = handle_node_reached(&mut self.reach_attempts, reach_event);
+                let (a, e) = handle_node_reached(&mut self.reach_attempts, &self.limits, reach_event);
                 action = a;
                 out_event = e;
             }
@@ -737,10 +1086,6 @@ where
             }
         }
 
-        if let Some((peer_id, handler, first, rest)) = action.start_dial_out {
-            self.start_dial_out(peer_id, handler, first, rest);
-        }
-
         if let Some(interrupt) = action.interrupt {
             // TODO: improve proof or remove; this is too complicated right now
             self.active_nodes
@@ -758,22 +1103,12 @@ where
 }
 
 /// Internal struct indicating an action to perform of the swarm.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[must_use]
-struct ActionItem<THandler> {
-    start_dial_out: Option<(PeerId, THandler, Multiaddr, Vec<Multiaddr>)>,
+struct ActionItem {
     interrupt: Option<ReachAttemptId>,
 }
 
-impl<THandler> Default for ActionItem<THandler> {
-    fn default() -> Self {
-        ActionItem {
-            start_dial_out: None,
-            interrupt: None,
-        }
-    }
-}
-
 /// Handles a node reached event from the collection.
 ///
 /// Returns an event to return from the stream.
@@ -782,8 +1117,9 @@ impl<THandler> Default for ActionItem<THandler> {
 /// >           panics will likely happen.
 fn handle_node_reached<'a, TTrans, TMuxer, TInEvent, TOutEvent, THandler, THandlerErr>(
     reach_attempts: &mut ReachAttempts,
+    limits: &ConnectionLimits,
     event: CollectionReachEvent<TInEvent, TOutEvent, THandler, RawSwarmReachError, THandlerErr>
-) -> (ActionItem<THandler>, RawSwarmEvent<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>)
+) -> (ActionItem, RawSwarmEvent<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>)
 where
     TTrans: Transport<Output = (PeerId, TMuxer)> + Clone,
     TMuxer: StreamMuxer + Send + Sync + 'static,
@@ -816,6 +1152,34 @@ where
             }
         }
 
+        // Refuse the connection if accepting it would go over the limits.
+        if reach_attempts.node_limit_reached(limits, event.would_replace()) {
+            match opened_endpoint {
+                ConnectedPoint::Listener { listen_addr, send_back_addr } => {
+                    return (Default::default(), RawSwarmEvent::IncomingConnectionError {
+                        listen_addr,
+                        send_back_addr,
+                        error: IoError::new(IoErrorKind::PermissionDenied,
+                                            "connection limit reached".to_string()),
+                    });
+                },
+                ConnectedPoint::Dialer { address } => {
+                    // We didn't dial this peer specifically, so this isn't a `DialError`.
+                    let peer_id = event.deny();
+                    return (Default::default(), RawSwarmEvent::UnknownPeerConnectionRefused {
+                        multiaddr: address,
+                        peer_id,
+                        error: IoError::new(IoErrorKind::PermissionDenied,
+                                            "connection limit reached".to_string()),
+                    });
+                },
+            }
+        }
+
+        if let ConnectedPoint::Dialer { ref address } = opened_endpoint {
+            reach_attempts.remember_dialed_addr(event.peer_id().clone(), address.clone());
+        }
+
         // Set the endpoint for this peer.
         let closed_endpoint = reach_attempts.connected_points.insert(event.peer_id().clone(), opened_endpoint.clone());
 
@@ -824,7 +1188,6 @@ where
             debug_assert_ne!(attempt.id, event.reach_attempt_id());
             ActionItem {
                 interrupt: Some(attempt.id),
-                .. Default::default()
             }
         } else {
             ActionItem::default()
@@ -861,9 +1224,23 @@ where
             .expect("is_outgoing_and_ok is true only if reach_attempts.out_reach_attempts.get(event.peer_id()) \
                         returned Some");
 
-        let opened_endpoint = ConnectedPoint::Dialer {
-            address: attempt.cur_attempted,
-        };
+        // Refuse the connection if accepting it would go over the limits.
+        if reach_attempts.node_limit_reached(limits, event.would_replace()) {
+            let peer_id = event.deny();
+            let mut addrs = attempt.addrs.lock();
+            return (Default::default(), RawSwarmEvent::DialError {
+                remain_addrs_attempt: addrs.remaining(),
+                peer_id,
+                multiaddr: addrs.reported.clone(),
+                failed_addrs: mem::replace(&mut addrs.failed, Vec::new()),
+                error: RawSwarmReachError::Transport(IoError::new(IoErrorKind::PermissionDenied,
+                                                     "connection limit reached".to_string())),
+            });
+        }
+
+        let address = attempt.addrs.lock().reported.clone();
+        reach_attempts.remember_dialed_addr(event.peer_id().clone(), address.clone());
+        let opened_endpoint = ConnectedPoint::Dialer { address };
 
         let closed_endpoint = reach_attempts.connected_points
             .insert(event.peer_id().clone(), opened_endpoint.clone());
@@ -914,7 +1291,7 @@ fn handle_reach_error<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>(
     reach_id: ReachAttemptId,
     error: RawSwarmReachError,
     handler: THandler,
-) -> (ActionItem<THandler>, RawSwarmEvent<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>)
+) -> (ActionItem, RawSwarmEvent<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>)
 where TTrans: Transport
 {
     // Search for the attempt in `out_reach_attempts`.
@@ -925,27 +1302,21 @@ where TTrans: Transport
         .find(|(_, a)| a.id == reach_id)
         .map(|(p, _)| p.clone());
     if let Some(peer_id) = out_reach_peer_id {
-        let mut attempt = reach_attempts.out_reach_attempts.remove(&peer_id)
+        let attempt = reach_attempts.out_reach_attempts.remove(&peer_id)
             .expect("out_reach_peer_id is a key that is grabbed from out_reach_attempts");
-
-        let num_remain = attempt.next_attempts.len();
-        let failed_addr = attempt.cur_attempted.clone();
-
-        let action = if !attempt.next_attempts.is_empty() {
-            let mut attempt = attempt;
-            let next_attempt = attempt.next_attempts.remove(0);
-            ActionItem {
-                start_dial_out: Some((peer_id.clone(), handler, next_attempt, attempt.next_attempts)),
-                .. Default::default()
-            }
-        } else {
-            Default::default()
+        let (failed_addr, failed_addrs, remain_addrs_attempt) = {
+            let mut addrs = attempt.addrs.lock();
+            (addrs.reported.clone(), mem::replace(&mut addrs.failed, Vec::new()), addrs.remaining())
         };
 
-        return (action, RawSwarmEvent::DialError {
-            remain_addrs_attempt: num_remain,
+        // The address through which we last reached the peer may not be valid anymore.
+        reach_attempts.last_dialed_addrs.remove(&peer_id);
+
+        return (Default::default(), RawSwarmEvent::DialError {
+            remain_addrs_attempt,
             peer_id,
             multiaddr: failed_addr,
+            failed_addrs,
             error,
         });
     }
@@ -1198,9 +1569,34 @@ impl<'a, TInEvent> PeerConnected<'a, TInEvent> {
 pub struct PeerPendingConnect<'a, TInEvent: 'a, TOutEvent: 'a, THandler: 'a, THandlerErr: 'a> {
     attempt: OccupiedEntry<'a, PeerId, OutReachAttempt>,
     active_nodes: &'a mut CollectionStream<TInEvent, TOutEvent, THandler, RawSwarmReachError, THandlerErr>,
+    /// Address being dialed when this struct was created. Returned by `attempted_multiaddr`.
+    attempted: Multiaddr,
+    /// Addresses pending when this struct was created. Returned by `pending_multiaddrs`.
+    pending: Vec<Multiaddr>,
 }
 
 impl<'a, TInEvent, TOutEvent, THandler, THandlerErr> PeerPendingConnect<'a, TInEvent, TOutEvent, THandler, THandlerErr> {
+    fn new(
+        attempt: OccupiedEntry<'a, PeerId, OutReachAttempt>,
+        active_nodes: &'a mut CollectionStream<TInEvent, TOutEvent, THandler, RawSwarmReachError, THandlerErr>,
+    ) -> Self {
+        let (attempted, pending) = {
+            let addrs = attempt.get().addrs.lock();
+            let attempted = addrs.in_progress.first()
+                .or_else(|| addrs.pending.first())
+                .unwrap_or(&addrs.reported)
+                .clone();
+            (attempted, addrs.pending.clone())
+        };
+
+        PeerPendingConnect {
+            attempt,
+            active_nodes,
+            attempted,
+            pending,
+        }
+    }
+
     /// Interrupt this connection attempt.
     // TODO: consider returning a PeerNotConnected; however that is really pain in terms of
     // borrows
@@ -1217,27 +1613,43 @@ impl<'a, TInEvent, TOutEvent, THandler, THandlerErr> PeerPendingConnect<'a, TInE
         }
     }
 
-    /// Returns the multiaddress we're currently trying to dial.
+    /// Returns the multiaddresses we're currently trying to dial.
+    #[inline]
+    pub fn attempted_multiaddrs(&self) -> Vec<Multiaddr> {
+        self.attempt.get().addrs.lock().in_progress.clone()
+    }
+
+    /// Returns the multiaddress we're currently trying to dial. If multiple addresses are being
+    /// dialed, returns the first one.
+    ///
+    /// The value is the one at the time this `PeerPendingConnect` was obtained. Use
+    /// `attempted_multiaddrs` to get all the addresses being dialed.
     #[inline]
     pub fn attempted_multiaddr(&self) -> &Multiaddr {
-        &self.attempt.get().cur_attempted
+        &self.attempted
     }
 
-    /// Returns a list of the multiaddresses we're going to try if the current dialing fails.
+    /// Returns the list of multiaddresses we're going to try if the current dials fail, in order.
+    ///
+    /// The list is the one at the time this `PeerPendingConnect` was obtained, plus the
+    /// addresses added with `append_multiaddr_attempt`.
     #[inline]
     pub fn pending_multiaddrs(&self) -> impl Iterator<Item = &Multiaddr> {
-        self.attempt.get().next_attempts.iter()
+        self.pending.iter()
     }
 
-    /// Adds a new multiaddr to attempt if the current dialing fails.
+    /// Adds a new multiaddr to attempt if the current dials fail.
     ///
-    /// Doesn't do anything if that multiaddress is already in the queue.
-    pub fn append_multiaddr_attempt(&mut self, addr: Multiaddr) {
-        if self.attempt.get().next_attempts.iter().any(|a| a == &addr) {
-            return;
+    /// Doesn't do anything if that multiaddress is already being dialed or in the queue. Returns
+    /// false if the attempt has already failed or succeeded, in which case the address will not
+    /// be dialed. The outcome of the attempt is then reported by the next call to `poll`.
+    pub fn append_multiaddr_attempt(&mut self, addr: Multiaddr) -> bool {
+        let mut addrs = self.attempt.get().addrs.lock();
+        if !addrs.append(addr) {
+            return false;
         }
-
-        self.attempt.get_mut().next_attempts.push(addr);
+        self.pending = addrs.pending.clone();
+        true
     }
 }
 
@@ -1254,7 +1666,7 @@ where
 impl<'a, TTrans, TInEvent, TOutEvent, TMuxer, THandler, THandlerErr>
     PeerNotConnected<'a, TTrans, TInEvent, TOutEvent, THandler, THandlerErr>
 where
-    TTrans: Transport<Output = (PeerId, TMuxer)> + Clone,
+    TTrans: Transport<Output = (PeerId, TMuxer)> + Clone + Send + 'static,
     TTrans::Dial: Send + 'static,
     TMuxer: StreamMuxer + Send + Sync + 'static,
     TMuxer::OutboundSubstream: Send,
@@ -1271,14 +1683,15 @@ where
     /// the whole connection is immediately closed.
     #[inline]
     pub fn connect(self, addr: Multiaddr, handler: THandler) -> Result<PeerPendingConnect<'a, TInEvent, TOutEvent, THandler, THandlerErr>, Self> {
-        self.connect_inner(handler, addr, Vec::new())
+        self.connect_inner(handler, vec![addr])
     }
 
     /// Attempts a new connection to this node using the given multiaddresses.
     ///
-    /// The multiaddresses passed as parameter will be tried one by one.
+    /// The multiaddresses passed as parameter are ranked and dialed according to the
+    /// `DialConfig` of the `RawSwarm`.
     ///
-    /// If the iterator is empty, TODO: what to do? at the moment we unwrap
+    /// If the iterator is empty, returns `Err(self)`.
     ///
     /// If we reach a peer but the `PeerId` doesn't correspond to the one we're expecting, then
     /// the whole connection is immediately closed.
@@ -1288,29 +1701,25 @@ where
     where
         TIter: IntoIterator<Item = Multiaddr>,
     {
-        let mut addrs = addrs.into_iter();
-        let first = match addrs.next() {
-            Some(f) => f,
-            None => return Err(self)
-        };
-        let rest = addrs.collect();
-        self.connect_inner(handler, first, rest)
+        let addrs: Vec<_> = addrs.into_iter().collect();
+        if addrs.is_empty() {
+            return Err(self);
+        }
+        self.connect_inner(handler, addrs)
     }
 
-    /// Inner implementation of `connect`.
-    fn connect_inner(self, handler: THandler, first: Multiaddr, rest: Vec<Multiaddr>)
+    /// Inner implementation of `connect`. `addrs` must not be empty.
+    fn connect_inner(self, handler: THandler, addrs: Vec<Multiaddr>)
         -> Result<PeerPendingConnect<'a, TInEvent, TOutEvent, THandler, THandlerErr>, Self>
     {
-        self.nodes.start_dial_out(self.peer_id.clone(), handler, first, rest);
-        Ok(PeerPendingConnect {
-            attempt: match self.nodes.reach_attempts.out_reach_attempts.entry(self.peer_id) {
-                Entry::Occupied(e) => e,
-                Entry::Vacant(_) => {
-                    panic!("We called out_reach_attempts.insert with this peer id just above")
-                },
+        self.nodes.start_dial_out(self.peer_id.clone(), handler, addrs);
+        let attempt = match self.nodes.reach_attempts.out_reach_attempts.entry(self.peer_id) {
+            Entry::Occupied(e) => e,
+            Entry::Vacant(_) => {
+                panic!("We called out_reach_attempts.insert with this peer id just above")
             },
-            active_nodes: &mut self.nodes.active_nodes,
-        })
+        };
+        Ok(PeerPendingConnect::new(attempt, &mut self.nodes.active_nodes))
     }
 }
 
@@ -1736,6 +2145,117 @@ mod tests {
         })).expect("tokio works");
     }
 
+    #[test]
+    fn incoming_connection_refused_by_policy() {
+        let mut transport = DummyTransport::new();
+        let peer_id = PeerId::random();
+        let muxer = DummyMuxer::new();
+        transport.set_initial_listener_state(ListenerState::Ok(Async::Ready(Some((peer_id, muxer)))));
+
+        let mut swarm = RawSwarm::<_, _, _, Handler, _>::new(transport, PeerId::random());
+        swarm.set_incoming_connection_policy(|_: &Multiaddr, _: &Multiaddr| false);
+        swarm.listen_on("/memory".parse().unwrap()).unwrap();
+
+        let mut rt = Runtime::new().unwrap();
+        let swarm = Arc::new(Mutex::new(swarm));
+        let swarm_fut = swarm.clone();
+        let fut = future::poll_fn(move || -> Poll<_, ()> {
+            let mut swarm_fut = swarm_fut.lock();
+            assert_matches!(swarm_fut.poll(), Async::Ready(RawSwarmEvent::IncomingConnectionError { error, .. }) => {
+                assert_eq!(error.kind(), IoErrorKind::PermissionDenied);
+            });
+            Ok(Async::Ready(()))
+        });
+        rt.block_on(fut).expect("tokio works");
+        assert_eq!(swarm.lock().num_incoming_negotiated(), 0);
+    }
+
+    #[test]
+    fn max_pending_incoming_is_enforced() {
+        let mut transport = DummyTransport::new();
+        let peer_id = PeerId::random();
+        let muxer = DummyMuxer::new();
+        transport.set_initial_listener_state(ListenerState::Ok(Async::Ready(Some((peer_id, muxer)))));
+
+        let limits = ConnectionLimits::default().max_pending_incoming(1);
+        let mut swarm = RawSwarm::<_, _, _, Handler, _>::with_limits(transport, PeerId::random(), limits);
+        swarm.listen_on("/memory".parse().unwrap()).unwrap();
+
+        let mut rt = Runtime::new().unwrap();
+        let swarm = Arc::new(Mutex::new(swarm));
+        let swarm_fut = swarm.clone();
+        let fut = future::poll_fn(move || -> Poll<_, ()> {
+            let mut swarm_fut = swarm_fut.lock();
+            assert_matches!(swarm_fut.poll(), Async::Ready(RawSwarmEvent::IncomingConnection(incoming)) => {
+                incoming.accept(Handler::default());
+            });
+            // The first connection is still being negotiated, so the second one is refused.
+            assert_matches!(swarm_fut.poll(), Async::Ready(RawSwarmEvent::IncomingConnectionError { .. }));
+            Ok(Async::Ready(()))
+        });
+        rt.block_on(fut).expect("tokio works");
+        assert_eq!(swarm.lock().num_incoming_negotiated(), 1);
+    }
+
+    #[test]
+    fn max_pending_outgoing_is_enforced() {
+        let limits = ConnectionLimits::default().max_pending_outgoing(1);
+        let mut swarm = RawSwarm::<_, _, _, Handler, _>::with_limits(DummyTransport::new(), PeerId::random(), limits);
+        let addr = "/ip4/127.0.0.1/tcp/1234".parse::<Multiaddr>().expect("bad multiaddr");
+        assert!(swarm.dial(addr.clone(), Handler::default()).is_ok());
+        assert!(swarm.dial(addr, Handler::default()).is_ok());
+        assert_eq!(swarm.num_outgoing_negotiated(), 2);
+
+        let swarm = Arc::new(Mutex::new(swarm));
+        let mut rt = Runtime::new().unwrap();
+        let mut refused = false;
+        while !refused {
+            let swarm_fut = swarm.clone();
+            refused = rt.block_on(future::poll_fn(move || -> Poll<bool, ()> {
+                let mut swarm = swarm_fut.lock();
+                match swarm.poll() {
+                    Async::Ready(RawSwarmEvent::UnknownPeerDialError { .. }) => Ok(Async::Ready(true)),
+                    _ => Ok(Async::Ready(false))
+                }
+            })).expect("tokio works");
+        }
+    }
+
+    #[test]
+    fn unknown_peer_over_the_limit_is_not_a_dial_error() {
+        let peer_id = PeerId::random();
+        let mut transport = DummyTransport::new();
+        transport.set_next_peer_id(&peer_id);
+        let limits = ConnectionLimits::default().max_established_per_peer(1);
+        let mut swarm = RawSwarm::<_, _, _, Handler, _>::with_limits(transport, PeerId::random(), limits);
+        let addr = "/ip4/127.0.0.1/tcp/1234".parse::<Multiaddr>().expect("bad multiaddr");
+        // Both attempts reach the same node, so the second one would replace the first.
+        assert!(swarm.dial(addr.clone(), Handler::default()).is_ok());
+        assert!(swarm.dial(addr.clone(), Handler::default()).is_ok());
+
+        let swarm = Arc::new(Mutex::new(swarm));
+        let mut rt = Runtime::new().unwrap();
+        let mut refused = false;
+        while !refused {
+            let swarm_fut = swarm.clone();
+            let addr = addr.clone();
+            let peer_id = peer_id.clone();
+            refused = rt.block_on(future::poll_fn(move || -> Poll<bool, ()> {
+                let mut swarm = swarm_fut.lock();
+                match swarm.poll() {
+                    Async::Ready(RawSwarmEvent::UnknownPeerConnectionRefused { multiaddr, peer_id: refused_id, .. }) => {
+                        assert_eq!(multiaddr, addr);
+                        assert_eq!(refused_id, peer_id);
+                        Ok(Async::Ready(true))
+                    },
+                    Async::Ready(RawSwarmEvent::DialError { .. }) => panic!("unexpected dial error"),
+                    _ => Ok(Async::Ready(false))
+                }
+            })).expect("tokio works");
+        }
+        assert!(swarm.lock().peer(peer_id).as_connected().is_some());
+    }
+
     #[test]
     fn local_prio_equivalence_relation() {
         for _ in 0..1000 {
@@ -1744,4 +2264,27 @@ mod tests {
             assert_ne!(has_dial_prio(&a, &b), has_dial_prio(&b, &a));
         }
     }
+
+    #[test]
+    fn last_dialed_addrs_is_bounded() {
+        let mut reach_attempts = ReachAttempts {
+            local_peer_id: PeerId::random(),
+            out_reach_attempts: Default::default(),
+            other_reach_attempts: Vec::new(),
+            connected_points: Default::default(),
+            last_dialed_addrs: Default::default(),
+        };
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+
+        let connected = PeerId::random();
+        reach_attempts.connected_points.insert(connected.clone(), ConnectedPoint::Dialer { address: addr.clone() });
+        reach_attempts.remember_dialed_addr(connected.clone(), addr.clone());
+        for _ in 0 .. MAX_LAST_DIALED_ADDRS + 10 {
+            reach_attempts.remember_dialed_addr(PeerId::random(), addr.clone());
+            assert!(reach_attempts.last_dialed_addrs.len() <= MAX_LAST_DIALED_ADDRS);
+        }
+
+        // The address of the connected peer is kept.
+        assert!(reach_attempts.last_dialed_addrs.contains_key(&connected));
+    }
 }
@@ -23,9 +23,14 @@ use multihash;
 use std::{fmt, str::FromStr};
 use PublicKey;
 
+/// Public keys whose protobuf encoding is at most this long are embedded in the `PeerId` with the
+/// identity multihash, instead of being hashed with SHA-256.
+const MAX_INLINE_KEY_LENGTH: usize = 42;
+
 /// Identifier of a peer of the network.
 ///
-/// The data is a multihash of the public key of the peer.
+/// The data is a multihash of the public key of the peer. Small keys, such as ed25519 keys, are
+/// not hashed and are instead embedded in the `PeerId` with the identity multihash.
 // TODO: maybe keep things in decoded version?
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct PeerId {
@@ -43,8 +48,13 @@ impl PeerId {
     #[inline]
     pub fn from_public_key(public_key: PublicKey) -> PeerId {
         let protobuf = public_key.into_protobuf_encoding();
-        let multihash = multihash::encode(multihash::Hash::SHA2256, &protobuf)
-            .expect("sha2-256 is always supported");
+        let algorithm = if protobuf.len() <= MAX_INLINE_KEY_LENGTH {
+            multihash::Hash::Identity
+        } else {
+            multihash::Hash::SHA2256
+        };
+        let multihash = multihash::encode(algorithm, &protobuf)
+            .expect("identity and sha2-256 are always supported");
         PeerId { multihash }
     }
 
@@ -53,25 +63,23 @@ impl PeerId {
     #[inline]
     pub fn from_bytes(data: Vec<u8>) -> Result<PeerId, Vec<u8>> {
         match multihash::Multihash::from_bytes(data) {
-            Ok(multihash) => {
-                if multihash.algorithm() == multihash::Hash::SHA2256 {
-                    Ok(PeerId { multihash })
-                } else {
-                    Err(multihash.into_bytes())
-                }
-            },
+            Ok(multihash) => PeerId::from_multihash(multihash).map_err(|mh| mh.into_bytes()),
             Err(err) => Err(err.data),
         }
     }
 
     /// Turns a `Multihash` into a `PeerId`. If the multihash doesn't use the correct algorithm,
     /// returns back the data as an error.
+    ///
+    /// Both SHA-256 multihashes and identity multihashes of small public keys are accepted.
     #[inline]
     pub fn from_multihash(data: multihash::Multihash) -> Result<PeerId, multihash::Multihash> {
-        if data.algorithm() == multihash::Hash::SHA2256 {
-            Ok(PeerId { multihash: data })
-        } else {
-            Err(data)
+        match data.algorithm() {
+            multihash::Hash::SHA2256 => Ok(PeerId { multihash: data }),
+            multihash::Hash::Identity if data.digest().len() <= MAX_INLINE_KEY_LENGTH => {
+                Ok(PeerId { multihash: data })
+            },
+            _ => Err(data),
         }
     }
 
@@ -187,6 +195,7 @@ impl FromStr for PeerId {
 
 #[cfg(test)]
 mod tests {
+    use multihash;
     use rand::random;
     use {PeerId, PublicKey};
 
@@ -211,6 +220,28 @@ mod tests {
         assert_eq!(peer_id, second);
     }
 
+    #[test]
+    fn ed25519_peer_id_inlines_the_key() {
+        let key = PublicKey::Ed25519(vec![
+            0x1e, 0xd1, 0xe8, 0xfa, 0xe2, 0xc4, 0xa1, 0x44, 0xb8, 0xbe, 0x8f, 0xd4, 0xb4, 0x7b,
+            0xf3, 0xd3, 0xb3, 0x4b, 0x87, 0x1c, 0x3c, 0xac, 0xf6, 0x01, 0x0f, 0x0e, 0x42, 0xd4,
+            0x74, 0xfc, 0xe2, 0x7e,
+        ]);
+        let peer_id = key.clone().into_peer_id();
+        assert_eq!(peer_id.to_base58(), "12D3KooWBtg3aaRMjxwedh83aGiUkwSxDwUZkzuJcfaqUmo7R3pq");
+        assert_eq!(peer_id.digest(), &key.clone().into_protobuf_encoding()[..]);
+        assert_eq!(peer_id.is_public_key(&key), Some(true));
+
+        let second: PeerId = peer_id.to_base58().parse().unwrap();
+        assert_eq!(peer_id, second);
+    }
+
+    #[test]
+    fn large_identity_multihash_is_rejected() {
+        let multihash = multihash::encode(multihash::Hash::Identity, &[0; 64]).unwrap();
+        assert!(PeerId::from_multihash(multihash).is_err());
+    }
+
     #[test]
     fn random_peer_id_is_valid() {
         for _ in 0 .. 5000 {
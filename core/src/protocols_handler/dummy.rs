@@ -19,7 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{
         InboundUpgrade,
         OutboundUpgrade,
@@ -87,6 +87,11 @@ where
     #[inline]
     fn inject_inbound_closed(&mut self) {}
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        KeepAlive::Now
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.shutting_down = true;
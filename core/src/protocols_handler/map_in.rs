@@ -19,7 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{
         InboundUpgrade,
         OutboundUpgrade,
@@ -98,6 +98,11 @@ where
         self.inner.inject_inbound_closed()
     }
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        self.inner.connection_keep_alive()
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.inner.shutdown()
@@ -24,7 +24,7 @@ use crate::upgrade::{
     UpgradeError,
 };
 use futures::prelude::*;
-use std::{error, fmt, io, time::Duration};
+use std::{cmp::Ordering, error, fmt, io, time::Duration, time::Instant};
 use tokio_io::{AsyncRead, AsyncWrite};
 
 pub use self::dummy::DummyProtocolsHandler;
@@ -71,6 +71,12 @@ mod select;
 /// might already be closed or unresponsive. They should therefore not rely on being able to
 /// deliver messages.
 ///
+/// # Keeping the connection alive
+///
+/// The `connection_keep_alive()` method indicates whether the handler still needs the
+/// connection. When the handlers of all the protocols of a connection report that they no longer
+/// need it, the connection is shut down.
+///
 /// # Relationship with `NodeHandler`.
 ///
 /// This trait is very similar to the `NodeHandler` trait. The fundamental differences are:
@@ -81,9 +87,6 @@ mod select;
 ///   protocols. Two or more implementations of `ProtocolsHandler` can be combined into one that
 ///   supports all the protocols together, which is not possible with `NodeHandler`.
 ///
-// TODO: add a "blocks connection closing" system, so that we can gracefully close a connection
-//       when it's no longer needed, and so that for example the periodic pinging system does not
-//       keep the connection alive forever
 pub trait ProtocolsHandler {
     /// Custom event that can be received from the outside.
     type InEvent;
@@ -131,6 +134,25 @@ pub trait ProtocolsHandler {
     /// therefore no more inbound substreams will be produced.
     fn inject_inbound_closed(&mut self);
 
+    /// Returns until when the connection should be kept alive.
+    ///
+    /// If returns `Until`, that indicates that this connection may be closed after the given
+    /// deadline if nothing new happens in the meanwhile. Returns `Forever` if the handler needs
+    /// the connection, for example because it is in the middle of a request.
+    ///
+    /// When multiple handlers are combined, the connection is kept alive for as long as one of
+    /// them needs it.
+    ///
+    /// This method is called after each call to `poll()`, and the value it returns is only taken
+    /// into account if no substream is being negotiated.
+    ///
+    /// The default implementation returns `Forever`, which matches the behaviour of handlers
+    /// written before this method existed.
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        KeepAlive::Forever
+    }
+
     /// Indicates to the node that it should shut down. After that, it is expected that `poll()`
     /// returns `Ready(None)` as soon as possible.
     ///
@@ -183,7 +205,7 @@ pub trait ProtocolsHandler {
     where
         Self: Sized,
     {
-        NodeHandlerWrapperBuilder::new(self, Duration::from_secs(10), Duration::from_secs(10))
+        NodeHandlerWrapperBuilder::new(self, Duration::from_secs(10), Duration::from_secs(10), Duration::from_secs(10))
     }
 
     /// Builds an implementation of `NodeHandler` that handles this protocol exclusively.
@@ -275,6 +297,49 @@ impl<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
     }
 }
 
+/// How long the connection should be kept alive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeepAlive {
+    /// If nothing new happens, the connection should be closed at the given `Instant`.
+    Until(Instant),
+    /// Keep the connection alive.
+    Forever,
+    /// Close the connection as soon as possible, after the idle timeout of the node handler.
+    Now,
+}
+
+impl KeepAlive {
+    /// Returns true for `Forever`, false otherwise.
+    #[inline]
+    pub fn is_forever(&self) -> bool {
+        match *self {
+            KeepAlive::Forever => true,
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for KeepAlive {
+    #[inline]
+    fn partial_cmp(&self, other: &KeepAlive) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `Now` is the smallest value and `Forever` the largest. `Until`s are ordered by deadline.
+impl Ord for KeepAlive {
+    fn cmp(&self, other: &KeepAlive) -> Ordering {
+        use self::KeepAlive::*;
+
+        match (self, other) {
+            (Now, Now) | (Forever, Forever) => Ordering::Equal,
+            (Now, _) | (_, Forever) => Ordering::Less,
+            (_, Now) | (Forever, _) => Ordering::Greater,
+            (Until(expiration), Until(other_expiration)) => expiration.cmp(other_expiration),
+        }
+    }
+}
+
 /// Error that can happen on an outbound substream opening attempt.
 #[derive(Debug)]
 pub enum ProtocolsHandlerUpgrErr<TUpgrErr> {
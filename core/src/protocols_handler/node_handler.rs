@@ -20,7 +20,7 @@
 
 use crate::{
     nodes::handled_node::{NodeHandler, NodeHandlerEndpoint, NodeHandlerEvent},
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{
         self,
         OutboundUpgrade,
@@ -29,8 +29,8 @@ use crate::{
     }
 };
 use futures::prelude::*;
-use std::{io, time::Duration};
-use tokio_timer::Timeout;
+use std::{io, time::Duration, time::Instant};
+use tokio_timer::{Delay, Timeout};
 
 /// Prototype for a `NodeHandlerWrapper`.
 pub struct NodeHandlerWrapperBuilder<TProtoHandler>
@@ -43,6 +43,8 @@ where
     in_timeout: Duration,
     /// Timeout for outgoing substreams negotiation.
     out_timeout: Duration,
+    /// How long to wait before closing a connection that the handler no longer needs.
+    idle_timeout: Duration,
 }
 
 impl<TProtoHandler> NodeHandlerWrapperBuilder<TProtoHandler>
@@ -51,11 +53,12 @@ where
 {
     /// Builds a `NodeHandlerWrapperBuilder`.
     #[inline]
-    pub(crate) fn new(handler: TProtoHandler, in_timeout: Duration, out_timeout: Duration) -> Self {
+    pub(crate) fn new(handler: TProtoHandler, in_timeout: Duration, out_timeout: Duration, idle_timeout: Duration) -> Self {
         NodeHandlerWrapperBuilder {
             handler,
             in_timeout,
             out_timeout,
+            idle_timeout,
         }
     }
 
@@ -73,6 +76,14 @@ where
         self
     }
 
+    /// Sets how long a connection is kept open after the handler has reported that it no longer
+    /// needs it with `KeepAlive::Now`.
+    #[inline]
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
     /// Builds the `NodeHandlerWrapper`.
     #[inline]
     pub fn build(self) -> NodeHandlerWrapper<TProtoHandler> {
@@ -82,8 +93,11 @@ where
             negotiating_out: Vec::new(),
             in_timeout: self.in_timeout,
             out_timeout: self.out_timeout,
+            idle_timeout: self.idle_timeout,
             queued_dial_upgrades: Vec::new(),
             unique_dial_upgrade_id: 0,
+            connection_shutdown: None,
+            shutting_down: false,
         }
     }
 }
@@ -109,11 +123,66 @@ where
     in_timeout: Duration,
     /// Timeout for outgoing substreams negotiation.
     out_timeout: Duration,
+    /// How long to wait before closing a connection that the handler no longer needs.
+    idle_timeout: Duration,
     /// For each outbound substream request, how to upgrade it. The first element of the tuple
     /// is the unique identifier (see `unique_dial_upgrade_id`).
     queued_dial_upgrades: Vec<(u64, TProtoHandler::OutboundProtocol)>,
     /// Unique identifier assigned to each queued dial upgrade.
     unique_dial_upgrade_id: u64,
+    /// If `Some`, the connection is no longer needed and will be shut down when the timer fires.
+    connection_shutdown: Option<Delay>,
+    /// True if `shutdown()` has been called on the handler.
+    shutting_down: bool,
+}
+
+impl<TProtoHandler> NodeHandlerWrapper<TProtoHandler>
+where
+    TProtoHandler: ProtocolsHandler,
+{
+    /// Updates `connection_shutdown` according to what the handler reports, then polls it.
+    ///
+    /// Returns true if the connection should now be shut down.
+    fn update_connection_shutdown(&mut self) -> bool {
+        // A substream that is being opened or negotiated always keeps the connection alive.
+        let keep_alive = if self.negotiating_in.is_empty() && self.negotiating_out.is_empty() &&
+            self.queued_dial_upgrades.is_empty()
+        {
+            self.handler.connection_keep_alive()
+        } else {
+            KeepAlive::Forever
+        };
+
+        match keep_alive {
+            KeepAlive::Forever => self.connection_shutdown = None,
+            KeepAlive::Until(expiration) => {
+                if let Some(ref mut delay) = self.connection_shutdown {
+                    if delay.deadline() != expiration {
+                        delay.reset(expiration);
+                    }
+                } else {
+                    self.connection_shutdown = Some(Delay::new(expiration));
+                }
+            },
+            KeepAlive::Now => {
+                if self.connection_shutdown.is_none() {
+                    let expiration = Instant::now() + self.idle_timeout;
+                    self.connection_shutdown = Some(Delay::new(expiration));
+                }
+            },
+        }
+
+        match self.connection_shutdown.as_mut().map(|delay| delay.poll()) {
+            None | Some(Ok(Async::NotReady)) => false,
+            Some(Ok(Async::Ready(()))) => true,
+            Some(Err(err)) => {
+                // A broken timer shouldn't keep the connection alive forever, nor close it
+                // before the handler is done with it.
+                debug!("Timer error while waiting for an idle connection: {:?}", err);
+                false
+            },
+        }
+    }
 }
 
 impl<TProtoHandler> NodeHandler for NodeHandlerWrapper<TProtoHandler>
@@ -195,6 +264,8 @@ where
 
     #[inline]
     fn shutdown(&mut self) {
+        self.connection_shutdown = None;
+        self.shutting_down = true;
         self.handler.shutdown();
     }
 
@@ -241,27 +312,136 @@ where
             }
         }
 
-        // Poll the handler at the end so that we see the consequences of the method calls on
-        // `self.handler`.
-        match self.handler.poll()? {
-            Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))) => {
-                return Ok(Async::Ready(Some(NodeHandlerEvent::Custom(event))));
+        loop {
+            // Poll the handler at the end so that we see the consequences of the method calls on
+            // `self.handler`.
+            match self.handler.poll()? {
+                Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))) => {
+                    return Ok(Async::Ready(Some(NodeHandlerEvent::Custom(event))));
+                }
+                Async::Ready(Some(ProtocolsHandlerEvent::OutboundSubstreamRequest {
+                    upgrade,
+                    info,
+                })) => {
+                    let id = self.unique_dial_upgrade_id;
+                    self.unique_dial_upgrade_id += 1;
+                    self.queued_dial_upgrades.push((id, upgrade));
+                    return Ok(Async::Ready(Some(
+                        NodeHandlerEvent::OutboundSubstreamRequest((id, info)),
+                    )));
+                }
+                Async::Ready(None) => return Ok(Async::Ready(None)),
+                Async::NotReady => (),
+            };
+
+            if self.shutting_down || !self.update_connection_shutdown() {
+                return Ok(Async::NotReady);
             }
-            Async::Ready(Some(ProtocolsHandlerEvent::OutboundSubstreamRequest {
-                upgrade,
-                info,
-            })) => {
-                let id = self.unique_dial_upgrade_id;
-                self.unique_dial_upgrade_id += 1;
-                self.queued_dial_upgrades.push((id, upgrade));
-                return Ok(Async::Ready(Some(
-                    NodeHandlerEvent::OutboundSubstreamRequest((id, info)),
-                )));
+
+            // The connection is no longer needed. Shut the handler down, and poll it again so
+            // that it gets a chance to finish.
+            self.connection_shutdown = None;
+            self.shutting_down = true;
+            self.handler.shutdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upgrade::{DeniedUpgrade, InboundUpgrade};
+    use futures::future;
+    use std::io::Cursor;
+    use tokio::runtime::Runtime;
+    use void::Void;
+
+    /// Handler that doesn't do anything, and reports a fixed keep-alive value.
+    struct KeepAliveHandler {
+        keep_alive: KeepAlive,
+        shutting_down: bool,
+    }
+
+    impl KeepAliveHandler {
+        fn new(keep_alive: KeepAlive) -> Self {
+            KeepAliveHandler { keep_alive, shutting_down: false }
+        }
+    }
+
+    impl ProtocolsHandler for KeepAliveHandler {
+        type InEvent = Void;
+        type OutEvent = Void;
+        type Substream = Cursor<Vec<u8>>;
+        type InboundProtocol = DeniedUpgrade;
+        type OutboundProtocol = DeniedUpgrade;
+        type OutboundOpenInfo = ();
+
+        fn listen_protocol(&self) -> Self::InboundProtocol {
+            DeniedUpgrade
+        }
+
+        fn inject_fully_negotiated_inbound(&mut self, _: <Self::InboundProtocol as InboundUpgrade<Self::Substream>>::Output) {}
+
+        fn inject_fully_negotiated_outbound(&mut self, _: <Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Output, _: ()) {}
+
+        fn inject_event(&mut self, _: Self::InEvent) {}
+
+        fn inject_dial_upgrade_error(&mut self, _: (), _: ProtocolsHandlerUpgrErr<Void>) {}
+
+        fn inject_inbound_closed(&mut self) {}
+
+        fn connection_keep_alive(&self) -> KeepAlive {
+            self.keep_alive
+        }
+
+        fn shutdown(&mut self) {
+            self.shutting_down = true;
+        }
+
+        fn poll(&mut self) -> Poll<Option<ProtocolsHandlerEvent<DeniedUpgrade, (), Void>>, io::Error> {
+            if self.shutting_down {
+                Ok(Async::Ready(None))
+            } else {
+                Ok(Async::NotReady)
             }
-            Async::Ready(None) => return Ok(Async::Ready(None)),
-            Async::NotReady => (),
-        };
+        }
+    }
+
+    fn build(keep_alive: KeepAlive, idle_timeout: Duration) -> NodeHandlerWrapper<KeepAliveHandler> {
+        let negotiation_timeout = Duration::from_secs(10);
+        NodeHandlerWrapperBuilder::new(KeepAliveHandler::new(keep_alive), negotiation_timeout,
+                                       negotiation_timeout, idle_timeout)
+            .build()
+    }
+
+    #[test]
+    fn idle_connection_is_closed_after_timeout() {
+        let idle_timeout = Duration::from_millis(50);
+        let mut wrapper = build(KeepAlive::Now, idle_timeout);
+        let start = Instant::now();
+
+        let mut rt = Runtime::new().unwrap();
+        let closed = rt.block_on(future::poll_fn(move || wrapper.poll())).unwrap();
+        assert!(closed.is_none());
+        assert!(start.elapsed() >= idle_timeout);
+    }
 
-        Ok(Async::NotReady)
+    #[test]
+    fn busy_connection_is_kept_open() {
+        let mut wrapper = build(KeepAlive::Forever, Duration::from_millis(10));
+        let mut deadline = Delay::new(Instant::now() + Duration::from_millis(200));
+
+        let mut rt = Runtime::new().unwrap();
+        let still_open = rt.block_on(future::poll_fn(move || -> Poll<bool, io::Error> {
+            if let Async::Ready(None) = wrapper.poll()? {
+                return Ok(Async::Ready(false));
+            }
+            match deadline.poll() {
+                Ok(Async::Ready(())) => Ok(Async::Ready(true)),
+                Ok(Async::NotReady) => Ok(Async::NotReady),
+                Err(err) => panic!("timer error: {:?}", err),
+            }
+        })).unwrap();
+        assert!(still_open);
     }
 }
@@ -21,7 +21,7 @@
 use crate::{
     either::EitherError,
     either::EitherOutput,
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{
         InboundUpgrade,
         OutboundUpgrade,
@@ -31,7 +31,7 @@ use crate::{
     }
 };
 use futures::prelude::*;
-use std::io;
+use std::{cmp, io};
 use tokio_io::{AsyncRead, AsyncWrite};
 
 /// Implementation of `ProtocolsHandler` that combines two protocols into one.
@@ -155,6 +155,11 @@ where
         }
     }
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        cmp::max(self.proto1.connection_keep_alive(), self.proto2.connection_keep_alive())
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.proto1.shutdown();
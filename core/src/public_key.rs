@@ -19,6 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use PeerId;
+use identity;
 use keys_proto;
 use protobuf::{self, Message};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
@@ -26,17 +27,39 @@ use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 /// Public key used by the remote.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PublicKey {
-    /// DER format.
+    /// DER encoding of a `SubjectPublicKeyInfo`.
     Rsa(Vec<u8>),
-    /// Format = ???
-    // TODO: ^
+    /// The 32 bytes of the public key, as defined in RFC 8032.
     Ed25519(Vec<u8>),
-    /// Format = ???
-    // TODO: ^
+    /// The 33 bytes of the compressed public key.
     Secp256k1(Vec<u8>),
 }
 
 impl PublicKey {
+    /// Verifies a signature of a message with this public key.
+    ///
+    /// Returns `false` if the signature is invalid, or if support for the type of key is
+    /// disabled.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        match self {
+            PublicKey::Ed25519(public) => identity::ed25519::verify(public, msg, sig),
+            #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
+            PublicKey::Rsa(public) => identity::rsa::verify(public, msg, sig),
+            #[cfg(not(all(feature = "rsa", not(target_os = "emscripten"))))]
+            PublicKey::Rsa(_) => {
+                debug!("support for RSA was disabled at compile-time");
+                false
+            },
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(public) => identity::secp256k1::verify(public, msg, sig),
+            #[cfg(not(feature = "secp256k1"))]
+            PublicKey::Secp256k1(_) => {
+                debug!("support for secp256k1 was disabled at compile-time");
+                false
+            },
+        }
+    }
+
     /// Encodes the public key as a protobuf message.
     ///
     /// Used at various locations in the wire protocol of libp2p.
@@ -19,19 +19,20 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    Transport, Multiaddr, PublicKey, PeerId, InboundUpgrade, OutboundUpgrade, UpgradeInfo, ProtocolName,
+    Transport, Multiaddr, PublicKey, PeerId, IpRange, InboundUpgrade, OutboundUpgrade, UpgradeInfo, ProtocolName,
     muxing::StreamMuxer,
     nodes::{
         handled_node::NodeHandler,
         node::Substream,
-        raw_swarm::{RawSwarm, RawSwarmEvent}
+        raw_swarm::{ConnectionLimits, DialConfig, IncomingConnectionPolicy, Peer, RawSwarm, RawSwarmEvent}
     },
     protocols_handler::{NodeHandlerWrapper, ProtocolsHandler},
     topology::Topology
 };
+use fnv::FnvHashSet;
 use futures::prelude::*;
 use smallvec::SmallVec;
-use std::{fmt, io, ops::{Deref, DerefMut}};
+use std::{error, fmt, io, ops::{Deref, DerefMut}};
 
 pub use crate::nodes::raw_swarm::ConnectedPoint;
 
@@ -61,6 +62,12 @@ where TTransport: Transport,
 
     /// List of multiaddresses we're listening on.
     listened_addrs: SmallVec<[Multiaddr; 8]>,
+
+    /// List of nodes we refuse to be connected to.
+    banned_peers: FnvHashSet<PeerId>,
+
+    /// List of ranges of IP addresses we refuse to be connected to.
+    banned_ranges: Vec<IpRange>,
 }
 
 impl<TTransport, TBehaviour, TTopology> Deref for Swarm<TTransport, TBehaviour, TTopology>
@@ -90,7 +97,7 @@ where TBehaviour: NetworkBehaviour<TTopology>,
       TMuxer: StreamMuxer + Send + Sync + 'static,
       <TMuxer as StreamMuxer>::OutboundSubstream: Send + 'static,
       <TMuxer as StreamMuxer>::Substream: Send + 'static,
-      TTransport: Transport<Output = (PeerId, TMuxer)> + Clone,
+      TTransport: Transport<Output = (PeerId, TMuxer)> + Clone + Send + 'static,
       TTransport::Listener: Send + 'static,
       TTransport::ListenerUpgrade: Send + 'static,
       TTransport::Dial: Send + 'static,
@@ -115,7 +122,15 @@ where TBehaviour: NetworkBehaviour<TTopology>,
 {
     /// Builds a new `Swarm`.
     #[inline]
-    pub fn new(transport: TTransport, mut behaviour: TBehaviour, topology: TTopology) -> Self {
+    pub fn new(transport: TTransport, behaviour: TBehaviour, topology: TTopology) -> Self {
+        Swarm::with_limits(transport, behaviour, topology, ConnectionLimits::default())
+    }
+
+    /// Builds a new `Swarm` that enforces the given connection limits.
+    ///
+    /// Connections refused because of the limits are reported to the behaviour like any other
+    /// connection error.
+    pub fn with_limits(transport: TTransport, mut behaviour: TBehaviour, topology: TTopology, limits: ConnectionLimits) -> Self {
         let supported_protocols = behaviour
             .new_handler()
             .listen_protocol()
@@ -124,7 +139,7 @@ where TBehaviour: NetworkBehaviour<TTopology>,
             .map(|info| info.protocol_name().to_vec())
             .collect();
 
-        let raw_swarm = RawSwarm::new(transport, topology.local_peer_id().clone());
+        let raw_swarm = RawSwarm::with_limits(transport, topology.local_peer_id().clone(), limits);
 
         Swarm {
             raw_swarm,
@@ -132,6 +147,8 @@ where TBehaviour: NetworkBehaviour<TTopology>,
             topology,
             supported_protocols,
             listened_addrs: SmallVec::new(),
+            banned_peers: Default::default(),
+            banned_ranges: Vec::new(),
         }
     }
 
@@ -141,6 +158,20 @@ where TBehaviour: NetworkBehaviour<TTopology>,
         me.raw_swarm.transport()
     }
 
+    /// Sets how the addresses of a peer are dialed.
+    #[inline]
+    pub fn set_dial_config(me: &mut Self, config: DialConfig) {
+        me.raw_swarm.set_dial_config(config)
+    }
+
+    /// Sets the policy to consult whenever a new connection arrives on a listener.
+    #[inline]
+    pub fn set_incoming_connection_policy<TPolicy>(me: &mut Self, policy: TPolicy)
+    where TPolicy: IncomingConnectionPolicy + Send + 'static
+    {
+        me.raw_swarm.set_incoming_connection_policy(policy)
+    }
+
     /// Starts listening on the given address.
     ///
     /// Returns an error if the address is not supported.
@@ -156,23 +187,35 @@ where TBehaviour: NetworkBehaviour<TTopology>,
 
     /// Tries to dial the given address.
     ///
-    /// Returns an error if the address is not supported.
+    /// Returns an error if the address is not supported or is part of a banned range.
     #[inline]
     pub fn dial_addr(me: &mut Self, addr: Multiaddr) -> Result<(), Multiaddr> {
+        if me.is_addr_banned(&addr) {
+            return Err(addr);
+        }
+
         let handler = me.behaviour.new_handler();
         me.raw_swarm.dial(addr, handler.into_node_handler())
     }
 
     /// Tries to reach the given peer using the elements in the topology.
     ///
-    /// Has no effect if we are already connected to that peer, or if no address is known for the
-    /// peer.
+    /// Has no effect if we are already connected to that peer. If no address is known for the
+    /// peer, or if the peer is banned, `inject_dial_failure` is called on the behaviour.
     #[inline]
     pub fn dial(me: &mut Self, peer_id: PeerId) {
-        let addrs = me.topology.addresses_of_peer(&peer_id);
+        if me.banned_peers.contains(&peer_id) {
+            me.behaviour.inject_dial_failure(&peer_id);
+            return;
+        }
+
+        let mut addrs = me.topology.addresses_of_peer(&peer_id);
+        addrs.retain(|addr| !me.banned_ranges.iter().any(|range| range.contains(addr)));
         let handler = me.behaviour.new_handler().into_node_handler();
-        if let Some(peer) = me.raw_swarm.peer(peer_id).as_not_connected() {
-            let _ = peer.connect_iter(addrs, handler);
+        if let Some(peer) = me.raw_swarm.peer(peer_id.clone()).as_not_connected() {
+            if peer.connect_iter(addrs, handler).is_err() {
+                me.behaviour.inject_dial_failure(&peer_id);
+            }
         }
     }
 
@@ -199,6 +242,102 @@ where TBehaviour: NetworkBehaviour<TTopology>,
     pub fn topology_mut(me: &mut Self) -> &mut TTopology {
         &mut me.topology
     }
+
+    /// Bans a peer.
+    ///
+    /// If we are connected to this peer, the connection is immediately closed and
+    /// `inject_disconnected` is called on the behaviour. A pending dial is interrupted and
+    /// reported through `inject_dial_failure`. Any future connection to or from this peer is
+    /// closed as soon as the handshake reveals its identity and reported to the behaviour as a
+    /// failure, and dialing it fails.
+    pub fn ban_peer(me: &mut Self, peer_id: PeerId) {
+        me.disconnect_banned(&peer_id);
+        me.banned_peers.insert(peer_id);
+    }
+
+    /// Removes a peer from the list of banned peers.
+    ///
+    /// Has no effect if the peer wasn't banned.
+    #[inline]
+    pub fn unban_peer(me: &mut Self, peer_id: &PeerId) {
+        me.banned_peers.remove(peer_id);
+    }
+
+    /// Returns true if the given peer is banned.
+    #[inline]
+    pub fn is_peer_banned(me: &Self, peer_id: &PeerId) -> bool {
+        me.banned_peers.contains(peer_id)
+    }
+
+    /// Bans a range of IP addresses.
+    ///
+    /// The connections to nodes whose address is part of the range are immediately closed.
+    /// Incoming connections from this range are refused and reported through
+    /// `inject_incoming_connection_error`, and addresses of this range are no longer dialed.
+    pub fn ban_addr_range(me: &mut Self, range: IpRange) {
+        let to_disconnect = me.raw_swarm.connections()
+            .filter(|(_, endpoint)| range.contains(endpoint.remote_addr()))
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect::<Vec<_>>();
+        for peer_id in to_disconnect {
+            me.disconnect_banned(&peer_id);
+        }
+
+        if !me.banned_ranges.contains(&range) {
+            me.banned_ranges.push(range);
+        }
+    }
+
+    /// Removes a range of IP addresses from the list of banned ranges.
+    ///
+    /// Has no effect if the range wasn't banned. Only exact matches of previously banned ranges
+    /// are removed.
+    #[inline]
+    pub fn unban_addr_range(me: &mut Self, range: &IpRange) {
+        me.banned_ranges.retain(|r| r != range);
+    }
+
+    /// Returns true if the given address is part of a banned range.
+    #[inline]
+    fn is_addr_banned(&self, addr: &Multiaddr) -> bool {
+        self.banned_ranges.iter().any(|range| range.contains(addr))
+    }
+
+    /// Closes a connection that has just been opened with a banned peer or from a banned address,
+    /// and informs the behaviour of the failure.
+    fn refuse_banned(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
+        if let Some(peer) = self.raw_swarm.peer(peer_id.clone()).as_connected() {
+            peer.close();
+        }
+
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "peer or address is banned");
+        match endpoint {
+            ConnectedPoint::Dialer { address } => {
+                self.behaviour.inject_addr_reach_failure(Some(&peer_id), &address, &error);
+                self.behaviour.inject_dial_failure(&peer_id);
+            },
+            ConnectedPoint::Listener { listen_addr, send_back_addr } => {
+                self.behaviour.inject_incoming_connection_error(&listen_addr, &send_back_addr, &error);
+            },
+        }
+    }
+
+    /// Closes the connection or the connection attempt to a peer that has been banned, and
+    /// informs the behaviour.
+    fn disconnect_banned(&mut self, peer_id: &PeerId) {
+        match self.raw_swarm.peer(peer_id.clone()) {
+            Peer::Connected(peer) => {
+                let endpoint = peer.endpoint().clone();
+                peer.close();
+                self.behaviour.inject_disconnected(peer_id, endpoint);
+            },
+            Peer::PendingConnect(peer) => {
+                peer.interrupt();
+                self.behaviour.inject_dial_failure(peer_id);
+            },
+            Peer::NotConnected(_) => {},
+        }
+    }
 }
 
 impl<TTransport, TBehaviour, TMuxer, TTopology> Stream for Swarm<TTransport, TBehaviour, TTopology>
@@ -206,7 +345,7 @@ where TBehaviour: NetworkBehaviour<TTopology>,
       TMuxer: StreamMuxer + Send + Sync + 'static,
       <TMuxer as StreamMuxer>::OutboundSubstream: Send + 'static,
       <TMuxer as StreamMuxer>::Substream: Send + 'static,
-      TTransport: Transport<Output = (PeerId, TMuxer)> + Clone,
+      TTransport: Transport<Output = (PeerId, TMuxer)> + Clone + Send + 'static,
       TTransport::Listener: Send + 'static,
       TTransport::ListenerUpgrade: Send + 'static,
       TTransport::Dial: Send + 'static,
@@ -243,7 +382,14 @@ where TBehaviour: NetworkBehaviour<TTopology>,
                     self.behaviour.inject_node_event(peer_id, event);
                 },
                 Async::Ready(RawSwarmEvent::Connected { peer_id, endpoint }) => {
-                    self.behaviour.inject_connected(peer_id, endpoint);
+                    if let ConnectedPoint::Dialer { ref address } = endpoint {
+                        self.topology.report_dial_success(&peer_id, address);
+                    }
+                    if self.banned_peers.contains(&peer_id) || self.is_addr_banned(endpoint.remote_addr()) {
+                        self.refuse_banned(peer_id, endpoint);
+                    } else {
+                        self.behaviour.inject_connected(peer_id, endpoint);
+                    }
                 },
                 Async::Ready(RawSwarmEvent::NodeClosed { peer_id, endpoint }) |
                 Async::Ready(RawSwarmEvent::NodeError { peer_id, endpoint, .. }) => {
@@ -251,16 +397,50 @@ where TBehaviour: NetworkBehaviour<TTopology>,
                 },
                 Async::Ready(RawSwarmEvent::Replaced { peer_id, closed_endpoint, endpoint }) => {
                     self.behaviour.inject_disconnected(&peer_id, closed_endpoint);
-                    self.behaviour.inject_connected(peer_id, endpoint);
+                    if let ConnectedPoint::Dialer { ref address } = endpoint {
+                        self.topology.report_dial_success(&peer_id, address);
+                    }
+                    if self.banned_peers.contains(&peer_id) || self.is_addr_banned(endpoint.remote_addr()) {
+                        self.refuse_banned(peer_id, endpoint);
+                    } else {
+                        self.behaviour.inject_connected(peer_id, endpoint);
+                    }
                 },
                 Async::Ready(RawSwarmEvent::IncomingConnection(incoming)) => {
-                    let handler = self.behaviour.new_handler();
-                    incoming.accept(handler.into_node_handler());
+                    if self.is_addr_banned(incoming.send_back_addr()) {
+                        // Dropping the event without accepting it closes the connection.
+                        let (listen_addr, send_back_addr) = (incoming.listen_addr().clone(), incoming.send_back_addr().clone());
+                        drop(incoming);
+                        let error = io::Error::new(io::ErrorKind::PermissionDenied, "address is banned");
+                        self.behaviour.inject_incoming_connection_error(&listen_addr, &send_back_addr, &error);
+                    } else {
+                        let handler = self.behaviour.new_handler();
+                        incoming.accept(handler.into_node_handler());
+                    }
+                },
+                Async::Ready(RawSwarmEvent::ListenerClosed { listen_addr, .. }) => {
+                    self.listened_addrs.retain(|a| a != &listen_addr);
+                    self.behaviour.inject_listener_closed(&listen_addr);
+                },
+                Async::Ready(RawSwarmEvent::IncomingConnectionError { listen_addr, send_back_addr, error }) => {
+                    self.behaviour.inject_incoming_connection_error(&listen_addr, &send_back_addr, &error);
+                },
+                Async::Ready(RawSwarmEvent::DialError { peer_id, multiaddr, failed_addrs, error, .. }) => {
+                    for (addr, error) in &failed_addrs {
+                        self.topology.report_dial_failure(&peer_id, addr);
+                        self.behaviour.inject_addr_reach_failure(Some(&peer_id), addr, error);
+                    }
+                    // The attempt can be aborted for a reason unrelated to a specific address.
+                    if !failed_addrs.iter().any(|(addr, _)| addr == &multiaddr) {
+                        self.behaviour.inject_addr_reach_failure(Some(&peer_id), &multiaddr, &error);
+                    }
+                    // The whole attempt is over, even if some addresses haven't been tried.
+                    self.behaviour.inject_dial_failure(&peer_id);
+                },
+                Async::Ready(RawSwarmEvent::UnknownPeerDialError { multiaddr, error, .. }) |
+                Async::Ready(RawSwarmEvent::UnknownPeerConnectionRefused { multiaddr, error, .. }) => {
+                    self.behaviour.inject_addr_reach_failure(None, &multiaddr, &error);
                 },
-                Async::Ready(RawSwarmEvent::ListenerClosed { .. }) => {},
-                Async::Ready(RawSwarmEvent::IncomingConnectionError { .. }) => {},
-                Async::Ready(RawSwarmEvent::DialError { .. }) => {},
-                Async::Ready(RawSwarmEvent::UnknownPeerDialError { .. }) => {},
             }
 
             let behaviour_poll = {
@@ -281,7 +461,9 @@ where TBehaviour: NetworkBehaviour<TTopology>,
                     return Ok(Async::Ready(Some(event)));
                 },
                 Async::Ready(NetworkBehaviourAction::DialAddress { address }) => {
-                    let _ = Swarm::dial_addr(self, address);
+                    if let Err(address) = Swarm::dial_addr(self, address) {
+                        debug!("Ignoring dial request for unsupported or banned address {}", address);
+                    }
                 },
                 Async::Ready(NetworkBehaviourAction::DialPeer { peer_id }) => {
                     Swarm::dial(self, peer_id)
@@ -330,6 +512,32 @@ pub trait NetworkBehaviour<TTopology> {
         event: <Self::ProtocolsHandler as ProtocolsHandler>::OutEvent
     );
 
+    /// Indicates to the behaviour that we tried to reach an address, but failed.
+    ///
+    /// If we were trying to reach a specific node, its ID is passed as parameter. If this is the
+    /// last address to attempt for the given node, then `inject_dial_failure` is called afterwards.
+    fn inject_addr_reach_failure(&mut self, _peer_id: Option<&PeerId>, _addr: &Multiaddr, _error: &dyn error::Error) {
+    }
+
+    /// Indicates to the behaviour that we tried to dial all the addresses known for a node, but
+    /// failed.
+    ///
+    /// This is also called if the swarm was asked to reach a node for which no address is known.
+    /// Any pending request for this node should be considered as failed.
+    fn inject_dial_failure(&mut self, _peer_id: &PeerId) {
+    }
+
+    /// Indicates to the behaviour that a connection arrived on one of our listeners, but that an
+    /// error happened while negotiating it.
+    fn inject_incoming_connection_error(&mut self, _listen_addr: &Multiaddr, _send_back_addr: &Multiaddr, _error: &dyn error::Error) {
+    }
+
+    /// Indicates to the behaviour that one of our listeners has been closed.
+    ///
+    /// The address is no longer part of `PollParameters::listened_addresses`.
+    fn inject_listener_closed(&mut self, _listen_addr: &Multiaddr) {
+    }
+
     /// Polls for things that swarm should do.
     ///
     /// This API mimics the API of the `Stream` trait.
@@ -440,3 +648,269 @@ pub enum NetworkBehaviourAction<TInEvent, TOutEvent> {
         address: Multiaddr,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::node::Substream;
+    use crate::protocols_handler::{DummyProtocolsHandler, ProtocolsHandler};
+    use crate::tests::dummy_muxer::DummyMuxer;
+    use crate::tests::dummy_transport::{DummyTransport, ListenerState};
+    use crate::topology::MemoryTopology;
+    use crate::{IpRange, Multiaddr, PeerId, PublicKey};
+    use futures::{future, prelude::*};
+    use parking_lot::Mutex;
+    use std::{error, sync::Arc};
+    use super::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters, Swarm};
+    use tokio::runtime::Runtime;
+    use void::{self, Void};
+
+    /// Behaviour that records the errors reported by the swarm.
+    #[derive(Default)]
+    struct ErrorRecorder {
+        connected: Vec<PeerId>,
+        addr_reach_failures: Vec<(Option<PeerId>, Multiaddr)>,
+        dial_failures: Vec<PeerId>,
+        incoming_connection_errors: Vec<(Multiaddr, Multiaddr)>,
+        closed_listeners: Vec<Multiaddr>,
+    }
+
+    impl<TTopology> NetworkBehaviour<TTopology> for ErrorRecorder {
+        type ProtocolsHandler = DummyProtocolsHandler<Substream<DummyMuxer>>;
+        type OutEvent = Void;
+
+        fn new_handler(&mut self) -> Self::ProtocolsHandler {
+            DummyProtocolsHandler::default()
+        }
+
+        fn inject_connected(&mut self, peer_id: PeerId, _: ConnectedPoint) {
+            self.connected.push(peer_id);
+        }
+
+        fn inject_disconnected(&mut self, _: &PeerId, _: ConnectedPoint) {}
+
+        fn inject_node_event(&mut self, _: PeerId, event: <Self::ProtocolsHandler as ProtocolsHandler>::OutEvent) {
+            void::unreachable(event)
+        }
+
+        fn inject_addr_reach_failure(&mut self, peer_id: Option<&PeerId>, addr: &Multiaddr, _: &dyn error::Error) {
+            self.addr_reach_failures.push((peer_id.cloned(), addr.clone()));
+        }
+
+        fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+            self.dial_failures.push(peer_id.clone());
+        }
+
+        fn inject_incoming_connection_error(&mut self, listen_addr: &Multiaddr, send_back_addr: &Multiaddr, _: &dyn error::Error) {
+            self.incoming_connection_errors.push((listen_addr.clone(), send_back_addr.clone()));
+        }
+
+        fn inject_listener_closed(&mut self, listen_addr: &Multiaddr) {
+            self.closed_listeners.push(listen_addr.clone());
+        }
+
+        fn poll(&mut self, _: &mut PollParameters<TTopology>) -> Async<NetworkBehaviourAction<<Self::ProtocolsHandler as ProtocolsHandler>::InEvent, Self::OutEvent>> {
+            Async::NotReady
+        }
+    }
+
+    type TestSwarm = Swarm<DummyTransport, ErrorRecorder, MemoryTopology>;
+
+    fn swarm(transport: DummyTransport) -> Arc<Mutex<TestSwarm>> {
+        let topology = MemoryTopology::empty(PublicKey::Ed25519(vec![1; 32]));
+        Arc::new(Mutex::new(Swarm::new(transport, ErrorRecorder::default(), topology)))
+    }
+
+    /// Polls the swarm until `condition` holds for its behaviour.
+    fn poll_until<F>(swarm: &Arc<Mutex<TestSwarm>>, condition: F)
+    where F: Fn(&ErrorRecorder) -> bool + Send + Sync + 'static
+    {
+        let condition = Arc::new(condition);
+        let mut rt = Runtime::new().unwrap();
+        loop {
+            let swarm_fut = swarm.clone();
+            let condition = condition.clone();
+            let done = rt.block_on(future::poll_fn(move || -> Poll<bool, ()> {
+                let mut swarm = swarm_fut.lock();
+                if let Async::Ready(Some(event)) = swarm.poll().expect("the swarm never errors") {
+                    void::unreachable(event)
+                }
+                let behaviour: &ErrorRecorder = &swarm;
+                Ok(Async::Ready((*condition)(behaviour)))
+            })).expect("tokio works");
+
+            if done {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn dialing_peer_without_address_reports_dial_failure() {
+        let swarm = swarm(DummyTransport::new());
+        let peer_id = PeerId::random();
+        Swarm::dial(&mut *swarm.lock(), peer_id.clone());
+        assert_eq!(swarm.lock().dial_failures, vec![peer_id]);
+    }
+
+    #[test]
+    fn unreachable_peer_reports_addr_reach_and_dial_failures() {
+        let mut transport = DummyTransport::new();
+        transport.make_dial_fail();
+        let swarm = swarm(transport);
+        let peer_id = PeerId::random();
+        let addr = "/memory".parse::<Multiaddr>().unwrap();
+        {
+            let mut swarm = swarm.lock();
+            Swarm::topology_mut(&mut *swarm).add_address(peer_id.clone(), addr.clone());
+            Swarm::dial(&mut *swarm, peer_id.clone());
+        }
+
+        poll_until(&swarm, |behaviour| !behaviour.dial_failures.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.addr_reach_failures, vec![(Some(peer_id.clone()), addr)]);
+        assert_eq!(swarm.dial_failures, vec![peer_id]);
+    }
+
+    #[test]
+    fn every_dead_address_is_reported() {
+        let mut transport = DummyTransport::new();
+        transport.make_dial_fail();
+        let swarm = swarm(transport);
+        let peer_id = PeerId::random();
+        let addrs = (1..4)
+            .map(|port| format!("/ip4/127.0.0.1/tcp/{}", port).parse::<Multiaddr>().unwrap())
+            .collect::<Vec<_>>();
+        {
+            let mut swarm = swarm.lock();
+            for addr in &addrs {
+                Swarm::topology_mut(&mut *swarm).add_address(peer_id.clone(), addr.clone());
+            }
+            Swarm::dial(&mut *swarm, peer_id.clone());
+        }
+
+        poll_until(&swarm, |behaviour| !behaviour.dial_failures.is_empty());
+        let swarm = swarm.lock();
+        let mut reported = swarm.addr_reach_failures.iter()
+            .map(|(peer, addr)| {
+                assert_eq!(peer.as_ref(), Some(&peer_id));
+                addr.to_string()
+            })
+            .collect::<Vec<_>>();
+        reported.sort();
+        assert_eq!(reported, addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>());
+        assert_eq!(swarm.dial_failures, vec![peer_id]);
+    }
+
+    #[test]
+    fn unreachable_address_reports_addr_reach_failure() {
+        let mut transport = DummyTransport::new();
+        transport.make_dial_fail();
+        let swarm = swarm(transport);
+        let addr = "/memory".parse::<Multiaddr>().unwrap();
+        Swarm::dial_addr(&mut *swarm.lock(), addr.clone()).unwrap();
+
+        poll_until(&swarm, |behaviour| !behaviour.addr_reach_failures.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.addr_reach_failures, vec![(None, addr)]);
+        assert!(swarm.dial_failures.is_empty());
+    }
+
+    #[test]
+    fn failed_incoming_connection_is_reported() {
+        let mut transport = DummyTransport::new();
+        transport.set_initial_listener_state(ListenerState::UpgradeError);
+        let swarm = swarm(transport);
+        let addr = "/memory".parse::<Multiaddr>().unwrap();
+        Swarm::listen_on(&mut *swarm.lock(), addr.clone()).unwrap();
+
+        poll_until(&swarm, |behaviour| !behaviour.incoming_connection_errors.is_empty());
+        assert_eq!(swarm.lock().incoming_connection_errors[0], (addr.clone(), addr));
+    }
+
+    #[test]
+    fn closed_listener_is_reported() {
+        let mut transport = DummyTransport::new();
+        transport.set_initial_listener_state(ListenerState::Ok(Async::Ready(None)));
+        let swarm = swarm(transport);
+        let addr = "/memory".parse::<Multiaddr>().unwrap();
+        Swarm::listen_on(&mut *swarm.lock(), addr.clone()).unwrap();
+
+        poll_until(&swarm, |behaviour| !behaviour.closed_listeners.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.closed_listeners, vec![addr]);
+        assert_eq!(Swarm::listeners(&*swarm).count(), 0);
+    }
+
+    #[test]
+    fn banned_peer_is_not_dialed() {
+        let swarm = swarm(DummyTransport::new());
+        let peer_id = PeerId::random();
+        {
+            let mut swarm = swarm.lock();
+            Swarm::topology_mut(&mut *swarm).add_address(peer_id.clone(), "/memory".parse().unwrap());
+            Swarm::ban_peer(&mut *swarm, peer_id.clone());
+            Swarm::dial(&mut *swarm, peer_id.clone());
+        }
+        assert_eq!(swarm.lock().dial_failures, vec![peer_id]);
+    }
+
+    #[test]
+    fn peer_banned_while_dialing_reports_dial_failure() {
+        let mut transport = DummyTransport::new();
+        let peer_id = PeerId::random();
+        transport.set_next_peer_id(&peer_id);
+        let swarm = swarm(transport);
+        {
+            let mut swarm = swarm.lock();
+            Swarm::topology_mut(&mut *swarm).add_address(peer_id.clone(), "/memory".parse().unwrap());
+            Swarm::dial(&mut *swarm, peer_id.clone());
+            Swarm::ban_peer(&mut *swarm, peer_id.clone());
+        }
+
+        poll_until(&swarm, |behaviour| !behaviour.dial_failures.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.dial_failures, vec![peer_id]);
+        assert!(swarm.connected.is_empty());
+    }
+
+    #[test]
+    fn range_banned_while_dialing_reports_dial_failure() {
+        let mut transport = DummyTransport::new();
+        let peer_id = PeerId::random();
+        transport.set_next_peer_id(&peer_id);
+        let swarm = swarm(transport);
+        let addr = "/ip4/10.0.0.1/tcp/1".parse::<Multiaddr>().unwrap();
+        {
+            let mut swarm = swarm.lock();
+            Swarm::topology_mut(&mut *swarm).add_address(peer_id.clone(), addr.clone());
+            Swarm::dial(&mut *swarm, peer_id.clone());
+            let range = IpRange::new("10.0.0.0".parse().unwrap(), 8).unwrap();
+            Swarm::ban_addr_range(&mut *swarm, range);
+        }
+
+        poll_until(&swarm, |behaviour| !behaviour.dial_failures.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.addr_reach_failures, vec![(Some(peer_id.clone()), addr)]);
+        assert_eq!(swarm.dial_failures, vec![peer_id]);
+        assert!(swarm.connected.is_empty());
+    }
+
+    #[test]
+    fn incoming_connection_from_banned_range_is_reported() {
+        let mut transport = DummyTransport::new();
+        let peer_id = PeerId::random();
+        transport.set_initial_listener_state(ListenerState::Ok(Async::Ready(Some((peer_id, DummyMuxer::new())))));
+        let swarm = swarm(transport);
+        let addr = "/ip4/10.0.0.1/tcp/1".parse::<Multiaddr>().unwrap();
+        {
+            let mut swarm = swarm.lock();
+            Swarm::listen_on(&mut *swarm, addr.clone()).unwrap();
+            Swarm::ban_addr_range(&mut *swarm, IpRange::from_multiaddr(&addr, 8).unwrap());
+        }
+
+        poll_until(&swarm, |behaviour| !behaviour.incoming_connection_errors.is_empty());
+        let swarm = swarm.lock();
+        assert_eq!(swarm.incoming_connection_errors[0], (addr.clone(), addr));
+        assert!(swarm.connected.is_empty());
+    }
+}
@@ -34,6 +34,8 @@ use tests::dummy_muxer::DummyMuxer;
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum ListenerState {
     Ok(Async<Option<(PeerId, DummyMuxer)>>),
+    /// Produces a single incoming connection whose upgrade fails.
+    UpgradeError,
     Error
 }
 
@@ -95,6 +97,12 @@ impl Transport for DummyTransport {
                     }
                 })
             }
+            ListenerState::UpgradeError => {
+                let upgrade = future::err(io::Error::new(io::ErrorKind::Other, "upgrade error"));
+                let stream = stream::once::<_, io::Error>(Ok((upgrade, addr)))
+                    .chain(stream::poll_fn(|| Ok(Async::NotReady)));
+                Ok((Box::new(stream), addr2))
+            }
             ListenerState::Error => Err((self, addr2)),
         }
     }
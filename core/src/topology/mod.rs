@@ -18,9 +18,16 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::collections::HashMap;
+use futures::sync::mpsc;
+use multihash::Multihash;
+use smallvec::SmallVec;
+use std::{cmp, collections::HashMap, time::Duration, time::Instant};
 use {Multiaddr, PeerId, PublicKey};
 
+pub use self::persistent::{AddressSource, PeerInfo, PersistentTopology};
+
+mod persistent;
+
 /// Storage for the network topology.
 ///
 /// The topology should also store information about the local node, including its public key, its
@@ -43,15 +50,141 @@ pub trait Topology {
 
     /// Returns the public key of the local node.
     fn local_public_key(&self) -> &PublicKey;
+
+    /// Reports that dialing `addr` successfully reached `peer`.
+    ///
+    /// The default implementation doesn't do anything.
+    #[inline]
+    fn report_dial_success(&mut self, _peer: &PeerId, _addr: &Multiaddr) {}
+
+    /// Reports that dialing `addr` failed to reach `peer`.
+    ///
+    /// The default implementation doesn't do anything.
+    #[inline]
+    fn report_dial_failure(&mut self, _peer: &PeerId, _addr: &Multiaddr) {}
+
+    /// Returns the peers that are known to support the given protocol.
+    ///
+    /// The default implementation doesn't know about protocols and returns an empty list.
+    #[inline]
+    fn peers_supporting_protocol(&self, _protocol: &[u8]) -> Vec<PeerId> {
+        Vec::new()
+    }
+
+    /// Returns a stream of the changes made to the topology from now on.
+    ///
+    /// The stream should be polled regularly. Implementations are free to drop events rather
+    /// than buffering them indefinitely for a subscriber that doesn't keep up.
+    ///
+    /// The default implementation doesn't report changes, and returns a stream that ends
+    /// immediately.
+    fn subscribe(&mut self) -> mpsc::Receiver<TopologyEvent> {
+        mpsc::channel(0).1
+    }
+}
+
+/// Change made to a topology, as reported to the streams returned by `Topology::subscribe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologyEvent {
+    /// A peer that had no address now has one.
+    ///
+    /// > **Note**: The peer can be the local node.
+    PeerAdded(PeerId),
+    /// The last address of a peer has been removed.
+    PeerRemoved(PeerId),
+    /// A new address of a peer has been added.
+    AddressAdded {
+        /// The peer the address belongs to.
+        peer: PeerId,
+        /// The new address.
+        addr: Multiaddr,
+    },
+    /// An address of a peer has expired or has been removed.
+    AddressRemoved {
+        /// The peer the address belonged to.
+        peer: PeerId,
+        /// The removed address.
+        addr: Multiaddr,
+    },
+    /// We learned which protocols a peer supports.
+    ProtocolsLearned {
+        /// The peer the protocols are supported by.
+        peer: PeerId,
+        /// The full list of protocols the peer supports.
+        protocols: Vec<String>,
+    },
 }
 
 /// Topology of the network stored in memory.
 pub struct MemoryTopology {
-    list: HashMap<PeerId, Vec<Multiaddr>>,
+    list: HashMap<PeerId, Vec<AddressRecord>>,
     local_peer_id: PeerId,
     local_public_key: PublicKey,
+    /// For each key, the peers that provide it and when their registration expires.
+    providers: HashMap<Multihash, SmallVec<[(PeerId, Instant); 8]>>,
+    /// How long a provider stays registered after a call to `add_provider`.
+    provider_ttl: Duration,
+    /// Maximum number of providers registered for each key.
+    max_providers_per_key: usize,
+    /// Maximum number of keys we store providers for.
+    max_provided_keys: usize,
+    /// When to next remove the expired providers.
+    next_providers_expiration: Instant,
+    /// How long we wait before dialing an address again after its first failure.
+    initial_backoff: Duration,
+    /// Upper bound of the delay before dialing an address again.
+    max_backoff: Duration,
+    /// Protocols supported by each peer, as reported with `set_protocols`.
+    protocols: HashMap<PeerId, Vec<String>>,
+    /// Senders of the streams returned by `subscribe`. Closed ones are removed when notifying.
+    subscribers: Vec<mpsc::Sender<TopologyEvent>>,
+}
+
+/// An address of a peer, along with the outcome of the previous dials to it.
+#[derive(Debug, Clone)]
+struct AddressRecord {
+    addr: Multiaddr,
+    /// Number of times dialing this address reached the peer.
+    successes: u32,
+    /// Number of failures since the last success.
+    consecutive_failures: u32,
+    /// If `Some`, the address shouldn't be dialed before this moment.
+    backoff_until: Option<Instant>,
+}
+
+impl AddressRecord {
+    #[inline]
+    fn new(addr: Multiaddr) -> AddressRecord {
+        AddressRecord {
+            addr,
+            successes: 0,
+            consecutive_failures: 0,
+            backoff_until: None,
+        }
+    }
+
+    /// Returns true if the address is in backoff at the moment `now`.
+    #[inline]
+    fn in_backoff(&self, now: Instant) -> bool {
+        self.backoff_until.map(|until| until > now).unwrap_or(false)
+    }
 }
 
+/// Default value for the time-to-live of providers.
+const DEFAULT_PROVIDER_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Default value for the maximum number of providers per key.
+const DEFAULT_MAX_PROVIDERS_PER_KEY: usize = 20;
+/// Default value for the maximum number of keys we store providers for.
+const DEFAULT_MAX_PROVIDED_KEYS: usize = 16 * 1024;
+/// Interval between two removals of the expired providers.
+const PROVIDERS_EXPIRATION_INTERVAL: Duration = Duration::from_secs(60);
+/// Default value for the backoff after the first failure to dial an address.
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+/// Default value for the upper bound of the backoff of an address.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+/// Number of events buffered for each subscriber before new events are dropped.
+const SUBSCRIBER_BUFFER: usize = 256;
+
 impl MemoryTopology {
     /// Creates an empty topology.
     #[inline]
@@ -62,9 +195,116 @@ impl MemoryTopology {
             list: Default::default(),
             local_peer_id,
             local_public_key: pubkey,
+            providers: Default::default(),
+            provider_ttl: DEFAULT_PROVIDER_TTL,
+            max_providers_per_key: DEFAULT_MAX_PROVIDERS_PER_KEY,
+            max_provided_keys: DEFAULT_MAX_PROVIDED_KEYS,
+            next_providers_expiration: Instant::now() + PROVIDERS_EXPIRATION_INTERVAL,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            protocols: Default::default(),
+            subscribers: Vec::new(),
         }
     }
 
+    /// Sets the backoff applied to an address after a failure to dial it.
+    ///
+    /// The backoff starts at `initial` and doubles after each consecutive failure, up to `max`.
+    /// Only applies to the failures reported afterwards.
+    #[inline]
+    pub fn set_dial_backoff(&mut self, initial: Duration, max: Duration) {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+    }
+
+    /// Sets how long a provider stays registered after a call to `add_provider`.
+    ///
+    /// Only applies to the providers added afterwards.
+    #[inline]
+    pub fn set_provider_ttl(&mut self, ttl: Duration) {
+        self.provider_ttl = ttl;
+    }
+
+    /// Sets the maximum number of providers registered for each key, and the maximum number of
+    /// keys we store providers for.
+    ///
+    /// Once a limit is reached, new providers are ignored until existing ones expire. The local
+    /// node is never ignored.
+    #[inline]
+    pub fn set_provider_limits(&mut self, max_providers_per_key: usize, max_provided_keys: usize) {
+        self.max_providers_per_key = max_providers_per_key;
+        self.max_provided_keys = max_provided_keys;
+    }
+
+    /// Registers `peer_id` as a provider of `key`, for the duration of the provider TTL.
+    ///
+    /// If `peer_id` is already registered, its registration is extended.
+    pub fn add_provider(&mut self, key: Multihash, peer_id: PeerId) {
+        let now = Instant::now();
+        if now >= self.next_providers_expiration {
+            self.remove_expired_providers();
+        }
+
+        let is_local = peer_id == self.local_peer_id;
+        if !self.providers.contains_key(&key) && self.providers.len() >= self.max_provided_keys {
+            self.remove_expired_providers();
+            if self.providers.len() >= self.max_provided_keys && !is_local {
+                return;
+            }
+        }
+
+        let expires = now + self.provider_ttl;
+        let providers = self.providers.entry(key).or_insert_with(SmallVec::new);
+        if let Some(entry) = providers.iter_mut().find(|(p, _)| p == &peer_id) {
+            entry.1 = expires;
+            return;
+        }
+
+        providers.retain(|(_, exp)| *exp > now);
+        if providers.len() >= self.max_providers_per_key && !is_local {
+            return;
+        }
+        providers.push((peer_id, expires));
+    }
+
+    /// Unregisters `peer_id` as a provider of `key`.
+    pub fn remove_provider(&mut self, key: &Multihash, peer_id: &PeerId) {
+        let is_empty = match self.providers.get_mut(key) {
+            Some(providers) => {
+                providers.retain(|(p, _)| p != peer_id);
+                providers.is_empty()
+            },
+            None => return,
+        };
+
+        if is_empty {
+            self.providers.remove(key);
+        }
+    }
+
+    /// Returns the peers that provide `key` and whose registration hasn't expired.
+    pub fn providers(&self, key: &Multihash) -> impl Iterator<Item = &PeerId> {
+        let now = Instant::now();
+        self.providers
+            .get(key)
+            .into_iter()
+            .flat_map(|list| list.iter())
+            .filter(move |(_, expires)| *expires > now)
+            .map(|(peer_id, _)| peer_id)
+    }
+
+    /// Removes all the providers whose registration has expired.
+    ///
+    /// This is done automatically from time to time when calling `add_provider`.
+    pub fn remove_expired_providers(&mut self) {
+        let now = Instant::now();
+        for providers in self.providers.values_mut() {
+            providers.retain(|(_, expires)| *expires > now);
+        }
+        self.providers.retain(|_, providers| !providers.is_empty());
+        self.next_providers_expiration = now + PROVIDERS_EXPIRATION_INTERVAL;
+    }
+
     /// Returns true if the topology is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -74,9 +314,78 @@ impl MemoryTopology {
     /// Adds an address to the topology.
     #[inline]
     pub fn add_address(&mut self, peer: PeerId, addr: Multiaddr) {
-        let addrs = self.list.entry(peer).or_insert_with(|| Vec::new());
-        if addrs.iter().all(|a| a != &addr) {
-            addrs.push(addr);
+        let is_new_peer = !self.list.contains_key(&peer);
+        {
+            let addrs = self.list.entry(peer.clone()).or_insert_with(|| Vec::new());
+            if addrs.iter().any(|a| a.addr == addr) {
+                return;
+            }
+            addrs.push(AddressRecord::new(addr.clone()));
+        }
+
+        if is_new_peer {
+            self.notify(TopologyEvent::PeerAdded(peer.clone()));
+        }
+        self.notify(TopologyEvent::AddressAdded { peer, addr });
+    }
+
+    /// Removes an address from the topology.
+    ///
+    /// The peer is removed as well if it doesn't have any address left, in which case its
+    /// protocols are forgotten.
+    pub fn remove_address(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        let is_empty = match self.list.get_mut(peer) {
+            Some(addrs) => {
+                let len = addrs.len();
+                addrs.retain(|r| &r.addr != addr);
+                if addrs.len() == len {
+                    return;
+                }
+                addrs.is_empty()
+            },
+            None => return,
+        };
+
+        self.notify(TopologyEvent::AddressRemoved { peer: peer.clone(), addr: addr.clone() });
+        if is_empty {
+            self.list.remove(peer);
+            self.protocols.remove(peer);
+            self.notify(TopologyEvent::PeerRemoved(peer.clone()));
+        }
+    }
+
+    /// Replaces the list of protocols that a peer supports.
+    pub fn set_protocols<TIter>(&mut self, peer: &PeerId, protocols: TIter)
+    where TIter: IntoIterator<Item = String>
+    {
+        let protocols = protocols.into_iter().collect::<Vec<_>>();
+        if self.protocols.get(peer) == Some(&protocols) {
+            return;
+        }
+
+        self.protocols.insert(peer.clone(), protocols.clone());
+        self.notify(TopologyEvent::ProtocolsLearned { peer: peer.clone(), protocols });
+    }
+
+    /// Returns the protocols that a peer supports, as reported with `set_protocols`.
+    #[inline]
+    pub fn protocols_of_peer(&self, peer: &PeerId) -> impl Iterator<Item = &str> {
+        self.protocols.get(peer).into_iter().flat_map(|list| list.iter()).map(|p| p.as_str())
+    }
+
+    /// Sends an event to all the streams returned by `subscribe`.
+    ///
+    /// Subscribers whose buffer is full miss the event, and the ones that have been dropped are
+    /// removed.
+    fn notify(&mut self, event: TopologyEvent) {
+        for n in (0..self.subscribers.len()).rev() {
+            let mut sender = self.subscribers.swap_remove(n);
+            match sender.try_send(event.clone()) {
+                Ok(()) => {},
+                Err(ref err) if err.is_disconnected() => continue,
+                Err(_) => trace!("Topology subscriber is full, dropping {:?}", event),
+            }
+            self.subscribers.push(sender);
         }
     }
 
@@ -89,13 +398,34 @@ impl MemoryTopology {
     /// Returns an iterator to all the entries in the topology.
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = (&PeerId, &Multiaddr)> {
-        self.list.iter().flat_map(|(p, l)| l.iter().map(move |ma| (p, ma)))
+        self.list.iter().flat_map(|(p, l)| l.iter().map(move |r| (p, &r.addr)))
     }
 }
 
+/// Returns the backoff to apply to an address after the given number of consecutive failures.
+fn backoff(initial: Duration, max: Duration, consecutive_failures: u32) -> Duration {
+    debug_assert!(consecutive_failures >= 1);
+    let shift = cmp::min(consecutive_failures - 1, 31);
+    initial.checked_mul(1 << shift)
+        .map(|backoff| cmp::min(backoff, max))
+        .unwrap_or(max)
+}
+
 impl Topology for MemoryTopology {
+    /// Returns the addresses of the peer that aren't in backoff, the most reliable ones first.
     fn addresses_of_peer(&mut self, peer: &PeerId) -> Vec<Multiaddr> {
-        self.list.get(peer).map(|v| v.clone()).unwrap_or(Vec::new())
+        let now = Instant::now();
+        let mut records = match self.list.get(peer) {
+            Some(records) => records.iter().filter(|r| !r.in_backoff(now)).collect::<Vec<_>>(),
+            None => return Vec::new(),
+        };
+
+        // The sort is stable, so addresses with the same record stay in insertion order.
+        records.sort_by(|a, b| {
+            a.consecutive_failures.cmp(&b.consecutive_failures)
+                .then(b.successes.cmp(&a.successes))
+        });
+        records.into_iter().map(|r| r.addr.clone()).collect()
     }
 
     fn add_local_external_addrs<TIter>(&mut self, addrs: TIter)
@@ -116,4 +446,210 @@ impl Topology for MemoryTopology {
     fn local_public_key(&self) -> &PublicKey {
         &self.local_public_key
     }
+
+    fn report_dial_success(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        let record = self.list.get_mut(peer)
+            .and_then(|records| records.iter_mut().find(|r| &r.addr == addr));
+        if let Some(record) = record {
+            record.successes = record.successes.saturating_add(1);
+            record.consecutive_failures = 0;
+            record.backoff_until = None;
+        }
+    }
+
+    fn report_dial_failure(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        let (initial, max) = (self.initial_backoff, self.max_backoff);
+        let record = self.list.get_mut(peer)
+            .and_then(|records| records.iter_mut().find(|r| &r.addr == addr));
+        if let Some(record) = record {
+            record.consecutive_failures = record.consecutive_failures.saturating_add(1);
+            let delay = backoff(initial, max, record.consecutive_failures);
+            record.backoff_until = Some(Instant::now() + delay);
+        }
+    }
+
+    fn peers_supporting_protocol(&self, protocol: &[u8]) -> Vec<PeerId> {
+        self.protocols.iter()
+            .filter(|(_, list)| list.iter().any(|p| p.as_bytes() == protocol))
+            .map(|(peer, _)| peer.clone())
+            .collect()
+    }
+
+    /// Returns a stream of the changes made to the topology from now on.
+    ///
+    /// Up to 256 events are buffered for the stream. Further events are dropped until it is
+    /// polled again.
+    fn subscribe(&mut self) -> mpsc::Receiver<TopologyEvent> {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_BUFFER);
+        self.subscribers.push(tx);
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backoff, MemoryTopology, Topology, TopologyEvent, SUBSCRIBER_BUFFER};
+    use futures::{Future, Stream};
+    use multihash::{encode, Hash};
+    use std::time::Duration;
+    use {Multiaddr, PeerId, PublicKey};
+
+    fn topology() -> MemoryTopology {
+        MemoryTopology::empty(PublicKey::Ed25519(vec![1; 32]))
+    }
+
+    #[test]
+    fn providers_are_returned() {
+        let mut topology = topology();
+        let key = encode(Hash::SHA2256, b"hello").unwrap();
+        let peer = PeerId::random();
+        topology.add_provider(key.clone(), peer.clone());
+        topology.add_provider(key.clone(), peer.clone());
+        assert_eq!(topology.providers(&key).collect::<Vec<_>>(), vec![&peer]);
+        assert_eq!(topology.providers(&encode(Hash::SHA2256, b"other").unwrap()).count(), 0);
+    }
+
+    #[test]
+    fn providers_expire() {
+        let mut topology = topology();
+        topology.set_provider_ttl(Duration::from_secs(0));
+        let key = encode(Hash::SHA2256, b"hello").unwrap();
+        topology.add_provider(key.clone(), PeerId::random());
+        assert_eq!(topology.providers(&key).count(), 0);
+        topology.remove_expired_providers();
+        assert!(topology.providers.is_empty());
+    }
+
+    #[test]
+    fn providers_are_removed() {
+        let mut topology = topology();
+        let key = encode(Hash::SHA2256, b"hello").unwrap();
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+        topology.add_provider(key.clone(), peer1.clone());
+        topology.add_provider(key.clone(), peer2.clone());
+        topology.remove_provider(&key, &peer1);
+        assert_eq!(topology.providers(&key).collect::<Vec<_>>(), vec![&peer2]);
+        topology.remove_provider(&key, &peer2);
+        assert!(topology.providers.is_empty());
+    }
+
+    #[test]
+    fn limits_spare_local_node() {
+        let mut topology = topology();
+        topology.set_provider_limits(1, 1);
+        let key = encode(Hash::SHA2256, b"hello").unwrap();
+        topology.add_provider(key.clone(), PeerId::random());
+        topology.add_provider(key.clone(), PeerId::random());
+        assert_eq!(topology.providers(&key).count(), 1);
+
+        let local = topology.local_peer_id.clone();
+        topology.add_provider(key.clone(), local.clone());
+        assert!(topology.providers(&key).any(|p| p == &local));
+
+        let other_key = encode(Hash::SHA2256, b"other").unwrap();
+        topology.add_provider(other_key.clone(), PeerId::random());
+        assert_eq!(topology.providers(&other_key).count(), 0);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let initial = Duration::from_secs(1);
+        let max = Duration::from_secs(10);
+        assert_eq!(backoff(initial, max, 1), Duration::from_secs(1));
+        assert_eq!(backoff(initial, max, 3), Duration::from_secs(4));
+        assert_eq!(backoff(initial, max, 5), max);
+        assert_eq!(backoff(initial, max, u32::max_value()), max);
+    }
+
+    #[test]
+    fn failing_addresses_are_skipped_then_ranked_last() {
+        let mut topology = topology();
+        let peer = PeerId::random();
+        let a1: Multiaddr = "/ip4/1.2.3.4/tcp/1".parse().unwrap();
+        let a2: Multiaddr = "/ip4/1.2.3.4/tcp/2".parse().unwrap();
+        let a3: Multiaddr = "/ip4/1.2.3.4/tcp/3".parse().unwrap();
+        for addr in vec![a1.clone(), a2.clone(), a3.clone()] {
+            topology.add_address(peer.clone(), addr);
+        }
+
+        topology.report_dial_success(&peer, &a3);
+        topology.report_dial_failure(&peer, &a1);
+        assert_eq!(topology.addresses_of_peer(&peer), vec![a3.clone(), a2.clone()]);
+
+        // Once the backoff is over, the failing address comes back last.
+        topology.set_dial_backoff(Duration::from_secs(0), Duration::from_secs(0));
+        topology.report_dial_failure(&peer, &a1);
+        assert_eq!(topology.addresses_of_peer(&peer), vec![a3.clone(), a2.clone(), a1.clone()]);
+
+        topology.report_dial_success(&peer, &a1);
+        topology.report_dial_success(&peer, &a1);
+        assert_eq!(topology.addresses_of_peer(&peer), vec![a1, a3, a2]);
+    }
+
+    #[test]
+    fn changes_are_notified() {
+        let mut topology = topology();
+        let events = topology.subscribe();
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1".parse().unwrap();
+        topology.add_address(peer.clone(), addr.clone());
+        topology.add_address(peer.clone(), addr.clone());
+        topology.set_protocols(&peer, vec!["/ipfs/kad/1.0.0".to_owned()]);
+        topology.remove_address(&peer, &addr);
+        drop(topology);
+
+        assert_eq!(events.collect().wait().unwrap(), vec![
+            TopologyEvent::PeerAdded(peer.clone()),
+            TopologyEvent::AddressAdded { peer: peer.clone(), addr: addr.clone() },
+            TopologyEvent::ProtocolsLearned { peer: peer.clone(), protocols: vec!["/ipfs/kad/1.0.0".to_owned()] },
+            TopologyEvent::AddressRemoved { peer: peer.clone(), addr },
+            TopologyEvent::PeerRemoved(peer),
+        ]);
+    }
+
+    #[test]
+    fn slow_subscribers_miss_events() {
+        let mut topology = topology();
+        let events = topology.subscribe();
+        drop(topology.subscribe());
+        let peer = PeerId::random();
+        for port in 0..2 * SUBSCRIBER_BUFFER {
+            let addr: Multiaddr = format!("/ip4/1.2.3.4/tcp/{}", port).parse().unwrap();
+            topology.add_address(peer.clone(), addr);
+        }
+        // The dropped subscriber has been removed, and the other one isn't buffering forever.
+        assert_eq!(topology.subscribers.len(), 1);
+        drop(topology);
+
+        let events = events.collect().wait().unwrap();
+        assert!(events.len() <= SUBSCRIBER_BUFFER + 1);
+        assert_eq!(events[0], TopologyEvent::PeerAdded(peer));
+    }
+
+    #[test]
+    fn removed_peers_lose_their_protocols() {
+        let mut topology = topology();
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1".parse().unwrap();
+        topology.add_address(peer.clone(), addr.clone());
+        topology.set_protocols(&peer, vec!["/ipfs/kad/1.0.0".to_owned()]);
+        assert_eq!(topology.peers_supporting_protocol(b"/ipfs/kad/1.0.0"), vec![peer.clone()]);
+
+        topology.remove_address(&peer, &addr);
+        assert!(topology.peers_supporting_protocol(b"/ipfs/kad/1.0.0").is_empty());
+        assert_eq!(topology.protocols_of_peer(&peer).count(), 0);
+    }
+
+    #[test]
+    fn peers_are_queried_by_protocol() {
+        let mut topology = topology();
+        let kad = PeerId::random();
+        let ping = PeerId::random();
+        topology.set_protocols(&kad, vec!["/ipfs/kad/1.0.0".to_owned(), "/ipfs/ping/1.0.0".to_owned()]);
+        topology.set_protocols(&ping, vec!["/ipfs/ping/1.0.0".to_owned()]);
+        assert_eq!(topology.peers_supporting_protocol(b"/ipfs/kad/1.0.0"), vec![kad.clone()]);
+        assert_eq!(topology.peers_supporting_protocol(b"/ipfs/ping/1.0.0").len(), 2);
+        assert!(topology.peers_supporting_protocol(b"/ipfs/id/1.0.0").is_empty());
+        assert_eq!(topology.protocols_of_peer(&ping).collect::<Vec<_>>(), vec!["/ipfs/ping/1.0.0"]);
+    }
 }
@@ -0,0 +1,632 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Topology that stores the peers it knows about on disk, so that they can be reused after a
+//! restart.
+//!
+//! The snapshot is a small line-based text file. The first line is a format header, followed by
+//! one block per peer:
+//!
+//! ```text
+//! libp2p-topology 1
+//! peer <base58 peer ID>
+//! key <base58 of the protobuf encoding of the public key>
+//! protocol <protocol name>
+//! addr <source> <last seen, in seconds since the UNIX epoch> <multiaddr>
+//! ```
+//!
+//! The `key` and `protocol` lines are optional.
+
+use bs58;
+use futures::sync::mpsc;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::{MemoryTopology, Topology, TopologyEvent};
+use multihash::Multihash;
+use {Multiaddr, PeerId, PublicKey};
+
+/// Header of the snapshot files. Must be changed whenever the format changes.
+const SNAPSHOT_HEADER: &str = "libp2p-topology 1";
+/// Default value for how long an address stays in the topology after it was last seen.
+const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Where an address of a `PersistentTopology` comes from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AddressSource {
+    /// Discovered through Kademlia.
+    Kademlia,
+    /// Discovered through mDNS.
+    Mdns,
+    /// Reported by the peer itself through identify.
+    Identify,
+    /// Added by the user. These addresses never become stale.
+    Manual,
+}
+
+impl AddressSource {
+    /// Returns the name of the source in the snapshots.
+    fn as_str(&self) -> &'static str {
+        match *self {
+            AddressSource::Kademlia => "kad",
+            AddressSource::Mdns => "mdns",
+            AddressSource::Identify => "identify",
+            AddressSource::Manual => "manual",
+        }
+    }
+
+    /// Parses the name of a source as written in the snapshots.
+    fn from_str(name: &str) -> Option<AddressSource> {
+        match name {
+            "kad" => Some(AddressSource::Kademlia),
+            "mdns" => Some(AddressSource::Mdns),
+            "identify" => Some(AddressSource::Identify),
+            "manual" => Some(AddressSource::Manual),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a `PersistentTopology` knows about a peer.
+#[derive(Debug, Clone, Default)]
+pub struct PeerInfo {
+    public_key: Option<PublicKey>,
+    protocols: Vec<String>,
+    addresses: Vec<AddressEntry>,
+}
+
+/// An address of a peer, along with where it comes from and when it was last seen.
+#[derive(Debug, Clone)]
+struct AddressEntry {
+    addr: Multiaddr,
+    source: AddressSource,
+    last_seen: SystemTime,
+}
+
+impl PeerInfo {
+    /// Returns the public key of the peer, if known.
+    #[inline]
+    pub fn public_key(&self) -> Option<&PublicKey> {
+        self.public_key.as_ref()
+    }
+
+    /// Returns the protocols that the peer reported supporting.
+    #[inline]
+    pub fn protocols(&self) -> impl Iterator<Item = &str> {
+        self.protocols.iter().map(|p| p.as_str())
+    }
+
+    /// Returns the addresses of the peer, along with their source and when they were last seen.
+    #[inline]
+    pub fn addresses(&self) -> impl Iterator<Item = (&Multiaddr, AddressSource, SystemTime)> {
+        self.addresses.iter().map(|a| (&a.addr, a.source, a.last_seen))
+    }
+
+    /// Returns the last time any of the addresses of the peer was seen.
+    pub fn last_seen(&self) -> Option<SystemTime> {
+        self.addresses.iter().map(|a| a.last_seen).max()
+    }
+}
+
+/// Topology of the network that can be saved to and restored from a file.
+///
+/// Dialing and providers are handled the same way as `MemoryTopology`. In addition, the topology
+/// remembers where each address comes from, when it was last seen, and the public key and
+/// protocols of the peers. Nothing is written to disk until `save()` is called.
+pub struct PersistentTopology {
+    /// Path of the snapshot.
+    path: PathBuf,
+    /// Addresses available for dialing, and providers.
+    memory: MemoryTopology,
+    /// Information about the remote peers. Never contains the local peer.
+    peers: HashMap<PeerId, PeerInfo>,
+    /// How long an address stays in the topology after it was last seen.
+    stale_after: Duration,
+}
+
+impl PersistentTopology {
+    /// Opens the topology saved at `path`, or creates an empty one if the file doesn't exist.
+    ///
+    /// The entries that are already stale are dropped.
+    pub fn open<P>(path: P, pubkey: PublicKey) -> io::Result<PersistentTopology>
+    where P: Into<PathBuf>
+    {
+        let path = path.into();
+        let peers = match File::open(&path) {
+            Ok(file) => read_snapshot(BufReader::new(file))?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut topology = PersistentTopology {
+            path,
+            memory: MemoryTopology::empty(pubkey),
+            peers: HashMap::new(),
+            stale_after: DEFAULT_STALE_AFTER,
+        };
+
+        for (peer, info) in peers {
+            if &peer == topology.memory.local_peer_id() {
+                continue;
+            }
+            for entry in &info.addresses {
+                topology.memory.add_address(peer.clone(), entry.addr.clone());
+            }
+            if !info.protocols.is_empty() {
+                topology.memory.set_protocols(&peer, info.protocols.iter().cloned());
+            }
+            topology.peers.insert(peer, info);
+        }
+
+        topology.remove_stale_entries();
+        Ok(topology)
+    }
+
+    /// Returns the path of the snapshot.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets how long an address stays in the topology after it was last seen.
+    ///
+    /// Only applies to the next call to `remove_stale_entries()` or `save()`.
+    #[inline]
+    pub fn set_stale_after(&mut self, stale_after: Duration) {
+        self.stale_after = stale_after;
+    }
+
+    /// Adds an address of a peer to the topology, or marks it as seen if it is already known.
+    ///
+    /// An address added manually keeps its source when discovered again. Addresses of the local
+    /// node can be dialed like any other but aren't saved.
+    pub fn add_address(&mut self, peer: PeerId, addr: Multiaddr, source: AddressSource) {
+        if &peer == self.memory.local_peer_id() {
+            self.memory.add_address(peer, addr);
+            return;
+        }
+
+        self.memory.add_address(peer.clone(), addr.clone());
+        let info = self.peers.entry(peer).or_insert_with(PeerInfo::default);
+        let now = SystemTime::now();
+        if let Some(entry) = info.addresses.iter_mut().find(|e| e.addr == addr) {
+            entry.last_seen = now;
+            if source == AddressSource::Manual {
+                entry.source = source;
+            }
+            return;
+        }
+
+        info.addresses.push(AddressEntry { addr, source, last_seen: now });
+    }
+
+    /// Removes an address of a peer from the topology.
+    ///
+    /// Everything we know about the peer is forgotten if it doesn't have any address left.
+    pub fn remove_address(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        self.memory.remove_address(peer, addr);
+        let is_empty = match self.peers.get_mut(peer) {
+            Some(info) => {
+                info.addresses.retain(|e| &e.addr != addr);
+                info.addresses.is_empty()
+            },
+            None => return,
+        };
+
+        if is_empty {
+            self.peers.remove(peer);
+        }
+    }
+
+    /// Sets the public key of a peer.
+    ///
+    /// Doesn't do anything if the key doesn't match the peer ID.
+    pub fn set_public_key(&mut self, peer: &PeerId, public_key: PublicKey) {
+        if peer.is_public_key(&public_key) == Some(false) || peer == self.memory.local_peer_id() {
+            return;
+        }
+
+        self.peers.entry(peer.clone()).or_insert_with(PeerInfo::default).public_key = Some(public_key);
+    }
+
+    /// Replaces the protocols that a peer supports.
+    ///
+    /// Protocol names that contain a line break can't be saved and are ignored.
+    pub fn set_protocols<TIter>(&mut self, peer: &PeerId, protocols: TIter)
+    where TIter: IntoIterator<Item = String>
+    {
+        if peer == self.memory.local_peer_id() {
+            return;
+        }
+
+        let protocols = protocols.into_iter().filter(|p| !p.contains('\n')).collect::<Vec<_>>();
+        self.memory.set_protocols(peer, protocols.iter().cloned());
+        self.peers.entry(peer.clone()).or_insert_with(PeerInfo::default).protocols = protocols;
+    }
+
+    /// Returns what we know about a remote peer.
+    #[inline]
+    pub fn peer_info(&self, peer: &PeerId) -> Option<&PeerInfo> {
+        self.peers.get(peer)
+    }
+
+    /// Returns a list of all the known peers in the topology, including the local node if it
+    /// has addresses.
+    #[inline]
+    pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.memory.peers()
+    }
+
+    /// Registers `peer_id` as a provider of `key`. Providers aren't saved.
+    ///
+    /// See `MemoryTopology::add_provider`.
+    #[inline]
+    pub fn add_provider(&mut self, key: Multihash, peer_id: PeerId) {
+        self.memory.add_provider(key, peer_id)
+    }
+
+    /// Unregisters `peer_id` as a provider of `key`.
+    #[inline]
+    pub fn remove_provider(&mut self, key: &Multihash, peer_id: &PeerId) {
+        self.memory.remove_provider(key, peer_id)
+    }
+
+    /// Returns the peers that provide `key` and whose registration hasn't expired.
+    #[inline]
+    pub fn providers(&self, key: &Multihash) -> impl Iterator<Item = &PeerId> {
+        self.memory.providers(key)
+    }
+
+    /// Removes the addresses that haven't been seen for longer than the stale delay, and the
+    /// peers that don't have any address left.
+    pub fn remove_stale_entries(&mut self) {
+        let now = SystemTime::now();
+        let stale_after = self.stale_after;
+        let memory = &mut self.memory;
+        for (peer, info) in self.peers.iter_mut() {
+            info.addresses.retain(|entry| {
+                if is_stale(entry, now, stale_after) {
+                    memory.remove_address(peer, &entry.addr);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.peers.retain(|_, info| !info.addresses.is_empty());
+    }
+
+    /// Writes the entries of the topology that aren't stale to disk.
+    ///
+    /// The snapshot is first written to a temporary file next to the destination, which is then
+    /// renamed over it, so that a crash never leaves a truncated snapshot behind.
+    pub fn save(&self) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        {
+            let mut file = BufWriter::new(File::create(&tmp_path)?);
+            write_snapshot(&mut file, &self.peers, SystemTime::now(), self.stale_after)?;
+            file.flush()?;
+            file.get_ref().sync_all()?;
+        }
+
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+impl Topology for PersistentTopology {
+    #[inline]
+    fn addresses_of_peer(&mut self, peer: &PeerId) -> Vec<Multiaddr> {
+        self.memory.addresses_of_peer(peer)
+    }
+
+    #[inline]
+    fn add_local_external_addrs<TIter>(&mut self, addrs: TIter)
+    where TIter: Iterator<Item = Multiaddr>
+    {
+        self.memory.add_local_external_addrs(addrs)
+    }
+
+    #[inline]
+    fn local_peer_id(&self) -> &PeerId {
+        self.memory.local_peer_id()
+    }
+
+    #[inline]
+    fn local_public_key(&self) -> &PublicKey {
+        self.memory.local_public_key()
+    }
+
+    fn report_dial_success(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        self.memory.report_dial_success(peer, addr);
+        let entry = self.peers.get_mut(peer)
+            .and_then(|info| info.addresses.iter_mut().find(|e| &e.addr == addr));
+        if let Some(entry) = entry {
+            entry.last_seen = SystemTime::now();
+        }
+    }
+
+    #[inline]
+    fn report_dial_failure(&mut self, peer: &PeerId, addr: &Multiaddr) {
+        self.memory.report_dial_failure(peer, addr)
+    }
+
+    #[inline]
+    fn peers_supporting_protocol(&self, protocol: &[u8]) -> Vec<PeerId> {
+        self.memory.peers_supporting_protocol(protocol)
+    }
+
+    #[inline]
+    fn subscribe(&mut self) -> mpsc::Receiver<TopologyEvent> {
+        self.memory.subscribe()
+    }
+}
+
+/// Returns true if `entry` hasn't been seen for longer than `stale_after`.
+fn is_stale(entry: &AddressEntry, now: SystemTime, stale_after: Duration) -> bool {
+    if entry.source == AddressSource::Manual {
+        return false;
+    }
+
+    match now.duration_since(entry.last_seen) {
+        Ok(age) => age > stale_after,
+        // The entry was seen in the future, which can happen if the clock went backwards.
+        Err(_) => false,
+    }
+}
+
+/// Writes the peers that have addresses that aren't stale.
+fn write_snapshot<W>(mut out: W, peers: &HashMap<PeerId, PeerInfo>, now: SystemTime,
+                     stale_after: Duration) -> io::Result<()>
+where W: Write
+{
+    writeln!(out, "{}", SNAPSHOT_HEADER)?;
+
+    for (peer, info) in peers {
+        let addresses = info.addresses.iter()
+            .filter(|entry| !is_stale(entry, now, stale_after))
+            .collect::<Vec<_>>();
+        if addresses.is_empty() {
+            continue;
+        }
+
+        writeln!(out, "peer {}", peer.to_base58())?;
+        if let Some(ref key) = info.public_key {
+            let encoded = bs58::encode(key.clone().into_protobuf_encoding()).into_string();
+            writeln!(out, "key {}", encoded)?;
+        }
+        for protocol in &info.protocols {
+            writeln!(out, "protocol {}", protocol)?;
+        }
+        for entry in addresses {
+            let last_seen = entry.last_seen.duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            writeln!(out, "addr {} {} {}", entry.source.as_str(), last_seen, entry.addr)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a snapshot written by `write_snapshot`.
+fn read_snapshot<R>(input: R) -> io::Result<HashMap<PeerId, PeerInfo>>
+where R: BufRead
+{
+    let mut peers = HashMap::new();
+    let mut lines = input.lines();
+
+    match lines.next() {
+        Some(header) => if header? != SNAPSHOT_HEADER {
+            return Err(invalid_data(1, "unknown snapshot format"));
+        },
+        None => return Ok(peers),
+    }
+
+    let mut current: Option<PeerId> = None;
+    for (line, content) in lines.enumerate() {
+        // Line numbers start at 1, and the header was already read.
+        let line = line + 2;
+        let content = content?;
+        if content.is_empty() {
+            continue;
+        }
+
+        let mut parts = content.splitn(2, ' ');
+        let kind = parts.next().unwrap_or("");
+        let value = parts.next().ok_or_else(|| invalid_data(line, "missing value"))?;
+
+        if kind == "peer" {
+            let peer = value.parse::<PeerId>().map_err(|_| invalid_data(line, "invalid peer ID"))?;
+            peers.entry(peer.clone()).or_insert_with(PeerInfo::default);
+            current = Some(peer);
+            continue;
+        }
+
+        let info = current.as_ref()
+            .and_then(|peer| peers.get_mut(peer))
+            .ok_or_else(|| invalid_data(line, "entry doesn't belong to any peer"))?;
+
+        match kind {
+            "key" => {
+                let bytes = bs58::decode(value).into_vec()
+                    .map_err(|_| invalid_data(line, "invalid public key encoding"))?;
+                let key = PublicKey::from_protobuf_encoding(&bytes)
+                    .map_err(|_| invalid_data(line, "invalid public key"))?;
+                info.public_key = Some(key);
+            },
+            "protocol" => info.protocols.push(value.to_owned()),
+            "addr" => {
+                let mut fields = value.splitn(3, ' ');
+                let source = fields.next()
+                    .and_then(AddressSource::from_str)
+                    .ok_or_else(|| invalid_data(line, "invalid address source"))?;
+                let last_seen = fields.next()
+                    .and_then(|secs| secs.parse::<u64>().ok())
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+                    .ok_or_else(|| invalid_data(line, "invalid last seen time"))?;
+                let addr = fields.next()
+                    .and_then(|addr| addr.parse::<Multiaddr>().ok())
+                    .ok_or_else(|| invalid_data(line, "invalid multiaddr"))?;
+                info.addresses.push(AddressEntry { addr, source, last_seen });
+            },
+            _ => return Err(invalid_data(line, "unknown entry")),
+        }
+    }
+
+    Ok(peers)
+}
+
+/// Builds the error returned when a snapshot is malformed.
+fn invalid_data(line: usize, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{Future, Stream};
+    use super::{read_snapshot, write_snapshot, AddressSource, PersistentTopology};
+    use std::{env, fs, time::Duration, time::SystemTime};
+    use topology::{Topology, TopologyEvent};
+    use {Multiaddr, PeerId, PublicKey};
+
+    fn local_key() -> PublicKey {
+        PublicKey::Ed25519(vec![1; 32])
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let path = env::temp_dir().join(format!("libp2p-topology-{}", PeerId::random().to_base58()));
+        let peer = PublicKey::Ed25519(vec![2; 32]).into_peer_id();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/5".parse().unwrap();
+
+        {
+            let mut topology = PersistentTopology::open(path.clone(), local_key()).unwrap();
+            topology.add_address(peer.clone(), addr.clone(), AddressSource::Kademlia);
+            topology.set_public_key(&peer, PublicKey::Ed25519(vec![2; 32]));
+            topology.set_protocols(&peer, vec!["/ipfs/ping/1.0.0".to_owned()]);
+            topology.save().unwrap();
+        }
+
+        let mut topology = PersistentTopology::open(path.clone(), local_key()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(topology.addresses_of_peer(&peer), vec![addr.clone()]);
+        let info = topology.peer_info(&peer).unwrap();
+        assert_eq!(info.public_key(), Some(&PublicKey::Ed25519(vec![2; 32])));
+        assert_eq!(info.protocols().collect::<Vec<_>>(), vec!["/ipfs/ping/1.0.0"]);
+        let (saved_addr, source, _) = info.addresses().next().unwrap();
+        assert_eq!((saved_addr, source), (&addr, AddressSource::Kademlia));
+    }
+
+    #[test]
+    fn stale_entries_are_removed() {
+        let path = env::temp_dir().join(format!("libp2p-topology-{}", PeerId::random().to_base58()));
+        let mut topology = PersistentTopology::open(path, local_key()).unwrap();
+        let discovered = PeerId::random();
+        let manual = PeerId::random();
+        topology.add_address(discovered.clone(), "/ip4/1.2.3.4/tcp/5".parse().unwrap(), AddressSource::Mdns);
+        topology.add_address(manual.clone(), "/ip4/1.2.3.4/tcp/6".parse().unwrap(), AddressSource::Manual);
+
+        let mut snapshot = Vec::new();
+        let later = SystemTime::now() + Duration::from_secs(60);
+        write_snapshot(&mut snapshot, &topology.peers, later, Duration::from_secs(1)).unwrap();
+        let peers = read_snapshot(&snapshot[..]).unwrap();
+        assert!(!peers.contains_key(&discovered));
+        assert!(peers.contains_key(&manual));
+
+        topology.set_stale_after(Duration::from_secs(0));
+        ::std::thread::sleep(Duration::from_millis(10));
+        topology.remove_stale_entries();
+        assert!(topology.peer_info(&discovered).is_none());
+        assert!(topology.addresses_of_peer(&discovered).is_empty());
+        assert!(topology.peer_info(&manual).is_some());
+    }
+
+    #[test]
+    fn manual_source_overrides_discovery() {
+        let path = env::temp_dir().join(format!("libp2p-topology-{}", PeerId::random().to_base58()));
+        let mut topology = PersistentTopology::open(path, local_key()).unwrap();
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/5".parse().unwrap();
+        let source = |topology: &PersistentTopology| {
+            let info = topology.peer_info(&peer).unwrap();
+            assert_eq!(info.addresses().count(), 1);
+            info.addresses().next().unwrap().1
+        };
+
+        topology.add_address(peer.clone(), addr.clone(), AddressSource::Mdns);
+        assert_eq!(source(&topology), AddressSource::Mdns);
+        topology.add_address(peer.clone(), addr.clone(), AddressSource::Manual);
+        assert_eq!(source(&topology), AddressSource::Manual);
+        // Discovering the address again doesn't downgrade it.
+        topology.add_address(peer.clone(), addr.clone(), AddressSource::Kademlia);
+        assert_eq!(source(&topology), AddressSource::Manual);
+    }
+
+    #[test]
+    fn peer_info_is_removed_with_last_address() {
+        let path = env::temp_dir().join(format!("libp2p-topology-{}", PeerId::random().to_base58()));
+        let mut topology = PersistentTopology::open(path, local_key()).unwrap();
+        let peer = PeerId::random();
+        let a1: Multiaddr = "/ip4/1.2.3.4/tcp/5".parse().unwrap();
+        let a2: Multiaddr = "/ip4/1.2.3.4/tcp/6".parse().unwrap();
+        topology.add_address(peer.clone(), a1.clone(), AddressSource::Mdns);
+        topology.add_address(peer.clone(), a2.clone(), AddressSource::Manual);
+        topology.set_protocols(&peer, vec!["/ipfs/ping/1.0.0".to_owned()]);
+
+        topology.remove_address(&peer, &a1);
+        assert!(topology.peer_info(&peer).is_some());
+        topology.remove_address(&peer, &a2);
+        assert!(topology.peer_info(&peer).is_none());
+    }
+
+    #[test]
+    fn stale_addresses_are_notified() {
+        let path = env::temp_dir().join(format!("libp2p-topology-{}", PeerId::random().to_base58()));
+        let mut topology = PersistentTopology::open(path, local_key()).unwrap();
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/5".parse().unwrap();
+        topology.add_address(peer.clone(), addr.clone(), AddressSource::Mdns);
+        topology.set_protocols(&peer, vec!["/ipfs/ping/1.0.0".to_owned()]);
+        let events = topology.subscribe();
+
+        topology.set_stale_after(Duration::from_secs(0));
+        ::std::thread::sleep(Duration::from_millis(10));
+        topology.remove_stale_entries();
+        assert!(topology.peers_supporting_protocol(b"/ipfs/ping/1.0.0").is_empty());
+        drop(topology);
+
+        assert_eq!(events.collect().wait().unwrap(), vec![
+            TopologyEvent::AddressRemoved { peer: peer.clone(), addr },
+            TopologyEvent::PeerRemoved(peer),
+        ]);
+    }
+
+    #[test]
+    fn malformed_snapshot_is_rejected() {
+        assert!(read_snapshot(&b"libp2p-topology 1\naddr kad 0 /ip4/1.2.3.4/tcp/5\n"[..]).is_err());
+        assert!(read_snapshot(&b"something else\n"[..]).is_err());
+        assert!(read_snapshot(&b""[..]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::PeerId;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Output of an upgrade that authenticates the remote, such as an encryption protocol.
+///
+/// Gives access to the identity of the remote and to the stream to communicate with it.
+pub trait AuthenticatedOutput {
+    /// Stream over which the communications with the remote happen.
+    type Stream: AsyncRead + AsyncWrite;
+
+    /// Splits the output into the `PeerId` of the remote and the stream.
+    fn into_authenticated(self) -> (PeerId, Self::Stream);
+}
+
+impl<S> AuthenticatedOutput for (PeerId, S)
+where
+    S: AsyncRead + AsyncWrite
+{
+    type Stream = S;
+
+    #[inline]
+    fn into_authenticated(self) -> (PeerId, S) {
+        self
+    }
+}
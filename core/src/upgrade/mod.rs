@@ -58,6 +58,7 @@
 //!
 
 mod apply;
+mod authenticated;
 mod denied;
 mod either;
 mod error;
@@ -68,6 +69,7 @@ use futures::future::Future;
 
 pub use self::{
     apply::{apply, apply_inbound, apply_outbound, InboundUpgradeApply, OutboundUpgradeApply},
+    authenticated::AuthenticatedOutput,
     denied::DeniedUpgrade,
     either::EitherUpgrade,
     error::UpgradeError,
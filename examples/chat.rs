@@ -86,10 +86,12 @@ fn main() {
 
     impl<TSubstream: libp2p::tokio_io::AsyncRead + libp2p::tokio_io::AsyncWrite> MyBehaviour<TSubstream> {
         // Called when `floodsub` produces an event.
-        fn on_floodsub<TTopology>(&mut self, message: <libp2p::floodsub::Floodsub<TSubstream> as libp2p::core::swarm::NetworkBehaviour<TTopology>>::OutEvent)
+        fn on_floodsub<TTopology>(&mut self, event: <libp2p::floodsub::Floodsub<TSubstream> as libp2p::core::swarm::NetworkBehaviour<TTopology>>::OutEvent)
         where TSubstream: libp2p::tokio_io::AsyncRead + libp2p::tokio_io::AsyncWrite
         {
-            println!("Received: '{:?}' from {:?}", String::from_utf8_lossy(&message.data), message.source);
+            if let libp2p::floodsub::FloodsubEvent::Message(message) = event {
+                println!("Received: '{:?}' from {:?}", String::from_utf8_lossy(&message.data), message.source);
+            }
         }
     }
 
@@ -56,6 +56,7 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> TokenStream {
     let proto_select_ident = quote!{::libp2p::core::protocols_handler::ProtocolsHandlerSelect};
     let peer_id = quote!{::libp2p::core::PeerId};
     let connected_point = quote!{::libp2p::core::swarm::ConnectedPoint};
+    let multiaddr = quote!{::libp2p::core::Multiaddr};
 
     // Name of the type parameter that represents the substream.
     let substream_generic = {
@@ -180,6 +181,62 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> TokenStream {
         })
     };
 
+    // Build the list of statements to put in the body of `inject_addr_reach_failure()`.
+    let inject_addr_reach_failure_stmts = {
+        data_struct.fields.iter().enumerate().filter_map(move |(field_n, field)| {
+            if is_ignored(&field) {
+                return None;
+            }
+
+            Some(match field.ident {
+                Some(ref i) => quote!{ self.#i.inject_addr_reach_failure(peer_id, addr, error); },
+                None => quote!{ self.#field_n.inject_addr_reach_failure(peer_id, addr, error); },
+            })
+        })
+    };
+
+    // Build the list of statements to put in the body of `inject_dial_failure()`.
+    let inject_dial_failure_stmts = {
+        data_struct.fields.iter().enumerate().filter_map(move |(field_n, field)| {
+            if is_ignored(&field) {
+                return None;
+            }
+
+            Some(match field.ident {
+                Some(ref i) => quote!{ self.#i.inject_dial_failure(peer_id); },
+                None => quote!{ self.#field_n.inject_dial_failure(peer_id); },
+            })
+        })
+    };
+
+    // Build the list of statements to put in the body of `inject_incoming_connection_error()`.
+    let inject_incoming_connection_error_stmts = {
+        data_struct.fields.iter().enumerate().filter_map(move |(field_n, field)| {
+            if is_ignored(&field) {
+                return None;
+            }
+
+            Some(match field.ident {
+                Some(ref i) => quote!{ self.#i.inject_incoming_connection_error(listen_addr, send_back_addr, error); },
+                None => quote!{ self.#field_n.inject_incoming_connection_error(listen_addr, send_back_addr, error); },
+            })
+        })
+    };
+
+    // Build the list of statements to put in the body of `inject_listener_closed()`.
+    let inject_listener_closed_stmts = {
+        data_struct.fields.iter().enumerate().filter_map(move |(field_n, field)| {
+            if is_ignored(&field) {
+                return None;
+            }
+
+            Some(match field.ident {
+                Some(ref i) => quote!{ self.#i.inject_listener_closed(listen_addr); },
+                None => quote!{ self.#field_n.inject_listener_closed(listen_addr); },
+            })
+        })
+    };
+
     // Build the list of variants to put in the body of `inject_node_event()`.
     //
     // The event type is a construction of nested `#either_ident`s of the events of the children.
@@ -357,6 +414,26 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> TokenStream {
                 #(#inject_disconnected_stmts);*
             }
 
+            #[inline]
+            fn inject_addr_reach_failure(&mut self, peer_id: Option<&#peer_id>, addr: &#multiaddr, error: &dyn ::std::error::Error) {
+                #(#inject_addr_reach_failure_stmts);*
+            }
+
+            #[inline]
+            fn inject_dial_failure(&mut self, peer_id: &#peer_id) {
+                #(#inject_dial_failure_stmts);*
+            }
+
+            #[inline]
+            fn inject_incoming_connection_error(&mut self, listen_addr: &#multiaddr, send_back_addr: &#multiaddr, error: &dyn ::std::error::Error) {
+                #(#inject_incoming_connection_error_stmts);*
+            }
+
+            #[inline]
+            fn inject_listener_closed(&mut self, listen_addr: &#multiaddr) {
+                #(#inject_listener_closed_stmts);*
+            }
+
             #[inline]
             fn inject_node_event(
                 &mut self,
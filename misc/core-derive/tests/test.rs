@@ -146,3 +146,93 @@ fn custom_event_and_polling() {
         require_net_behaviour::<Foo<TSubstream>>();
     }
 }
+
+/// Behaviour that records which error callbacks it received.
+struct ErrorRecorder<TSubstream> {
+    calls: Vec<&'static str>,
+    marker: std::marker::PhantomData<TSubstream>,
+}
+
+impl<TSubstream> ErrorRecorder<TSubstream> {
+    fn new() -> Self {
+        ErrorRecorder { calls: Vec::new(), marker: std::marker::PhantomData }
+    }
+}
+
+impl<TSubstream, TTopology> libp2p::core::swarm::NetworkBehaviour<TTopology> for ErrorRecorder<TSubstream>
+where
+    TSubstream: libp2p::tokio_io::AsyncRead + libp2p::tokio_io::AsyncWrite,
+{
+    type ProtocolsHandler = libp2p::core::protocols_handler::DummyProtocolsHandler<TSubstream>;
+    type OutEvent = ();
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        Default::default()
+    }
+
+    fn inject_connected(&mut self, _: libp2p::PeerId, _: libp2p::core::swarm::ConnectedPoint) {}
+
+    fn inject_disconnected(&mut self, _: &libp2p::PeerId, _: libp2p::core::swarm::ConnectedPoint) {}
+
+    fn inject_node_event(
+        &mut self,
+        _: libp2p::PeerId,
+        _: <Self::ProtocolsHandler as libp2p::core::ProtocolsHandler>::OutEvent,
+    ) {}
+
+    fn inject_addr_reach_failure(&mut self, _: Option<&libp2p::PeerId>, _: &libp2p::Multiaddr, _: &dyn std::error::Error) {
+        self.calls.push("addr_reach_failure");
+    }
+
+    fn inject_dial_failure(&mut self, _: &libp2p::PeerId) {
+        self.calls.push("dial_failure");
+    }
+
+    fn inject_incoming_connection_error(&mut self, _: &libp2p::Multiaddr, _: &libp2p::Multiaddr, _: &dyn std::error::Error) {
+        self.calls.push("incoming_connection_error");
+    }
+
+    fn inject_listener_closed(&mut self, _: &libp2p::Multiaddr) {
+        self.calls.push("listener_closed");
+    }
+
+    fn poll(
+        &mut self,
+        _: &mut libp2p::core::swarm::PollParameters<TTopology>,
+    ) -> libp2p::futures::Async<libp2p::core::swarm::NetworkBehaviourAction<<Self::ProtocolsHandler as libp2p::core::ProtocolsHandler>::InEvent, ()>> {
+        libp2p::futures::Async::NotReady
+    }
+}
+
+#[test]
+fn error_callbacks_reach_every_field() {
+    #[allow(dead_code)]
+    #[derive(NetworkBehaviour)]
+    struct Foo<TSubstream> {
+        first: ErrorRecorder<TSubstream>,
+        second: ErrorRecorder<TSubstream>,
+        #[behaviour(ignore)]
+        foo: String,
+    }
+
+    fn inject_errors<T: libp2p::core::swarm::NetworkBehaviour<libp2p::core::topology::MemoryTopology>>(behaviour: &mut T) {
+        let peer_id = libp2p::PeerId::random();
+        let addr: libp2p::Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+        let error = std::io::Error::new(std::io::ErrorKind::Other, "test");
+        behaviour.inject_addr_reach_failure(Some(&peer_id), &addr, &error);
+        behaviour.inject_dial_failure(&peer_id);
+        behaviour.inject_incoming_connection_error(&addr, &addr, &error);
+        behaviour.inject_listener_closed(&addr);
+    }
+
+    let mut foo = Foo::<std::io::Cursor<Vec<u8>>> {
+        first: ErrorRecorder::new(),
+        second: ErrorRecorder::new(),
+        foo: String::new(),
+    };
+    inject_errors(&mut foo);
+
+    let expected = vec!["addr_reach_failure", "dial_failure", "incoming_connection_error", "listener_closed"];
+    assert_eq!(foo.first.calls, expected);
+    assert_eq!(foo.second.calls, expected);
+}
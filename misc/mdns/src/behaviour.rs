@@ -23,6 +23,7 @@ use futures::prelude::*;
 use libp2p_core::protocols_handler::{DummyProtocolsHandler, ProtocolsHandler};
 use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
 use libp2p_core::{Multiaddr, PeerId, multiaddr::Protocol, topology::MemoryTopology, topology::Topology};
+use libp2p_core::topology::{AddressSource, PersistentTopology};
 use smallvec::SmallVec;
 use std::{fmt, io, iter, marker::PhantomData, time::Duration};
 use tokio_io::{AsyncRead, AsyncWrite};
@@ -69,6 +70,13 @@ impl MdnsTopology for MemoryTopology {
     }
 }
 
+impl MdnsTopology for PersistentTopology {
+    #[inline]
+    fn add_mdns_discovered_address(&mut self, peer: PeerId, addr: Multiaddr) {
+        self.add_address(peer, addr, AddressSource::Mdns)
+    }
+}
+
 impl<TSubstream, TTopology> NetworkBehaviour<TTopology> for Mdns<TSubstream>
 where
     TSubstream: AsyncRead + AsyncWrite,
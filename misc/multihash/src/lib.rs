@@ -86,6 +86,16 @@ pub fn encode(hash: Hash, input: &[u8]) -> Result<Multihash, EncodeError> {
     let mut buf = encode::u16_buffer();
     let code = encode::u16(hash.code(), &mut buf);
 
+    if hash == Hash::Identity {
+        let mut len_buf = encode::u64_buffer();
+        let len = encode::u64(input.len() as u64, &mut len_buf);
+        let mut output = Vec::with_capacity(code.len() + len.len() + input.len());
+        output.extend_from_slice(code);
+        output.extend_from_slice(len);
+        output.extend_from_slice(input);
+        return Ok(Multihash { bytes: output });
+    }
+
     let header_len = code.len() + 1;
     let size = hash.size();
 
@@ -211,14 +221,15 @@ impl<'a> MultihashRef<'a> {
         let (code, bytes) = decode::u16(&input).map_err(|_| DecodeError::BadInputLength)?;
 
         let alg = Hash::from_code(code).ok_or(DecodeError::UnknownCode)?;
-        let hash_len = alg.size() as usize;
+        let (hash_len, bytes) = decode::u64(&bytes).map_err(|_| DecodeError::BadInputLength)?;
 
-        // Length of input after hash code should be exactly hash_len + 1
-        if bytes.len() != hash_len + 1 {
+        // The length of the identity "hash" is the length of its input.
+        if alg != Hash::Identity && hash_len != u64::from(alg.size()) {
             return Err(DecodeError::BadInputLength);
         }
 
-        if bytes[0] as usize != hash_len {
+        // Length of input after the length prefix should be exactly hash_len
+        if bytes.len() as u64 != hash_len {
             return Err(DecodeError::BadInputLength);
         }
 
@@ -236,7 +247,8 @@ impl<'a> MultihashRef<'a> {
     #[inline]
     pub fn digest(&self) -> &'a [u8] {
         let (_, bytes) = decode::u16(&self.bytes).expect("multihash is known to be valid digest");
-        &bytes[1..]
+        let (_, bytes) = decode::u64(&bytes).expect("multihash is known to be valid digest");
+        bytes
     }
 
     /// Builds a `Multihash` that owns the data.
@@ -276,7 +288,7 @@ pub fn to_hex(bytes: &[u8]) -> String {
 
 #[cfg(test)]
 mod tests {
-    use {Hash, Multihash};
+    use {encode, Hash, Multihash};
 
     #[test]
     fn rand_generates_valid_multihash() {
@@ -293,4 +305,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn identity_hash() {
+        let input = b"hello world";
+        let hash = encode(Hash::Identity, input).unwrap();
+        assert_eq!(hash.as_bytes(), &b"\x00\x0bhello world"[..]);
+        assert_eq!(hash.algorithm(), Hash::Identity);
+        assert_eq!(hash.digest(), &input[..]);
+        assert_eq!(hash, Multihash::from_bytes(hash.clone().into_bytes()).unwrap());
+
+        let long_input = vec![7; 300];
+        let hash = encode(Hash::Identity, &long_input).unwrap();
+        assert_eq!(hash.digest(), &long_input[..]);
+        assert_eq!(hash, Multihash::from_bytes(hash.clone().into_bytes()).unwrap());
+    }
+
+    #[test]
+    fn bad_length_is_rejected() {
+        let mut bytes = encode(Hash::SHA2256, b"hello world").unwrap().into_bytes();
+        bytes.pop();
+        assert!(Multihash::from_bytes(bytes).is_err());
+        assert!(Multihash::from_bytes(vec![0x00, 0x05, 1, 2, 3]).is_err());
+    }
 }
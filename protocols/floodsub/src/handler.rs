@@ -22,8 +22,8 @@ use crate::protocol::{FloodsubCodec, FloodsubConfig, FloodsubRpc};
 use futures::prelude::*;
 use libp2p_core::{
     ProtocolsHandler, ProtocolsHandlerEvent,
-    protocols_handler::ProtocolsHandlerUpgrErr,
-    upgrade::{InboundUpgrade, OutboundUpgrade}
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
+    upgrade::{InboundUpgrade, OutboundUpgrade, UpgradeError}
 };
 use smallvec::SmallVec;
 use std::{fmt, io};
@@ -52,6 +52,22 @@ where
 
     /// Queue of values that we want to send to the remote.
     send_queue: SmallVec<[FloodsubRpc; 16]>,
+
+    /// True if the remote has refused to negotiate floodsub on a substream we opened. We then
+    /// stop opening substreams until the remote opens one.
+    protocol_unsupported: bool,
+
+    /// True if `protocol_unsupported` has been reported to the behaviour.
+    protocol_unsupported_reported: bool,
+}
+
+/// Event produced by the `FloodsubHandler`.
+#[derive(Debug, Clone)]
+pub enum FloodsubHandlerEvent {
+    /// The remote doesn't support the floodsub protocol.
+    ProtocolNotSupported,
+    /// The remote has sent us an RPC.
+    Rpc(FloodsubRpc),
 }
 
 /// State of an active substream, opened either by us or by the remote.
@@ -95,6 +111,8 @@ where
             shutting_down: false,
             substreams: Vec::new(),
             send_queue: SmallVec::new(),
+            protocol_unsupported: false,
+            protocol_unsupported_reported: false,
         }
     }
 }
@@ -104,7 +122,7 @@ where
     TSubstream: AsyncRead + AsyncWrite,
 {
     type InEvent = FloodsubRpc;
-    type OutEvent = FloodsubRpc;
+    type OutEvent = FloodsubHandlerEvent;
     type Substream = TSubstream;
     type InboundProtocol = FloodsubConfig;
     type OutboundProtocol = FloodsubConfig;
@@ -122,6 +140,8 @@ where
         if self.shutting_down {
             return ()
         }
+        self.protocol_unsupported = false;
+        self.protocol_unsupported_reported = false;
         self.substreams.push(SubstreamState::WaitingInput(protocol))
     }
 
@@ -138,6 +158,9 @@ where
 
     #[inline]
     fn inject_event(&mut self, message: FloodsubRpc) {
+        if self.protocol_unsupported {
+            return;
+        }
         self.send_queue.push(message);
     }
 
@@ -145,7 +168,22 @@ where
     fn inject_inbound_closed(&mut self) {}
 
     #[inline]
-    fn inject_dial_upgrade_error(&mut self, _: Self::OutboundOpenInfo, _: ProtocolsHandlerUpgrErr<<Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Error>) {}
+    fn inject_dial_upgrade_error(&mut self, _: Self::OutboundOpenInfo, error: ProtocolsHandlerUpgrErr<<Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Error>) {
+        // Only a failed negotiation means that the remote doesn't support the protocol. Other
+        // errors, such as timeouts, may be transient.
+        if let ProtocolsHandlerUpgrErr::Upgrade(UpgradeError::Select(_)) = error {
+            self.protocol_unsupported = true;
+            // This also drops our subscriptions. The behaviour sends them again if the remote
+            // opens a floodsub substream later.
+            self.send_queue.clear();
+        }
+    }
+
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        // Messages are propagated to all the connected nodes, so the connection is always useful.
+        KeepAlive::Forever
+    }
 
     #[inline]
     fn shutdown(&mut self) {
@@ -162,6 +200,13 @@ where
         Option<ProtocolsHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::OutEvent>>,
         io::Error,
     > {
+        if self.protocol_unsupported && !self.protocol_unsupported_reported {
+            self.protocol_unsupported_reported = true;
+            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(
+                FloodsubHandlerEvent::ProtocolNotSupported
+            ))));
+        }
+
         if !self.send_queue.is_empty() {
             let message = self.send_queue.remove(0);
             return Ok(Async::Ready(Some(
@@ -180,7 +225,8 @@ where
                         Ok(Async::Ready(Some(message))) => {
                             self.substreams
                                 .push(SubstreamState::WaitingInput(substream));
-                            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(message))));
+                            let event = FloodsubHandlerEvent::Rpc(message);
+                            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
                         }
                         Ok(Async::Ready(None)) => SubstreamState::Closing(substream),
                         Ok(Async::NotReady) => {
@@ -235,6 +281,7 @@ where
             .field("shutting_down", &self.shutting_down)
             .field("substreams", &self.substreams.len())
             .field("send_queue", &self.send_queue.len())
+            .field("protocol_unsupported", &self.protocol_unsupported)
             .finish()
     }
 }
@@ -19,31 +19,37 @@
 // DEALINGS IN THE SOFTWARE.
 
 use cuckoofilter::CuckooFilter;
-use futures::prelude::*;
-use handler::FloodsubHandler;
+use futures::{future, prelude::*};
+use handler::{FloodsubHandler, FloodsubHandlerEvent};
 use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
-use libp2p_core::{protocols_handler::ProtocolsHandler, PeerId};
+use libp2p_core::{identity::Keypair, protocols_handler::ProtocolsHandler, PeerId};
 use protocol::{FloodsubMessage, FloodsubRpc, FloodsubSubscription, FloodsubSubscriptionAction};
 use rand;
 use smallvec::SmallVec;
 use std::{collections::VecDeque, iter, marker::PhantomData};
 use std::collections::hash_map::{DefaultHasher, HashMap};
+use std::time::{Duration, Instant};
 use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Delay;
 use topic::{Topic, TopicHash};
 
 /// Network behaviour that automatically identifies nodes periodically, and returns information
 /// about them.
 pub struct Floodsub<TSubstream> {
     /// Events that need to be yielded to the outside when polling.
-    events: VecDeque<NetworkBehaviourAction<FloodsubRpc, FloodsubMessage>>,
+    events: VecDeque<NetworkBehaviourAction<FloodsubRpc, FloodsubEvent>>,
 
     /// Peer id of the local node. Used for the source of the messages that we publish.
     local_peer_id: PeerId,
 
+    /// Keypair of the local node, used to sign the messages that we publish.
+    keypair: Option<Keypair>,
+
+    /// How to sign the messages we publish and check the signatures of the messages we receive.
+    signature_policy: SignaturePolicy,
+
     /// List of peers the network is connected to, and the topics that they're subscribed to.
-    // TODO: filter out peers that don't support floodsub, so that we avoid hammering them with
-    //       opened substreams
-    connected_peers: HashMap<PeerId, SmallVec<[TopicHash; 8]>>,
+    connected_peers: HashMap<PeerId, PeerInfo>,
 
     // List of topics we're subscribed to. Necessary to filter out messages that we receive
     // erroneously.
@@ -53,19 +59,136 @@ pub struct Floodsub<TSubstream> {
     // we don't dispatch the same message twice if we receive it twice on the network.
     received: CuckooFilter<DefaultHasher>,
 
+    /// Validators of the messages of each topic.
+    validators: HashMap<TopicHash, TopicValidator>,
+
+    /// Received messages whose asynchronous validation is in progress.
+    pending_validations: Vec<PendingValidation>,
+
+    /// Maximum number of messages in `pending_validations`.
+    max_pending_validations: usize,
+
+    /// How long asynchronous validators have to produce a verdict.
+    validation_timeout: Duration,
+
     /// Marker to pin the generics.
     marker: PhantomData<TSubstream>,
 }
 
+/// Information about a peer we are connected to.
+#[derive(Debug)]
+struct PeerInfo {
+    /// Topics the peer is subscribed to.
+    topics: SmallVec<[TopicHash; 8]>,
+    /// False if the peer has refused to negotiate floodsub. We don't send anything to such peers,
+    /// so that we avoid hammering them with opened substreams.
+    supports_floodsub: bool,
+}
+
+/// Event that can happen on the floodsub behaviour.
+#[derive(Debug, Clone)]
+pub enum FloodsubEvent {
+    /// A message has been received on a topic we are subscribed to, and has been accepted by the
+    /// validators of its topics.
+    Message(FloodsubMessage),
+
+    /// A message has been rejected, either because of its signature or by a validator. It has not
+    /// been propagated.
+    Rejected {
+        /// Remote that has sent us the message.
+        propagation_source: PeerId,
+        /// The rejected message.
+        message: FloodsubMessage,
+    },
+}
+
+/// Verdict of a validator on a received message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationResult {
+    /// The message is valid. It is propagated and reported to the user.
+    Accept,
+    /// The message is invalid. It is dropped, and reported as a `FloodsubEvent::Rejected`.
+    Reject,
+    /// The message is dropped silently.
+    Ignore,
+}
+
+impl ValidationResult {
+    /// Combines the verdicts of the validators of two topics of the same message.
+    fn combine(self, other: ValidationResult) -> ValidationResult {
+        match (self, other) {
+            (ValidationResult::Reject, _) | (_, ValidationResult::Reject) => ValidationResult::Reject,
+            (ValidationResult::Ignore, _) | (_, ValidationResult::Ignore) => ValidationResult::Ignore,
+            (ValidationResult::Accept, ValidationResult::Accept) => ValidationResult::Accept,
+        }
+    }
+}
+
+/// Future producing the verdict of asynchronous validators.
+type ValidationFuture = Box<dyn Future<Item = ValidationResult, Error = ()> + Send>;
+
+/// Validator registered for a topic.
+enum TopicValidator {
+    Sync(Box<dyn FnMut(&PeerId, &FloodsubMessage) -> ValidationResult + Send>),
+    Async(Box<dyn FnMut(&PeerId, &FloodsubMessage) -> ValidationFuture + Send>),
+}
+
+/// A received message waiting for the verdict of asynchronous validators.
+struct PendingValidation {
+    propagation_source: PeerId,
+    message: FloodsubMessage,
+    verdict: ValidationFuture,
+    /// When it fires, the message is rejected.
+    timeout: Delay,
+}
+
+/// Default value for the maximum number of messages whose validation is in progress.
+const DEFAULT_MAX_PENDING_VALIDATIONS: usize = 1024;
+/// Default value for how long asynchronous validators have to produce a verdict.
+const DEFAULT_VALIDATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Determines how messages are signed and how their signatures are verified.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    /// Sign the messages we publish if we have a keypair, and reject the received messages
+    /// whose signature is invalid. Unsigned messages are accepted, unless they have a key.
+    Lax,
+    /// Sign all the messages we publish, and reject the received messages that aren't signed or
+    /// whose signature is invalid.
+    StrictSign,
+    /// Never sign the messages we publish, and reject the received messages that have a
+    /// signature or a key.
+    StrictNoSign,
+}
+
 impl<TSubstream> Floodsub<TSubstream> {
-    /// Creates a `Floodsub`.
+    /// Creates a `Floodsub` that doesn't sign the messages it publishes.
+    ///
+    /// The signatures of received messages are verified with the `Lax` policy.
     pub fn new(local_peer_id: PeerId) -> Self {
+        Floodsub::with_config(local_peer_id, None, SignaturePolicy::Lax)
+    }
+
+    /// Creates a `Floodsub` that signs the messages it publishes with the given keypair,
+    /// according to the given policy.
+    pub fn with_signing(keypair: Keypair, signature_policy: SignaturePolicy) -> Self {
+        let local_peer_id = keypair.public().into_peer_id();
+        Floodsub::with_config(local_peer_id, Some(keypair), signature_policy)
+    }
+
+    fn with_config(local_peer_id: PeerId, keypair: Option<Keypair>, signature_policy: SignaturePolicy) -> Self {
         Floodsub {
             events: VecDeque::new(),
             local_peer_id,
+            keypair,
+            signature_policy,
             connected_peers: HashMap::new(),
             subscribed_topics: SmallVec::new(),
             received: CuckooFilter::new(),
+            validators: HashMap::new(),
+            pending_validations: Vec::new(),
+            max_pending_validations: DEFAULT_MAX_PENDING_VALIDATIONS,
+            validation_timeout: DEFAULT_VALIDATION_TIMEOUT,
             marker: PhantomData,
         }
     }
@@ -80,7 +203,7 @@ impl<TSubstream> Floodsub<TSubstream> {
             return false;
         }
 
-        for peer in self.connected_peers.keys() {
+        for (peer, _) in self.connected_peers.iter().filter(|(_, info)| info.supports_floodsub) {
             self.events.push_back(NetworkBehaviourAction::SendEvent {
                 peer_id: peer.clone(),
                 event: FloodsubRpc {
@@ -111,7 +234,7 @@ impl<TSubstream> Floodsub<TSubstream> {
 
         self.subscribed_topics.remove(pos);
 
-        for peer in self.connected_peers.keys() {
+        for (peer, _) in self.connected_peers.iter().filter(|(_, info)| info.supports_floodsub) {
             self.events.push_back(NetworkBehaviourAction::SendEvent {
                 peer_id: peer.clone(),
                 event: FloodsubRpc {
@@ -127,6 +250,66 @@ impl<TSubstream> Floodsub<TSubstream> {
         true
     }
 
+    /// Returns the peers we are connected to that support floodsub and are subscribed to the
+    /// given topic. These are the peers our messages on this topic are sent to.
+    pub fn peers_on_topic<'a>(&'a self, topic: &'a TopicHash) -> impl Iterator<Item = &'a PeerId> + 'a {
+        self.connected_peers
+            .iter()
+            .filter(move |(_, info)| info.supports_floodsub && info.topics.contains(topic))
+            .map(|(peer_id, _)| peer_id)
+    }
+
+    /// Sets the validator of the messages received on a topic, replacing the previous one.
+    ///
+    /// The validator is called with the remote that has sent us the message and the message
+    /// itself. A message belonging to multiple topics is only accepted if the validators of all
+    /// its topics accept it. Our own messages aren't validated.
+    pub fn set_validator<F>(&mut self, topic: impl Into<TopicHash>, validator: F)
+    where
+        F: FnMut(&PeerId, &FloodsubMessage) -> ValidationResult + Send + 'static,
+    {
+        self.validators.insert(topic.into(), TopicValidator::Sync(Box::new(validator)));
+    }
+
+    /// Same as `set_validator`, but the verdict is produced asynchronously.
+    ///
+    /// The message is neither propagated nor reported until the future resolves. A future that
+    /// errors is equivalent to `ValidationResult::Ignore`, and a future that doesn't resolve
+    /// before the validation timeout is equivalent to `ValidationResult::Reject`.
+    pub fn set_async_validator<F, TFut>(&mut self, topic: impl Into<TopicHash>, mut validator: F)
+    where
+        F: FnMut(&PeerId, &FloodsubMessage) -> TFut + Send + 'static,
+        TFut: IntoFuture<Item = ValidationResult, Error = ()>,
+        TFut::Future: Send + 'static,
+    {
+        let validator = move |source: &PeerId, message: &FloodsubMessage| -> ValidationFuture {
+            Box::new(validator(source, message).into_future())
+        };
+        self.validators.insert(topic.into(), TopicValidator::Async(Box::new(validator)));
+    }
+
+    /// Sets the maximum number of received messages whose asynchronous validation can be in
+    /// progress at the same time. Messages received while this limit is reached are ignored.
+    #[inline]
+    pub fn set_max_pending_validations(&mut self, max: usize) {
+        self.max_pending_validations = max;
+    }
+
+    /// Sets how long asynchronous validators have to produce a verdict.
+    ///
+    /// Only applies to the messages received afterwards.
+    #[inline]
+    pub fn set_validation_timeout(&mut self, timeout: Duration) {
+        self.validation_timeout = timeout;
+    }
+
+    /// Removes the validator of a topic.
+    ///
+    /// Returns true if there was a validator for this topic.
+    pub fn remove_validator(&mut self, topic: impl AsRef<TopicHash>) -> bool {
+        self.validators.remove(topic.as_ref()).is_some()
+    }
+
     /// Publishes a message to the network.
     ///
     /// > **Note**: Doesn't do anything if we're not subscribed to the topic.
@@ -137,8 +320,11 @@ impl<TSubstream> Floodsub<TSubstream> {
     /// Publishes a message with multiple topics to the network.
     ///
     /// > **Note**: Doesn't do anything if we're not subscribed to any of the topics.
+    ///
+    /// > **Note**: With the `StrictSign` policy, doesn't do anything if the message can't be
+    /// >           signed.
     pub fn publish_many(&mut self, topic: impl IntoIterator<Item = impl Into<TopicHash>>, data: impl Into<Vec<u8>>) {
-        let message = FloodsubMessage {
+        let mut message = FloodsubMessage {
             source: self.local_peer_id.clone(),
             data: data.into(),
             // If the sequence numbers are predictable, then an attacker could flood the network
@@ -146,6 +332,8 @@ impl<TSubstream> Floodsub<TSubstream> {
             // messages. We therefore use a random number.
             sequence_number: rand::random::<[u8; 20]>().to_vec(),
             topics: topic.into_iter().map(|t| t.into().clone()).collect(),
+            signature: None,
+            key: None,
         };
 
         // Don't publish the message if we're not subscribed ourselves to any of the topics.
@@ -153,11 +341,32 @@ impl<TSubstream> Floodsub<TSubstream> {
             return;
         }
 
+        if self.signature_policy != SignaturePolicy::StrictNoSign {
+            let signed = match self.keypair {
+                Some(ref keypair) => match message.sign(keypair) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        debug!("Failed to sign floodsub message: {}", err);
+                        false
+                    },
+                },
+                None => false,
+            };
+
+            if !signed && self.signature_policy == SignaturePolicy::StrictSign {
+                return;
+            }
+        }
+
         self.received.add(&message);
 
         // Send to peers we know are subscribed to the topic.
-        for (peer_id, sub_topic) in self.connected_peers.iter() {
-            if !sub_topic.iter().any(|t| message.topics.iter().any(|u| t == u)) {
+        for (peer_id, info) in self.connected_peers.iter() {
+            if !info.supports_floodsub {
+                continue;
+            }
+
+            if !info.topics.iter().any(|t| message.topics.iter().any(|u| t == u)) {
                 continue;
             }
 
@@ -172,12 +381,151 @@ impl<TSubstream> Floodsub<TSubstream> {
     }
 }
 
+impl<TSubstream> Floodsub<TSubstream> {
+    /// Runs the validators of the topics of a received message.
+    ///
+    /// Returns the verdict if it is known immediately, or a future producing it if some of the
+    /// validators are asynchronous.
+    fn validate(&mut self, propagation_source: &PeerId, message: &FloodsubMessage)
+        -> Result<ValidationResult, ValidationFuture>
+    {
+        let mut verdict = ValidationResult::Accept;
+        let mut pending = Vec::new();
+        for topic in message.topics.iter() {
+            match self.validators.get_mut(topic) {
+                Some(TopicValidator::Sync(validator)) => {
+                    verdict = verdict.combine(validator(propagation_source, message));
+                },
+                Some(TopicValidator::Async(validator)) => {
+                    pending.push(validator(propagation_source, message));
+                },
+                None => {},
+            }
+        }
+
+        if pending.is_empty() || verdict == ValidationResult::Reject {
+            return Ok(verdict);
+        }
+
+        Err(Box::new(future::join_all(pending).then(move |results| {
+            Ok(match results {
+                Ok(results) => results.into_iter().fold(verdict, ValidationResult::combine),
+                Err(()) => verdict.combine(ValidationResult::Ignore),
+            })
+        })))
+    }
+
+    /// Applies the verdict of the validators on a received message. Accepted messages are
+    /// reported to the user if we're subscribed to one of their topics, and added to
+    /// `rpcs_to_dispatch` for each peer subscribed to one of their topics.
+    fn apply_verdict(
+        &mut self,
+        propagation_source: PeerId,
+        message: FloodsubMessage,
+        verdict: ValidationResult,
+        rpcs_to_dispatch: &mut Vec<(PeerId, FloodsubRpc)>,
+    ) {
+        match verdict {
+            ValidationResult::Accept => {},
+            ValidationResult::Reject => {
+                debug!("Floodsub message from {:?} rejected by a validator", propagation_source);
+                self.events.push_back(NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Rejected {
+                    propagation_source,
+                    message,
+                }));
+                return;
+            },
+            ValidationResult::Ignore => return,
+        }
+
+        // Propagate the message to everyone else who is subscribed to any of the topics.
+        for (peer_id, info) in self.connected_peers.iter() {
+            if peer_id == &propagation_source || !info.supports_floodsub {
+                continue;
+            }
+
+            if !info.topics.iter().any(|t| message.topics.iter().any(|u| t == u)) {
+                continue;
+            }
+
+            if let Some(pos) = rpcs_to_dispatch.iter().position(|(p, _)| p == peer_id) {
+                rpcs_to_dispatch[pos].1.messages.push(message.clone());
+            } else {
+                rpcs_to_dispatch.push((peer_id.clone(), FloodsubRpc {
+                    subscriptions: Vec::new(),
+                    messages: vec![message.clone()],
+                }));
+            }
+        }
+
+        // Add the message to be dispatched to the user.
+        if self.subscribed_topics.iter().any(|t| message.topics.iter().any(|u| t.hash() == u)) {
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message)));
+        }
+    }
+
+    /// Applies the verdicts of the asynchronous validators that have finished or timed out.
+    fn poll_pending_validations(&mut self) {
+        let mut rpcs_to_dispatch = Vec::new();
+
+        // We remove each element from `pending_validations` one by one and add them back if not
+        // ready.
+        for n in (0..self.pending_validations.len()).rev() {
+            let mut pending = self.pending_validations.swap_remove(n);
+            let verdict = match pending.verdict.poll() {
+                Ok(Async::Ready(verdict)) => verdict,
+                Err(()) => ValidationResult::Ignore,
+                Ok(Async::NotReady) => match pending.timeout.poll() {
+                    Ok(Async::NotReady) => {
+                        self.pending_validations.push(pending);
+                        continue;
+                    },
+                    Ok(Async::Ready(())) => {
+                        debug!("Validation of floodsub message from {:?} timed out",
+                               pending.propagation_source);
+                        ValidationResult::Reject
+                    },
+                    Err(err) => {
+                        debug!("Timer error while validating a floodsub message: {:?}", err);
+                        ValidationResult::Ignore
+                    },
+                },
+            };
+
+            self.apply_verdict(pending.propagation_source, pending.message, verdict, &mut rpcs_to_dispatch);
+        }
+
+        self.dispatch_rpcs(rpcs_to_dispatch);
+    }
+
+    /// Queues the RPCs built by `apply_verdict`.
+    fn dispatch_rpcs(&mut self, rpcs_to_dispatch: Vec<(PeerId, FloodsubRpc)>) {
+        for (peer_id, rpc) in rpcs_to_dispatch {
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id,
+                event: rpc,
+            });
+        }
+    }
+
+    /// Returns true if a received message is acceptable according to our signature policy.
+    fn is_signature_valid(&self, message: &FloodsubMessage) -> bool {
+        match self.signature_policy {
+            SignaturePolicy::Lax => {
+                (message.signature.is_none() && message.key.is_none()) || message.verify_signature()
+            },
+            SignaturePolicy::StrictSign => message.verify_signature(),
+            SignaturePolicy::StrictNoSign => message.signature.is_none() && message.key.is_none(),
+        }
+    }
+}
+
 impl<TSubstream, TTopology> NetworkBehaviour<TTopology> for Floodsub<TSubstream>
 where
     TSubstream: AsyncRead + AsyncWrite,
 {
     type ProtocolsHandler = FloodsubHandler<TSubstream>;
-    type OutEvent = FloodsubMessage;
+    type OutEvent = FloodsubEvent;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
         FloodsubHandler::new()
@@ -198,7 +546,10 @@ where
             });
         }
 
-        self.connected_peers.insert(id.clone(), SmallVec::new());
+        self.connected_peers.insert(id.clone(), PeerInfo {
+            topics: SmallVec::new(),
+            supports_floodsub: true,
+        });
     }
 
     fn inject_disconnected(&mut self, id: &PeerId, _: ConnectedPoint) {
@@ -209,22 +560,56 @@ where
     fn inject_node_event(
         &mut self,
         propagation_source: PeerId,
-        event: FloodsubRpc,
+        event: FloodsubHandlerEvent,
     ) {
-        // Update connected peers topics
-        for subscription in event.subscriptions {
-            let mut remote_peer_topics = self.connected_peers
+        let event = match event {
+            FloodsubHandlerEvent::ProtocolNotSupported => {
+                debug!("Peer {:?} doesn't support floodsub", propagation_source);
+                if let Some(info) = self.connected_peers.get_mut(&propagation_source) {
+                    info.supports_floodsub = false;
+                }
+                return;
+            },
+            FloodsubHandlerEvent::Rpc(rpc) => rpc,
+        };
+
+        {
+            let remote_peer = self.connected_peers
                 .get_mut(&propagation_source)
                 .expect("connected_peers is kept in sync with the peers we are connected to; we are guaranteed to only receive events from connected peers; QED");
-            match subscription.action {
-                FloodsubSubscriptionAction::Subscribe => {
-                    if !remote_peer_topics.contains(&subscription.topic) {
-                        remote_peer_topics.push(subscription.topic);
+            // The remote has opened a floodsub substream, which proves that it supports the protocol.
+            // If we previously thought otherwise, the handler has dropped the subscriptions we
+            // sent on connection, and we have to send them again.
+            if !remote_peer.supports_floodsub && !self.subscribed_topics.is_empty() {
+                self.events.push_back(NetworkBehaviourAction::SendEvent {
+                    peer_id: propagation_source.clone(),
+                    event: FloodsubRpc {
+                        messages: Vec::new(),
+                        subscriptions: self.subscribed_topics
+                            .iter()
+                            .map(|topic| FloodsubSubscription {
+                                topic: topic.hash().clone(),
+                                action: FloodsubSubscriptionAction::Subscribe,
+                            })
+                            .collect(),
+                    },
+                });
+            }
+            remote_peer.supports_floodsub = true;
+
+            // Update connected peers topics
+            for subscription in event.subscriptions {
+                let remote_peer_topics = &mut remote_peer.topics;
+                match subscription.action {
+                    FloodsubSubscriptionAction::Subscribe => {
+                        if !remote_peer_topics.contains(&subscription.topic) {
+                            remote_peer_topics.push(subscription.topic);
+                        }
                     }
-                }
-                FloodsubSubscriptionAction::Unsubscribe => {
-                    if let Some(pos) = remote_peer_topics.iter().position(|t| t == &subscription.topic ) {
-                        remote_peer_topics.remove(pos);
+                    FloodsubSubscriptionAction::Unsubscribe => {
+                        if let Some(pos) = remote_peer_topics.iter().position(|t| t == &subscription.topic ) {
+                            remote_peer_topics.remove(pos);
+                        }
                     }
                 }
             }
@@ -234,44 +619,47 @@ where
         let mut rpcs_to_dispatch: Vec<(PeerId, FloodsubRpc)> = Vec::new();
 
         for message in event.messages {
+            // Reject the messages that don't comply with our signature policy. They are neither
+            // dispatched to the user nor propagated. This is checked first so that they don't
+            // end up in `self.received`.
+            if !self.is_signature_valid(&message) {
+                debug!("Floodsub message from {:?} has an invalid signature", message.source);
+                self.events.push_back(NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Rejected {
+                    propagation_source: propagation_source.clone(),
+                    message,
+                }));
+                continue;
+            }
+
             // Use `self.received` to skip the messages that we have already received in the past.
             // Note that this can false positive.
             if !self.received.test_and_add(&message) {
                 continue;
             }
 
-            // Add the message to be dispatched to the user.
-            if self.subscribed_topics.iter().any(|t| message.topics.iter().any(|u| t.hash() == u)) {
-                self.events.push_back(NetworkBehaviourAction::GenerateEvent(message.clone()));
-            }
-
-            // Propagate the message to everyone else who is subscribed to any of the topics.
-            for (peer_id, subscr_topics) in self.connected_peers.iter() {
-                if peer_id == &propagation_source {
-                    continue;
-                }
-
-                if !subscr_topics.iter().any(|t| message.topics.iter().any(|u| t == u)) {
-                    continue;
-                }
+            match self.validate(&propagation_source, &message) {
+                Ok(verdict) => {
+                    let source = propagation_source.clone();
+                    self.apply_verdict(source, message, verdict, &mut rpcs_to_dispatch);
+                },
+                Err(verdict) => {
+                    if self.pending_validations.len() >= self.max_pending_validations {
+                        debug!("Too many floodsub messages being validated; ignoring message \
+                                from {:?}", propagation_source);
+                        continue;
+                    }
 
-                if let Some(pos) = rpcs_to_dispatch.iter().position(|(p, _)| p == peer_id) {
-                    rpcs_to_dispatch[pos].1.messages.push(message.clone());
-                } else {
-                    rpcs_to_dispatch.push((peer_id.clone(), FloodsubRpc {
-                        subscriptions: Vec::new(),
-                        messages: vec![message.clone()],
-                    }));
-                }
+                    self.pending_validations.push(PendingValidation {
+                        propagation_source: propagation_source.clone(),
+                        message,
+                        verdict,
+                        timeout: Delay::new(Instant::now() + self.validation_timeout),
+                    });
+                },
             }
         }
 
-        for (peer_id, rpc) in rpcs_to_dispatch {
-            self.events.push_back(NetworkBehaviourAction::SendEvent {
-                peer_id,
-                event: rpc,
-            });
-        }
+        self.dispatch_rpcs(rpcs_to_dispatch);
     }
 
     fn poll(
@@ -283,6 +671,8 @@ where
             Self::OutEvent,
         >,
     > {
+        self.poll_pending_validations();
+
         if let Some(event) = self.events.pop_front() {
             return Async::Ready(event);
         }
@@ -290,3 +680,321 @@ where
         Async::NotReady
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::io::Cursor;
+    use tokio::runtime::current_thread::Runtime;
+    use topic::TopicBuilder;
+
+    type TestFloodsub = Floodsub<Cursor<Vec<u8>>>;
+
+    /// Builds a behaviour subscribed to `topic` and connected to `remote`.
+    fn build(topic: &Topic, remote: &PeerId) -> TestFloodsub {
+        let mut fs = Floodsub::new(PeerId::random());
+        fs.subscribe(topic.clone());
+        connect(&mut fs, remote);
+        fs
+    }
+
+    fn connect(fs: &mut TestFloodsub, remote: &PeerId) {
+        NetworkBehaviour::<()>::inject_connected(fs, remote.clone(), ConnectedPoint::Dialer {
+            address: "/memory".parse().unwrap(),
+        });
+        fs.events.clear();
+    }
+
+    /// Simulates `remote` subscribing to `topic`.
+    fn remote_subscribe(fs: &mut TestFloodsub, remote: &PeerId, topic: &Topic) {
+        NetworkBehaviour::<()>::inject_node_event(fs, remote.clone(), FloodsubHandlerEvent::Rpc(FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: vec![FloodsubSubscription {
+                topic: topic.hash().clone(),
+                action: FloodsubSubscriptionAction::Subscribe,
+            }],
+        }));
+    }
+
+    /// Returns the peers we have queued an RPC for.
+    fn rpc_targets(fs: &TestFloodsub) -> Vec<PeerId> {
+        fs.events.iter().filter_map(|ev| match ev {
+            NetworkBehaviourAction::SendEvent { peer_id, .. } => Some(peer_id.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    /// Builds an unsigned message published by `source` on `topic`.
+    fn message(source: &PeerId, topic: &Topic, data: &[u8]) -> FloodsubMessage {
+        FloodsubMessage {
+            source: source.clone(),
+            data: data.to_vec(),
+            sequence_number: rand::random::<[u8; 20]>().to_vec(),
+            topics: vec![topic.hash().clone()],
+            signature: None,
+            key: None,
+        }
+    }
+
+    /// Simulates `remote` forwarding us `message`.
+    fn inject_message(fs: &mut TestFloodsub, remote: &PeerId, message: FloodsubMessage) {
+        NetworkBehaviour::<()>::inject_node_event(fs, remote.clone(), FloodsubHandlerEvent::Rpc(FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+        }));
+    }
+
+    /// Simulates `remote` sending us a message on `topic`.
+    fn receive(fs: &mut TestFloodsub, remote: &PeerId, topic: &Topic, data: &[u8]) {
+        inject_message(fs, remote, message(remote, topic, data));
+    }
+
+    /// Builds a behaviour with the given signature policy, subscribed to `topic` and connected
+    /// to `remote`.
+    fn build_with_policy(topic: &Topic, remote: &PeerId, policy: SignaturePolicy) -> TestFloodsub {
+        let mut fs = Floodsub::with_signing(Keypair::generate_ed25519(), policy);
+        fs.subscribe(topic.clone());
+        connect(&mut fs, remote);
+        fs
+    }
+
+    /// Builds a message on `topic` signed by a new keypair.
+    fn signed_message(topic: &Topic, data: &[u8]) -> FloodsubMessage {
+        let keypair = Keypair::generate_ed25519();
+        let mut message = message(&keypair.public().into_peer_id(), topic, data);
+        message.sign(&keypair).unwrap();
+        message
+    }
+
+    fn messages(fs: &TestFloodsub) -> Vec<Vec<u8>> {
+        fs.events.iter().filter_map(|ev| match ev {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(msg)) => Some(msg.data.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    fn rejected(fs: &TestFloodsub) -> Vec<Vec<u8>> {
+        fs.events.iter().filter_map(|ev| match ev {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Rejected { message, .. }) => Some(message.data.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn sync_validator_accepts_and_rejects() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        fs.set_validator(topic.hash().clone(), |_, msg| {
+            if msg.data == b"good" { ValidationResult::Accept } else { ValidationResult::Reject }
+        });
+
+        receive(&mut fs, &remote, &topic, b"good");
+        receive(&mut fs, &remote, &topic, b"bad");
+        assert_eq!(messages(&fs), vec![b"good".to_vec()]);
+        assert_eq!(rejected(&fs), vec![b"bad".to_vec()]);
+        assert!(fs.pending_validations.is_empty());
+    }
+
+    #[test]
+    fn async_validator_accepts_and_rejects() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        fs.set_async_validator(topic.hash().clone(), |_, msg| {
+            if msg.data == b"good" {
+                future::ok::<_, ()>(ValidationResult::Accept)
+            } else {
+                future::ok::<_, ()>(ValidationResult::Reject)
+            }
+        });
+
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(future::lazy(|| -> Result<_, ()> {
+            receive(&mut fs, &remote, &topic, b"good");
+            receive(&mut fs, &remote, &topic, b"bad");
+            // Nothing is reported before the verdicts are polled.
+            assert!(fs.events.is_empty());
+            assert_eq!(fs.pending_validations.len(), 2);
+
+            fs.poll_pending_validations();
+            Ok(())
+        })).unwrap();
+
+        assert_eq!(messages(&fs), vec![b"good".to_vec()]);
+        assert!(fs.events.iter().any(|ev| match ev {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Rejected { propagation_source, message }) =>
+                propagation_source == &remote && message.data == b"bad",
+            _ => false,
+        }));
+        assert!(fs.pending_validations.is_empty());
+    }
+
+    #[test]
+    fn async_validation_timeout_rejects() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        fs.set_validation_timeout(Duration::from_millis(50));
+        fs.set_async_validator(topic.hash().clone(), |_, _| future::empty::<ValidationResult, ()>());
+
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(future::lazy(|| -> Result<_, ()> {
+            receive(&mut fs, &remote, &topic, b"slow");
+            Ok(())
+        })).unwrap();
+
+        rt.block_on(future::poll_fn(|| -> Poll<_, ()> {
+            fs.poll_pending_validations();
+            if fs.pending_validations.is_empty() {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        })).unwrap();
+
+        assert!(messages(&fs).is_empty());
+        assert_eq!(rejected(&fs), vec![b"slow".to_vec()]);
+    }
+
+    #[test]
+    fn pending_validations_are_capped() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        fs.set_max_pending_validations(2);
+        fs.set_async_validator(topic.hash().clone(), |_, _| future::empty::<ValidationResult, ()>());
+
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(future::lazy(|| -> Result<_, ()> {
+            for n in 0 .. 5u8 {
+                receive(&mut fs, &remote, &topic, &[n]);
+            }
+            Ok(())
+        })).unwrap();
+
+        assert_eq!(fs.pending_validations.len(), 2);
+        assert!(fs.events.is_empty());
+    }
+
+    #[test]
+    fn peers_on_topic_only_lists_subscribed_floodsub_peers() {
+        let topic = TopicBuilder::new("test").build();
+        let other_topic = TopicBuilder::new("other").build();
+        let subscribed = PeerId::random();
+        let mut fs = build(&topic, &subscribed);
+        remote_subscribe(&mut fs, &subscribed, &topic);
+
+        let not_subscribed = PeerId::random();
+        connect(&mut fs, &not_subscribed);
+        remote_subscribe(&mut fs, &not_subscribed, &other_topic);
+
+        let unsupported = PeerId::random();
+        connect(&mut fs, &unsupported);
+        remote_subscribe(&mut fs, &unsupported, &topic);
+        NetworkBehaviour::<()>::inject_node_event(&mut fs, unsupported.clone(), FloodsubHandlerEvent::ProtocolNotSupported);
+
+        let peers = fs.peers_on_topic(topic.hash()).cloned().collect::<Vec<_>>();
+        assert_eq!(peers, vec![subscribed.clone()]);
+        let peers = fs.peers_on_topic(other_topic.hash()).cloned().collect::<Vec<_>>();
+        assert_eq!(peers, vec![not_subscribed]);
+    }
+
+    #[test]
+    fn nothing_is_sent_to_unsupported_peers() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        remote_subscribe(&mut fs, &remote, &topic);
+        NetworkBehaviour::<()>::inject_node_event(&mut fs, remote.clone(), FloodsubHandlerEvent::ProtocolNotSupported);
+        fs.events.clear();
+
+        fs.publish(topic.hash().clone(), b"hello".to_vec());
+        fs.subscribe(TopicBuilder::new("other").build());
+        fs.unsubscribe(TopicBuilder::new("other").build());
+        assert!(rpc_targets(&fs).is_empty());
+    }
+
+    #[test]
+    fn subscriptions_are_sent_again_when_peer_turns_out_to_support_floodsub() {
+        let topic = TopicBuilder::new("test").build();
+        let other_topic = TopicBuilder::new("other").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+        fs.subscribe(other_topic.clone());
+        NetworkBehaviour::<()>::inject_node_event(&mut fs, remote.clone(), FloodsubHandlerEvent::ProtocolNotSupported);
+        fs.events.clear();
+
+        remote_subscribe(&mut fs, &remote, &topic);
+        let subscriptions = fs.events.iter().filter_map(|ev| match ev {
+            NetworkBehaviourAction::SendEvent { peer_id, event } if peer_id == &remote =>
+                Some(event.subscriptions.clone()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(subscriptions.len(), 1);
+        let topics = subscriptions[0].iter().map(|s| {
+            assert_eq!(s.action, FloodsubSubscriptionAction::Subscribe);
+            s.topic.clone()
+        }).collect::<Vec<_>>();
+        assert_eq!(topics, vec![topic.hash().clone(), other_topic.hash().clone()]);
+        assert_eq!(fs.peers_on_topic(topic.hash()).collect::<Vec<_>>(), vec![&remote]);
+
+        // Further RPCs don't trigger the subscriptions again.
+        fs.events.clear();
+        remote_subscribe(&mut fs, &remote, &other_topic);
+        assert!(rpc_targets(&fs).is_empty());
+    }
+
+    #[test]
+    fn strict_sign_drops_unsigned_messages() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build_with_policy(&topic, &remote, SignaturePolicy::StrictSign);
+
+        receive(&mut fs, &remote, &topic, b"unsigned");
+        inject_message(&mut fs, &remote, signed_message(&topic, b"signed"));
+        assert_eq!(messages(&fs), vec![b"signed".to_vec()]);
+        assert_eq!(rejected(&fs), vec![b"unsigned".to_vec()]);
+    }
+
+    #[test]
+    fn strict_no_sign_drops_signed_messages() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build_with_policy(&topic, &remote, SignaturePolicy::StrictNoSign);
+
+        receive(&mut fs, &remote, &topic, b"unsigned");
+        inject_message(&mut fs, &remote, signed_message(&topic, b"signed"));
+        assert_eq!(messages(&fs), vec![b"unsigned".to_vec()]);
+        assert_eq!(rejected(&fs), vec![b"signed".to_vec()]);
+    }
+
+    #[test]
+    fn lax_drops_messages_with_key_but_no_signature() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build(&topic, &remote);
+
+        let mut keyed = message(&remote, &topic, b"keyed");
+        keyed.key = Some(Keypair::generate_ed25519().public().into_protobuf_encoding());
+        inject_message(&mut fs, &remote, keyed);
+        receive(&mut fs, &remote, &topic, b"unsigned");
+        inject_message(&mut fs, &remote, signed_message(&topic, b"signed"));
+        assert_eq!(messages(&fs), vec![b"unsigned".to_vec(), b"signed".to_vec()]);
+        assert_eq!(rejected(&fs), vec![b"keyed".to_vec()]);
+    }
+
+    #[test]
+    fn rejected_messages_are_not_remembered() {
+        let topic = TopicBuilder::new("test").build();
+        let remote = PeerId::random();
+        let mut fs = build_with_policy(&topic, &remote, SignaturePolicy::StrictSign);
+
+        let unsigned = message(&remote, &topic, b"unsigned");
+        inject_message(&mut fs, &remote, unsigned.clone());
+        inject_message(&mut fs, &remote, unsigned);
+        assert_eq!(rejected(&fs), vec![b"unsigned".to_vec(), b"unsigned".to_vec()]);
+        assert!(messages(&fs).is_empty());
+    }
+}
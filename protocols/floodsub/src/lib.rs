@@ -27,19 +27,27 @@ extern crate cuckoofilter;
 extern crate fnv;
 extern crate futures;
 extern crate libp2p_core;
+#[macro_use]
+extern crate log;
 extern crate protobuf;
 extern crate rand;
 extern crate smallvec;
 extern crate tokio_codec;
 extern crate tokio_io;
+extern crate tokio_timer;
 extern crate unsigned_varint;
 
+#[cfg(test)]
+extern crate tokio;
+
 pub mod handler;
 pub mod protocol;
+/// Protobuf definitions of the pubsub messages. Shared with the gossipsub implementation.
+#[doc(hidden)]
+pub mod rpc_proto;
 
 mod layer;
-mod rpc_proto;
 mod topic;
 
-pub use self::layer::Floodsub;
+pub use self::layer::{Floodsub, FloodsubEvent, SignaturePolicy, ValidationResult};
 pub use self::topic::{Topic, TopicBuilder, TopicHash};
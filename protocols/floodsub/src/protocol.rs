@@ -21,7 +21,8 @@
 use bytes::{BufMut, BytesMut};
 use crate::rpc_proto;
 use futures::future;
-use libp2p_core::{InboundUpgrade, OutboundUpgrade, UpgradeInfo, PeerId};
+use libp2p_core::{InboundUpgrade, OutboundUpgrade, UpgradeInfo, PeerId, PublicKey};
+use libp2p_core::identity::{Keypair, SigningError};
 use protobuf::Message as ProtobufMessage;
 use std::{io, iter};
 use tokio_codec::{Decoder, Encoder, Framed};
@@ -93,17 +94,13 @@ impl Encoder for FloodsubCodec {
         let mut proto = rpc_proto::RPC::new();
 
         for message in item.messages.into_iter() {
-            let mut msg = rpc_proto::Message::new();
-            msg.set_from(message.source.into_bytes());
-            msg.set_data(message.data);
-            msg.set_seqno(message.sequence_number);
-            msg.set_topicIDs(
-                message
-                    .topics
-                    .into_iter()
-                    .map(TopicHash::into_string)
-                    .collect(),
-            );
+            let mut msg = message.unsigned_proto();
+            if let Some(signature) = message.signature {
+                msg.set_signature(signature);
+            }
+            if let Some(key) = message.key {
+                msg.set_key(key);
+            }
             proto.mut_publish().push(msg);
         }
 
@@ -154,6 +151,16 @@ impl Decoder for FloodsubCodec {
                     .into_iter()
                     .map(|topic| TopicHash::from_raw(topic))
                     .collect(),
+                signature: if publish.has_signature() {
+                    Some(publish.take_signature())
+                } else {
+                    None
+                },
+                key: if publish.has_key() {
+                    Some(publish.take_key())
+                } else {
+                    None
+                },
             });
         }
 
@@ -200,6 +207,99 @@ pub struct FloodsubMessage {
     ///
     /// Each message can belong to multiple topics at once.
     pub topics: Vec<TopicHash>,
+
+    /// Signature of the message by the private key of `source`, if the message is signed.
+    pub signature: Option<Vec<u8>>,
+
+    /// Protobuf encoding of the public key of `source`.
+    ///
+    /// Omitted if the public key can be extracted from `source`, which is the case for Ed25519
+    /// keys.
+    pub key: Option<Vec<u8>>,
+}
+
+impl FloodsubMessage {
+    /// Signs the message with the keypair of the local node, which must be the one of `source`,
+    /// and fills the `signature` and `key` fields.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), SigningError> {
+        let signature = keypair.sign(&self.signed_bytes())?;
+        let public = keypair.public();
+        self.key = if public_key_from_peer_id(&self.source).as_ref() == Some(&public) {
+            None
+        } else {
+            Some(public.into_protobuf_encoding())
+        };
+        self.signature = Some(signature);
+        Ok(())
+    }
+
+    /// Returns true if the message has a signature and if this signature is valid for the public
+    /// key of `source`.
+    pub fn verify_signature(&self) -> bool {
+        let signature = match self.signature {
+            Some(ref signature) => signature,
+            None => return false,
+        };
+
+        let public = match self.key {
+            Some(ref key) => match PublicKey::from_protobuf_encoding(key) {
+                Ok(public) => public,
+                Err(_) => return false,
+            },
+            None => match public_key_from_peer_id(&self.source) {
+                Some(public) => public,
+                None => return false,
+            },
+        };
+
+        if self.source.is_public_key(&public) != Some(true) {
+            return false;
+        }
+
+        public.verify(&self.signed_bytes(), signature)
+    }
+
+    /// Builds the protobuf message of this message, without the `signature` and `key` fields.
+    fn unsigned_proto(&self) -> rpc_proto::Message {
+        let mut msg = rpc_proto::Message::new();
+        msg.set_from(self.source.clone().into_bytes());
+        msg.set_data(self.data.clone());
+        msg.set_seqno(self.sequence_number.clone());
+        msg.set_topicIDs(
+            self.topics
+                .iter()
+                .map(|topic| topic.clone().into_string())
+                .collect(),
+        );
+        msg
+    }
+
+    /// Returns the bytes covered by the signature of the message, which are the protobuf encoding
+    /// of the message without its signature and key, prefixed with `SIGNING_PREFIX`.
+    fn signed_bytes(&self) -> Vec<u8> {
+        let proto = self.unsigned_proto()
+            .write_to_bytes()
+            .expect("there is no situation in which the protobuf message can be invalid");
+        let mut bytes = Vec::with_capacity(SIGNING_PREFIX.len() + proto.len());
+        bytes.extend_from_slice(SIGNING_PREFIX);
+        bytes.extend_from_slice(&proto);
+        bytes
+    }
+}
+
+/// Prefix of the data signed by the source of a message, as defined by the pubsub specifications.
+const SIGNING_PREFIX: &[u8] = b"libp2p-pubsub:";
+
+/// Extracts the public key from a peer ID if it is inlined in it.
+fn public_key_from_peer_id(peer_id: &PeerId) -> Option<PublicKey> {
+    // Peer IDs of small public keys are identity multihashes of the protobuf encoding of the key.
+    // A SHA-256 digest can't be decoded as a key that hashes back to the same peer ID.
+    let public = PublicKey::from_protobuf_encoding(peer_id.digest()).ok()?;
+    if peer_id.is_public_key(&public) == Some(true) {
+        Some(public)
+    } else {
+        None
+    }
 }
 
 /// A subscription received by the floodsub system.
@@ -219,3 +319,58 @@ pub enum FloodsubSubscriptionAction {
     /// The remote wants to unsubscribe from the given topic.
     Unsubscribe,
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use libp2p_core::identity::Keypair;
+    use super::{FloodsubCodec, FloodsubMessage, FloodsubRpc};
+    use tokio_codec::{Decoder, Encoder};
+    use topic::TopicBuilder;
+
+    fn message(keypair: &Keypair) -> FloodsubMessage {
+        FloodsubMessage {
+            source: keypair.public().into_peer_id(),
+            data: b"hello world".to_vec(),
+            sequence_number: vec![1, 2, 3],
+            topics: vec![TopicBuilder::new("topic").build().hash().clone()],
+            signature: None,
+            key: None,
+        }
+    }
+
+    #[test]
+    fn sign_then_verify() {
+        let keypair = Keypair::generate_ed25519();
+        let mut message = message(&keypair);
+        assert!(!message.verify_signature());
+
+        message.sign(&keypair).unwrap();
+        // Ed25519 keys are inlined in the peer ID.
+        assert!(message.key.is_none());
+        assert!(message.verify_signature());
+
+        let mut tampered = message.clone();
+        tampered.data.push(0);
+        assert!(!tampered.verify_signature());
+
+        let mut forged = message.clone();
+        forged.source = Keypair::generate_ed25519().public().into_peer_id();
+        assert!(!forged.verify_signature());
+    }
+
+    #[test]
+    fn signature_survives_encoding() {
+        let keypair = Keypair::generate_ed25519();
+        let mut message = message(&keypair);
+        message.sign(&keypair).unwrap();
+
+        let rpc = FloodsubRpc { messages: vec![message], subscriptions: Vec::new() };
+        let mut codec = FloodsubCodec { length_prefix: Default::default() };
+        let mut buf = BytesMut::new();
+        codec.encode(rpc.clone(), &mut buf).unwrap();
+        let decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded, rpc);
+        assert!(decoded.messages[0].verify_signature());
+    }
+}
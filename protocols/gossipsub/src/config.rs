@@ -0,0 +1,118 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::time::Duration;
+
+/// Configuration of the gossipsub protocol.
+///
+/// The default values are the ones recommended by the specification.
+#[derive(Debug, Clone)]
+pub struct GossipsubConfig {
+    /// Number of heartbeats during which we keep the messages in the cache.
+    pub(crate) history_length: usize,
+    /// Number of past heartbeats whose messages are advertised with `IHAVE`.
+    pub(crate) history_gossip: usize,
+    /// Target number of peers in the mesh of a topic.
+    pub(crate) mesh_n: usize,
+    /// Minimum number of peers in the mesh of a topic before we add more.
+    pub(crate) mesh_n_low: usize,
+    /// Maximum number of peers in the mesh of a topic before we remove some.
+    pub(crate) mesh_n_high: usize,
+    /// Number of peers outside of the mesh we send `IHAVE` messages to at each heartbeat.
+    pub(crate) gossip_lazy: usize,
+    /// Delay before the first heartbeat.
+    pub(crate) heartbeat_initial_delay: Duration,
+    /// Interval between two heartbeats.
+    pub(crate) heartbeat_interval: Duration,
+    /// How long we keep the fanout of a topic after our last publication on it.
+    pub(crate) fanout_ttl: Duration,
+    /// Maximum size of an RPC message sent or received on a substream.
+    pub(crate) max_transmit_size: usize,
+}
+
+impl Default for GossipsubConfig {
+    fn default() -> Self {
+        GossipsubConfig {
+            history_length: 5,
+            history_gossip: 3,
+            mesh_n: 6,
+            mesh_n_low: 4,
+            mesh_n_high: 12,
+            gossip_lazy: 6,
+            heartbeat_initial_delay: Duration::from_secs(5),
+            heartbeat_interval: Duration::from_secs(1),
+            fanout_ttl: Duration::from_secs(60),
+            max_transmit_size: 2048 * 1024,
+        }
+    }
+}
+
+impl GossipsubConfig {
+    /// Sets the number of heartbeats during which messages are kept in the cache, and the number
+    /// of those heartbeats whose messages are advertised to other peers.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `history_gossip` is larger than `history_length`.
+    pub fn with_history(mut self, history_length: usize, history_gossip: usize) -> Self {
+        assert!(history_gossip <= history_length, "history_gossip must not exceed history_length");
+        self.history_length = history_length;
+        self.history_gossip = history_gossip;
+        self
+    }
+
+    /// Sets the target, minimum and maximum number of peers in the mesh of a topic.
+    ///
+    /// # Panic
+    ///
+    /// Panics if we don't have `mesh_n_low <= mesh_n <= mesh_n_high`.
+    pub fn with_mesh_n(mut self, mesh_n_low: usize, mesh_n: usize, mesh_n_high: usize) -> Self {
+        assert!(mesh_n_low <= mesh_n && mesh_n <= mesh_n_high, "invalid mesh bounds");
+        self.mesh_n_low = mesh_n_low;
+        self.mesh_n = mesh_n;
+        self.mesh_n_high = mesh_n_high;
+        self
+    }
+
+    /// Sets the number of peers outside of the mesh we gossip to at each heartbeat.
+    pub fn with_gossip_lazy(mut self, gossip_lazy: usize) -> Self {
+        self.gossip_lazy = gossip_lazy;
+        self
+    }
+
+    /// Sets the delay before the first heartbeat and the interval between two heartbeats.
+    pub fn with_heartbeat(mut self, initial_delay: Duration, interval: Duration) -> Self {
+        self.heartbeat_initial_delay = initial_delay;
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Sets how long we keep the fanout of a topic after our last publication on it.
+    pub fn with_fanout_ttl(mut self, fanout_ttl: Duration) -> Self {
+        self.fanout_ttl = fanout_ttl;
+        self
+    }
+
+    /// Sets the maximum size of an RPC message sent or received on a substream.
+    pub fn with_max_transmit_size(mut self, max_transmit_size: usize) -> Self {
+        self.max_transmit_size = max_transmit_size;
+        self
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::prelude::*;
+use libp2p_core::{
+    ProtocolsHandler, ProtocolsHandlerEvent,
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
+    upgrade::{InboundUpgrade, OutboundUpgrade}
+};
+use protocol::{GossipsubCodec, GossipsubProtocol, GossipsubRpc, PeerKind};
+use smallvec::SmallVec;
+use std::{fmt, io};
+use tokio_codec::Framed;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Maximum number of substreams open at the same time with a remote, including the outbound
+/// substreams being negotiated. Inbound substreams over the limit are closed, and outbound
+/// messages wait in the queue.
+const MAX_SUBSTREAMS: usize = 32;
+
+/// Protocol handler that handles communication with the remote for the gossipsub protocol.
+///
+/// The handler will automatically open a substream with the remote for each request we make.
+///
+/// It also handles requests made by the remote.
+pub struct GossipsubHandler<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    /// Configuration for the gossipsub protocol.
+    config: GossipsubProtocol,
+
+    /// If true, we are trying to shut down the existing gossipsub substream and should refuse any
+    /// incoming connection.
+    shutting_down: bool,
+
+    /// The active substreams. Never contains more than `MAX_SUBSTREAMS` elements.
+    substreams: Vec<SubstreamState<TSubstream>>,
+
+    /// Number of outbound substreams that have been requested and that are being negotiated.
+    pending_outbound: usize,
+
+    /// Queue of values that we want to send to the remote.
+    send_queue: SmallVec<[GossipsubRpc; 16]>,
+
+    /// Protocol spoken by the remote, once a substream has been negotiated.
+    peer_kind: Option<PeerKind>,
+
+    /// True if `peer_kind` has been reported to the behaviour.
+    peer_kind_reported: bool,
+}
+
+/// Event produced by the `GossipsubHandler`.
+#[derive(Debug, Clone)]
+pub enum GossipsubHandlerEvent {
+    /// The protocol spoken by the remote has been determined.
+    PeerKind(PeerKind),
+    /// The remote has sent us an RPC.
+    Rpc(GossipsubRpc),
+}
+
+/// State of an active substream, opened either by us or by the remote.
+enum SubstreamState<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    /// Waiting for a message from the remote.
+    WaitingInput(Framed<TSubstream, GossipsubCodec>),
+    /// Waiting to send a message to the remote.
+    PendingSend(Framed<TSubstream, GossipsubCodec>, GossipsubRpc),
+    /// Waiting to flush the substream so that the data arrives to the remote.
+    PendingFlush(Framed<TSubstream, GossipsubCodec>),
+    /// The substream is being closed.
+    Closing(Framed<TSubstream, GossipsubCodec>),
+}
+
+impl<TSubstream> SubstreamState<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    /// Consumes this state and produces the substream.
+    fn into_substream(self) -> Framed<TSubstream, GossipsubCodec> {
+        match self {
+            SubstreamState::WaitingInput(substream) => substream,
+            SubstreamState::PendingSend(substream, _) => substream,
+            SubstreamState::PendingFlush(substream) => substream,
+            SubstreamState::Closing(substream) => substream,
+        }
+    }
+}
+
+impl<TSubstream> GossipsubHandler<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    /// Builds a new `GossipsubHandler`.
+    pub fn new(max_transmit_size: usize) -> Self {
+        GossipsubHandler {
+            config: GossipsubProtocol::new(max_transmit_size),
+            shutting_down: false,
+            substreams: Vec::new(),
+            pending_outbound: 0,
+            send_queue: SmallVec::new(),
+            peer_kind: None,
+            peer_kind_reported: false,
+        }
+    }
+}
+
+impl<TSubstream> ProtocolsHandler for GossipsubHandler<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    type InEvent = GossipsubRpc;
+    type OutEvent = GossipsubHandlerEvent;
+    type Substream = TSubstream;
+    type InboundProtocol = GossipsubProtocol;
+    type OutboundProtocol = GossipsubProtocol;
+    type OutboundOpenInfo = GossipsubRpc;
+
+    #[inline]
+    fn listen_protocol(&self) -> Self::InboundProtocol {
+        self.config.clone()
+    }
+
+    fn inject_fully_negotiated_inbound(
+        &mut self,
+        (substream, kind): <Self::InboundProtocol as InboundUpgrade<TSubstream>>::Output
+    ) {
+        // Dropping the substream closes it.
+        if self.shutting_down || self.substreams.len() + self.pending_outbound >= MAX_SUBSTREAMS {
+            return ()
+        }
+        self.peer_kind.get_or_insert(kind);
+        self.substreams.push(SubstreamState::WaitingInput(substream))
+    }
+
+    fn inject_fully_negotiated_outbound(
+        &mut self,
+        (substream, kind): <Self::OutboundProtocol as OutboundUpgrade<TSubstream>>::Output,
+        message: Self::OutboundOpenInfo
+    ) {
+        self.pending_outbound -= 1;
+        if self.shutting_down {
+            return ()
+        }
+        self.peer_kind.get_or_insert(kind);
+        self.substreams.push(SubstreamState::PendingSend(substream, message))
+    }
+
+    #[inline]
+    fn inject_event(&mut self, message: GossipsubRpc) {
+        self.send_queue.push(message);
+    }
+
+    #[inline]
+    fn inject_inbound_closed(&mut self) {}
+
+    #[inline]
+    fn inject_dial_upgrade_error(&mut self, _: Self::OutboundOpenInfo, _: ProtocolsHandlerUpgrErr<<Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Error>) {
+        self.pending_outbound -= 1;
+    }
+
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        // The remote may be part of our meshes at any time, so the connection is always useful.
+        KeepAlive::Forever
+    }
+
+    #[inline]
+    fn shutdown(&mut self) {
+        self.shutting_down = true;
+        for n in (0..self.substreams.len()).rev() {
+            let substream = self.substreams.swap_remove(n);
+            self.substreams.push(SubstreamState::Closing(substream.into_substream()));
+        }
+    }
+
+    fn poll(
+        &mut self,
+    ) -> Poll<
+        Option<ProtocolsHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::OutEvent>>,
+        io::Error,
+    > {
+        if !self.peer_kind_reported {
+            if let Some(kind) = self.peer_kind {
+                self.peer_kind_reported = true;
+                return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(
+                    GossipsubHandlerEvent::PeerKind(kind)
+                ))));
+            }
+        }
+
+        if !self.send_queue.is_empty() && self.substreams.len() + self.pending_outbound < MAX_SUBSTREAMS {
+            let message = self.send_queue.remove(0);
+            self.pending_outbound += 1;
+            return Ok(Async::Ready(Some(
+                ProtocolsHandlerEvent::OutboundSubstreamRequest {
+                    info: message,
+                    upgrade: self.config.clone(),
+                },
+            )));
+        }
+
+        for n in (0..self.substreams.len()).rev() {
+            let mut substream = self.substreams.swap_remove(n);
+            loop {
+                substream = match substream {
+                    SubstreamState::WaitingInput(mut substream) => match substream.poll() {
+                        Ok(Async::Ready(Some(message))) => {
+                            self.substreams
+                                .push(SubstreamState::WaitingInput(substream));
+                            let event = GossipsubHandlerEvent::Rpc(message);
+                            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
+                        }
+                        Ok(Async::Ready(None)) => SubstreamState::Closing(substream),
+                        Ok(Async::NotReady) => {
+                            self.substreams
+                                .push(SubstreamState::WaitingInput(substream));
+                            return Ok(Async::NotReady);
+                        }
+                        Err(_) => SubstreamState::Closing(substream),
+                    },
+                    SubstreamState::PendingSend(mut substream, message) => {
+                        match substream.start_send(message)? {
+                            AsyncSink::Ready => SubstreamState::PendingFlush(substream),
+                            AsyncSink::NotReady(message) => {
+                                self.substreams
+                                    .push(SubstreamState::PendingSend(substream, message));
+                                return Ok(Async::NotReady);
+                            }
+                        }
+                    }
+                    SubstreamState::PendingFlush(mut substream) => {
+                        match substream.poll_complete()? {
+                            Async::Ready(()) => SubstreamState::Closing(substream),
+                            Async::NotReady => {
+                                self.substreams
+                                    .push(SubstreamState::PendingFlush(substream));
+                                return Ok(Async::NotReady);
+                            }
+                        }
+                    }
+                    SubstreamState::Closing(mut substream) => match substream.close() {
+                        Ok(Async::Ready(())) => break,
+                        Ok(Async::NotReady) => {
+                            self.substreams.push(SubstreamState::Closing(substream));
+                            return Ok(Async::NotReady);
+                        }
+                        Err(_) => return Ok(Async::Ready(None)),
+                    },
+                }
+            }
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<TSubstream> fmt::Debug for GossipsubHandler<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("GossipsubHandler")
+            .field("shutting_down", &self.shutting_down)
+            .field("substreams", &self.substreams.len())
+            .field("pending_outbound", &self.pending_outbound)
+            .field("send_queue", &self.send_queue.len())
+            .field("peer_kind", &self.peer_kind)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GossipsubHandler, MAX_SUBSTREAMS};
+    use futures::prelude::*;
+    use libp2p_core::{ProtocolsHandler, ProtocolsHandlerEvent, upgrade::InboundUpgrade};
+    use protocol::{GossipsubProtocol, GossipsubRpc};
+    use std::io::Cursor;
+
+    type TestHandler = GossipsubHandler<Cursor<Vec<u8>>>;
+
+    #[test]
+    fn inbound_substreams_are_limited() {
+        let mut handler = TestHandler::new(1024);
+        for _ in 0..MAX_SUBSTREAMS + 1 {
+            let negotiated = GossipsubProtocol::new(1024)
+                .upgrade_inbound(Cursor::new(Vec::new()), b"/meshsub/1.0.0")
+                .wait()
+                .unwrap();
+            handler.inject_fully_negotiated_inbound(negotiated);
+        }
+        assert_eq!(handler.substreams.len(), MAX_SUBSTREAMS);
+    }
+
+    #[test]
+    fn outbound_substreams_are_limited() {
+        let mut handler = TestHandler::new(1024);
+        for _ in 0..MAX_SUBSTREAMS + 1 {
+            handler.inject_event(GossipsubRpc::default());
+        }
+
+        let mut requests = 0;
+        while let Async::Ready(Some(event)) = handler.poll().unwrap() {
+            match event {
+                ProtocolsHandlerEvent::OutboundSubstreamRequest { .. } => requests += 1,
+                _ => panic!("unexpected event"),
+            }
+        }
+        assert_eq!(requests, MAX_SUBSTREAMS);
+        assert_eq!(handler.send_queue.len(), 1);
+    }
+}
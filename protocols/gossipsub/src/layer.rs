@@ -0,0 +1,818 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use config::GossipsubConfig;
+use cuckoofilter::CuckooFilter;
+use futures::prelude::*;
+use handler::{GossipsubHandler, GossipsubHandlerEvent};
+use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
+use libp2p_core::{protocols_handler::ProtocolsHandler, PeerId};
+use libp2p_floodsub::{Topic, TopicHash};
+use mcache::MessageCache;
+use protocol::{GossipsubControlAction, GossipsubMessage, GossipsubRpc, GossipsubSubscription};
+use protocol::{GossipsubSubscriptionAction, MessageId, PeerKind};
+use rand::{self, seq::SliceRandom};
+use smallvec::SmallVec;
+use std::{collections::VecDeque, iter, marker::PhantomData, time::Instant};
+use std::collections::hash_map::{DefaultHasher, HashMap};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Interval;
+
+/// Network behaviour that handles the gossipsub protocol.
+///
+/// Messages are only forwarded to the peers of a mesh maintained for each topic we are subscribed
+/// to, and to the floodsub peers. The other peers are notified of the messages with gossip, and
+/// can request them.
+pub struct Gossipsub<TSubstream> {
+    /// Configuration of the protocol.
+    config: GossipsubConfig,
+
+    /// Events that need to be yielded to the outside when polling.
+    events: VecDeque<NetworkBehaviourAction<GossipsubRpc, GossipsubEvent>>,
+
+    /// Control messages to send at the next heartbeat, piggybacked together.
+    control_pool: HashMap<PeerId, Vec<GossipsubControlAction>>,
+
+    /// Peer id of the local node. Used for the source of the messages that we publish.
+    local_peer_id: PeerId,
+
+    /// List of peers we are connected to, with the topics they are subscribed to and the protocol
+    /// they speak.
+    connected_peers: HashMap<PeerId, PeerInfo>,
+
+    /// List of topics we're subscribed to.
+    subscribed_topics: SmallVec<[Topic; 16]>,
+
+    /// For each topic we're subscribed to, the peers we forward the messages to.
+    mesh: HashMap<TopicHash, Vec<PeerId>>,
+
+    /// For each topic we publish to without being subscribed, the peers we send the messages to.
+    fanout: HashMap<TopicHash, Vec<PeerId>>,
+
+    /// When we last published on each of the topics of `fanout`.
+    fanout_last_pub: HashMap<TopicHash, Instant>,
+
+    /// Messages of the last heartbeats, for gossip and `IWANT` requests.
+    mcache: MessageCache,
+
+    // We keep track of the messages we received (in the format `hash(source ID, seq_no)`) so that
+    // we don't dispatch the same message twice if we receive it twice on the network.
+    received: CuckooFilter<DefaultHasher>,
+
+    /// Interval at which we maintain the meshes and emit gossip.
+    heartbeat: Interval,
+
+    /// Marker to pin the generics.
+    marker: PhantomData<TSubstream>,
+}
+
+/// Information about a connected peer.
+#[derive(Debug, Clone)]
+struct PeerInfo {
+    /// Topics the peer is subscribed to.
+    topics: SmallVec<[TopicHash; 8]>,
+    /// Protocol spoken by the peer. Assumed to be gossipsub until the handler reports otherwise.
+    kind: PeerKind,
+}
+
+/// Event that can happen on the gossipsub behaviour.
+#[derive(Debug, Clone)]
+pub enum GossipsubEvent {
+    /// A message has been received on a topic we are subscribed to.
+    Message(GossipsubMessage),
+
+    /// A remote subscribed to a topic.
+    Subscribed {
+        /// Remote that has subscribed.
+        peer_id: PeerId,
+        /// The topic it has subscribed to.
+        topic: TopicHash,
+    },
+
+    /// A remote unsubscribed from a topic.
+    Unsubscribed {
+        /// Remote that has unsubscribed.
+        peer_id: PeerId,
+        /// The topic it has unsubscribed from.
+        topic: TopicHash,
+    },
+}
+
+impl<TSubstream> Gossipsub<TSubstream> {
+    /// Creates a `Gossipsub`.
+    pub fn new(local_peer_id: PeerId, config: GossipsubConfig) -> Self {
+        let heartbeat = Interval::new(
+            Instant::now() + config.heartbeat_initial_delay,
+            config.heartbeat_interval,
+        );
+
+        Gossipsub {
+            events: VecDeque::new(),
+            control_pool: HashMap::new(),
+            local_peer_id,
+            connected_peers: HashMap::new(),
+            subscribed_topics: SmallVec::new(),
+            mesh: HashMap::new(),
+            fanout: HashMap::new(),
+            fanout_last_pub: HashMap::new(),
+            mcache: MessageCache::new(config.history_gossip, config.history_length),
+            received: CuckooFilter::new(),
+            heartbeat,
+            config,
+            marker: PhantomData,
+        }
+    }
+
+    /// Subscribes to a topic.
+    ///
+    /// Returns true if the subscription worked. Returns false if we were already subscribed.
+    pub fn subscribe(&mut self, topic: Topic) -> bool {
+        if self.subscribed_topics.iter().any(|t| t.hash() == topic.hash()) {
+            return false;
+        }
+
+        for peer in self.connected_peers.keys() {
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id: peer.clone(),
+                event: GossipsubRpc {
+                    subscriptions: vec![GossipsubSubscription {
+                        topic: topic.hash().clone(),
+                        action: GossipsubSubscriptionAction::Subscribe,
+                    }],
+                    ..GossipsubRpc::default()
+                },
+            });
+        }
+
+        // Build the mesh from the fanout peers first, then from the other peers subscribed to
+        // the topic.
+        let hash = topic.hash().clone();
+        let mut mesh_peers = self.fanout.remove(&hash).unwrap_or_default();
+        self.fanout_last_pub.remove(&hash);
+        mesh_peers.truncate(self.config.mesh_n);
+        if mesh_peers.len() < self.config.mesh_n {
+            let missing = self.config.mesh_n - mesh_peers.len();
+            let new_peers = self.random_peers(&hash, missing, |p| !mesh_peers.contains(p));
+            mesh_peers.extend(new_peers);
+        }
+
+        for peer_id in mesh_peers.iter() {
+            self.send_rpc(peer_id.clone(), GossipsubRpc {
+                control_msgs: vec![GossipsubControlAction::Graft { topic: hash.clone() }],
+                ..GossipsubRpc::default()
+            });
+        }
+
+        self.mesh.insert(hash, mesh_peers);
+        self.subscribed_topics.push(topic);
+        true
+    }
+
+    /// Unsubscribes from a topic.
+    ///
+    /// Note that this only requires a `TopicHash` and not a full `Topic`.
+    ///
+    /// Returns true if we were subscribed to this topic.
+    pub fn unsubscribe(&mut self, topic: impl AsRef<TopicHash>) -> bool {
+        let topic = topic.as_ref();
+        let pos = match self.subscribed_topics.iter().position(|t| t.hash() == topic) {
+            Some(pos) => pos,
+            None => return false
+        };
+
+        self.subscribed_topics.remove(pos);
+
+        for peer in self.connected_peers.keys() {
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id: peer.clone(),
+                event: GossipsubRpc {
+                    subscriptions: vec![GossipsubSubscription {
+                        topic: topic.clone(),
+                        action: GossipsubSubscriptionAction::Unsubscribe,
+                    }],
+                    ..GossipsubRpc::default()
+                },
+            });
+        }
+
+        for peer_id in self.mesh.remove(topic).unwrap_or_default() {
+            self.send_rpc(peer_id, GossipsubRpc {
+                control_msgs: vec![GossipsubControlAction::Prune { topic: topic.clone() }],
+                ..GossipsubRpc::default()
+            });
+        }
+
+        true
+    }
+
+    /// Publishes a message to the network.
+    ///
+    /// Contrary to floodsub, we don't need to be subscribed to the topic. The message is then
+    /// sent to a set of peers subscribed to the topic, which is kept for subsequent publications.
+    pub fn publish(&mut self, topic: impl Into<TopicHash>, data: impl Into<Vec<u8>>) {
+        self.publish_many(iter::once(topic), data)
+    }
+
+    /// Publishes a message with multiple topics to the network.
+    pub fn publish_many(&mut self, topic: impl IntoIterator<Item = impl Into<TopicHash>>, data: impl Into<Vec<u8>>) {
+        let message = GossipsubMessage {
+            source: self.local_peer_id.clone(),
+            data: data.into(),
+            // If the sequence numbers are predictable, then an attacker could flood the network
+            // with packets with the predetermined sequence numbers and absorb our legitimate
+            // messages. We therefore use a random number.
+            sequence_number: rand::random::<[u8; 20]>().to_vec(),
+            topics: topic.into_iter().map(|t| t.into().clone()).collect(),
+        };
+
+        self.received.add(&message.id());
+        self.mcache.put(message.clone());
+
+        let mut recipients: Vec<PeerId> = Vec::new();
+        for topic in message.topics.iter() {
+            // Floodsub peers receive all the messages of the topics they are subscribed to.
+            let mut topic_peers = self.connected_peers
+                .iter()
+                .filter(|(_, info)| info.kind == PeerKind::Floodsub && info.topics.contains(topic))
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect::<Vec<_>>();
+
+            if let Some(mesh_peers) = self.mesh.get(topic) {
+                topic_peers.extend(mesh_peers.iter().cloned());
+            } else {
+                self.fanout_last_pub.insert(topic.clone(), Instant::now());
+                if !self.fanout.contains_key(topic) {
+                    let peers = self.random_peers(topic, self.config.mesh_n, |_| true);
+                    self.fanout.insert(topic.clone(), peers);
+                }
+                topic_peers.extend(self.fanout[topic].iter().cloned());
+            }
+
+            for peer_id in topic_peers {
+                if !recipients.contains(&peer_id) {
+                    recipients.push(peer_id);
+                }
+            }
+        }
+
+        for peer_id in recipients {
+            self.send_rpc(peer_id, GossipsubRpc {
+                messages: vec![message.clone()],
+                ..GossipsubRpc::default()
+            });
+        }
+    }
+
+    /// Returns up to `count` random gossipsub peers subscribed to `topic` for which `filter`
+    /// returns true.
+    fn random_peers(&self, topic: &TopicHash, count: usize, filter: impl Fn(&PeerId) -> bool) -> Vec<PeerId> {
+        let mut peers = self.connected_peers
+            .iter()
+            .filter(|(_, info)| info.kind == PeerKind::Gossipsub && info.topics.contains(topic))
+            .map(|(peer_id, _)| peer_id)
+            .filter(|peer_id| filter(peer_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        peers.shuffle(&mut rand::thread_rng());
+        peers.truncate(count);
+        peers
+    }
+
+    /// Queues an RPC to send to a peer.
+    fn send_rpc(&mut self, peer_id: PeerId, rpc: GossipsubRpc) {
+        self.events.push_back(NetworkBehaviourAction::SendEvent {
+            peer_id,
+            event: rpc,
+        });
+    }
+
+    /// Removes the peer from the mesh and the fanout of all the topics, or of the given topic.
+    fn remove_from_meshes(&mut self, peer_id: &PeerId, topic: Option<&TopicHash>) {
+        for (t, peers) in self.mesh.iter_mut().chain(self.fanout.iter_mut()) {
+            if topic.map(|topic| topic == t).unwrap_or(true) {
+                peers.retain(|p| p != peer_id);
+            }
+        }
+    }
+
+    /// Handles the control messages sent by a remote, and returns the messages and control
+    /// messages to send back.
+    fn handle_control(&mut self, peer_id: &PeerId, control_msgs: Vec<GossipsubControlAction>)
+        -> (Vec<GossipsubMessage>, Vec<GossipsubControlAction>)
+    {
+        let mut messages = Vec::new();
+        let mut answer = Vec::new();
+        let mut iwant = Vec::new();
+
+        for action in control_msgs {
+            match action {
+                GossipsubControlAction::IHave { topic, message_ids } => {
+                    if !self.mesh.contains_key(&topic) {
+                        continue;
+                    }
+                    for id in message_ids {
+                        if !self.received.contains(&id) && !iwant.contains(&id) {
+                            iwant.push(id);
+                        }
+                    }
+                },
+                GossipsubControlAction::IWant { message_ids } => {
+                    for id in message_ids {
+                        if let Some(message) = self.mcache.get(&id) {
+                            messages.push(message.clone());
+                        }
+                    }
+                },
+                GossipsubControlAction::Graft { topic } => {
+                    match self.mesh.get_mut(&topic) {
+                        Some(peers) => {
+                            if !peers.contains(peer_id) {
+                                peers.push(peer_id.clone());
+                            }
+                        },
+                        // We're not subscribed to the topic; refuse the graft.
+                        None => answer.push(GossipsubControlAction::Prune { topic }),
+                    }
+                },
+                GossipsubControlAction::Prune { topic } => {
+                    if let Some(peers) = self.mesh.get_mut(&topic) {
+                        peers.retain(|p| p != peer_id);
+                    }
+                },
+            }
+        }
+
+        if !iwant.is_empty() {
+            answer.push(GossipsubControlAction::IWant { message_ids: iwant });
+        }
+
+        (messages, answer)
+    }
+
+    /// Maintains the meshes and the fanout, and emits gossip. Called at each heartbeat.
+    fn on_heartbeat(&mut self) {
+        let mut to_graft: Vec<(PeerId, TopicHash)> = Vec::new();
+        let mut to_prune: Vec<(PeerId, TopicHash)> = Vec::new();
+
+        let topics = self.mesh.keys().cloned().collect::<Vec<_>>();
+        for topic in topics {
+            let mut peers = self.mesh.remove(&topic).unwrap_or_default();
+            if peers.len() < self.config.mesh_n_low {
+                let missing = self.config.mesh_n - peers.len();
+                let new_peers = self.random_peers(&topic, missing, |p| !peers.contains(p));
+                for peer_id in new_peers {
+                    to_graft.push((peer_id.clone(), topic.clone()));
+                    peers.push(peer_id);
+                }
+            } else if peers.len() > self.config.mesh_n_high {
+                peers.shuffle(&mut rand::thread_rng());
+                for peer_id in peers.drain(self.config.mesh_n..) {
+                    to_prune.push((peer_id, topic.clone()));
+                }
+            }
+            self.mesh.insert(topic, peers);
+        }
+
+        // Forget the fanout of the topics we haven't published to for a while.
+        let now = Instant::now();
+        let fanout_ttl = self.config.fanout_ttl;
+        let expired = self.fanout_last_pub
+            .iter()
+            .filter(|(_, last_pub)| **last_pub + fanout_ttl < now)
+            .map(|(topic, _)| topic.clone())
+            .collect::<Vec<_>>();
+        for topic in expired {
+            self.fanout.remove(&topic);
+            self.fanout_last_pub.remove(&topic);
+        }
+
+        let topics = self.fanout.keys().cloned().collect::<Vec<_>>();
+        for topic in topics {
+            let mut peers = self.fanout.remove(&topic).unwrap_or_default();
+            if peers.len() < self.config.mesh_n {
+                let missing = self.config.mesh_n - peers.len();
+                let new_peers = self.random_peers(&topic, missing, |p| !peers.contains(p));
+                peers.extend(new_peers);
+            }
+            self.fanout.insert(topic, peers);
+        }
+
+        // Advertise the recent messages to some of the peers outside of the meshes.
+        let topics = self.mesh.iter().chain(self.fanout.iter())
+            .map(|(topic, peers)| (topic.clone(), peers.clone()))
+            .collect::<Vec<_>>();
+        for (topic, peers) in topics {
+            let message_ids = self.mcache.gossip_ids(&topic);
+            if message_ids.is_empty() {
+                continue;
+            }
+
+            let gossip_peers = self.random_peers(&topic, self.config.gossip_lazy, |p| !peers.contains(p));
+            for peer_id in gossip_peers {
+                self.control_pool.entry(peer_id).or_insert_with(Vec::new).push(GossipsubControlAction::IHave {
+                    topic: topic.clone(),
+                    message_ids: message_ids.clone(),
+                });
+            }
+        }
+
+        for (peer_id, topic) in to_graft {
+            self.control_pool.entry(peer_id).or_insert_with(Vec::new)
+                .push(GossipsubControlAction::Graft { topic });
+        }
+        for (peer_id, topic) in to_prune {
+            self.control_pool.entry(peer_id).or_insert_with(Vec::new)
+                .push(GossipsubControlAction::Prune { topic });
+        }
+
+        let control_pool = self.control_pool.drain().collect::<Vec<_>>();
+        for (peer_id, control_msgs) in control_pool {
+            self.send_rpc(peer_id, GossipsubRpc {
+                control_msgs,
+                ..GossipsubRpc::default()
+            });
+        }
+
+        self.mcache.shift();
+    }
+}
+
+impl<TSubstream, TTopology> NetworkBehaviour<TTopology> for Gossipsub<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    type ProtocolsHandler = GossipsubHandler<TSubstream>;
+    type OutEvent = GossipsubEvent;
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        GossipsubHandler::new(self.config.max_transmit_size)
+    }
+
+    fn inject_connected(&mut self, id: PeerId, _: ConnectedPoint) {
+        // We need to send our subscriptions to the newly-connected node.
+        if !self.subscribed_topics.is_empty() {
+            let subscriptions = self.subscribed_topics
+                .iter()
+                .map(|topic| GossipsubSubscription {
+                    topic: topic.hash().clone(),
+                    action: GossipsubSubscriptionAction::Subscribe,
+                })
+                .collect();
+            self.send_rpc(id.clone(), GossipsubRpc {
+                subscriptions,
+                ..GossipsubRpc::default()
+            });
+        }
+
+        self.connected_peers.insert(id, PeerInfo {
+            topics: SmallVec::new(),
+            kind: PeerKind::Gossipsub,
+        });
+    }
+
+    fn inject_disconnected(&mut self, id: &PeerId, _: ConnectedPoint) {
+        let was_in = self.connected_peers.remove(id);
+        debug_assert!(was_in.is_some());
+        self.remove_from_meshes(id, None);
+        self.control_pool.remove(id);
+    }
+
+    fn inject_node_event(
+        &mut self,
+        propagation_source: PeerId,
+        event: GossipsubHandlerEvent,
+    ) {
+        let event = match event {
+            GossipsubHandlerEvent::PeerKind(kind) => {
+                if let Some(info) = self.connected_peers.get_mut(&propagation_source) {
+                    info.kind = kind;
+                }
+                if kind == PeerKind::Floodsub {
+                    self.remove_from_meshes(&propagation_source, None);
+                }
+                return;
+            },
+            GossipsubHandlerEvent::Rpc(rpc) => rpc,
+        };
+
+        // Update connected peers topics
+        for subscription in event.subscriptions {
+            {
+                let remote_peer_topics = &mut self.connected_peers
+                    .get_mut(&propagation_source)
+                    .expect("connected_peers is kept in sync with the peers we are connected to; we are guaranteed to only receive events from connected peers; QED")
+                    .topics;
+                match subscription.action {
+                    GossipsubSubscriptionAction::Subscribe => {
+                        if remote_peer_topics.contains(&subscription.topic) {
+                            continue;
+                        }
+                        remote_peer_topics.push(subscription.topic.clone());
+                    }
+                    GossipsubSubscriptionAction::Unsubscribe => {
+                        match remote_peer_topics.iter().position(|t| t == &subscription.topic) {
+                            Some(pos) => { remote_peer_topics.remove(pos); },
+                            None => continue,
+                        }
+                    }
+                }
+            }
+
+            let event = match subscription.action {
+                GossipsubSubscriptionAction::Subscribe => GossipsubEvent::Subscribed {
+                    peer_id: propagation_source.clone(),
+                    topic: subscription.topic,
+                },
+                GossipsubSubscriptionAction::Unsubscribe => {
+                    self.remove_from_meshes(&propagation_source, Some(&subscription.topic));
+                    GossipsubEvent::Unsubscribed {
+                        peer_id: propagation_source.clone(),
+                        topic: subscription.topic,
+                    }
+                },
+            };
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(event));
+        }
+
+        // List of messages we're going to propagate on the network.
+        let mut rpcs_to_dispatch: Vec<(PeerId, GossipsubRpc)> = Vec::new();
+
+        for message in event.messages {
+            // Use `self.received` to skip the messages that we have already received in the past.
+            // Note that this can false positive.
+            if !self.received.test_and_add(&message.id()) {
+                continue;
+            }
+
+            self.mcache.put(message.clone());
+
+            // Add the message to be dispatched to the user.
+            if self.subscribed_topics.iter().any(|t| message.topics.iter().any(|u| t.hash() == u)) {
+                self.events.push_back(NetworkBehaviourAction::GenerateEvent(GossipsubEvent::Message(message.clone())));
+            }
+
+            // Propagate the message to our mesh peers and to the floodsub peers subscribed to any
+            // of the topics.
+            for (peer_id, info) in self.connected_peers.iter() {
+                if peer_id == &propagation_source || peer_id == &message.source {
+                    continue;
+                }
+
+                let in_mesh = message.topics.iter()
+                    .any(|t| self.mesh.get(t).map(|peers| peers.contains(peer_id)).unwrap_or(false));
+                let floodsub = info.kind == PeerKind::Floodsub &&
+                    info.topics.iter().any(|t| message.topics.iter().any(|u| t == u));
+                if !in_mesh && !floodsub {
+                    continue;
+                }
+
+                if let Some(pos) = rpcs_to_dispatch.iter().position(|(p, _)| p == peer_id) {
+                    rpcs_to_dispatch[pos].1.messages.push(message.clone());
+                } else {
+                    rpcs_to_dispatch.push((peer_id.clone(), GossipsubRpc {
+                        messages: vec![message.clone()],
+                        ..GossipsubRpc::default()
+                    }));
+                }
+            }
+        }
+
+        // Answer the control messages directly.
+        let (messages, control_msgs) = self.handle_control(&propagation_source, event.control_msgs);
+        if !messages.is_empty() || !control_msgs.is_empty() {
+            self.send_rpc(propagation_source, GossipsubRpc {
+                messages,
+                control_msgs,
+                ..GossipsubRpc::default()
+            });
+        }
+
+        for (peer_id, rpc) in rpcs_to_dispatch {
+            self.send_rpc(peer_id, rpc);
+        }
+    }
+
+    fn poll(
+        &mut self,
+        _: &mut PollParameters<TTopology>,
+    ) -> Async<
+        NetworkBehaviourAction<
+            <Self::ProtocolsHandler as ProtocolsHandler>::InEvent,
+            Self::OutEvent,
+        >,
+    > {
+        // Errors of the timer are ignored; we'll try again at the next poll.
+        while let Ok(Async::Ready(Some(_))) = self.heartbeat.poll() {
+            self.on_heartbeat();
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Async::Ready(event);
+        }
+
+        Async::NotReady
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gossipsub, GossipsubEvent};
+    use config::GossipsubConfig;
+    use handler::GossipsubHandlerEvent;
+    use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction};
+    use libp2p_core::PeerId;
+    use libp2p_floodsub::{Topic, TopicBuilder};
+    use protocol::{GossipsubControlAction, GossipsubRpc, GossipsubSubscription};
+    use protocol::{GossipsubSubscriptionAction, PeerKind};
+
+    type TestGossipsub = Gossipsub<::std::io::Cursor<Vec<u8>>>;
+
+    fn endpoint() -> ConnectedPoint {
+        ConnectedPoint::Dialer { address: "/memory".parse().unwrap() }
+    }
+
+    fn subscribe(gs: &mut TestGossipsub, peer: &PeerId, topic: &libp2p_floodsub::TopicHash) {
+        NetworkBehaviour::<()>::inject_node_event(gs, peer.clone(), GossipsubHandlerEvent::Rpc(GossipsubRpc {
+            subscriptions: vec![GossipsubSubscription {
+                action: GossipsubSubscriptionAction::Subscribe,
+                topic: topic.clone(),
+            }],
+            ..GossipsubRpc::default()
+        }));
+    }
+
+    fn drain(gs: &mut TestGossipsub) -> Vec<NetworkBehaviourAction<GossipsubRpc, GossipsubEvent>> {
+        gs.events.drain(..).collect()
+    }
+
+    #[test]
+    fn subscribe_grafts_up_to_mesh_n() {
+        let mut gs = TestGossipsub::new(PeerId::random(), GossipsubConfig::default().with_mesh_n(1, 2, 3));
+        let topic = TopicBuilder::new("test").build();
+        let peers = (0..4).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for peer in peers.iter() {
+            NetworkBehaviour::<()>::inject_connected(&mut gs, peer.clone(), endpoint());
+            subscribe(&mut gs, peer, topic.hash());
+        }
+        drain(&mut gs);
+
+        assert!(gs.subscribe(topic.clone()));
+        assert_eq!(gs.mesh[topic.hash()].len(), 2);
+        let grafts = drain(&mut gs).into_iter().filter(|ev| match ev {
+            NetworkBehaviourAction::SendEvent { event, .. } => event.control_msgs.iter().any(|c| match c {
+                GossipsubControlAction::Graft { .. } => true,
+                _ => false,
+            }),
+            _ => false,
+        }).count();
+        assert_eq!(grafts, 2);
+    }
+
+    #[test]
+    fn graft_on_unknown_topic_is_pruned() {
+        let mut gs = TestGossipsub::new(PeerId::random(), GossipsubConfig::default());
+        let peer = PeerId::random();
+        let topic = TopicBuilder::new("test").build().hash().clone();
+        NetworkBehaviour::<()>::inject_connected(&mut gs, peer.clone(), endpoint());
+        NetworkBehaviour::<()>::inject_node_event(&mut gs, peer.clone(), GossipsubHandlerEvent::Rpc(GossipsubRpc {
+            control_msgs: vec![GossipsubControlAction::Graft { topic: topic.clone() }],
+            ..GossipsubRpc::default()
+        }));
+
+        match drain(&mut gs).pop() {
+            Some(NetworkBehaviourAction::SendEvent { peer_id, event }) => {
+                assert_eq!(peer_id, peer);
+                assert_eq!(event.control_msgs, vec![GossipsubControlAction::Prune { topic }]);
+            },
+            _ => panic!("expected a PRUNE"),
+        }
+    }
+
+    #[test]
+    fn floodsub_peers_receive_publications() {
+        let mut gs = TestGossipsub::new(PeerId::random(), GossipsubConfig::default());
+        let topic = TopicBuilder::new("test").build();
+        let peer = PeerId::random();
+        NetworkBehaviour::<()>::inject_connected(&mut gs, peer.clone(), endpoint());
+        NetworkBehaviour::<()>::inject_node_event(&mut gs, peer.clone(), GossipsubHandlerEvent::PeerKind(PeerKind::Floodsub));
+        subscribe(&mut gs, &peer, topic.hash());
+        gs.subscribe(topic.clone());
+        assert!(gs.mesh[topic.hash()].is_empty());
+        drain(&mut gs);
+
+        gs.publish(topic.hash().clone(), b"hello".to_vec());
+        let sent = drain(&mut gs).into_iter().any(|ev| match ev {
+            NetworkBehaviourAction::SendEvent { peer_id, event } => peer_id == peer && event.messages.len() == 1,
+            _ => false,
+        });
+        assert!(sent);
+    }
+
+    /// Returns the control messages that have been queued, with the peer they are sent to.
+    fn control_msgs(events: Vec<NetworkBehaviourAction<GossipsubRpc, GossipsubEvent>>) -> Vec<(PeerId, GossipsubControlAction)> {
+        events.into_iter()
+            .filter_map(|ev| match ev {
+                NetworkBehaviourAction::SendEvent { peer_id, event } => Some((peer_id, event.control_msgs)),
+                _ => None,
+            })
+            .flat_map(|(peer_id, msgs)| msgs.into_iter().map(move |msg| (peer_id.clone(), msg)))
+            .collect()
+    }
+
+    /// Builds a behaviour subscribed to `topic`, connected to `num_peers` peers that are also
+    /// subscribed to it.
+    fn subscribed_with_peers(config: GossipsubConfig, topic: &Topic, num_peers: usize) -> (TestGossipsub, Vec<PeerId>) {
+        let mut gs = TestGossipsub::new(PeerId::random(), config);
+        gs.subscribe(topic.clone());
+        let peers = (0..num_peers).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for peer in peers.iter() {
+            NetworkBehaviour::<()>::inject_connected(&mut gs, peer.clone(), endpoint());
+            subscribe(&mut gs, peer, topic.hash());
+        }
+        drain(&mut gs);
+        (gs, peers)
+    }
+
+    fn control(gs: &mut TestGossipsub, peer: &PeerId, action: GossipsubControlAction) {
+        NetworkBehaviour::<()>::inject_node_event(gs, peer.clone(), GossipsubHandlerEvent::Rpc(GossipsubRpc {
+            control_msgs: vec![action],
+            ..GossipsubRpc::default()
+        }));
+    }
+
+    #[test]
+    fn graft_and_prune_update_the_mesh() {
+        let topic = TopicBuilder::new("test").build();
+        let (mut gs, peers) = subscribed_with_peers(GossipsubConfig::default(), &topic, 1);
+        assert!(gs.mesh[topic.hash()].is_empty());
+
+        control(&mut gs, &peers[0], GossipsubControlAction::Graft { topic: topic.hash().clone() });
+        assert_eq!(gs.mesh[topic.hash()], peers);
+        assert!(control_msgs(drain(&mut gs)).is_empty());
+
+        control(&mut gs, &peers[0], GossipsubControlAction::Prune { topic: topic.hash().clone() });
+        assert!(gs.mesh[topic.hash()].is_empty());
+    }
+
+    #[test]
+    fn heartbeat_fills_mesh_below_mesh_n_low() {
+        let topic = TopicBuilder::new("test").build();
+        let (mut gs, peers) = subscribed_with_peers(GossipsubConfig::default().with_mesh_n(2, 3, 4), &topic, 5);
+        assert!(gs.mesh[topic.hash()].is_empty());
+
+        gs.on_heartbeat();
+        let mesh = gs.mesh[topic.hash()].clone();
+        assert_eq!(mesh.len(), 3);
+        assert!(mesh.iter().all(|p| peers.contains(p)));
+        let mut grafted = control_msgs(drain(&mut gs)).into_iter()
+            .filter_map(|(peer_id, msg)| match msg {
+                GossipsubControlAction::Graft { .. } => Some(peer_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        grafted.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        let mut expected = mesh;
+        expected.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        assert_eq!(grafted, expected);
+    }
+
+    #[test]
+    fn heartbeat_prunes_mesh_above_mesh_n_high() {
+        let topic = TopicBuilder::new("test").build();
+        let (mut gs, peers) = subscribed_with_peers(GossipsubConfig::default().with_mesh_n(1, 2, 3), &topic, 5);
+        for peer in peers.iter() {
+            control(&mut gs, peer, GossipsubControlAction::Graft { topic: topic.hash().clone() });
+        }
+        assert_eq!(gs.mesh[topic.hash()].len(), 5);
+        drain(&mut gs);
+
+        gs.on_heartbeat();
+        let mesh = gs.mesh[topic.hash()].clone();
+        assert_eq!(mesh.len(), 2);
+        let pruned = control_msgs(drain(&mut gs)).into_iter()
+            .filter_map(|(peer_id, msg)| match msg {
+                GossipsubControlAction::Prune { .. } => Some(peer_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(pruned.len(), 3);
+        assert!(pruned.iter().all(|p| !mesh.contains(p)));
+    }
+}
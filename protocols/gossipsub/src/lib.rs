@@ -0,0 +1,62 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implements the gossipsub protocol, see also the
+//! [spec](https://github.com/libp2p/specs/tree/master/pubsub/gossipsub).
+//!
+//! Contrary to floodsub, which sends every message to every connected peer subscribed to its
+//! topic, gossipsub only forwards messages to a small mesh of peers maintained for each topic.
+//! The rest of the peers are periodically told which messages we have received with `IHAVE`
+//! gossip, and can ask for the ones they missed with `IWANT`.
+//!
+//! # Usage
+//!
+//! Create a `Gossipsub` with a `GossipsubConfig`, and use it as the network behaviour of a swarm.
+//! Topics are built with a `TopicBuilder`, and are compatible with the ones of floodsub.
+//!
+//! Peers that only support floodsub are detected when negotiating the protocol. They receive
+//! every message of the topics they are subscribed to, as they would from a floodsub node.
+
+extern crate bs58;
+extern crate bytes;
+extern crate cuckoofilter;
+extern crate fnv;
+extern crate futures;
+extern crate libp2p_core;
+extern crate libp2p_floodsub;
+extern crate protobuf;
+extern crate rand;
+extern crate smallvec;
+extern crate tokio_codec;
+extern crate tokio_io;
+extern crate tokio_timer;
+extern crate unsigned_varint;
+
+pub mod handler;
+pub mod protocol;
+
+mod config;
+mod layer;
+mod mcache;
+
+pub use self::config::GossipsubConfig;
+pub use self::layer::{Gossipsub, GossipsubEvent};
+pub use self::protocol::{GossipsubMessage, MessageId};
+pub use libp2p_floodsub::{Topic, TopicBuilder, TopicHash};
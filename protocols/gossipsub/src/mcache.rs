@@ -0,0 +1,143 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use fnv::FnvHashMap;
+use libp2p_floodsub::TopicHash;
+use protocol::{GossipsubMessage, MessageId};
+
+/// Cache of the messages received during the last heartbeats.
+///
+/// Messages are stored in windows, one per heartbeat. The cache answers `IWANT` requests for
+/// all the windows, while only the most recent ones are advertised with `IHAVE`.
+#[derive(Debug, Clone)]
+pub struct MessageCache {
+    /// The messages, indexed by their identifier.
+    msgs: FnvHashMap<MessageId, GossipsubMessage>,
+    /// Identifiers and topics of the messages of each window, the most recent window first.
+    history: Vec<Vec<(MessageId, Vec<TopicHash>)>>,
+    /// Number of windows whose messages are returned by `gossip_ids`.
+    gossip: usize,
+}
+
+impl MessageCache {
+    /// Creates a cache that keeps `history_length` windows and advertises the `history_gossip`
+    /// most recent ones.
+    pub fn new(history_gossip: usize, history_length: usize) -> MessageCache {
+        debug_assert!(history_gossip <= history_length);
+        MessageCache {
+            msgs: FnvHashMap::default(),
+            history: vec![Vec::new(); history_length],
+            gossip: history_gossip,
+        }
+    }
+
+    /// Adds a message to the current window.
+    pub fn put(&mut self, message: GossipsubMessage) {
+        let id = message.id();
+        if self.msgs.contains_key(&id) {
+            return;
+        }
+
+        if let Some(window) = self.history.first_mut() {
+            window.push((id.clone(), message.topics.clone()));
+            self.msgs.insert(id, message);
+        }
+    }
+
+    /// Returns the message with the given identifier, if it is in the cache.
+    #[inline]
+    pub fn get(&self, id: &MessageId) -> Option<&GossipsubMessage> {
+        self.msgs.get(id)
+    }
+
+    /// Returns the identifiers of the messages of the given topic in the windows to advertise.
+    pub fn gossip_ids(&self, topic: &TopicHash) -> Vec<MessageId> {
+        self.history
+            .iter()
+            .take(self.gossip)
+            .flat_map(|window| window.iter())
+            .filter(|(_, topics)| topics.iter().any(|t| t == topic))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Opens a new window and drops the messages of the oldest one. Called at each heartbeat.
+    pub fn shift(&mut self) {
+        if let Some(oldest) = self.history.pop() {
+            for (id, _) in oldest {
+                self.msgs.remove(&id);
+            }
+            self.history.insert(0, Vec::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageCache;
+    use libp2p_core::PeerId;
+    use libp2p_floodsub::{TopicBuilder, TopicHash};
+    use protocol::GossipsubMessage;
+
+    fn message(topic: &TopicHash, seq: u8) -> GossipsubMessage {
+        GossipsubMessage {
+            source: PeerId::random(),
+            data: vec![seq],
+            sequence_number: vec![seq],
+            topics: vec![topic.clone()],
+        }
+    }
+
+    #[test]
+    fn gossip_covers_recent_windows() {
+        let topic = TopicBuilder::new("a").build().hash().clone();
+        let other = TopicBuilder::new("b").build().hash().clone();
+        let mut cache = MessageCache::new(2, 3);
+
+        let first = message(&topic, 1);
+        cache.put(first.clone());
+        cache.put(message(&other, 2));
+        assert_eq!(cache.gossip_ids(&topic), vec![first.id()]);
+
+        cache.shift();
+        let second = message(&topic, 3);
+        cache.put(second.clone());
+        assert_eq!(cache.gossip_ids(&topic), vec![second.id(), first.id()]);
+
+        // The first message is still in the cache, but no longer advertised.
+        cache.shift();
+        assert_eq!(cache.gossip_ids(&topic), vec![second.id()]);
+        assert!(cache.get(&first.id()).is_some());
+
+        cache.shift();
+        assert!(cache.get(&first.id()).is_none());
+        assert!(cache.get(&second.id()).is_some());
+    }
+
+    #[test]
+    fn duplicates_ignored() {
+        let topic = TopicBuilder::new("a").build().hash().clone();
+        let mut cache = MessageCache::new(1, 1);
+        let msg = message(&topic, 1);
+        cache.put(msg.clone());
+        cache.put(msg.clone());
+        assert_eq!(cache.gossip_ids(&topic), vec![msg.id()]);
+    }
+}
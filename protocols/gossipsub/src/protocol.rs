@@ -0,0 +1,429 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use bs58;
+use bytes::{BufMut, BytesMut};
+use futures::future;
+use libp2p_core::{InboundUpgrade, OutboundUpgrade, UpgradeInfo, PeerId};
+use libp2p_floodsub::{rpc_proto, TopicHash};
+use protobuf::Message as ProtobufMessage;
+use std::{io, vec};
+use tokio_codec::{Decoder, Encoder, Framed};
+use tokio_io::{AsyncRead, AsyncWrite};
+use unsigned_varint::codec;
+
+/// Name of the gossipsub protocol.
+const GOSSIPSUB_PROTOCOL: &[u8] = b"/meshsub/1.0.0";
+/// Name of the floodsub protocol, which we support in order to talk to floodsub peers.
+const FLOODSUB_PROTOCOL: &[u8] = b"/floodsub/1.0.0";
+
+/// Implementation of `ConnectionUpgrade` for the gossipsub protocol.
+///
+/// Negotiates either gossipsub or floodsub, and reports which one was picked alongside the
+/// substream.
+#[derive(Debug, Clone)]
+pub struct GossipsubProtocol {
+    /// Maximum size of an RPC message.
+    max_transmit_size: usize,
+}
+
+impl GossipsubProtocol {
+    /// Builds a new `GossipsubProtocol`.
+    #[inline]
+    pub fn new(max_transmit_size: usize) -> GossipsubProtocol {
+        GossipsubProtocol { max_transmit_size }
+    }
+
+    /// Wraps the socket in a codec.
+    fn framed<TSocket>(&self, socket: TSocket, info: &'static [u8]) -> (Framed<TSocket, GossipsubCodec>, PeerKind)
+    where
+        TSocket: AsyncRead + AsyncWrite,
+    {
+        let mut length_prefix = codec::UviBytes::default();
+        length_prefix.set_max_len(self.max_transmit_size);
+        let codec = GossipsubCodec { length_prefix, max_transmit_size: self.max_transmit_size };
+        let kind = if info == GOSSIPSUB_PROTOCOL { PeerKind::Gossipsub } else { PeerKind::Floodsub };
+        (Framed::new(socket, codec), kind)
+    }
+}
+
+impl UpgradeInfo for GossipsubProtocol {
+    type Info = &'static [u8];
+    type InfoIter = vec::IntoIter<Self::Info>;
+
+    #[inline]
+    fn protocol_info(&self) -> Self::InfoIter {
+        // Gossipsub comes first so that it is preferred when the remote supports both.
+        vec![GOSSIPSUB_PROTOCOL, FLOODSUB_PROTOCOL].into_iter()
+    }
+}
+
+impl<TSocket> InboundUpgrade<TSocket> for GossipsubProtocol
+where
+    TSocket: AsyncRead + AsyncWrite,
+{
+    type Output = (Framed<TSocket, GossipsubCodec>, PeerKind);
+    type Error = io::Error;
+    type Future = future::FutureResult<Self::Output, Self::Error>;
+
+    #[inline]
+    fn upgrade_inbound(self, socket: TSocket, info: Self::Info) -> Self::Future {
+        future::ok(self.framed(socket, info))
+    }
+}
+
+impl<TSocket> OutboundUpgrade<TSocket> for GossipsubProtocol
+where
+    TSocket: AsyncRead + AsyncWrite,
+{
+    type Output = (Framed<TSocket, GossipsubCodec>, PeerKind);
+    type Error = io::Error;
+    type Future = future::FutureResult<Self::Output, Self::Error>;
+
+    #[inline]
+    fn upgrade_outbound(self, socket: TSocket, info: Self::Info) -> Self::Future {
+        future::ok(self.framed(socket, info))
+    }
+}
+
+/// Protocol spoken by a remote.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PeerKind {
+    /// The remote supports gossipsub.
+    Gossipsub,
+    /// The remote only supports floodsub. It doesn't understand the control messages, and must
+    /// receive every message published on the topics it is subscribed to.
+    Floodsub,
+}
+
+/// Implementation of `tokio_codec::Codec`.
+pub struct GossipsubCodec {
+    /// The codec for encoding/decoding the length prefix of messages.
+    length_prefix: codec::UviBytes,
+    /// Maximum size of an RPC message.
+    max_transmit_size: usize,
+}
+
+impl Encoder for GossipsubCodec {
+    type Item = GossipsubRpc;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut proto = rpc_proto::RPC::new();
+
+        for message in item.messages.into_iter() {
+            let mut msg = rpc_proto::Message::new();
+            msg.set_from(message.source.into_bytes());
+            msg.set_data(message.data);
+            msg.set_seqno(message.sequence_number);
+            msg.set_topicIDs(
+                message
+                    .topics
+                    .into_iter()
+                    .map(TopicHash::into_string)
+                    .collect(),
+            );
+            proto.mut_publish().push(msg);
+        }
+
+        for topic in item.subscriptions.into_iter() {
+            let mut subscription = rpc_proto::RPC_SubOpts::new();
+            subscription.set_subscribe(topic.action == GossipsubSubscriptionAction::Subscribe);
+            subscription.set_topicid(topic.topic.into_string());
+            proto.mut_subscriptions().push(subscription);
+        }
+
+        if !item.control_msgs.is_empty() {
+            let control = proto.mut_control();
+            for action in item.control_msgs.into_iter() {
+                match action {
+                    GossipsubControlAction::IHave { topic, message_ids } => {
+                        let mut ihave = rpc_proto::ControlIHave::new();
+                        ihave.set_topicID(topic.into_string());
+                        ihave.set_messageIDs(message_ids.into_iter().map(MessageId::into_string).collect());
+                        control.mut_ihave().push(ihave);
+                    },
+                    GossipsubControlAction::IWant { message_ids } => {
+                        let mut iwant = rpc_proto::ControlIWant::new();
+                        iwant.set_messageIDs(message_ids.into_iter().map(MessageId::into_string).collect());
+                        control.mut_iwant().push(iwant);
+                    },
+                    GossipsubControlAction::Graft { topic } => {
+                        let mut graft = rpc_proto::ControlGraft::new();
+                        graft.set_topicID(topic.into_string());
+                        control.mut_graft().push(graft);
+                    },
+                    GossipsubControlAction::Prune { topic } => {
+                        let mut prune = rpc_proto::ControlPrune::new();
+                        prune.set_topicID(topic.into_string());
+                        control.mut_prune().push(prune);
+                    },
+                }
+            }
+        }
+
+        let msg_size = proto.compute_size() as usize;
+        if msg_size > self.max_transmit_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "RPC message is too large"));
+        }
+
+        // Reserve enough space for the data and the length. The length has a maximum of 32 bits,
+        // which means that 5 bytes is enough for the variable-length integer.
+        dst.reserve(msg_size + 5);
+
+        proto
+            .write_length_delimited_to_writer(&mut dst.by_ref().writer())
+            .expect(
+                "there is no situation in which the protobuf message can be invalid, and \
+                 writing to a BytesMut never fails as we reserved enough space beforehand",
+            );
+        Ok(())
+    }
+}
+
+impl Decoder for GossipsubCodec {
+    type Item = GossipsubRpc;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let packet = match self.length_prefix.decode(src)? {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        let mut rpc: rpc_proto::RPC = protobuf::parse_from_bytes(&packet)?;
+
+        let mut messages = Vec::with_capacity(rpc.get_publish().len());
+        for mut publish in rpc.take_publish().into_iter() {
+            messages.push(GossipsubMessage {
+                source: PeerId::from_bytes(publish.take_from()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Invalid peer ID in message")
+                })?,
+                data: publish.take_data(),
+                sequence_number: publish.take_seqno(),
+                topics: publish
+                    .take_topicIDs()
+                    .into_iter()
+                    .map(TopicHash::from_raw)
+                    .collect(),
+            });
+        }
+
+        let mut control = rpc.take_control();
+        let mut control_msgs = Vec::new();
+        for mut ihave in control.take_ihave().into_iter() {
+            control_msgs.push(GossipsubControlAction::IHave {
+                topic: TopicHash::from_raw(ihave.take_topicID()),
+                message_ids: ihave.take_messageIDs().into_iter().map(MessageId::from_raw).collect(),
+            });
+        }
+        for mut iwant in control.take_iwant().into_iter() {
+            control_msgs.push(GossipsubControlAction::IWant {
+                message_ids: iwant.take_messageIDs().into_iter().map(MessageId::from_raw).collect(),
+            });
+        }
+        for mut graft in control.take_graft().into_iter() {
+            control_msgs.push(GossipsubControlAction::Graft {
+                topic: TopicHash::from_raw(graft.take_topicID()),
+            });
+        }
+        for mut prune in control.take_prune().into_iter() {
+            control_msgs.push(GossipsubControlAction::Prune {
+                topic: TopicHash::from_raw(prune.take_topicID()),
+            });
+        }
+
+        Ok(Some(GossipsubRpc {
+            messages,
+            subscriptions: rpc
+                .take_subscriptions()
+                .into_iter()
+                .map(|mut sub| GossipsubSubscription {
+                    action: if sub.get_subscribe() {
+                        GossipsubSubscriptionAction::Subscribe
+                    } else {
+                        GossipsubSubscriptionAction::Unsubscribe
+                    },
+                    topic: TopicHash::from_raw(sub.take_topicid()),
+                })
+                .collect(),
+            control_msgs,
+        }))
+    }
+}
+
+/// An RPC received or sent by the gossipsub system.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GossipsubRpc {
+    /// List of messages that were part of this RPC query.
+    pub messages: Vec<GossipsubMessage>,
+    /// List of subscriptions.
+    pub subscriptions: Vec<GossipsubSubscription>,
+    /// List of gossipsub control messages.
+    pub control_msgs: Vec<GossipsubControlAction>,
+}
+
+impl GossipsubRpc {
+    /// Returns true if the RPC doesn't contain anything.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.subscriptions.is_empty() && self.control_msgs.is_empty()
+    }
+}
+
+/// Identifier of a message, built from its source and its sequence number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageId(String);
+
+impl MessageId {
+    /// Builds a `MessageId` from its string representation.
+    #[inline]
+    pub fn from_raw(id: String) -> MessageId {
+        MessageId(id)
+    }
+
+    /// Returns the string representation of the identifier.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// A message received by the gossipsub system.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GossipsubMessage {
+    /// Id of the peer that published this message.
+    pub source: PeerId,
+
+    /// Content of the message. Its meaning is out of scope of this library.
+    pub data: Vec<u8>,
+
+    /// A random sequence number.
+    pub sequence_number: Vec<u8>,
+
+    /// List of topics this message belongs to.
+    ///
+    /// Each message can belong to multiple topics at once.
+    pub topics: Vec<TopicHash>,
+}
+
+impl GossipsubMessage {
+    /// Returns the identifier of the message, which is the same for all the copies of the message
+    /// on the network.
+    pub fn id(&self) -> MessageId {
+        let mut id = self.source.to_base58();
+        id.push_str(&bs58::encode(&self.sequence_number).into_string());
+        MessageId(id)
+    }
+}
+
+/// A subscription received by the gossipsub system.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GossipsubSubscription {
+    /// Action to perform.
+    pub action: GossipsubSubscriptionAction,
+    /// The topic from which to subscribe or unsubscribe.
+    pub topic: TopicHash,
+}
+
+/// Action that a subscription wants to perform.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GossipsubSubscriptionAction {
+    /// The remote wants to subscribe to the given topic.
+    Subscribe,
+    /// The remote wants to unsubscribe from the given topic.
+    Unsubscribe,
+}
+
+/// A control message of the gossipsub protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GossipsubControlAction {
+    /// The node has the given messages of a topic in its cache.
+    IHave {
+        /// The topic of the messages.
+        topic: TopicHash,
+        /// Identifiers of the messages.
+        message_ids: Vec<MessageId>,
+    },
+    /// The node requests the given messages, after receiving an `IHave` for them.
+    IWant {
+        /// Identifiers of the requested messages.
+        message_ids: Vec<MessageId>,
+    },
+    /// The node has added us to its mesh for the given topic.
+    Graft {
+        /// The topic of the mesh.
+        topic: TopicHash,
+    },
+    /// The node has removed us from its mesh for the given topic.
+    Prune {
+        /// The topic of the mesh.
+        topic: TopicHash,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p_floodsub::TopicBuilder;
+
+    #[test]
+    fn encode_decode() {
+        let topic = TopicBuilder::new("test").build().hash().clone();
+        let message = GossipsubMessage {
+            source: PeerId::random(),
+            data: b"hello".to_vec(),
+            sequence_number: vec![1, 2, 3],
+            topics: vec![topic.clone()],
+        };
+        let rpc = GossipsubRpc {
+            messages: vec![message.clone()],
+            subscriptions: vec![GossipsubSubscription {
+                action: GossipsubSubscriptionAction::Subscribe,
+                topic: topic.clone(),
+            }],
+            control_msgs: vec![
+                GossipsubControlAction::IHave { topic: topic.clone(), message_ids: vec![message.id()] },
+                GossipsubControlAction::IWant { message_ids: vec![message.id()] },
+                GossipsubControlAction::Graft { topic: topic.clone() },
+                GossipsubControlAction::Prune { topic },
+            ],
+        };
+
+        let mut codec = GossipsubCodec { length_prefix: Default::default(), max_transmit_size: 1024 };
+        let mut buf = BytesMut::new();
+        codec.encode(rpc.clone(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(rpc));
+    }
+
+    #[test]
+    fn refuses_large_rpc() {
+        let message = GossipsubMessage {
+            source: PeerId::random(),
+            data: vec![0; 64],
+            sequence_number: vec![1],
+            topics: Vec::new(),
+        };
+        let rpc = GossipsubRpc { messages: vec![message], ..GossipsubRpc::default() };
+
+        let mut codec = GossipsubCodec { length_prefix: Default::default(), max_transmit_size: 32 };
+        assert!(codec.encode(rpc, &mut BytesMut::new()).is_err());
+    }
+}
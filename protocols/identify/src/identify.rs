@@ -136,6 +136,7 @@ where
             if let NetworkBehaviourAction::GenerateEvent(IdentifyEvent::Identified { ref peer_id, ref info, .. }) = event {
                 let iter = info.listen_addrs.iter().cloned();
                 params.topology().add_identify_discovered_addrs(peer_id, iter);
+                params.topology().add_identify_info(peer_id, info);
             }
 
             return Async::Ready(event);
@@ -21,7 +21,7 @@
 use crate::protocol::{IdentifySender, IdentifyProtocolConfig};
 use futures::prelude::*;
 use libp2p_core::{
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{DeniedUpgrade, InboundUpgrade, OutboundUpgrade}
 };
 use smallvec::SmallVec;
@@ -89,6 +89,15 @@ where
     #[inline]
     fn inject_dial_upgrade_error(&mut self, _: Self::OutboundOpenInfo, _: ProtocolsHandlerUpgrErr<<Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Error>) {}
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        if self.pending_result.is_empty() {
+            KeepAlive::Now
+        } else {
+            KeepAlive::Forever
+        }
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.shutdown = true;
@@ -21,7 +21,7 @@
 use crate::protocol::{RemoteInfo, IdentifyProtocolConfig};
 use futures::prelude::*;
 use libp2p_core::{
-    protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
+    protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr},
     upgrade::{DeniedUpgrade, OutboundUpgrade}
 };
 use std::{io, marker::PhantomData, time::{Duration, Instant}};
@@ -117,6 +117,15 @@ where
         }
     }
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        if self.pending_result.is_some() {
+            KeepAlive::Forever
+        } else {
+            KeepAlive::Now
+        }
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.next_id = None;
@@ -18,8 +18,9 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use crate::protocol::IdentifyInfo;
 use libp2p_core::{Multiaddr, PeerId};
-use libp2p_core::topology::{MemoryTopology, Topology};
+use libp2p_core::topology::{AddressSource, MemoryTopology, PersistentTopology, Topology};
 
 /// Trait required on the topology for the identify system to store addresses.
 pub trait IdentifyTopology: Topology {
@@ -29,6 +30,15 @@ pub trait IdentifyTopology: Topology {
     fn add_identify_discovered_addrs<TIter>(&mut self, peer: &PeerId, addr: TIter)
     where
         TIter: Iterator<Item = Multiaddr>;
+
+    /// Stores the information that a peer sent about itself during identification.
+    ///
+    /// The listening addresses have already been passed to `add_identify_discovered_addrs`. The
+    /// default implementation doesn't do anything.
+    ///
+    /// > **Note**: Will never be called with the local peer ID.
+    #[inline]
+    fn add_identify_info(&mut self, _peer: &PeerId, _info: &IdentifyInfo) {}
 }
 
 impl IdentifyTopology for MemoryTopology {
@@ -40,4 +50,25 @@ impl IdentifyTopology for MemoryTopology {
             self.add_address(peer.clone(), addr);
         }
     }
+
+    #[inline]
+    fn add_identify_info(&mut self, peer: &PeerId, info: &IdentifyInfo) {
+        self.set_protocols(peer, info.protocols.iter().cloned());
+    }
+}
+
+impl IdentifyTopology for PersistentTopology {
+    fn add_identify_discovered_addrs<TIter>(&mut self, peer: &PeerId, addr: TIter)
+    where
+        TIter: Iterator<Item = Multiaddr>,
+    {
+        for addr in addr {
+            self.add_address(peer.clone(), addr, AddressSource::Identify);
+        }
+    }
+
+    fn add_identify_info(&mut self, peer: &PeerId, info: &IdentifyInfo) {
+        self.set_public_key(peer, info.public_key.clone());
+        self.set_protocols(peer, info.protocols.iter().cloned());
+    }
 }
@@ -27,14 +27,17 @@ use multihash::Multihash;
 use protocol::{KadConnectionType, KadPeer};
 use query::{QueryConfig, QueryState, QueryStatePollOut, QueryTarget};
 use rand;
+use record::{MemoryRecordStore, Record, RecordStore, RecordStoreError};
 use smallvec::SmallVec;
-use std::{cmp::Ordering, marker::PhantomData, time::Duration, time::Instant};
+use std::{cmp, cmp::Ordering, iter, marker::PhantomData, time::Duration, time::Instant};
 use tokio_io::{AsyncRead, AsyncWrite};
 use tokio_timer::Interval;
 use topology::KademliaTopology;
 
 /// Network behaviour that handles Kademlia.
-pub struct Kademlia<TSubstream> {
+///
+/// The records of the DHT are kept in a `TStore`, which is by default stored in memory.
+pub struct Kademlia<TSubstream, TStore = MemoryRecordStore> {
     /// Peer ID of the local node.
     local_peer_id: PeerId,
 
@@ -67,6 +70,19 @@ pub struct Kademlia<TSubstream> {
     /// Interval to send `ADD_PROVIDER` messages to everyone.
     refresh_add_providers: stream::Fuse<Interval>,
 
+    /// Storage for the records of the DHT.
+    records: TStore,
+
+    /// Keys of the records that were published with `put_record`. Only these records are
+    /// published again, and remotes aren't allowed to overwrite them.
+    published_keys: FnvHashSet<Multihash>,
+
+    /// How long we keep the records that remotes ask us to store.
+    record_ttl: Duration,
+
+    /// Interval to publish again the records that we published, and to remove expired records.
+    republish_records: stream::Fuse<Interval>,
+
     /// `α` in the Kademlia reference papers. Designates the maximum number of queries that we
     /// perform in parallel.
     parallelism: usize,
@@ -86,6 +102,10 @@ pub struct Kademlia<TSubstream> {
     /// List of providers to add to the topology as soon as we are in `poll()`.
     add_provider: SmallVec<[(Multihash, PeerId); 32]>,
 
+    /// Keys that we no longer provide, and for which the local node must be removed from the
+    /// providers in the topology as soon as we are in `poll()`.
+    remove_local_provider: SmallVec<[Multihash; 8]>,
+
     /// Marker to pin the generics.
     marker: PhantomData<TSubstream>,
 }
@@ -103,13 +123,69 @@ enum QueryPurpose {
     UserRequest,
     /// We should add an `ADD_PROVIDER` message to the peers of the outcome.
     AddProvider(Multihash),
+    /// The user wants the record of the target. Contains the records found so far.
+    GetRecord {
+        /// Number of records to find before reporting a success.
+        quorum: usize,
+        /// Records found so far.
+        records: Vec<Record>,
+    },
+    /// We are looking for the peers closest to the key of the record, in order to store the
+    /// record on them afterwards with a `QueryTarget::PutRecord`.
+    PutRecordFindPeers {
+        /// The record to store.
+        record: Record,
+        /// Number of peers that must store the record for the operation to succeed.
+        quorum: Quorum,
+        /// True if we are publishing the record again. The outcome isn't reported.
+        republish: bool,
+    },
+    /// We are storing the record on the peers closest to its key.
+    PutRecord {
+        /// Key of the record.
+        key: Multihash,
+        /// Number of peers that must store the record for the operation to succeed.
+        quorum: usize,
+        /// Peers that have stored the record.
+        success: Vec<PeerId>,
+        /// True if we are publishing the record again. The outcome isn't reported.
+        republish: bool,
+    },
+}
+
+/// Number of peers that must answer a record query for it to succeed.
+///
+/// For `put_record`, the closest peers are the ones found by the query. For `get_record`, they
+/// are the `k` results that a query looks for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Quorum {
+    /// A single peer.
+    One,
+    /// More than half of the closest peers.
+    Majority,
+    /// All the closest peers found by the query.
+    All,
+    /// The given number of peers. Always at least 1, and at most the number of closest peers.
+    N(usize),
+}
+
+impl Quorum {
+    /// Returns the number of peers, knowing the number of closest peers `k`.
+    fn eval(&self, k: usize) -> usize {
+        match self {
+            Quorum::One => 1,
+            Quorum::Majority => k / 2 + 1,
+            Quorum::All => cmp::max(1, k),
+            Quorum::N(n) => cmp::max(1, cmp::min(*n, k)),
+        }
+    }
 }
 
 impl<TSubstream> Kademlia<TSubstream> {
     /// Creates a `Kademlia`.
     #[inline]
     pub fn new(local_peer_id: PeerId) -> Self {
-        Self::new_inner(local_peer_id, true)
+        Self::new_inner(local_peer_id, MemoryRecordStore::default(), true)
     }
 
     /// Creates a `Kademlia`.
@@ -118,11 +194,22 @@ impl<TSubstream> Kademlia<TSubstream> {
     /// the DHT.
     #[inline]
     pub fn without_init(local_peer_id: PeerId) -> Self {
-        Self::new_inner(local_peer_id, false)
+        Self::new_inner(local_peer_id, MemoryRecordStore::default(), false)
+    }
+}
+
+impl<TSubstream, TStore> Kademlia<TSubstream, TStore>
+where
+    TStore: RecordStore,
+{
+    /// Creates a `Kademlia` that keeps the records of the DHT in the given store.
+    #[inline]
+    pub fn with_record_store(local_peer_id: PeerId, records: TStore) -> Self {
+        Self::new_inner(local_peer_id, records, true)
     }
 
     /// Inner implementation of the constructors.
-    fn new_inner(local_peer_id: PeerId, initialize: bool) -> Self {
+    fn new_inner(local_peer_id: PeerId, records: TStore, initialize: bool) -> Self {
         let parallelism = 3;
 
         let mut behaviour = Kademlia {
@@ -136,11 +223,16 @@ impl<TSubstream> Kademlia<TSubstream> {
             remote_requests: SmallVec::new(),
             providing_keys: SmallVec::new(),
             refresh_add_providers: Interval::new_interval(Duration::from_secs(60)).fuse(),     // TODO: constant
+            records,
+            published_keys: Default::default(),
+            record_ttl: Duration::from_secs(36 * 60 * 60),
+            republish_records: Interval::new_interval(Duration::from_secs(60 * 60)).fuse(),
             parallelism,
             num_results: 20,
             rpc_timeout: Duration::from_secs(8),
             add_to_topology: SmallVec::new(),
             add_provider: SmallVec::new(),
+            remove_local_provider: SmallVec::new(),
             marker: PhantomData,
         };
 
@@ -200,8 +292,12 @@ impl<TSubstream> Kademlia<TSubstream> {
 
                 let local_node_is_providing = self.providing_keys.iter().any(|k| k.as_ref() == &key);
 
+                // The topology also holds an entry for the local node, which we skip because we
+                // add ourselves below with our up-to-date addresses.
+                let local_peer_id = &self.local_peer_id;
                 let provider_peers = topology
                     .get_providers(&key)
+                    .filter(|peer_id| peer_id != local_peer_id)
                     .map(|peer_id| build_kad_peer(peer_id, topology, &self.connected_peers))
                     .chain(if local_node_is_providing {
                         Some(local_kad_peer)
@@ -216,11 +312,40 @@ impl<TSubstream> Kademlia<TSubstream> {
                     request_id,
                 }
             },
+            QueryTarget::GetRecord(key) => {
+                let mut topology = parameters.topology();
+                let closer_peers = topology
+                    .closest_peers(&key, self.num_results)
+                    .map(|peer_id| build_kad_peer(peer_id, topology, &self.connected_peers))
+                    .collect();
+
+                let now = Instant::now();
+                let record = self.records
+                    .get(&key)
+                    .filter(|record| !record.is_expired(now))
+                    .cloned();
+
+                KademliaHandlerIn::GetRecordRes {
+                    record,
+                    closer_peers,
+                    request_id,
+                }
+            },
+            QueryTarget::PutRecord(record) => {
+                KademliaHandlerIn::PutRecordRes {
+                    key: record.key,
+                    value: record.value,
+                    request_id,
+                }
+            },
         }
     }
 }
 
-impl<TSubstream> Kademlia<TSubstream> {
+impl<TSubstream, TStore> Kademlia<TSubstream, TStore>
+where
+    TStore: RecordStore,
+{
     /// Starts an iterative `FIND_NODE` request.
     ///
     /// This will eventually produce an event containing the nodes of the DHT closest to the
@@ -245,6 +370,7 @@ impl<TSubstream> Kademlia<TSubstream> {
     /// The actual meaning of *providing* the value of a key is not defined, and is specific to
     /// the value whose key is the hash.
     pub fn add_providing(&mut self, key: PeerId) {
+        self.remove_local_provider.retain(|k| k != key.as_ref());
         if !self.providing_keys.iter().any(|k| k == &key) {
             self.providing_keys.push(key);
         }
@@ -260,9 +386,99 @@ impl<TSubstream> Kademlia<TSubstream> {
     pub fn remove_providing(&mut self, key: &Multihash) {
         if let Some(position) = self.providing_keys.iter().position(|k| k.as_ref() == key) {
             self.providing_keys.remove(position);
+            self.remove_local_provider.push(key.clone());
         }
     }
 
+    /// Starts an iterative `GET_VALUE` request.
+    ///
+    /// The query finishes once `quorum` peers have answered with the record, and produces a
+    /// `KademliaOut::GetRecordResult` event. If we have the record locally, it counts as one of
+    /// the answers.
+    ///
+    /// The `quorum` is evaluated against the number of results of a query, `k`. For example
+    /// `Quorum::All` requires `k` records.
+    pub fn get_record(&mut self, key: &Multihash, quorum: Quorum) {
+        let quorum = quorum.eval(self.num_results);
+        let now = Instant::now();
+        let records = self.records
+            .get(key)
+            .filter(|record| !record.is_expired(now))
+            .cloned()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        if records.len() >= quorum {
+            let event = KademliaOut::GetRecordResult(Ok(GetRecordOk { records }));
+            self.queued_events.push(NetworkBehaviourAction::GenerateEvent(event));
+            return;
+        }
+
+        let purpose = QueryPurpose::GetRecord { quorum, records };
+        self.start_query(QueryTarget::GetRecord(key.clone()), purpose);
+    }
+
+    /// Stores a record in the DHT.
+    ///
+    /// The record is stored locally, then sent to the peers closest to its key. Produces a
+    /// `KademliaOut::PutRecordResult` event once at least `quorum` of them have stored it.
+    ///
+    /// The record is published again periodically, until it is removed with `remove_record`. In
+    /// the meantime, remotes can't overwrite it in the local store.
+    pub fn put_record(&mut self, mut record: Record, quorum: Quorum) {
+        if record.publisher.is_none() {
+            record.publisher = Some(self.local_peer_id.clone());
+        }
+
+        if let Err(error) = self.records.put(record.clone()) {
+            let event = KademliaOut::PutRecordResult(Err(PutRecordError::LocalStorageError {
+                key: record.key,
+                error,
+            }));
+            self.queued_events.push(NetworkBehaviourAction::GenerateEvent(event));
+            return;
+        }
+
+        self.published_keys.insert(record.key.clone());
+        let target = QueryTarget::GetRecord(record.key.clone());
+        self.start_query(target, QueryPurpose::PutRecordFindPeers { record, quorum, republish: false });
+    }
+
+    /// Removes a record from the local store. If we published it, stops publishing it again.
+    ///
+    /// There doesn't exist any message to remove a record from the network, therefore the
+    /// record will be available in the DHT until it expires on the remotes.
+    #[inline]
+    pub fn remove_record(&mut self, key: &Multihash) {
+        self.published_keys.remove(key);
+        self.records.remove(key);
+    }
+
+    /// Returns the store that contains the records.
+    #[inline]
+    pub fn record_store(&self) -> &TStore {
+        &self.records
+    }
+
+    /// Returns the store that contains the records.
+    #[inline]
+    pub fn record_store_mut(&mut self) -> &mut TStore {
+        &mut self.records
+    }
+
+    /// Sets how long we keep the records that remotes ask us to store.
+    #[inline]
+    pub fn set_record_ttl(&mut self, ttl: Duration) {
+        self.record_ttl = ttl;
+    }
+
+    /// Sets the interval after which we publish again the records that we published, and remove
+    /// the expired records from the store.
+    #[inline]
+    pub fn set_record_republish_interval(&mut self, interval: Duration) {
+        self.republish_records = Interval::new(Instant::now() + interval, interval).fuse();
+    }
+
     /// Internal function that starts a query.
     fn start_query(&mut self, target: QueryTarget, purpose: QueryPurpose) {
         let query_id = self.next_query_id.clone();
@@ -271,9 +487,10 @@ impl<TSubstream> Kademlia<TSubstream> {
     }
 }
 
-impl<TSubstream, TTopology> NetworkBehaviour<TTopology> for Kademlia<TSubstream>
+impl<TSubstream, TStore, TTopology> NetworkBehaviour<TTopology> for Kademlia<TSubstream, TStore>
 where
     TSubstream: AsyncRead + AsyncWrite,
+    TStore: RecordStore,
     TTopology: KademliaTopology,
 {
     type ProtocolsHandler = KademliaHandler<TSubstream, QueryId>;
@@ -304,6 +521,15 @@ where
         }
     }
 
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        // The RPCs that were waiting for this peer to connect will never be sent.
+        self.pending_rpcs.retain(|(id, _)| id != peer_id);
+
+        for (query, _, _) in self.active_queries.values_mut() {
+            query.inject_rpc_error(peer_id);
+        }
+    }
+
     fn inject_node_event(&mut self, source: PeerId, event: KademliaHandlerEvent<QueryId>) {
         match event {
             KademliaHandlerEvent::FindNodeReq { key, request_id } => {
@@ -365,6 +591,86 @@ where
                 self.add_provider.push((key, provider_peer.node_id));
                 return;
             }
+            KademliaHandlerEvent::GetRecord { key, request_id } => {
+                self.remote_requests.push((source, request_id, QueryTarget::GetRecord(key)));
+                return;
+            }
+            KademliaHandlerEvent::GetRecordRes {
+                record,
+                closer_peers,
+                user_data,
+            } => {
+                for peer in closer_peers.iter() {
+                    for addr in peer.multiaddrs.iter() {
+                        self.add_to_topology
+                            .push((peer.node_id.clone(), addr.clone(), peer.connection_ty));
+                    }
+                }
+
+                // It is possible that we obtain a response for a query that has finished, which is
+                // why we may not find an entry in `self.active_queries`.
+                let quorum_reached = if let Some((query, purpose, _)) = self.active_queries.get_mut(&user_data) {
+                    query.inject_rpc_result(&source, closer_peers.into_iter().map(|kp| kp.node_id));
+                    match (purpose, record) {
+                        (QueryPurpose::GetRecord { quorum, records }, Some(record)) => {
+                            if &record.key == query.target().as_hash() {
+                                records.push(record);
+                            }
+                            records.len() >= *quorum
+                        },
+                        _ => false,
+                    }
+                } else {
+                    false
+                };
+
+                if quorum_reached {
+                    if let Some((_, QueryPurpose::GetRecord { records, .. }, _)) = self.active_queries.remove(&user_data) {
+                        let event = KademliaOut::GetRecordResult(Ok(GetRecordOk { records }));
+                        self.queued_events.push(NetworkBehaviourAction::GenerateEvent(event));
+                    }
+                }
+            }
+            KademliaHandlerEvent::PutRecord { mut record, request_id } => {
+                // Remotes can't overwrite the records that we published.
+                if self.published_keys.contains(&record.key) {
+                    self.queued_events.push(NetworkBehaviourAction::SendEvent {
+                        peer_id: source,
+                        event: KademliaHandlerIn::Reset(request_id),
+                    });
+                    return;
+                }
+
+                // Anyone can claim to be us, so we don't trust a record that says we published it.
+                if record.publisher.as_ref() == Some(&self.local_peer_id) {
+                    record.publisher = None;
+                }
+
+                record.expires = Some(Instant::now() + self.record_ttl);
+                match self.records.put(record.clone()) {
+                    Ok(()) => {
+                        self.remote_requests.push((source, request_id, QueryTarget::PutRecord(record)));
+                    },
+                    Err(_) => {
+                        self.queued_events.push(NetworkBehaviourAction::SendEvent {
+                            peer_id: source,
+                            event: KademliaHandlerIn::Reset(request_id),
+                        });
+                    },
+                }
+            }
+            KademliaHandlerEvent::PutRecordRes { key, user_data } => {
+                // It is possible that we obtain a response for a query that has finished, which is
+                // why we may not find an entry in `self.active_queries`.
+                if let Some((query, purpose, _)) = self.active_queries.get_mut(&user_data) {
+                    if let QueryPurpose::PutRecord { key: ref expected, ref mut success, .. } = *purpose {
+                        if &key == expected {
+                            success.push(source.clone());
+                        }
+                    }
+                    query.inject_rpc_result(&source, iter::empty());
+                }
+            }
         };
     }
 
@@ -386,12 +692,20 @@ where
             parameters.topology().add_provider(key, provider);
         }
         self.add_provider.shrink_to_fit();
+        for key in self.remove_local_provider.drain() {
+            parameters.topology().remove_provider(&key, &self.local_peer_id);
+        }
+        self.remove_local_provider.shrink_to_fit();
 
         // Handle `refresh_add_providers`.
         match self.refresh_add_providers.poll() {
             Ok(Async::NotReady) => {},
             Ok(Async::Ready(Some(_))) => {
                 for provided in self.providing_keys.clone().into_iter() {
+                    // Register ourselves in the topology as well, so that it can return the
+                    // local node when asked for the providers of the key. Doing it at each
+                    // refresh keeps the registration from expiring.
+                    parameters.topology().add_provider(provided.as_ref().clone(), self.local_peer_id.clone());
                     let purpose = QueryPurpose::AddProvider(provided.as_ref().clone());
                     self.start_query(QueryTarget::FindPeer(provided), purpose);
                 }
@@ -400,6 +714,28 @@ where
             Ok(Async::Ready(None)) | Err(_) => {},
         }
 
+        // Handle `republish_records`.
+        match self.republish_records.poll() {
+            Ok(Async::NotReady) => {},
+            Ok(Async::Ready(Some(_))) => {
+                self.records.remove_expired(Instant::now());
+                // Forget about the records that have expired or have been removed from the store.
+                let records = &self.records;
+                self.published_keys.retain(|key| records.get(key).is_some());
+                let to_republish = self.published_keys
+                    .iter()
+                    .filter_map(|key| self.records.get(key).cloned())
+                    .collect::<Vec<_>>();
+                for record in to_republish {
+                    let target = QueryTarget::GetRecord(record.key.clone());
+                    let purpose = QueryPurpose::PutRecordFindPeers { record, quorum: Quorum::One, republish: true };
+                    self.start_query(target, purpose);
+                }
+            },
+            // Ignore errors.
+            Ok(Async::Ready(None)) | Err(_) => {},
+        }
+
         // Start queries that are waiting to start.
         for (query_id, query_target, query_purpose) in self.queries_to_starts.drain() {
             let known_closest_peers = parameters
@@ -498,10 +834,59 @@ where
                                     provider_peers,
                                 }
                             },
+                            QueryTarget::GetRecord(_) | QueryTarget::PutRecord(_) => {
+                                unreachable!("record queries are started with a dedicated purpose; QED")
+                            },
+                        };
+
+                        break Async::Ready(NetworkBehaviourAction::GenerateEvent(event));
+                    },
+                    QueryPurpose::GetRecord { quorum, records } => {
+                        let key = query.target().as_hash().clone();
+                        let closest_peers = query.into_closest_peers().collect::<Vec<_>>();
+                        let result = if records.is_empty() {
+                            Err(GetRecordError::NotFound { key, closest_peers })
+                        } else if records.len() < quorum {
+                            Err(GetRecordError::QuorumFailed { key, records, quorum })
+                        } else {
+                            Ok(GetRecordOk { records })
                         };
 
+                        let event = KademliaOut::GetRecordResult(result);
                         break Async::Ready(NetworkBehaviourAction::GenerateEvent(event));
                     },
+                    QueryPurpose::PutRecordFindPeers { record, quorum, republish } => {
+                        // Now that we know the closest peers, store the record on them.
+                        let key = record.key.clone();
+                        let known_closest_peers = query.into_closest_peers().collect::<Vec<_>>();
+                        let quorum = quorum.eval(known_closest_peers.len());
+                        self.active_queries.insert(
+                            finished_query,
+                            (
+                                QueryState::new(QueryConfig {
+                                    target: QueryTarget::PutRecord(record),
+                                    parallelism: self.parallelism,
+                                    num_results: self.num_results,
+                                    rpc_timeout: self.rpc_timeout,
+                                    known_closest_peers,
+                                }),
+                                QueryPurpose::PutRecord { key, quorum, success: Vec::new(), republish },
+                                Vec::new(),
+                            )
+                        );
+                    },
+                    QueryPurpose::PutRecord { key, quorum, success, republish } => {
+                        if !republish {
+                            let result = if success.len() >= quorum {
+                                Ok(PutRecordOk { key })
+                            } else {
+                                Err(PutRecordError::QuorumFailed { key, success, quorum })
+                            };
+
+                            let event = KademliaOut::PutRecordResult(result);
+                            break Async::Ready(NetworkBehaviourAction::GenerateEvent(event));
+                        }
+                    },
                     QueryPurpose::AddProvider(key) => {
                         for closest in query.into_closest_peers() {
                             let event = NetworkBehaviourAction::SendEvent {
@@ -543,6 +928,68 @@ pub enum KademliaOut {
         /// List of peers ordered from closest to furthest away.
         closer_peers: Vec<PeerId>,
     },
+
+    /// Result of a `get_record`.
+    GetRecordResult(Result<GetRecordOk, GetRecordError>),
+
+    /// Result of a `put_record`.
+    PutRecordResult(Result<PutRecordOk, PutRecordError>),
+}
+
+/// Successful outcome of a `get_record`.
+#[derive(Debug, Clone)]
+pub struct GetRecordOk {
+    /// The records that have been found. Contains at least as many records as the quorum.
+    pub records: Vec<Record>,
+}
+
+/// Failed outcome of a `get_record`.
+#[derive(Debug, Clone)]
+pub enum GetRecordError {
+    /// No peer had the record.
+    NotFound {
+        /// The key of the record.
+        key: Multihash,
+        /// The peers closest to the key that we have reached.
+        closest_peers: Vec<PeerId>,
+    },
+    /// Fewer records than the quorum have been found.
+    QuorumFailed {
+        /// The key of the record.
+        key: Multihash,
+        /// The records that have been found.
+        records: Vec<Record>,
+        /// The quorum that we failed to reach.
+        quorum: usize,
+    },
+}
+
+/// Successful outcome of a `put_record`.
+#[derive(Debug, Clone)]
+pub struct PutRecordOk {
+    /// The key of the record.
+    pub key: Multihash,
+}
+
+/// Failed outcome of a `put_record`.
+#[derive(Debug, Clone)]
+pub enum PutRecordError {
+    /// Fewer peers than the quorum have stored the record.
+    QuorumFailed {
+        /// The key of the record.
+        key: Multihash,
+        /// The peers that have stored the record.
+        success: Vec<PeerId>,
+        /// The quorum that we failed to reach.
+        quorum: usize,
+    },
+    /// The record couldn't be stored locally, and hasn't been published.
+    LocalStorageError {
+        /// The key of the record.
+        key: Multihash,
+        /// The error returned by the store.
+        error: RecordStoreError,
+    },
 }
 
 // Generates a random `PeerId` that belongs to the given bucket.
@@ -599,3 +1046,103 @@ where TTopology: Topology
         connection_ty,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use handler::KademliaHandlerEvent;
+    use libp2p_core::swarm::{NetworkBehaviour, NetworkBehaviourAction};
+    use libp2p_core::{topology::MemoryTopology, PeerId};
+    use multihash::{encode, Hash};
+    use query::{QueryConfig, QueryState};
+    use record::{Record, RecordStore};
+    use std::io::Cursor;
+    use super::{Kademlia, KademliaOut, Quorum};
+
+    type TestKademlia = Kademlia<Cursor<Vec<u8>>>;
+
+    fn record() -> Record {
+        Record::new(encode(Hash::SHA2256, &[1, 2, 3]).unwrap(), vec![4, 5, 6])
+    }
+
+    // Starts the queries waiting to start, the way `poll` does, with the given closest peers.
+    fn start_queries(kad: &mut TestKademlia, peers: &[PeerId]) {
+        let to_start = kad.queries_to_starts.drain().collect::<Vec<_>>();
+        for (query_id, target, purpose) in to_start {
+            let query = QueryState::new(QueryConfig {
+                target,
+                parallelism: kad.parallelism,
+                num_results: kad.num_results,
+                rpc_timeout: kad.rpc_timeout,
+                known_closest_peers: peers.to_vec(),
+            });
+            kad.active_queries.insert(query_id, (query, purpose, Vec::new()));
+        }
+    }
+
+    // Injects a `GET_VALUE` response containing the record from `peer` for every active query.
+    fn answer(kad: &mut TestKademlia, peer: &PeerId, record: &Record) {
+        let query_ids = kad.active_queries.keys().cloned().collect::<Vec<_>>();
+        for user_data in query_ids {
+            let event = KademliaHandlerEvent::GetRecordRes {
+                record: Some(record.clone()),
+                closer_peers: Vec::new(),
+                user_data,
+            };
+            NetworkBehaviour::<MemoryTopology>::inject_node_event(kad, peer.clone(), event);
+        }
+    }
+
+    // Returns the number of records of each successful `GetRecordResult` that has been produced.
+    fn get_record_successes(kad: &TestKademlia) -> Vec<usize> {
+        kad.queued_events.iter()
+            .filter_map(|event| match event {
+                NetworkBehaviourAction::GenerateEvent(KademliaOut::GetRecordResult(Ok(ok))) => Some(ok.records.len()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get_record_waits_for_quorum() {
+        let mut kad = TestKademlia::without_init(PeerId::random());
+        let peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+        let record = record();
+
+        kad.get_record(&record.key, Quorum::N(2));
+        start_queries(&mut kad, &peers);
+
+        answer(&mut kad, &peers[0], &record);
+        assert!(get_record_successes(&kad).is_empty());
+        answer(&mut kad, &peers[1], &record);
+        assert_eq!(get_record_successes(&kad), vec![2]);
+        assert!(kad.active_queries.is_empty());
+    }
+
+    #[test]
+    fn local_record_counts_towards_quorum() {
+        let mut kad = TestKademlia::without_init(PeerId::random());
+        let record = record();
+        kad.records.put(record.clone()).unwrap();
+
+        kad.get_record(&record.key, Quorum::One);
+        assert_eq!(get_record_successes(&kad), vec![1]);
+        assert!(kad.queries_to_starts.is_empty());
+    }
+
+    #[test]
+    fn quorum_all_requires_k_records() {
+        let mut kad = TestKademlia::without_init(PeerId::random());
+        let peers = (0..kad.num_results).map(|_| PeerId::random()).collect::<Vec<_>>();
+        let record = record();
+
+        kad.get_record(&record.key, Quorum::All);
+        start_queries(&mut kad, &peers);
+
+        for peer in &peers[..peers.len() - 1] {
+            answer(&mut kad, peer, &record);
+        }
+        assert!(get_record_successes(&kad).is_empty());
+        answer(&mut kad, &peers[peers.len() - 1], &record);
+        assert_eq!(get_record_successes(&kad), vec![kad.num_results]);
+    }
+}
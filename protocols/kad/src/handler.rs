@@ -19,16 +19,20 @@
 // DEALINGS IN THE SOFTWARE.
 
 use futures::prelude::*;
-use libp2p_core::protocols_handler::{ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr};
+use libp2p_core::protocols_handler::{KeepAlive, ProtocolsHandler, ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr};
 use libp2p_core::{upgrade, either::EitherOutput, InboundUpgrade, OutboundUpgrade, PeerId};
 use multihash::Multihash;
 use protocol::{
     KadInStreamSink, KadOutStreamSink, KadPeer, KadRequestMsg, KadResponseMsg,
     KademliaProtocolConfig,
 };
-use std::{error, fmt, io};
+use record::Record;
+use std::{error, fmt, io, time::Duration, time::Instant};
 use tokio_io::{AsyncRead, AsyncWrite};
 
+/// How long to keep a connection open after the last Kademlia substream has been closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Protocol handler that handles Kademlia communications with the remote.
 ///
 /// The handler will automatically open a Kademlia substream with the remote for each request we
@@ -54,6 +58,9 @@ where
 
     /// List of active substreams with the state they are in.
     substreams: Vec<SubstreamState<TSubstream, TUserData>>,
+
+    /// Until when to keep the connection alive.
+    keep_alive: KeepAlive,
 }
 
 /// State of an active substream, opened either by us or by the remote.
@@ -180,6 +187,40 @@ pub enum KademliaHandlerEvent<TUserData> {
         /// Known provider for this key.
         provider_peer: KadPeer,
     },
+
+    /// Request for the record with the given key.
+    GetRecord {
+        /// Key of the record.
+        key: Multihash,
+        /// Identifier of the request. Needs to be passed back when answering.
+        request_id: KademliaRequestId,
+    },
+
+    /// Response to a `KademliaHandlerIn::GetRecord`.
+    GetRecordRes {
+        /// The record, if the remote has it.
+        record: Option<Record>,
+        /// Nodes closest to the key.
+        closer_peers: Vec<KadPeer>,
+        /// The user data passed to the `GetRecord`.
+        user_data: TUserData,
+    },
+
+    /// The remote asks us to store a record.
+    PutRecord {
+        /// The record to store.
+        record: Record,
+        /// Identifier of the request. Needs to be passed back when answering.
+        request_id: KademliaRequestId,
+    },
+
+    /// Response to a `KademliaHandlerIn::PutRecord`.
+    PutRecordRes {
+        /// Key of the record that the remote has stored.
+        key: Multihash,
+        /// The user data passed to the `PutRecord`.
+        user_data: TUserData,
+    },
 }
 
 /// Error that can happen when requesting an RPC query.
@@ -278,6 +319,49 @@ pub enum KademliaHandlerIn<TUserData> {
         /// Known provider for this key.
         provider_peer: KadPeer,
     },
+
+    /// Request for the record with the given key.
+    GetRecord {
+        /// Key of the record.
+        key: Multihash,
+        /// Custom user data. Passed back in the out event when the results arrive.
+        user_data: TUserData,
+    },
+
+    /// Response to a `GetRecord`.
+    GetRecordRes {
+        /// The record, if we have it.
+        record: Option<Record>,
+        /// Nodes closest to the key.
+        closer_peers: Vec<KadPeer>,
+        /// Identifier of the request that was made by the remote.
+        ///
+        /// It is a logic error to use an id of the handler of a different node.
+        request_id: KademliaRequestId,
+    },
+
+    /// Asks the remote to store a record.
+    PutRecord {
+        /// The record to store.
+        record: Record,
+        /// Custom user data. Passed back in the out event when the results arrive.
+        user_data: TUserData,
+    },
+
+    /// Response to a `PutRecord`, indicating that the record has been stored.
+    PutRecordRes {
+        /// Key of the record.
+        key: Multihash,
+        /// Value of the record.
+        value: Vec<u8>,
+        /// Identifier of the request that was made by the remote.
+        ///
+        /// It is a logic error to use an id of the handler of a different node.
+        request_id: KademliaRequestId,
+    },
+
+    /// Closes the substream of a request made by the remote without answering it.
+    Reset(KademliaRequestId),
 }
 
 /// Unique identifier for a request. Must be passed back in order to answer a request from
@@ -322,6 +406,29 @@ where
             allow_listening,
             next_connec_unique_id: UniqueConnecId(0),
             substreams: Vec::new(),
+            keep_alive: KeepAlive::Until(Instant::now() + IDLE_TIMEOUT),
+        }
+    }
+}
+
+impl<TSubstream, TUserData> KademliaHandler<TSubstream, TUserData>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    /// Removes from the list of substreams the one that waits for the answer to the given
+    /// request, and returns it.
+    fn take_waiting_user(&mut self, request_id: &KademliaRequestId)
+        -> Option<(UniqueConnecId, KadInStreamSink<TSubstream>)>
+    {
+        let pos = self.substreams.iter().position(|state| match state {
+            SubstreamState::InWaitingUser(ref conn_id, _) =>
+                conn_id == &request_id.connec_unique_id,
+            _ => false,
+        })?;
+
+        match self.substreams.remove(pos) {
+            SubstreamState::InWaitingUser(conn_id, substream) => Some((conn_id, substream)),
+            _ => unreachable!(),
         }
     }
 }
@@ -468,6 +575,46 @@ where
                 self.substreams
                     .push(SubstreamState::OutPendingOpen(msg, None));
             }
+            KademliaHandlerIn::GetRecord { key, user_data } => {
+                let msg = KadRequestMsg::GetValue { key };
+                self.substreams
+                    .push(SubstreamState::OutPendingOpen(msg, Some(user_data)));
+            }
+            KademliaHandlerIn::GetRecordRes {
+                record,
+                closer_peers,
+                request_id,
+            } => {
+                if let Some((conn_id, substream)) = self.take_waiting_user(&request_id) {
+                    let msg = KadResponseMsg::GetValue {
+                        record,
+                        closer_peers,
+                    };
+                    self.substreams
+                        .push(SubstreamState::InPendingSend(conn_id, substream, msg));
+                }
+            }
+            KademliaHandlerIn::PutRecord { record, user_data } => {
+                let msg = KadRequestMsg::PutValue { record };
+                self.substreams
+                    .push(SubstreamState::OutPendingOpen(msg, Some(user_data)));
+            }
+            KademliaHandlerIn::PutRecordRes {
+                key,
+                value,
+                request_id,
+            } => {
+                if let Some((conn_id, substream)) = self.take_waiting_user(&request_id) {
+                    let msg = KadResponseMsg::PutValue { key, value };
+                    self.substreams
+                        .push(SubstreamState::InPendingSend(conn_id, substream, msg));
+                }
+            }
+            KademliaHandlerIn::Reset(request_id) => {
+                if let Some((_, substream)) = self.take_waiting_user(&request_id) {
+                    self.substreams.push(SubstreamState::InClosing(substream));
+                }
+            }
         }
     }
 
@@ -488,6 +635,11 @@ where
         }
     }
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        self.keep_alive
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         self.shutting_down = true;
@@ -545,6 +697,16 @@ where
             }
         }
 
+        // Once the last substream is gone, give the connection a bit of time before letting it
+        // be closed, in case a new request comes in.
+        if self.substreams.is_empty() {
+            if self.keep_alive.is_forever() {
+                self.keep_alive = KeepAlive::Until(Instant::now() + IDLE_TIMEOUT);
+            }
+        } else {
+            self.keep_alive = KeepAlive::Forever;
+        }
+
         Ok(Async::NotReady)
     }
 }
@@ -763,6 +925,14 @@ fn process_kad_request<TUserData>(
         KadRequestMsg::AddProvider { key, provider_peer } => {
             Ok(KademliaHandlerEvent::AddProvider { key, provider_peer })
         }
+        KadRequestMsg::GetValue { key } => Ok(KademliaHandlerEvent::GetRecord {
+            key,
+            request_id: KademliaRequestId { connec_unique_id },
+        }),
+        KadRequestMsg::PutValue { record } => Ok(KademliaHandlerEvent::PutRecord {
+            record,
+            request_id: KademliaRequestId { connec_unique_id },
+        }),
     }
 }
 
@@ -792,5 +962,17 @@ fn process_kad_response<TUserData>(
             provider_peers,
             user_data,
         },
+        KadResponseMsg::GetValue {
+            record,
+            closer_peers,
+        } => KademliaHandlerEvent::GetRecordRes {
+            record,
+            closer_peers,
+            user_data,
+        },
+        KadResponseMsg::PutValue { key, .. } => KademliaHandlerEvent::PutRecordRes {
+            key,
+            user_data,
+        },
     }
 }
@@ -28,9 +28,10 @@
 //! corresponding to its distance with the reference key.
 
 use arrayvec::ArrayVec;
-use bigint::U512;
+use bigint::U256;
 use multihash::Multihash;
 use parking_lot::{Mutex, MutexGuard};
+use sha2::{Digest, Sha256};
 use std::mem;
 use std::slice::Iter as SliceIter;
 use std::time::{Duration, Instant};
@@ -126,19 +127,20 @@ pub trait KBucketsPeerId: Eq + Clone {
 }
 
 impl KBucketsPeerId for Multihash {
-    type Distance = U512;
+    type Distance = U256;
 
     #[inline]
     fn num_bits() -> usize {
-        512
+        256
     }
 
     #[inline]
     fn distance_with(&self, other: &Self) -> Self::Distance {
-        // Note that we don't compare the hash functions because there's no chance of collision
-        // of the same value hashed with two different hash functions.
-        let my_hash = U512::from(self.digest());
-        let other_hash = U512::from(other.digest());
+        // We can't use the digest directly, as it isn't uniformly distributed. For example, the
+        // digest of an identity multihash of an ed25519 key always starts with the same bytes.
+        // Instead, the key of a multihash in the DHT is the SHA-256 of its bytes.
+        let my_hash = U256::from(Sha256::digest(self.as_bytes()).as_slice());
+        let other_hash = U256::from(Sha256::digest(other.as_bytes()).as_slice());
         my_hash ^ other_hash
     }
 
@@ -357,7 +359,8 @@ impl<'a, Id: 'a, Val: 'a> Bucket<'a, Id, Val> {
 mod tests {
     extern crate rand;
     use self::rand::random;
-    use kbucket::{KBucketsTable, UpdateOutcome, MAX_NODES_PER_BUCKET};
+    use kbucket::{KBucketsPeerId, KBucketsTable, UpdateOutcome, MAX_NODES_PER_BUCKET};
+    use libp2p_core::PublicKey;
     use multihash::Multihash;
     use std::thread;
     use std::time::Duration;
@@ -392,6 +395,29 @@ mod tests {
         assert_eq!(res[0], other_id);
     }
 
+    #[test]
+    fn ed25519_peers_are_spread() {
+        let ed25519_id = || -> Multihash {
+            let key = (0..32).map(|_| random()).collect::<Vec<u8>>();
+            PublicKey::Ed25519(key).into_peer_id().into()
+        };
+
+        // The identity multihashes of ed25519 keys share their first bytes. The leading zeros of
+        // their distance must nonetheless be as random as for any other key, which means that
+        // two peers land in the farthest buckets, and not in the same one.
+        let table = KBucketsTable::<Multihash, ()>::new(ed25519_id(), Duration::from_secs(5));
+        let mut buckets = (0..16)
+            .map(|_| table.bucket_num(&ed25519_id()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(buckets.iter().all(|&bucket| bucket >= 256 - 32));
+        buckets.sort();
+        buckets.dedup();
+        assert!(buckets.len() > 1);
+
+        let (a, b) = (ed25519_id(), ed25519_id());
+        assert!(Multihash::leading_zeros(a.distance_with(&b)) < 32);
+    }
+
     #[test]
     fn update_local_id_fails() {
         let my_id = {
@@ -417,17 +443,20 @@ mod tests {
             Multihash::from_bytes(bytes).unwrap()
         };
 
-        // Generate some other IDs varying by just one bit.
+        let table = KBucketsTable::new(my_id.clone(), Duration::from_secs(5));
+
+        // Generate some other IDs varying by just one bit, alongside with their bucket.
         let other_ids = (0..random::<usize>() % 20)
             .map(|_| {
                 let bit_num = random::<usize>() % 256;
                 let mut id = my_id.as_bytes().to_vec().clone();
                 id[33 - (bit_num / 8)] ^= 1 << (bit_num % 8);
-                (Multihash::from_bytes(id).unwrap(), bit_num)
+                let id = Multihash::from_bytes(id).unwrap();
+                let bucket = table.bucket_num(&id).unwrap();
+                (id, bucket)
             })
             .collect::<Vec<_>>();
 
-        let table = KBucketsTable::new(my_id, Duration::from_secs(5));
         let before_update = table.buckets().map(|b| b.last_update()).collect::<Vec<_>>();
 
         thread::sleep(Duration::from_secs(2));
@@ -455,21 +484,23 @@ mod tests {
             Multihash::from_bytes(bytes).unwrap()
         };
 
-        assert!(MAX_NODES_PER_BUCKET <= 251); // Test doesn't work otherwise.
-        let mut fill_ids = (0..MAX_NODES_PER_BUCKET + 3)
-            .map(|n| {
-                let mut id = my_id.clone().into_bytes();
-                id[2] ^= 0x80; // Flip the first bit so that we get in the most distant bucket.
-                id[33] = id[33].wrapping_add(n as u8);
-                Multihash::from_bytes(id).unwrap()
+        let table = KBucketsTable::new(my_id.clone(), Duration::from_secs(1));
+
+        // Half of the random IDs are in the most distant bucket.
+        let mut fill_ids = (0..)
+            .map(|_| {
+                let mut bytes = (0..34).map(|_| random()).collect::<Vec<u8>>();
+                bytes[0] = 18;
+                bytes[1] = 32;
+                Multihash::from_bytes(bytes).unwrap()
             })
+            .filter(|id| table.bucket_num(id) == Some(255))
+            .take(MAX_NODES_PER_BUCKET + 3)
             .collect::<Vec<_>>();
 
         let first_node = fill_ids[0].clone();
         let second_node = fill_ids[1].clone();
 
-        let table = KBucketsTable::new(my_id.clone(), Duration::from_secs(1));
-
         for (num, id) in fill_ids.drain(..MAX_NODES_PER_BUCKET).enumerate() {
             assert_eq!(table.update(id, ()), UpdateOutcome::Added);
             assert_eq!(table.buckets().nth(255).unwrap().num_entries(), num + 1);
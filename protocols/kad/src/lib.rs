@@ -72,6 +72,7 @@ extern crate multihash;
 extern crate parking_lot;
 extern crate protobuf;
 extern crate rand;
+extern crate sha2;
 extern crate smallvec;
 extern crate tokio_codec;
 extern crate tokio_io;
@@ -82,13 +83,16 @@ extern crate void;
 #[cfg(test)]
 extern crate tokio;
 
-pub use self::behaviour::{Kademlia, KademliaOut};
+pub use self::behaviour::{Kademlia, KademliaOut, Quorum};
+pub use self::behaviour::{GetRecordOk, GetRecordError, PutRecordOk, PutRecordError};
 pub use self::kbucket::KBucketsPeerId;
 pub use self::protocol::KadConnectionType;
+pub use self::record::{MemoryRecordStore, Record, RecordStore, RecordStoreError};
 pub use self::topology::KademliaTopology;
 
 pub mod handler;
 pub mod protocol;
+pub mod record;
 
 mod behaviour;
 mod kbucket;
@@ -26,12 +26,14 @@
 //! The `Stream` component is used to poll the underlying transport, and the `Sink` component is
 //! used to send messages.
 
+use bs58;
 use bytes::BytesMut;
 use futures::{future, sink, stream, Sink, Stream};
 use libp2p_core::{InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId, UpgradeInfo};
 use multihash::Multihash;
 use protobuf::{self, Message};
 use protobuf_structs;
+use record::Record;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::iter;
 use tokio_codec::Framed;
@@ -250,6 +252,18 @@ pub enum KadRequestMsg {
         /// Known provider for this key.
         provider_peer: KadPeer,
     },
+
+    /// Request to get the record with the given key.
+    GetValue {
+        /// Key of the record.
+        key: Multihash,
+    },
+
+    /// Request to store a record.
+    PutValue {
+        /// The record to store.
+        record: Record,
+    },
 }
 
 /// Response that we can send to a peer or that we received from a peer.
@@ -271,6 +285,22 @@ pub enum KadResponseMsg {
         /// Known providers for this key.
         provider_peers: Vec<KadPeer>,
     },
+
+    /// Response to a `GetValue`.
+    GetValue {
+        /// The record, if the remote has it.
+        record: Option<Record>,
+        /// Nodes closest to the key.
+        closer_peers: Vec<KadPeer>,
+    },
+
+    /// Response to a `PutValue`. The remote sends back the record it has stored.
+    PutValue {
+        /// Key of the record.
+        key: Multihash,
+        /// Value of the record.
+        value: Vec<u8>,
+    },
 }
 
 // Turns a type-safe Kadmelia message into the corresponding raw protobuf message.
@@ -303,6 +333,20 @@ fn req_msg_to_proto(kad_msg: KadRequestMsg) -> protobuf_structs::dht::Message {
             msg.mut_providerPeers().push(provider_peer.into());
             msg
         }
+        KadRequestMsg::GetValue { key } => {
+            let mut msg = protobuf_structs::dht::Message::new();
+            msg.set_field_type(protobuf_structs::dht::Message_MessageType::GET_VALUE);
+            msg.set_clusterLevelRaw(10);
+            msg.set_key(key.into_bytes());
+            msg
+        }
+        KadRequestMsg::PutValue { record } => {
+            let mut msg = protobuf_structs::dht::Message::new();
+            msg.set_field_type(protobuf_structs::dht::Message_MessageType::PUT_VALUE);
+            msg.set_key(record.key.as_bytes().to_vec());
+            msg.set_record(record_to_proto(record));
+            msg
+        }
     }
 }
 
@@ -340,26 +384,83 @@ fn resp_msg_to_proto(kad_msg: KadResponseMsg) -> protobuf_structs::dht::Message
             }
             msg
         }
+        KadResponseMsg::GetValue { record, closer_peers } => {
+            let mut msg = protobuf_structs::dht::Message::new();
+            msg.set_field_type(protobuf_structs::dht::Message_MessageType::GET_VALUE);
+            msg.set_clusterLevelRaw(9);
+            for peer in closer_peers {
+                msg.mut_closerPeers().push(peer.into());
+            }
+            if let Some(record) = record {
+                msg.set_key(record.key.as_bytes().to_vec());
+                msg.set_record(record_to_proto(record));
+            }
+            msg
+        }
+        KadResponseMsg::PutValue { key, value } => {
+            let mut msg = protobuf_structs::dht::Message::new();
+            msg.set_field_type(protobuf_structs::dht::Message_MessageType::PUT_VALUE);
+            msg.set_key(key.as_bytes().to_vec());
+            msg.set_record(record_to_proto(Record::new(key, value)));
+            msg
+        }
     }
 }
 
+// Turns a record into the corresponding raw protobuf message.
+//
+// The key of the raw record is a string, while keys are binary multihashes. The key of the message
+// itself is authoritative, and the key of the record is set to its base58 representation.
+fn record_to_proto(record: Record) -> protobuf_structs::record::Record {
+    let mut out = protobuf_structs::record::Record::new();
+    out.set_key(bs58::encode(record.key.as_bytes()).into_string());
+    out.set_value(record.value);
+    if let Some(publisher) = record.publisher {
+        out.set_author(publisher.to_base58());
+    }
+    out
+}
+
+// Builds a `Record` from its raw protobuf equivalent and the key of the message.
+fn proto_to_record(key: Vec<u8>, mut record: protobuf_structs::record::Record) -> Result<Record, IoError> {
+    let key = Multihash::from_bytes(key)
+        .map_err(|err| IoError::new(IoErrorKind::InvalidData, err))?;
+    let publisher = if record.has_author() {
+        let publisher = record.get_author().parse::<PeerId>()
+            .map_err(|_| IoError::new(IoErrorKind::InvalidData, "invalid record author"))?;
+        Some(publisher)
+    } else {
+        None
+    };
+
+    Ok(Record {
+        key,
+        value: record.take_value(),
+        publisher,
+        expires: None,
+    })
+}
+
 /// Turns a raw Kademlia message into a type-safe message.
 fn proto_to_req_msg(mut message: protobuf_structs::dht::Message) -> Result<KadRequestMsg, IoError> {
     match message.get_field_type() {
         protobuf_structs::dht::Message_MessageType::PING => Ok(KadRequestMsg::Ping),
 
         protobuf_structs::dht::Message_MessageType::PUT_VALUE => {
-            Err(IoError::new(
-                IoErrorKind::InvalidData,
-                "received a PUT_VALUE message, but this is not supported by rust-libp2p yet",
-            ))
+            if !message.has_record() {
+                return Err(IoError::new(
+                    IoErrorKind::InvalidData,
+                    "received a PUT_VALUE message with no record",
+                ));
+            }
+            let record = proto_to_record(message.take_key(), message.take_record())?;
+            Ok(KadRequestMsg::PutValue { record })
         }
 
         protobuf_structs::dht::Message_MessageType::GET_VALUE => {
-            Err(IoError::new(
-                IoErrorKind::InvalidData,
-                "received a GET_VALUE message, but this is not supported by rust-libp2p yet",
-            ))
+            let key = Multihash::from_bytes(message.take_key())
+                .map_err(|err| IoError::new(IoErrorKind::InvalidData, err))?;
+            Ok(KadRequestMsg::GetValue { key })
         }
 
         protobuf_structs::dht::Message_MessageType::FIND_NODE => {
@@ -407,10 +508,18 @@ fn proto_to_resp_msg(
         protobuf_structs::dht::Message_MessageType::PING => Ok(KadResponseMsg::Pong),
 
         protobuf_structs::dht::Message_MessageType::GET_VALUE => {
-            Err(IoError::new(
-                IoErrorKind::InvalidData,
-                "received a GET_VALUE message, but this is not supported by rust-libp2p yet",
-            ))
+            let closer_peers = message
+                .mut_closerPeers()
+                .iter_mut()
+                .filter_map(|peer| KadPeer::from_peer(peer).ok())
+                .collect::<Vec<_>>();
+            let record = if message.has_record() {
+                Some(proto_to_record(message.take_key(), message.take_record())?)
+            } else {
+                None
+            };
+
+            Ok(KadResponseMsg::GetValue { record, closer_peers })
         }
 
         protobuf_structs::dht::Message_MessageType::FIND_NODE => {
@@ -441,10 +550,19 @@ fn proto_to_resp_msg(
             })
         }
 
-        protobuf_structs::dht::Message_MessageType::PUT_VALUE => Err(IoError::new(
-            IoErrorKind::InvalidData,
-            "received an unexpected PUT_VALUE message",
-        )),
+        protobuf_structs::dht::Message_MessageType::PUT_VALUE => {
+            if !message.has_record() {
+                return Err(IoError::new(
+                    IoErrorKind::InvalidData,
+                    "received a PUT_VALUE response with no record",
+                ));
+            }
+            let record = proto_to_record(message.take_key(), message.take_record())?;
+            Ok(KadResponseMsg::PutValue {
+                key: record.key,
+                value: record.value,
+            })
+        }
 
         protobuf_structs::dht::Message_MessageType::ADD_PROVIDER => Err(IoError::new(
             IoErrorKind::InvalidData,
@@ -458,6 +576,74 @@ mod tests {
     extern crate libp2p_tcp;
     extern crate tokio;
 
+    use libp2p_core::PeerId;
+    use multihash::{encode, Hash};
+    use protobuf::{self, Message};
+    use protobuf_structs;
+    use protocol::{KadConnectionType, KadPeer, KadRequestMsg, KadResponseMsg};
+    use protocol::{proto_to_req_msg, proto_to_resp_msg, req_msg_to_proto, resp_msg_to_proto};
+    use record::Record;
+
+    // Encodes a message to bytes and decodes it back, like it would go through the network.
+    fn through_bytes(msg: protobuf_structs::dht::Message) -> protobuf_structs::dht::Message {
+        let bytes = msg.write_to_bytes().unwrap();
+        protobuf::parse_from_bytes(&bytes).unwrap()
+    }
+
+    fn record() -> Record {
+        Record {
+            key: encode(Hash::SHA2256, &[9, 12, 0, 245, 245, 201, 28, 95]).unwrap(),
+            value: vec![1, 2, 3],
+            publisher: Some(PeerId::random()),
+            expires: None,
+        }
+    }
+
+    #[test]
+    fn value_requests_round_trip() {
+        let requests = vec![
+            KadRequestMsg::GetValue { key: record().key },
+            KadRequestMsg::PutValue { record: record() },
+        ];
+
+        for request in requests {
+            let decoded = proto_to_req_msg(through_bytes(req_msg_to_proto(request.clone()))).unwrap();
+            assert_eq!(decoded, request);
+        }
+    }
+
+    #[test]
+    fn value_responses_round_trip() {
+        let record = record();
+        let responses = vec![
+            KadResponseMsg::GetValue {
+                record: Some(record.clone()),
+                closer_peers: vec![KadPeer {
+                    node_id: PeerId::random(),
+                    multiaddrs: vec!["/ip4/100.101.102.103/tcp/20105".parse().unwrap()],
+                    connection_ty: KadConnectionType::Connected,
+                }],
+            },
+            KadResponseMsg::GetValue { record: None, closer_peers: Vec::new() },
+            KadResponseMsg::PutValue { key: record.key, value: record.value },
+        ];
+
+        for response in responses {
+            let decoded = proto_to_resp_msg(through_bytes(resp_msg_to_proto(response.clone()))).unwrap();
+            assert_eq!(decoded, response);
+        }
+    }
+
+    #[test]
+    fn put_value_without_record_is_rejected() {
+        let mut msg = protobuf_structs::dht::Message::new();
+        msg.set_field_type(protobuf_structs::dht::Message_MessageType::PUT_VALUE);
+        msg.set_key(record().key.into_bytes());
+
+        assert!(proto_to_req_msg(through_bytes(msg.clone())).is_err());
+        assert!(proto_to_resp_msg(through_bytes(msg)).is_err());
+    }
+
     /*// TODO: restore
     use self::libp2p_tcp::TcpConfig;
     use self::tokio::runtime::current_thread::Runtime;
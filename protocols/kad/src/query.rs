@@ -28,6 +28,7 @@ use handler::KademliaHandlerIn;
 use kbucket::KBucketsPeerId;
 use libp2p_core::PeerId;
 use multihash::Multihash;
+use record::Record;
 use smallvec::SmallVec;
 use std::time::{Duration, Instant};
 use tokio_timer::Delay;
@@ -284,6 +285,8 @@ impl QueryState {
                     QueryStage::Frozen => match self.target {
                         QueryTarget::FindPeer(_) => true,
                         QueryTarget::GetProviders(_) => false,
+                        QueryTarget::GetRecord(_) => false,
+                        QueryTarget::PutRecord(_) => true,
                     },
                 },
                 _ => false,
@@ -370,6 +373,13 @@ pub enum QueryTarget {
     FindPeer(PeerId),
     /// Find the peers that provide a certain value.
     GetProviders(Multihash),
+    /// Find the record with the given key.
+    GetRecord(Multihash),
+    /// Store a record on the peers of the query.
+    ///
+    /// The peers passed as `known_closest_peers` are expected to already be the closest to the
+    /// key, as the responses to `PUT_VALUE` don't contain any peer.
+    PutRecord(Record),
 }
 
 impl QueryTarget {
@@ -390,6 +400,14 @@ impl QueryTarget {
                 key,
                 user_data,
             },
+            QueryTarget::GetRecord(key) => KademliaHandlerIn::GetRecord {
+                key,
+                user_data,
+            },
+            QueryTarget::PutRecord(record) => KademliaHandlerIn::PutRecord {
+                record,
+                user_data,
+            },
         }
     }
 
@@ -398,6 +416,8 @@ impl QueryTarget {
         match self {
             QueryTarget::FindPeer(peer) => peer.as_ref(),
             QueryTarget::GetProviders(key) => key,
+            QueryTarget::GetRecord(key) => key,
+            QueryTarget::PutRecord(record) => &record.key,
         }
     }
 }
@@ -0,0 +1,210 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Records stored in the DHT with `PUT_VALUE`, and the storage they are kept in.
+
+use fnv::FnvHashMap;
+use libp2p_core::PeerId;
+use multihash::Multihash;
+use std::{error, fmt, time::Instant};
+
+/// A record stored in the DHT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Key of the record.
+    pub key: Multihash,
+    /// Value of the record.
+    pub value: Vec<u8>,
+    /// The node that originally published the record, if known.
+    pub publisher: Option<PeerId>,
+    /// When the record expires and stops being returned. `None` if the record never expires.
+    ///
+    /// This is not transmitted over the network. Each node decides for how long it keeps the
+    /// records it receives.
+    pub expires: Option<Instant>,
+}
+
+impl Record {
+    /// Builds a record that has no publisher and never expires.
+    #[inline]
+    pub fn new(key: Multihash, value: Vec<u8>) -> Self {
+        Record {
+            key,
+            value,
+            publisher: None,
+            expires: None,
+        }
+    }
+
+    /// Returns true if the record has expired at the given point in time.
+    #[inline]
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.expires.map(|expires| expires <= now).unwrap_or(false)
+    }
+}
+
+/// Storage for the records of the DHT.
+///
+/// The `Kademlia` behaviour stores in it the records that the local node publishes, plus the
+/// records that remotes ask us to store.
+pub trait RecordStore {
+    /// Returns the record with the given key, if any.
+    fn get(&self, key: &Multihash) -> Option<&Record>;
+
+    /// Stores a record, replacing any existing record with the same key.
+    fn put(&mut self, record: Record) -> Result<(), RecordStoreError>;
+
+    /// Removes the record with the given key, if any.
+    fn remove(&mut self, key: &Multihash);
+
+    /// Returns the list of all the records of the store.
+    fn records<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Record> + 'a>;
+
+    /// Removes all the records that have expired at the given point in time.
+    fn remove_expired(&mut self, now: Instant);
+}
+
+/// Error that can happen when storing a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordStoreError {
+    /// The store is full.
+    MaxRecords,
+    /// The value of the record is too large.
+    ValueTooLarge,
+}
+
+impl fmt::Display for RecordStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordStoreError::MaxRecords => write!(f, "the record store is full"),
+            RecordStoreError::ValueTooLarge => write!(f, "the value of the record is too large"),
+        }
+    }
+}
+
+impl error::Error for RecordStoreError {}
+
+/// Implementation of `RecordStore` that keeps the records in memory.
+#[derive(Debug, Clone)]
+pub struct MemoryRecordStore {
+    /// The records, indexed by key.
+    records: FnvHashMap<Multihash, Record>,
+    /// Maximum number of records.
+    max_records: usize,
+    /// Maximum size in bytes of the value of a record.
+    max_value_bytes: usize,
+}
+
+impl MemoryRecordStore {
+    /// Creates a new empty store, with the given limits.
+    #[inline]
+    pub fn new(max_records: usize, max_value_bytes: usize) -> Self {
+        MemoryRecordStore {
+            records: FnvHashMap::default(),
+            max_records,
+            max_value_bytes,
+        }
+    }
+}
+
+impl Default for MemoryRecordStore {
+    #[inline]
+    fn default() -> Self {
+        MemoryRecordStore::new(1024, 65 * 1024)
+    }
+}
+
+impl RecordStore for MemoryRecordStore {
+    #[inline]
+    fn get(&self, key: &Multihash) -> Option<&Record> {
+        self.records.get(key)
+    }
+
+    fn put(&mut self, record: Record) -> Result<(), RecordStoreError> {
+        if record.value.len() > self.max_value_bytes {
+            return Err(RecordStoreError::ValueTooLarge);
+        }
+
+        if self.records.len() >= self.max_records && !self.records.contains_key(&record.key) {
+            return Err(RecordStoreError::MaxRecords);
+        }
+
+        self.records.insert(record.key.clone(), record);
+        Ok(())
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Multihash) {
+        self.records.remove(key);
+    }
+
+    #[inline]
+    fn records<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Record> + 'a> {
+        Box::new(self.records.values())
+    }
+
+    #[inline]
+    fn remove_expired(&mut self, now: Instant) {
+        self.records.retain(|_, record| !record.is_expired(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryRecordStore, Record, RecordStore, RecordStoreError};
+    use multihash::{Hash, Multihash};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn put_get_remove() {
+        let mut store = MemoryRecordStore::default();
+        let record = Record::new(Multihash::random(Hash::SHA2256), vec![1, 2, 3]);
+        store.put(record.clone()).unwrap();
+        assert_eq!(store.get(&record.key), Some(&record));
+        store.remove(&record.key);
+        assert!(store.get(&record.key).is_none());
+    }
+
+    #[test]
+    fn limits() {
+        let mut store = MemoryRecordStore::new(1, 4);
+        let record = Record::new(Multihash::random(Hash::SHA2256), vec![0; 5]);
+        assert_eq!(store.put(record), Err(RecordStoreError::ValueTooLarge));
+        let record = Record::new(Multihash::random(Hash::SHA2256), vec![0; 4]);
+        store.put(record.clone()).unwrap();
+        let other = Record::new(Multihash::random(Hash::SHA2256), vec![0; 4]);
+        assert_eq!(store.put(other), Err(RecordStoreError::MaxRecords));
+        // Replacing an existing record is always possible.
+        store.put(record).unwrap();
+    }
+
+    #[test]
+    fn expired_records_are_removed() {
+        let mut store = MemoryRecordStore::default();
+        let now = Instant::now();
+        let mut record = Record::new(Multihash::random(Hash::SHA2256), vec![1]);
+        record.expires = Some(now + Duration::from_secs(10));
+        store.put(record.clone()).unwrap();
+        store.remove_expired(now);
+        assert!(store.get(&record.key).is_some());
+        store.remove_expired(now + Duration::from_secs(10));
+        assert!(store.get(&record.key).is_none());
+    }
+}
@@ -20,6 +20,7 @@
 
 use kbucket::KBucketsPeerId;
 use libp2p_core::{Multiaddr, PeerId, topology::MemoryTopology, topology::Topology};
+use libp2p_core::topology::{AddressSource, PersistentTopology};
 use multihash::Multihash;
 use protocol::KadConnectionType;
 use std::vec;
@@ -48,12 +49,19 @@ pub trait KademliaTopology: Topology {
 
     /// Registers the given peer as provider of the resource with the given ID.
     ///
-    /// > **Note**: There is no `remove_provider` method. Implementations must include a
+    /// > **Note**: Remotes never tell us that they stop providing a key, and `remove_provider`
+    /// >           is only called for the local node. Implementations must include a
     /// >           time-to-live system so that entries disappear after a while.
     // TODO: specify the TTL? it has to match the timeout in the behaviour somehow, but this could
     //       also be handled by the user
     fn add_provider(&mut self, key: Multihash, peer_id: PeerId);
 
+    /// Unregisters the given peer as provider of the resource with the given ID.
+    ///
+    /// The default implementation doesn't do anything.
+    #[inline]
+    fn remove_provider(&mut self, _key: &Multihash, _peer_id: &PeerId) {}
+
     /// Returns the list of providers that have been registered with `add_provider`.
     ///
     /// If the local node is a provider for `key`, our local peer ID should also be returned.
@@ -77,11 +85,48 @@ impl KademliaTopology for MemoryTopology {
         list.into_iter()
     }
 
-    fn add_provider(&mut self, _: Multihash, _: PeerId) {
-        unimplemented!()
+    #[inline]
+    fn add_provider(&mut self, key: Multihash, peer_id: PeerId) {
+        MemoryTopology::add_provider(self, key, peer_id)
+    }
+
+    #[inline]
+    fn remove_provider(&mut self, key: &Multihash, peer_id: &PeerId) {
+        MemoryTopology::remove_provider(self, key, peer_id)
+    }
+
+    fn get_providers(&mut self, key: &Multihash) -> Self::GetProvidersIter {
+        self.providers(key).cloned().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl KademliaTopology for PersistentTopology {
+    type ClosestPeersIter = vec::IntoIter<PeerId>;
+    type GetProvidersIter = vec::IntoIter<PeerId>;
+
+    fn add_kad_discovered_address(&mut self, peer: PeerId, addr: Multiaddr, _: KadConnectionType) {
+        if &peer != self.local_peer_id() {
+            self.add_address(peer, addr, AddressSource::Kademlia)
+        }
+    }
+
+    fn closest_peers(&mut self, target: &Multihash, _: usize) -> Self::ClosestPeersIter {
+        let mut list = self.peers().cloned().collect::<Vec<_>>();
+        list.sort_by(|a, b| target.distance_with(b.as_ref()).cmp(&target.distance_with(a.as_ref())));
+        list.into_iter()
+    }
+
+    #[inline]
+    fn add_provider(&mut self, key: Multihash, peer_id: PeerId) {
+        PersistentTopology::add_provider(self, key, peer_id)
+    }
+
+    #[inline]
+    fn remove_provider(&mut self, key: &Multihash, peer_id: &PeerId) {
+        PersistentTopology::remove_provider(self, key, peer_id)
     }
 
-    fn get_providers(&mut self, _: &Multihash) -> Self::GetProvidersIter {
-        unimplemented!()
+    fn get_providers(&mut self, key: &Multihash) -> Self::GetProvidersIter {
+        self.providers(key).cloned().collect::<Vec<_>>().into_iter()
     }
 }
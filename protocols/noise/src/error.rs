@@ -0,0 +1,117 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Defines the `NoiseError` enum that groups all possible errors of the noise handshake.
+
+use libp2p_secio::SecioError;
+use protobuf::error::ProtobufError;
+use std::error;
+use std::fmt;
+use std::io::Error as IoError;
+
+/// Error during a noise handshake or on an encrypted stream.
+#[derive(Debug)]
+pub enum NoiseError {
+    /// I/O error.
+    Io(IoError),
+
+    /// Error reported by the noise state machine.
+    Noise(snow::Error),
+
+    /// Failed to decode the payload of a handshake message.
+    InvalidPayload(ProtobufError),
+
+    /// Failed to sign our static DH key with our identity key.
+    SigningFailure(SecioError),
+
+    /// The remote sent an identity key that we couldn't decode.
+    InvalidKey,
+
+    /// The signature of the remote's static DH key doesn't match its identity key.
+    BadSignature,
+
+    /// The identity of the remote isn't the one we expected.
+    UnexpectedIdentity,
+
+    /// We tried to dial with the IK pattern without knowing the static DH key of the remote.
+    MissingRemoteStaticKey,
+
+    #[doc(hidden)]
+    __Nonexhaustive
+}
+
+impl error::Error for NoiseError {
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            NoiseError::Io(ref err) => Some(err),
+            NoiseError::Noise(ref err) => Some(err),
+            NoiseError::InvalidPayload(ref err) => Some(err),
+            NoiseError::SigningFailure(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NoiseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            NoiseError::Io(e) =>
+                write!(f, "I/O error: {}", e),
+            NoiseError::Noise(e) =>
+                write!(f, "noise error: {}", e),
+            NoiseError::InvalidPayload(e) =>
+                write!(f, "invalid handshake payload: {}", e),
+            NoiseError::SigningFailure(e) =>
+                write!(f, "failed to sign the static DH key: {}", e),
+            NoiseError::InvalidKey =>
+                f.write_str("The remote sent an invalid identity key"),
+            NoiseError::BadSignature =>
+                f.write_str("The signature of the remote's static DH key doesn't match its identity key"),
+            NoiseError::UnexpectedIdentity =>
+                f.write_str("The identity of the remote isn't the one we expected"),
+            NoiseError::MissingRemoteStaticKey =>
+                f.write_str("The static DH key of the remote is required to dial with the IK pattern"),
+            NoiseError::__Nonexhaustive =>
+                f.write_str("__Nonexhaustive")
+        }
+    }
+}
+
+impl From<IoError> for NoiseError {
+    #[inline]
+    fn from(err: IoError) -> NoiseError {
+        NoiseError::Io(err)
+    }
+}
+
+impl From<snow::Error> for NoiseError {
+    #[inline]
+    fn from(err: snow::Error) -> NoiseError {
+        NoiseError::Noise(err)
+    }
+}
+
+impl From<ProtobufError> for NoiseError {
+    #[inline]
+    fn from(err: ProtobufError) -> NoiseError {
+        NoiseError::InvalidPayload(err)
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of the handshake for each supported pattern.
+//!
+//! The messages of the handshake are framed like the messages of the encrypted stream: they are
+//! prefixed with their length, as a big-endian 16-bit integer.
+//!
+//! Once a party has learned the static DH key of the remote, it receives a payload containing
+//! the identity key of the remote and a signature of the static DH key made with that identity
+//! key. The handshake fails if the signature is invalid.
+
+use crate::error::NoiseError;
+use crate::io::{self, NoiseStream, MAX_FRAME_LEN};
+use crate::payload_proto::NoiseHandshakePayload;
+use crate::{HandshakePattern, NoiseConfig, NoiseOutput, STATIC_KEY_DOMAIN};
+use futures::{future, prelude::*};
+use libp2p_core::PublicKey;
+use log::{debug, trace};
+use snow::HandshakeState;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Performs a handshake on the given socket.
+///
+/// `initiator` must be true for the dialer of the connection, and false for the listener.
+pub fn handshake<T>(socket: T, config: NoiseConfig, initiator: bool)
+    -> Box<dyn Future<Item = NoiseOutput<T>, Error = NoiseError> + Send>
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let state = match build_state(&config, initiator) {
+        Ok(state) => state,
+        Err(err) => return Box::new(future::err(err)),
+    };
+
+    // Identity that the remote must prove, if we know it in advance.
+    let expected = match config.pattern {
+        HandshakePattern::IKDialer { ref remote_key, .. } if initiator => Some(remote_key.clone()),
+        _ => None,
+    };
+    let payload = config.keys.payload.clone();
+
+    let exchange: Box<dyn Future<Item = _, Error = _> + Send> = match (&config.pattern, initiator) {
+        // -> e
+        // <- e, ee, s, es
+        // -> s, se
+        (HandshakePattern::XX, true) => Box::new(
+            send_message(socket, state, Vec::new())
+                .and_then(|(socket, state)| recv_message(socket, state))
+                .and_then(|(socket, state, remote_payload)| {
+                    let remote_key = verify_payload(&state, &remote_payload, None)?;
+                    Ok((socket, state, remote_key))
+                })
+                .and_then(move |(socket, state, remote_key)| {
+                    send_message(socket, state, payload)
+                        .map(move |(socket, state)| (socket, state, remote_key))
+                })
+        ),
+        (HandshakePattern::XX, false) => Box::new(
+            recv_message(socket, state)
+                .and_then(move |(socket, state, _)| send_message(socket, state, payload))
+                .and_then(|(socket, state)| recv_message(socket, state))
+                .and_then(|(socket, state, remote_payload)| {
+                    let remote_key = verify_payload(&state, &remote_payload, None)?;
+                    Ok((socket, state, remote_key))
+                })
+        ),
+        // IX:
+        // -> e, s
+        // <- e, ee, se, s, es
+        //
+        // IK:
+        // <- s
+        // ...
+        // -> e, es, s, ss
+        // <- e, ee, se
+        (_, true) => Box::new(
+            send_message(socket, state, payload)
+                .and_then(|(socket, state)| recv_message(socket, state))
+                .and_then(move |(socket, state, remote_payload)| {
+                    let remote_key = verify_payload(&state, &remote_payload, expected.as_ref())?;
+                    Ok((socket, state, remote_key))
+                })
+        ),
+        (_, false) => Box::new(
+            recv_message(socket, state)
+                .and_then(|(socket, state, remote_payload)| {
+                    let remote_key = verify_payload(&state, &remote_payload, None)?;
+                    Ok((socket, state, remote_key))
+                })
+                .and_then(move |(socket, state, remote_key)| {
+                    send_message(socket, state, payload)
+                        .map(move |(socket, state)| (socket, state, remote_key))
+                })
+        ),
+    };
+
+    Box::new(exchange.and_then(|(socket, state, remote_key)| {
+        trace!("handshake with {:?} finished", remote_key);
+        let session = state.into_transport_mode()?;
+        Ok(NoiseOutput {
+            stream: NoiseStream::new(socket, session),
+            remote_key,
+        })
+    }))
+}
+
+/// Builds the noise state machine corresponding to the configuration.
+fn build_state(config: &NoiseConfig, initiator: bool) -> Result<HandshakeState, NoiseError> {
+    let builder = snow::Builder::new(config.noise_params())
+        .local_private_key(&config.keys.secret);
+
+    match config.pattern {
+        HandshakePattern::IKListener if initiator => Err(NoiseError::MissingRemoteStaticKey),
+        HandshakePattern::IKDialer { ref remote_static_key, .. } if initiator => {
+            Ok(builder.remote_public_key(remote_static_key).build_initiator()?)
+        },
+        _ if initiator => Ok(builder.build_initiator()?),
+        _ => Ok(builder.build_responder()?),
+    }
+}
+
+/// Writes a handshake message containing `payload` on the socket.
+fn send_message<T>(socket: T, mut state: HandshakeState, payload: Vec<u8>)
+    -> impl Future<Item = (T, HandshakeState), Error = NoiseError>
+where
+    T: AsyncWrite,
+{
+    let mut message = vec![0; 2 + MAX_FRAME_LEN];
+    let message = state.write_message(&payload, &mut message[2..])
+        .map(|len| {
+            io::encode_len(&mut message, len);
+            message.truncate(2 + len);
+            message
+        });
+
+    future::result(message)
+        .from_err()
+        .and_then(move |message| {
+            tokio_io::io::write_all(socket, message)
+                .and_then(|(socket, _)| tokio_io::io::flush(socket))
+                .from_err()
+        })
+        .map(move |socket| (socket, state))
+}
+
+/// Reads a handshake message from the socket, and returns its payload.
+fn recv_message<T>(socket: T, mut state: HandshakeState)
+    -> impl Future<Item = (T, HandshakeState, Vec<u8>), Error = NoiseError>
+where
+    T: AsyncRead,
+{
+    tokio_io::io::read_exact(socket, [0; 2])
+        .and_then(|(socket, len)| tokio_io::io::read_exact(socket, vec![0; io::decode_len(len)]))
+        .from_err()
+        .and_then(move |(socket, message)| {
+            let mut payload = vec![0; message.len()];
+            let len = state.read_message(&message, &mut payload)?;
+            payload.truncate(len);
+            Ok((socket, state, payload))
+        })
+}
+
+/// Verifies the payload sent by the remote after its static DH key, and returns its identity.
+fn verify_payload(state: &HandshakeState, payload: &[u8], expected: Option<&PublicKey>)
+    -> Result<PublicKey, NoiseError>
+{
+    let payload: NoiseHandshakePayload = protobuf::parse_from_bytes(payload)?;
+    let remote_key = PublicKey::from_protobuf_encoding(payload.get_identity_key())
+        .map_err(|_| NoiseError::InvalidKey)?;
+
+    let remote_static_key = state.get_remote_static().ok_or(NoiseError::InvalidKey)?;
+    let mut message = STATIC_KEY_DOMAIN.to_vec();
+    message.extend_from_slice(remote_static_key);
+    if libp2p_secio::verify_signature(&remote_key, &message, payload.get_identity_sig()).is_err() {
+        debug!("the remote's static DH key isn't signed by its identity key");
+        return Err(NoiseError::BadSignature);
+    }
+
+    if let Some(expected) = expected {
+        if *expected != remote_key {
+            debug!("expected {:?} but the remote is {:?}", expected, remote_key);
+            return Err(NoiseError::UnexpectedIdentity);
+        }
+    }
+
+    Ok(remote_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NoiseConfig, NoiseError, NoiseHash};
+    use futures::prelude::*;
+    use libp2p_secio::SecioKeyPair;
+    use super::handshake;
+    use tokio::runtime::current_thread::Runtime;
+    use tokio_tcp::{TcpListener, TcpStream};
+
+    #[test]
+    fn xx_handshake_succeeds() {
+        let key1 = SecioKeyPair::ed25519_generated().unwrap();
+        let key2 = SecioKeyPair::ed25519_generated().unwrap();
+        let config1 = NoiseConfig::xx(&key1).unwrap();
+        let config2 = NoiseConfig::xx(&key2).unwrap();
+        handshake_with_self_succeeds(config1, config2);
+    }
+
+    #[test]
+    fn ix_handshake_succeeds() {
+        let key1 = SecioKeyPair::ed25519_generated().unwrap();
+        let key2 = SecioKeyPair::ed25519_generated().unwrap();
+        let config1 = NoiseConfig::ix(&key1).unwrap();
+        let config2 = NoiseConfig::ix(&key2).unwrap();
+        handshake_with_self_succeeds(config1, config2);
+    }
+
+    #[test]
+    fn ik_handshake_succeeds() {
+        let key1 = SecioKeyPair::ed25519_generated().unwrap();
+        let key2 = SecioKeyPair::ed25519_generated().unwrap();
+        let config1 = NoiseConfig::ik_listener(&key1).unwrap();
+        let remote_static = config1.static_public_key().to_vec();
+        let config2 = NoiseConfig::ik_dialer(&key2, key1.to_public_key(), remote_static).unwrap();
+        handshake_with_self_succeeds(config1, config2);
+    }
+
+    #[test]
+    fn blake2s_handshake_succeeds() {
+        let key1 = SecioKeyPair::ed25519_generated().unwrap();
+        let key2 = SecioKeyPair::ed25519_generated().unwrap();
+        let config1 = NoiseConfig::xx(&key1).unwrap().with_hash(NoiseHash::Blake2s);
+        let config2 = NoiseConfig::xx(&key2).unwrap().with_hash(NoiseHash::Blake2s);
+        handshake_with_self_succeeds(config1, config2);
+    }
+
+    #[test]
+    fn ik_handshake_with_wrong_identity_fails() {
+        let key1 = SecioKeyPair::ed25519_generated().unwrap();
+        let key2 = SecioKeyPair::ed25519_generated().unwrap();
+        let other = SecioKeyPair::ed25519_generated().unwrap();
+        let config1 = NoiseConfig::ik_listener(&key1).unwrap();
+        let remote_static = config1.static_public_key().to_vec();
+        let config2 = NoiseConfig::ik_dialer(&key2, other.to_public_key(), remote_static).unwrap();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(e, _)| e.into())
+            .and_then(move |(connec, _)| handshake(connec.unwrap(), config1, false))
+            .then(|_| Ok(()));
+
+        let client = TcpStream::connect(&listener_addr)
+            .map_err(|e| e.into())
+            .and_then(move |stream| handshake(stream, config2, true));
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+        match rt.block_on(client) {
+            Err(NoiseError::UnexpectedIdentity) => (),
+            Err(err) => panic!("unexpected error: {:?}", err),
+            Ok(_) => panic!("handshake succeeded with the wrong identity"),
+        }
+    }
+
+    #[test]
+    fn ik_dialing_with_listener_config_fails() {
+        let key = SecioKeyPair::ed25519_generated().unwrap();
+        let config = NoiseConfig::ik_listener(&key).unwrap();
+        let socket = std::io::Cursor::new(Vec::new());
+        match handshake(socket, config, true).wait() {
+            Err(NoiseError::MissingRemoteStaticKey) => (),
+            _ => panic!("dialing with an IK listener configuration should fail"),
+        }
+    }
+
+    fn handshake_with_self_succeeds(config1: NoiseConfig, config2: NoiseConfig) {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        // Larger than a single frame, in order to test the splitting of the writes.
+        let data = (0 .. 100_000).map(|n| n as u8).collect::<Vec<_>>();
+        let expected = data.clone();
+
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(e, _)| e.into())
+            .and_then(move |(connec, _)| handshake(connec.unwrap(), config1, false))
+            .and_then(|out| {
+                tokio_io::io::read_exact(out.stream, vec![0; 100_000])
+                    .and_then(|(stream, buf)| tokio_io::io::write_all(stream, buf))
+                    .and_then(|(stream, _)| tokio_io::io::flush(stream))
+                    .from_err()
+            });
+
+        let client = TcpStream::connect(&listener_addr)
+            .map_err(|e| e.into())
+            .and_then(move |stream| handshake(stream, config2, true))
+            .and_then(move |out| {
+                tokio_io::io::write_all(out.stream, data)
+                    .and_then(|(stream, _)| tokio_io::io::flush(stream))
+                    .and_then(|stream| tokio_io::io::read_exact(stream, vec![0; 100_000]))
+                    .from_err()
+            });
+
+        let mut rt = Runtime::new().unwrap();
+        let (_, (_, received)) = rt.block_on(server.join(client)).unwrap();
+        assert_eq!(received, expected);
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Encrypted stream obtained after a successful handshake.
+//!
+//! Each chunk of data is encrypted individually and sent as a frame prefixed with its length,
+//! encoded as a big-endian 16-bit integer.
+
+use futures::{Poll, try_ready};
+use std::{cmp, fmt, io};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Maximum size of a frame, including the authentication tag.
+pub(crate) const MAX_FRAME_LEN: usize = 65535;
+/// Size of the authentication tag appended to each frame.
+const TAG_LEN: usize = 16;
+/// Maximum number of bytes of data that fit in a frame.
+const MAX_WRITE_LEN: usize = MAX_FRAME_LEN - TAG_LEN;
+
+/// Stream that encrypts data written to it and decrypts data read from it.
+pub struct NoiseStream<T> {
+    /// The underlying socket.
+    socket: T,
+    /// The noise session, in transport mode.
+    session: snow::TransportState,
+    /// Length prefix of the frame being received.
+    read_len: [u8; 2],
+    /// Number of bytes of `read_len` that have been received.
+    read_len_pos: usize,
+    /// Encrypted frame being received.
+    read_frame: Vec<u8>,
+    /// Number of bytes of `read_frame` that have been received.
+    read_frame_pos: usize,
+    /// Decrypted data waiting to be read.
+    decrypted: Vec<u8>,
+    /// Number of bytes of `decrypted` that have been read.
+    decrypted_pos: usize,
+    /// Encrypted frame, including its length prefix, waiting to be sent.
+    write_frame: Vec<u8>,
+    /// Number of bytes of `write_frame` that have been sent.
+    write_frame_pos: usize,
+}
+
+impl<T> NoiseStream<T> {
+    /// Wraps around a socket on which a handshake has been successfully performed.
+    pub(crate) fn new(socket: T, session: snow::TransportState) -> Self {
+        NoiseStream {
+            socket,
+            session,
+            read_len: [0; 2],
+            read_len_pos: 0,
+            read_frame: Vec::new(),
+            read_frame_pos: 0,
+            decrypted: Vec::new(),
+            decrypted_pos: 0,
+            write_frame: Vec::new(),
+            write_frame_pos: 0,
+        }
+    }
+
+    /// Returns the static DH public key of the remote.
+    pub fn remote_static_key(&self) -> Option<&[u8]> {
+        self.session.get_remote_static()
+    }
+}
+
+impl<T> NoiseStream<T>
+where
+    T: io::Write
+{
+    /// Sends the frame in `write_frame`, if any.
+    fn send_frame(&mut self) -> io::Result<()> {
+        while self.write_frame_pos < self.write_frame.len() {
+            let n = self.socket.write(&self.write_frame[self.write_frame_pos..])?;
+            if n == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.write_frame_pos += n;
+        }
+        Ok(())
+    }
+}
+
+impl<T> fmt::Debug for NoiseStream<T>
+where
+    T: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NoiseStream")
+            .field("socket", &self.socket)
+            .finish()
+    }
+}
+
+impl<T> io::Read for NoiseStream<T>
+where
+    T: io::Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.decrypted_pos < self.decrypted.len() {
+                let n = cmp::min(buf.len(), self.decrypted.len() - self.decrypted_pos);
+                buf[..n].copy_from_slice(&self.decrypted[self.decrypted_pos..self.decrypted_pos + n]);
+                self.decrypted_pos += n;
+                return Ok(n);
+            }
+
+            while self.read_len_pos < 2 {
+                let n = self.socket.read(&mut self.read_len[self.read_len_pos..])?;
+                if n == 0 {
+                    if self.read_len_pos == 0 {
+                        return Ok(0);
+                    }
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.read_len_pos += n;
+            }
+
+            let len = decode_len(self.read_len);
+            self.read_frame.resize(len, 0);
+            while self.read_frame_pos < len {
+                let n = self.socket.read(&mut self.read_frame[self.read_frame_pos..])?;
+                if n == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.read_frame_pos += n;
+            }
+
+            self.decrypted.resize(len, 0);
+            let n = self.session.read_message(&self.read_frame, &mut self.decrypted)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            self.decrypted.truncate(n);
+            self.decrypted_pos = 0;
+            self.read_len_pos = 0;
+            self.read_frame_pos = 0;
+        }
+    }
+}
+
+impl<T> AsyncRead for NoiseStream<T>
+where
+    T: AsyncRead
+{
+}
+
+impl<T> io::Write for NoiseStream<T>
+where
+    T: io::Write
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // We only encrypt new data once the previous frame has been entirely sent.
+        self.send_frame()?;
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = cmp::min(buf.len(), MAX_WRITE_LEN);
+        self.write_frame.resize(2 + n + TAG_LEN, 0);
+        let len = self.session.write_message(&buf[..n], &mut self.write_frame[2..])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        encode_len(&mut self.write_frame, len);
+        self.write_frame.truncate(2 + len);
+        self.write_frame_pos = 0;
+
+        // The data is now in our buffer and is considered as written, even if we fail to send it
+        // immediately.
+        match self.send_frame() {
+            Ok(()) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => (),
+            Err(err) => return Err(err),
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_frame()?;
+        self.socket.flush()
+    }
+}
+
+impl<T> AsyncWrite for NoiseStream<T>
+where
+    T: AsyncWrite
+{
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        self.socket.shutdown()
+    }
+}
+
+/// Encodes `len` as the big-endian prefix of `frame`.
+#[inline]
+pub(crate) fn encode_len(frame: &mut [u8], len: usize) {
+    debug_assert!(len <= MAX_FRAME_LEN);
+    frame[0] = (len >> 8) as u8;
+    frame[1] = len as u8;
+}
+
+/// Decodes a big-endian length prefix.
+#[inline]
+pub(crate) fn decode_len(prefix: [u8; 2]) -> usize {
+    (usize::from(prefix[0]) << 8) | usize::from(prefix[1])
+}
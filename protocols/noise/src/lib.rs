@@ -0,0 +1,296 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of the [noise protocol framework](https://noiseprotocol.org) as a libp2p
+//! connection upgrade.
+//!
+//! # Usage
+//!
+//! The `NoiseConfig` struct implements the `InboundUpgrade` and `OutboundUpgrade` traits, and can
+//! be applied over a `Transport` by using the `with_upgrade` method, similarly to secio. The
+//! output of the upgrade is a `NoiseOutput`, which contains the encrypted stream and the identity
+//! key of the remote.
+//!
+//! Three handshake patterns are supported, all using X25519 for the key agreement and
+//! ChaChaPoly for the encryption:
+//!
+//! - `XX`, in which the static DH keys of both parties are exchanged during the handshake. This
+//!   is the default choice.
+//! - `IX`, in which the dialer sends its static DH key in the clear in the first message. This
+//!   saves a round-trip compared to `XX`.
+//! - `IK`, in which the dialer already knows the static DH key and the identity of the listener.
+//!   This saves a round-trip compared to `XX` and keeps the identity of the dialer hidden.
+//!
+//! Each node generates a static DH key pair when its `NoiseConfig` is created, and signs the
+//! public key with its identity key. The signature is sent to the remote during the handshake,
+//! which lets the remote bind the noise session to our `PeerId`.
+//!
+//! ```no_run
+//! # fn main() {
+//! use futures::Future;
+//! use libp2p_core::{Multiaddr, Transport};
+//! use libp2p_noise::{NoiseConfig, NoiseOutput};
+//! use libp2p_secio::SecioKeyPair;
+//! use libp2p_tcp::TcpConfig;
+//! use tokio_io::io::write_all;
+//!
+//! let keypair = SecioKeyPair::ed25519_generated().unwrap();
+//! let dialer = TcpConfig::new()
+//!     .with_upgrade(NoiseConfig::xx(&keypair).unwrap())
+//!     .map(|out: NoiseOutput<_>, _| out.stream);
+//!
+//! let future = dialer.dial("/ip4/127.0.0.1/tcp/12345".parse::<Multiaddr>().unwrap())
+//!     .unwrap_or_else(|_| panic!("Unable to dial node"))
+//!     .and_then(|connection| write_all(connection, "hello world"))
+//!     .map_err(|e| panic!("error: {:?}", e));
+//!
+//! tokio::run(future.map(|_| ()));
+//! # }
+//! ```
+
+mod error;
+mod handshake;
+mod io;
+mod payload_proto;
+
+pub use crate::error::NoiseError;
+pub use crate::io::NoiseStream;
+
+use crate::payload_proto::NoiseHandshakePayload;
+use futures::Future;
+use libp2p_core::{PeerId, PublicKey, upgrade::{AuthenticatedOutput, UpgradeInfo, InboundUpgrade, OutboundUpgrade}};
+use libp2p_secio::SecioKeyPair;
+use protobuf::Message;
+use std::{iter, sync::Arc};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Prefix of the message signed by the identity key to authenticate the static DH key.
+const STATIC_KEY_DOMAIN: &[u8] = b"noise-libp2p-static-key:";
+
+/// Implementation of the `InboundUpgrade` and `OutboundUpgrade` traits of `libp2p_core`.
+/// Automatically applies noise on any connection.
+#[derive(Clone)]
+pub struct NoiseConfig {
+    /// Our static DH key pair, and the payload that authenticates it.
+    keys: Arc<StaticKeys>,
+    /// Handshake pattern to use.
+    pattern: HandshakePattern,
+    /// Hash function to use.
+    hash: NoiseHash,
+}
+
+/// Static DH key pair of the local node.
+struct StaticKeys {
+    /// Private X25519 key.
+    secret: Vec<u8>,
+    /// Public X25519 key.
+    public: Vec<u8>,
+    /// Encoded `NoiseHandshakePayload` containing our identity key and the signature of `public`.
+    payload: Vec<u8>,
+}
+
+/// Handshake pattern, and the role we play in it if the pattern is asymmetric.
+#[derive(Debug, Clone)]
+enum HandshakePattern {
+    XX,
+    IX,
+    /// Listening side of `IK`. Can't be used to dial.
+    IKListener,
+    /// Dialing side of `IK`. Behaves like `IKListener` when listening.
+    IKDialer {
+        /// Identity key that the listener must prove.
+        remote_key: PublicKey,
+        /// Static DH key of the listener.
+        remote_static_key: Vec<u8>,
+    },
+}
+
+/// Hash function used by the noise handshake.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NoiseHash {
+    /// SHA-256. This is the default.
+    Sha256,
+    /// BLAKE2s.
+    Blake2s,
+}
+
+impl NoiseConfig {
+    /// Builds a configuration that uses the `XX` handshake pattern.
+    #[inline]
+    pub fn xx(identity: &SecioKeyPair) -> Result<NoiseConfig, NoiseError> {
+        NoiseConfig::new(identity, HandshakePattern::XX)
+    }
+
+    /// Builds a configuration that uses the `IX` handshake pattern.
+    #[inline]
+    pub fn ix(identity: &SecioKeyPair) -> Result<NoiseConfig, NoiseError> {
+        NoiseConfig::new(identity, HandshakePattern::IX)
+    }
+
+    /// Builds a configuration that accepts connections using the `IK` handshake pattern.
+    ///
+    /// Dialers must know the result of `static_public_key()` in advance. Dialing with this
+    /// configuration always fails.
+    #[inline]
+    pub fn ik_listener(identity: &SecioKeyPair) -> Result<NoiseConfig, NoiseError> {
+        NoiseConfig::new(identity, HandshakePattern::IKListener)
+    }
+
+    /// Builds a configuration that dials a specific node using the `IK` handshake pattern.
+    ///
+    /// `remote_static_key` is the static DH key of the remote, and `remote_key` is its identity
+    /// key. The handshake fails if the remote can't prove that it owns both.
+    #[inline]
+    pub fn ik_dialer(identity: &SecioKeyPair, remote_key: PublicKey, remote_static_key: Vec<u8>)
+        -> Result<NoiseConfig, NoiseError>
+    {
+        NoiseConfig::new(identity, HandshakePattern::IKDialer { remote_key, remote_static_key })
+    }
+
+    /// Changes the hash function used by the handshake. Both parties must use the same one.
+    #[inline]
+    pub fn with_hash(mut self, hash: NoiseHash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Returns our static DH public key.
+    #[inline]
+    pub fn static_public_key(&self) -> &[u8] {
+        &self.keys.public
+    }
+
+    fn new(identity: &SecioKeyPair, pattern: HandshakePattern) -> Result<NoiseConfig, NoiseError> {
+        let hash = NoiseHash::Sha256;
+        let keypair = snow::Builder::new(noise_params(&pattern, hash)).generate_keypair()?;
+
+        let mut message = STATIC_KEY_DOMAIN.to_vec();
+        message.extend_from_slice(&keypair.public);
+        let signature = identity.sign(&message).map_err(NoiseError::SigningFailure)?;
+
+        let mut payload = NoiseHandshakePayload::new();
+        payload.set_identity_key(identity.to_public_key().into_protobuf_encoding());
+        payload.set_identity_sig(signature);
+        let payload = payload.write_to_bytes()?;
+
+        Ok(NoiseConfig {
+            keys: Arc::new(StaticKeys {
+                secret: keypair.private,
+                public: keypair.public,
+                payload,
+            }),
+            pattern,
+            hash,
+        })
+    }
+
+    /// Returns the noise parameters corresponding to this configuration.
+    fn noise_params(&self) -> snow::params::NoiseParams {
+        noise_params(&self.pattern, self.hash)
+    }
+}
+
+/// Returns the noise parameters for the given pattern and hash function.
+fn noise_params(pattern: &HandshakePattern, hash: NoiseHash) -> snow::params::NoiseParams {
+    let pattern = match pattern {
+        HandshakePattern::XX => "XX",
+        HandshakePattern::IX => "IX",
+        HandshakePattern::IKListener | HandshakePattern::IKDialer { .. } => "IK",
+    };
+    let hash = match hash {
+        NoiseHash::Sha256 => "SHA256",
+        NoiseHash::Blake2s => "BLAKE2s",
+    };
+
+    format!("Noise_{}_25519_ChaChaPoly_{}", pattern, hash)
+        .parse()
+        .expect("all the supported combinations are valid noise parameters")
+}
+
+/// Output of the noise protocol.
+pub struct NoiseOutput<T> {
+    /// The encrypted stream.
+    pub stream: NoiseStream<T>,
+    /// The public identity key of the remote.
+    pub remote_key: PublicKey,
+}
+
+impl<T> NoiseOutput<T> {
+    /// Returns the `PeerId` of the remote.
+    #[inline]
+    pub fn remote_peer_id(&self) -> PeerId {
+        self.remote_key.clone().into_peer_id()
+    }
+}
+
+impl<T> AuthenticatedOutput for NoiseOutput<T>
+where
+    T: AsyncRead + AsyncWrite
+{
+    type Stream = NoiseStream<T>;
+
+    #[inline]
+    fn into_authenticated(self) -> (PeerId, NoiseStream<T>) {
+        (self.remote_key.into_peer_id(), self.stream)
+    }
+}
+
+impl UpgradeInfo for NoiseConfig {
+    type Info = &'static [u8];
+    type InfoIter = iter::Once<Self::Info>;
+
+    fn protocol_info(&self) -> Self::InfoIter {
+        let name: &'static [u8] = match (&self.pattern, self.hash) {
+            (HandshakePattern::XX, NoiseHash::Sha256) => b"/noise/xx/25519/chachapoly/sha256/0.1.0",
+            (HandshakePattern::XX, NoiseHash::Blake2s) => b"/noise/xx/25519/chachapoly/blake2s/0.1.0",
+            (HandshakePattern::IX, NoiseHash::Sha256) => b"/noise/ix/25519/chachapoly/sha256/0.1.0",
+            (HandshakePattern::IX, NoiseHash::Blake2s) => b"/noise/ix/25519/chachapoly/blake2s/0.1.0",
+            (_, NoiseHash::Sha256) => b"/noise/ik/25519/chachapoly/sha256/0.1.0",
+            (_, NoiseHash::Blake2s) => b"/noise/ik/25519/chachapoly/blake2s/0.1.0",
+        };
+        iter::once(name)
+    }
+}
+
+impl<T> InboundUpgrade<T> for NoiseConfig
+where
+    T: AsyncRead + AsyncWrite + Send + 'static
+{
+    type Output = NoiseOutput<T>;
+    type Error = NoiseError;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_inbound(self, socket: T, _: Self::Info) -> Self::Future {
+        handshake::handshake(socket, self, false)
+    }
+}
+
+impl<T> OutboundUpgrade<T> for NoiseConfig
+where
+    T: AsyncRead + AsyncWrite + Send + 'static
+{
+    type Output = NoiseOutput<T>;
+    type Error = NoiseError;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_outbound(self, socket: T, _: Self::Info) -> Self::Future {
+        handshake::handshake(socket, self, true)
+    }
+}
@@ -0,0 +1,339 @@
+// This file is generated by rust-protobuf 2.0.2. Do not edit
+// @generated
+
+// https://github.com/Manishearth/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy)]
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unsafe_code)]
+#![allow(unused_imports)]
+#![allow(unused_results)]
+
+use protobuf::Message as Message_imported_for_functions;
+use protobuf::ProtobufEnum as ProtobufEnum_imported_for_functions;
+
+#[derive(PartialEq,Clone,Default)]
+pub struct NoiseHandshakePayload {
+    // message fields
+    identity_key: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    identity_sig: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    data: ::protobuf::SingularField<::std::vec::Vec<u8>>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl NoiseHandshakePayload {
+    pub fn new() -> NoiseHandshakePayload {
+        ::std::default::Default::default()
+    }
+
+    // optional bytes identity_key = 1;
+
+    pub fn clear_identity_key(&mut self) {
+        self.identity_key.clear();
+    }
+
+    pub fn has_identity_key(&self) -> bool {
+        self.identity_key.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_identity_key(&mut self, v: ::std::vec::Vec<u8>) {
+        self.identity_key = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_identity_key(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.identity_key.is_none() {
+            self.identity_key.set_default();
+        }
+        self.identity_key.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_identity_key(&mut self) -> ::std::vec::Vec<u8> {
+        self.identity_key.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    pub fn get_identity_key(&self) -> &[u8] {
+        match self.identity_key.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+
+    // optional bytes identity_sig = 2;
+
+    pub fn clear_identity_sig(&mut self) {
+        self.identity_sig.clear();
+    }
+
+    pub fn has_identity_sig(&self) -> bool {
+        self.identity_sig.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_identity_sig(&mut self, v: ::std::vec::Vec<u8>) {
+        self.identity_sig = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_identity_sig(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.identity_sig.is_none() {
+            self.identity_sig.set_default();
+        }
+        self.identity_sig.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_identity_sig(&mut self) -> ::std::vec::Vec<u8> {
+        self.identity_sig.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    pub fn get_identity_sig(&self) -> &[u8] {
+        match self.identity_sig.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+
+    // optional bytes data = 3;
+
+    pub fn clear_data(&mut self) {
+        self.data.clear();
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.data.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_data(&mut self, v: ::std::vec::Vec<u8>) {
+        self.data = ::protobuf::SingularField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_data(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if self.data.is_none() {
+            self.data.set_default();
+        }
+        self.data.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_data(&mut self) -> ::std::vec::Vec<u8> {
+        self.data.take().unwrap_or_else(|| ::std::vec::Vec::new())
+    }
+
+    pub fn get_data(&self) -> &[u8] {
+        match self.data.as_ref() {
+            Some(v) => &v,
+            None => &[],
+        }
+    }
+}
+
+impl ::protobuf::Message for NoiseHandshakePayload {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.identity_key)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.identity_sig)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_bytes_into(wire_type, is, &mut self.data)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.identity_key.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(1, &v);
+        }
+        if let Some(ref v) = self.identity_sig.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(2, &v);
+        }
+        if let Some(ref v) = self.data.as_ref() {
+            my_size += ::protobuf::rt::bytes_size(3, &v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.identity_key.as_ref() {
+            os.write_bytes(1, &v)?;
+        }
+        if let Some(ref v) = self.identity_sig.as_ref() {
+            os.write_bytes(2, &v)?;
+        }
+        if let Some(ref v) = self.data.as_ref() {
+            os.write_bytes(3, &v)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> NoiseHandshakePayload {
+        NoiseHandshakePayload::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "identity_key",
+                    |m: &NoiseHandshakePayload| { &m.identity_key },
+                    |m: &mut NoiseHandshakePayload| { &mut m.identity_key },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "identity_sig",
+                    |m: &NoiseHandshakePayload| { &m.identity_sig },
+                    |m: &mut NoiseHandshakePayload| { &mut m.identity_sig },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "data",
+                    |m: &NoiseHandshakePayload| { &m.data },
+                    |m: &mut NoiseHandshakePayload| { &mut m.data },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<NoiseHandshakePayload>(
+                    "NoiseHandshakePayload",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static NoiseHandshakePayload {
+        static mut instance: ::protobuf::lazy::Lazy<NoiseHandshakePayload> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const NoiseHandshakePayload,
+        };
+        unsafe {
+            instance.get(NoiseHandshakePayload::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for NoiseHandshakePayload {
+    fn clear(&mut self) {
+        self.clear_identity_key();
+        self.clear_identity_sig();
+        self.clear_data();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for NoiseHandshakePayload {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for NoiseHandshakePayload {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+static file_descriptor_proto_data: &'static [u8] = b"\
+    \n\rpayload.proto\x12\rpayload.proto\"s\n\x15NoiseHandshakePayload\x12\"\
+    \n\x0cidentity_key\x18\x01\x20\x01(\x0cR\x0cidentity_key\x12\"\n\x0ciden\
+    tity_sig\x18\x02\x20\x01(\x0cR\x0cidentity_sig\x12\x12\n\x04data\x18\x03\
+    \x20\x01(\x0cR\x04dataJ\xfb\x03\n\x06\x12\x04\0\0\n\x01\n\x08\n\x01\x02\
+    \x12\x03\0\x08\x15\nc\n\x02\x04\0\x12\x04\x03\0\n\x01\x1aW\x20Payload\
+    \x20sent\x20in\x20the\x20handshake\x20messages,\x20after\x20the\x20stati\
+    c\x20DH\x20key\x20has\x20been\x20transmitted.\n\n\n\n\x03\x04\0\x01\x12\
+    \x03\x03\x08\x1d\nJ\n\x04\x04\0\x02\0\x12\x03\x05\x08(\x1a=\x20Protobuf\
+    \x20encoding\x20of\x20the\x20public\x20identity\x20key\x20of\x20the\x20s\
+    ender.\n\n\x0c\n\x05\x04\0\x02\0\x04\x12\x03\x05\x08\x10\n\x0c\n\x05\x04\
+    \0\x02\0\x05\x12\x03\x05\x11\x16\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x05\
+    \x17#\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\x05&'\nJ\n\x04\x04\0\x02\x01\
+    \x12\x03\x07\x08(\x1a=\x20Signature\x20of\x20the\x20static\x20DH\x20key,\
+    \x20made\x20with\x20the\x20identity\x20key.\n\n\x0c\n\x05\x04\0\x02\x01\
+    \x04\x12\x03\x07\x08\x10\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\x07\x11\
+    \x16\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\x07\x17#\n\x0c\n\x05\x04\0\
+    \x02\x01\x03\x12\x03\x07&'\n6\n\x04\x04\0\x02\x02\x12\x03\t\x08\x20\x1a)\
+    \x20Application\x20data.\x20Unused\x20at\x20the\x20moment.\n\n\x0c\n\x05\
+    \x04\0\x02\x02\x04\x12\x03\t\x08\x10\n\x0c\n\x05\x04\0\x02\x02\x05\x12\
+    \x03\t\x11\x16\n\x0c\n\x05\x04\0\x02\x02\x01\x12\x03\t\x17\x1b\n\x0c\n\
+    \x05\x04\0\x02\x02\x03\x12\x03\t\x1e\x1f\
+";
+
+static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
+    lock: ::protobuf::lazy::ONCE_INIT,
+    ptr: 0 as *const ::protobuf::descriptor::FileDescriptorProto,
+};
+
+fn parse_descriptor_proto() -> ::protobuf::descriptor::FileDescriptorProto {
+    ::protobuf::parse_from_bytes(file_descriptor_proto_data).unwrap()
+}
+
+pub fn file_descriptor_proto() -> &'static ::protobuf::descriptor::FileDescriptorProto {
+    unsafe {
+        file_descriptor_proto_lazy.get(|| {
+            parse_descriptor_proto()
+        })
+    }
+}
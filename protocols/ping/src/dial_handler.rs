@@ -24,7 +24,7 @@ use libp2p_core::{
     OutboundUpgrade,
     ProtocolsHandler,
     ProtocolsHandlerEvent,
-    protocols_handler::ProtocolsHandlerUpgrErr,
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
     upgrade::DeniedUpgrade
 };
 use log::warn;
@@ -195,6 +195,20 @@ where
         }
     }
 
+    fn connection_keep_alive(&self) -> KeepAlive {
+        // Pinging periodically is not a reason to keep the connection open, but we don't want
+        // the connection to be closed while a ping is in progress.
+        match self.out_state {
+            OutState::NeedToOpen { .. } |
+            OutState::Upgrading { .. } |
+            OutState::WaitingForPong { .. } => KeepAlive::Forever,
+            OutState::Idle { .. } |
+            OutState::Disabled |
+            OutState::Shutdown |
+            OutState::Poisoned => KeepAlive::Now,
+        }
+    }
+
     fn shutdown(&mut self) {
         // Put `Shutdown` in `self.out_state` if we don't have any substream open.
         // Otherwise, keep the state as it is but call `shutdown()` on the substream. This
@@ -26,7 +26,7 @@ use libp2p_core::{
     OutboundUpgrade,
     ProtocolsHandler,
     ProtocolsHandlerEvent,
-    protocols_handler::ProtocolsHandlerUpgrErr,
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
     upgrade::DeniedUpgrade
 };
 use log::warn;
@@ -105,6 +105,16 @@ where
     #[inline]
     fn inject_dial_upgrade_error(&mut self, _: Self::OutboundOpenInfo, _: ProtocolsHandlerUpgrErr<<Self::OutboundProtocol as OutboundUpgrade<Self::Substream>>::Error>) {}
 
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        // Don't close the connection while the remote is pinging us.
+        if self.ping_in_substreams.is_empty() {
+            KeepAlive::Now
+        } else {
+            KeepAlive::Forever
+        }
+    }
+
     #[inline]
     fn shutdown(&mut self) {
         for ping in self.ping_in_substreams.iter_mut() {
@@ -0,0 +1,13 @@
+#!/bin/sh
+
+# This script regenerates the `src/message_proto.rs` file from `message.proto`.
+
+docker run --rm -v `pwd`:/usr/code:z -w /usr/code rust /bin/bash -c " \
+    apt-get update; \
+    apt-get install -y protobuf-compiler; \
+    cargo install --version 2.0.2 protobuf-codegen; \
+    protoc --rust_out . message.proto"
+
+sudo chown $USER:$USER *.rs
+
+mv -f message.rs ./src/message_proto.rs
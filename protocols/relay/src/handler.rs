@@ -0,0 +1,299 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::protocol::{RelayInbound, RelayListen, RelayOutbound, RelayPeer};
+use futures::prelude::*;
+use libp2p_core::{
+    ProtocolsHandler, ProtocolsHandlerEvent,
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
+    upgrade::{InboundUpgrade, OutboundUpgrade, UpgradeError}
+};
+use std::{collections::VecDeque, fmt, io, marker::PhantomData, time::{Duration, Instant}};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Identifier of a relay request sent through a `RelayHandler`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Returns the identifier that follows this one.
+    #[inline]
+    pub(crate) fn next(&mut self) -> RequestId {
+        let id = *self;
+        self.0 += 1;
+        id
+    }
+
+    /// Returns the first identifier.
+    #[inline]
+    pub(crate) fn zero() -> RequestId {
+        RequestId(0)
+    }
+}
+
+/// Event that can be sent to the handler.
+#[derive(Debug)]
+pub enum RelayHandlerIn {
+    /// Opens a substream and sends a relay request on it.
+    OutgoingRequest {
+        /// Identifier to report back with the outcome.
+        request_id: RequestId,
+        /// The request to send.
+        request: RelayOutbound,
+    },
+    /// The connection is used to receive relayed connections, and must be kept alive.
+    UsedForListening,
+    /// One of the circuits whose substream has been reported by the handler has been closed.
+    CircuitClosed,
+}
+
+/// Event produced by the handler.
+///
+/// Each event that contains a substream counts as an active circuit, and keeps the connection
+/// alive until `RelayHandlerIn::CircuitClosed` is received.
+pub enum RelayHandlerEvent<TSubstream> {
+    /// The remote asks us to relay a connection. The request must be answered on the substream.
+    HopRequest {
+        /// Substream on which to answer.
+        substream: TSubstream,
+        /// The peer to relay the connection to.
+        dst: RelayPeer,
+    },
+    /// The remote, acting as a relay, opens a circuit to us. The request must be answered on the
+    /// substream.
+    StopRequest {
+        /// Substream on which to answer.
+        substream: TSubstream,
+        /// The peer that opened the circuit.
+        src: RelayPeer,
+    },
+    /// The remote accepted one of our requests. The substream carries the data of the circuit.
+    OutgoingRequestSuccess {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// The substream.
+        substream: TSubstream,
+    },
+    /// One of our requests has failed or has been refused.
+    OutgoingRequestFailure {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// What went wrong.
+        error: io::Error,
+    },
+}
+
+/// Protocol handler that sends and receives relay requests.
+///
+/// The handler only negotiates the circuits. The substreams are then handed over to the
+/// behaviour.
+pub struct RelayHandler<TSubstream> {
+    /// Upgrade used to accept the requests of the remote.
+    listen: RelayListen,
+
+    /// How long to keep the connection alive when it isn't used.
+    idle_timeout: Duration,
+
+    /// Until when to keep the connection alive.
+    keep_alive: KeepAlive,
+
+    /// If true, the behaviour expects the remote to open circuits to us.
+    used_for_listening: bool,
+
+    /// Number of circuits reported to the behaviour and not closed yet.
+    active_circuits: usize,
+
+    /// Requests for which we still have to open a substream.
+    pending_requests: VecDeque<(RequestId, RelayOutbound)>,
+
+    /// Number of requests whose substream is being negotiated.
+    requests_in_progress: usize,
+
+    /// Events to report to the behaviour.
+    pending_events: VecDeque<RelayHandlerEvent<TSubstream>>,
+
+    /// If true, we are shutting down and should no longer report anything.
+    shutting_down: bool,
+
+    /// Marker to pin the generics.
+    marker: PhantomData<TSubstream>,
+}
+
+impl<TSubstream> RelayHandler<TSubstream> {
+    /// Builds a new `RelayHandler`.
+    pub fn new(hop_enabled: bool, idle_timeout: Duration) -> Self {
+        RelayHandler {
+            listen: RelayListen::new(hop_enabled),
+            idle_timeout,
+            keep_alive: KeepAlive::Until(Instant::now() + idle_timeout),
+            used_for_listening: false,
+            active_circuits: 0,
+            pending_requests: VecDeque::new(),
+            requests_in_progress: 0,
+            pending_events: VecDeque::new(),
+            shutting_down: false,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<TSubstream> ProtocolsHandler for RelayHandler<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+{
+    type InEvent = RelayHandlerIn;
+    type OutEvent = RelayHandlerEvent<TSubstream>;
+    type Substream = TSubstream;
+    type InboundProtocol = RelayListen;
+    type OutboundProtocol = RelayOutbound;
+    type OutboundOpenInfo = RequestId;
+
+    #[inline]
+    fn listen_protocol(&self) -> Self::InboundProtocol {
+        self.listen.clone()
+    }
+
+    fn inject_fully_negotiated_inbound(
+        &mut self,
+        request: <Self::InboundProtocol as InboundUpgrade<TSubstream>>::Output,
+    ) {
+        if self.shutting_down {
+            return;
+        }
+
+        let event = match request {
+            // The source reported by the remote is ignored; the behaviour knows who the remote is.
+            RelayInbound::Hop { substream, dst, .. } => RelayHandlerEvent::HopRequest { substream, dst },
+            RelayInbound::Stop { substream, src } => RelayHandlerEvent::StopRequest { substream, src },
+            RelayInbound::CanHop => return,
+        };
+
+        self.active_circuits += 1;
+        self.pending_events.push_back(event);
+    }
+
+    fn inject_fully_negotiated_outbound(
+        &mut self,
+        substream: <Self::OutboundProtocol as OutboundUpgrade<TSubstream>>::Output,
+        request_id: Self::OutboundOpenInfo,
+    ) {
+        self.requests_in_progress -= 1;
+        if self.shutting_down {
+            return;
+        }
+
+        self.active_circuits += 1;
+        self.pending_events.push_back(RelayHandlerEvent::OutgoingRequestSuccess {
+            request_id,
+            substream,
+        });
+    }
+
+    #[inline]
+    fn inject_event(&mut self, event: Self::InEvent) {
+        match event {
+            RelayHandlerIn::OutgoingRequest { request_id, request } => {
+                self.pending_requests.push_back((request_id, request));
+            }
+            RelayHandlerIn::UsedForListening => {
+                self.used_for_listening = true;
+            }
+            RelayHandlerIn::CircuitClosed => {
+                self.active_circuits = self.active_circuits.saturating_sub(1);
+            }
+        }
+    }
+
+    #[inline]
+    fn inject_inbound_closed(&mut self) {}
+
+    fn inject_dial_upgrade_error(
+        &mut self,
+        request_id: Self::OutboundOpenInfo,
+        error: ProtocolsHandlerUpgrErr<io::Error>,
+    ) {
+        self.requests_in_progress -= 1;
+
+        let error = match error {
+            ProtocolsHandlerUpgrErr::Upgrade(UpgradeError::Apply(err)) => err,
+            ProtocolsHandlerUpgrErr::Timeout =>
+                io::Error::new(io::ErrorKind::TimedOut, "timeout while negotiating the circuit"),
+            err => io::Error::new(io::ErrorKind::Other, err.to_string()),
+        };
+
+        self.pending_events.push_back(RelayHandlerEvent::OutgoingRequestFailure {
+            request_id,
+            error,
+        });
+    }
+
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        self.keep_alive
+    }
+
+    #[inline]
+    fn shutdown(&mut self) {
+        self.shutting_down = true;
+    }
+
+    fn poll(
+        &mut self,
+    ) -> Poll<
+        Option<ProtocolsHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::OutEvent>>,
+        io::Error,
+    > {
+        // The substreams of the circuits are owned by the behaviour, so we have nothing to close.
+        if self.shutting_down {
+            return Ok(Async::Ready(None));
+        }
+
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
+        }
+
+        if let Some((request_id, request)) = self.pending_requests.pop_front() {
+            self.requests_in_progress += 1;
+            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::OutboundSubstreamRequest {
+                upgrade: request,
+                info: request_id,
+            })));
+        }
+
+        if self.active_circuits > 0 || self.requests_in_progress > 0 || self.used_for_listening {
+            self.keep_alive = KeepAlive::Forever;
+        } else if self.keep_alive.is_forever() {
+            self.keep_alive = KeepAlive::Until(Instant::now() + self.idle_timeout);
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<TSubstream> fmt::Debug for RelayHandler<TSubstream> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("RelayHandler")
+            .field("used_for_listening", &self.used_for_listening)
+            .field("active_circuits", &self.active_circuits)
+            .field("pending_requests", &self.pending_requests.len())
+            .field("requests_in_progress", &self.requests_in_progress)
+            .finish()
+    }
+}
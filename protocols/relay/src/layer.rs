@@ -0,0 +1,702 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::handler::{RelayHandler, RelayHandlerEvent, RelayHandlerIn, RequestId};
+use crate::message_proto::CircuitRelay_Status;
+use crate::protocol::{self, RelayOutbound, RelayPeer};
+use crate::transport::{Connection, RelayTransport, TransportToBehaviourMsg};
+use fnv::FnvHashMap;
+use futures::{prelude::*, sync::{mpsc, oneshot}};
+use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
+use libp2p_core::{Multiaddr, PeerId, multiaddr::Protocol, protocols_handler::ProtocolsHandler};
+use log::debug;
+use smallvec::SmallVec;
+use std::{collections::VecDeque, error, io, marker::PhantomData, mem, time::Duration};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Configuration of a `Relay` behaviour.
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    hop_enabled: bool,
+    max_circuits: usize,
+    max_circuits_per_peer: usize,
+    connection_idle_timeout: Duration,
+}
+
+impl Default for RelayConfig {
+    #[inline]
+    fn default() -> Self {
+        RelayConfig {
+            hop_enabled: false,
+            max_circuits: 64,
+            max_circuits_per_peer: 4,
+            connection_idle_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RelayConfig {
+    /// Sets whether we accept to relay connections between other peers. Disabled by default.
+    #[inline]
+    pub fn with_hop_enabled(mut self, hop_enabled: bool) -> Self {
+        self.hop_enabled = hop_enabled;
+        self
+    }
+
+    /// Sets the maximum number of connections we relay at the same time.
+    #[inline]
+    pub fn with_max_circuits(mut self, max_circuits: usize) -> Self {
+        self.max_circuits = max_circuits;
+        self
+    }
+
+    /// Sets the maximum number of connections we relay at the same time on behalf of a single
+    /// peer.
+    #[inline]
+    pub fn with_max_circuits_per_peer(mut self, max_circuits_per_peer: usize) -> Self {
+        self.max_circuits_per_peer = max_circuits_per_peer;
+        self
+    }
+
+    /// Sets how long a connection is kept open when no circuit goes through it.
+    #[inline]
+    pub fn with_connection_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_idle_timeout = timeout;
+        self
+    }
+}
+
+/// Event produced by the `Relay` behaviour.
+#[derive(Debug, Clone)]
+pub enum RelayEvent {
+    /// We started relaying a connection between two peers.
+    CircuitOpened {
+        /// The peer that asked for the circuit.
+        src: PeerId,
+        /// The peer the circuit goes to.
+        dst: PeerId,
+    },
+    /// We refused to relay a connection between two peers, or the destination refused it.
+    CircuitRefused {
+        /// The peer that asked for the circuit.
+        src: PeerId,
+        /// The peer the circuit was supposed to go to.
+        dst: PeerId,
+    },
+}
+
+/// Network behaviour that relays connections between other peers, and that opens and accepts
+/// the relayed connections of the `RelayTransport` it has been created with.
+///
+/// Create it with `new_transport_and_behaviour`.
+pub struct Relay<TSubstream> {
+    /// Peer id of the local node.
+    local_peer_id: PeerId,
+
+    /// Configuration of the behaviour.
+    config: RelayConfig,
+
+    /// Requests of the transport.
+    from_transport: mpsc::UnboundedReceiver<TransportToBehaviourMsg>,
+
+    /// Sends the relayed connections we accept to the listener of the transport.
+    to_listener: mpsc::UnboundedSender<(Connection, Multiaddr)>,
+
+    /// Cloned in each `CircuitGuard`.
+    circuit_closed_tx: mpsc::UnboundedSender<CircuitClosed>,
+
+    /// Reports circuits that have been closed.
+    circuit_closed_rx: mpsc::UnboundedReceiver<CircuitClosed>,
+
+    /// If true, the transport is listening for relayed connections.
+    listening: bool,
+
+    /// Peers we're connected to, and the address we reach them at.
+    connected: FnvHashMap<PeerId, Multiaddr>,
+
+    /// Relays we listen through, and their address. We stay connected to them.
+    listening_relays: FnvHashMap<PeerId, Multiaddr>,
+
+    /// Dials of the transport through relays we're not connected to yet.
+    pending_dials: FnvHashMap<PeerId, SmallVec<[PendingDial; 2]>>,
+
+    /// Dials of the transport for which a `HOP` request has been sent to the relay, and the
+    /// relay in question.
+    outgoing_dials: FnvHashMap<RequestId, (PeerId, oneshot::Sender<Result<Connection, io::Error>>)>,
+
+    /// Circuits we relay and for which a `STOP` request has been sent to the destination.
+    pending_circuits: FnvHashMap<RequestId, PendingCircuit<TSubstream>>,
+
+    /// Number of circuits we relay, per source.
+    circuits_per_peer: FnvHashMap<PeerId, usize>,
+
+    /// Total number of circuits we relay.
+    num_circuits: usize,
+
+    /// Identifier of the next request to send.
+    next_request_id: RequestId,
+
+    /// Futures that answer requests and copy the data of the circuits we relay.
+    tasks: Vec<Box<dyn Future<Item = (), Error = ()> + Send>>,
+
+    /// Events to produce from `poll()`.
+    events: VecDeque<NetworkBehaviourAction<RelayHandlerIn, RelayEvent>>,
+
+    /// Marker to pin the generics.
+    marker: PhantomData<TSubstream>,
+}
+
+/// Dial of the transport waiting for the connection to the relay.
+struct PendingDial {
+    /// Address the relay has been dialed at. Can be empty.
+    relay_addr: Multiaddr,
+    /// The peer to open a circuit with.
+    dst: PeerId,
+    /// Where to send the outcome.
+    sender: oneshot::Sender<Result<Connection, io::Error>>,
+}
+
+/// Circuit we relay, waiting for the destination to accept it.
+struct PendingCircuit<TSubstream> {
+    /// The peer that asked for the circuit.
+    src: PeerId,
+    /// The peer the circuit goes to.
+    dst: PeerId,
+    /// Substream on which the source waits for our answer.
+    substream: TSubstream,
+    /// Closes the circuit when dropped.
+    guard: CircuitGuard,
+}
+
+/// Reports to the behaviour that a circuit has been closed when dropped.
+///
+/// Makes sure that the handlers that reported the substreams of the circuit get notified, and
+/// that the circuit no longer counts towards the limits.
+pub(crate) struct CircuitGuard {
+    /// The handlers to notify.
+    peers: SmallVec<[PeerId; 2]>,
+    /// The peer whose limits the circuit counts towards, if any.
+    counted: Option<PeerId>,
+    /// Channel to the behaviour.
+    sender: mpsc::UnboundedSender<CircuitClosed>,
+}
+
+/// Message sent by a `CircuitGuard` when dropped.
+pub(crate) struct CircuitClosed {
+    /// The handlers to notify.
+    peers: SmallVec<[PeerId; 2]>,
+    /// The peer whose limits the circuit counted towards, if any.
+    counted: Option<PeerId>,
+}
+
+impl Drop for CircuitGuard {
+    fn drop(&mut self) {
+        let message = CircuitClosed {
+            peers: mem::replace(&mut self.peers, SmallVec::new()),
+            counted: self.counted.take(),
+        };
+        // The behaviour being gone means that there is nothing to clean up.
+        let _ = self.sender.unbounded_send(message);
+    }
+}
+
+/// Builds a `RelayTransport` wrapping around `transport` and the `Relay` behaviour that handles
+/// its circuits. The behaviour must be put in the `Swarm` that uses the transport.
+pub fn new_transport_and_behaviour<TTransport, TSubstream>(
+    local_peer_id: PeerId,
+    config: RelayConfig,
+    transport: TTransport,
+) -> (RelayTransport<TTransport>, Relay<TSubstream>) {
+    let (to_behaviour, from_transport) = mpsc::unbounded();
+    let (to_listener, incoming) = mpsc::unbounded();
+    let (circuit_closed_tx, circuit_closed_rx) = mpsc::unbounded();
+
+    let transport = RelayTransport::new(transport, to_behaviour, incoming);
+    let behaviour = Relay {
+        local_peer_id,
+        config,
+        from_transport,
+        to_listener,
+        circuit_closed_tx,
+        circuit_closed_rx,
+        listening: false,
+        connected: FnvHashMap::default(),
+        listening_relays: FnvHashMap::default(),
+        pending_dials: FnvHashMap::default(),
+        outgoing_dials: FnvHashMap::default(),
+        pending_circuits: FnvHashMap::default(),
+        circuits_per_peer: FnvHashMap::default(),
+        num_circuits: 0,
+        next_request_id: RequestId::zero(),
+        tasks: Vec::new(),
+        events: VecDeque::new(),
+        marker: PhantomData,
+    };
+
+    (transport, behaviour)
+}
+
+impl<TSubstream> Relay<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+{
+    /// Returns the number of connections we relay between other peers.
+    #[inline]
+    pub fn num_circuits(&self) -> usize {
+        self.num_circuits
+    }
+
+    /// Builds a guard that notifies the handler of `peer_id` when dropped.
+    fn guard(&self, peer_id: PeerId) -> CircuitGuard {
+        let mut peers = SmallVec::new();
+        peers.push(peer_id);
+        CircuitGuard {
+            peers,
+            counted: None,
+            sender: self.circuit_closed_tx.clone(),
+        }
+    }
+
+    /// Asks `relay`, which we're connected to, to open a circuit to `dst`.
+    fn send_hop_request(&mut self, relay: PeerId, dst: PeerId, sender: oneshot::Sender<Result<Connection, io::Error>>) {
+        let request_id = self.next_request_id.next();
+        self.outgoing_dials.insert(request_id, (relay.clone(), sender));
+        self.events.push_back(NetworkBehaviourAction::SendEvent {
+            peer_id: relay,
+            event: RelayHandlerIn::OutgoingRequest {
+                request_id,
+                request: RelayOutbound::hop(RelayPeer::new(self.local_peer_id.clone()), RelayPeer::new(dst)),
+            },
+        });
+    }
+
+    /// Answers a request with an error status, then closes the substream.
+    fn refuse(&mut self, substream: TSubstream, status: CircuitRelay_Status, guard: CircuitGuard) {
+        debug!("Refusing relay request: {:?}", status);
+        let future = protocol::send_status(substream, status).then(move |_| {
+            drop(guard);
+            Ok::<_, ()>(())
+        });
+        self.tasks.push(Box::new(future));
+    }
+
+    /// Handles a request of the transport.
+    fn on_transport_message(&mut self, message: TransportToBehaviourMsg) {
+        match message {
+            TransportToBehaviourMsg::Dial { relay, relay_addr, dst, sender } => {
+                if self.connected.contains_key(&relay) {
+                    self.send_hop_request(relay, dst, sender);
+                    return;
+                }
+
+                let pending = self.pending_dials.entry(relay.clone()).or_insert_with(SmallVec::new);
+                if pending.is_empty() {
+                    self.events.push_back(dial_action(relay, relay_addr.clone()));
+                }
+                pending.push(PendingDial { relay_addr, dst, sender });
+            }
+            TransportToBehaviourMsg::Listen { relay } => {
+                self.listening = true;
+                if let Some((relay, relay_addr)) = relay {
+                    if self.connected.contains_key(&relay) {
+                        self.events.push_back(NetworkBehaviourAction::SendEvent {
+                            peer_id: relay.clone(),
+                            event: RelayHandlerIn::UsedForListening,
+                        });
+                    } else {
+                        self.events.push_back(dial_action(relay.clone(), relay_addr.clone()));
+                    }
+                    self.listening_relays.insert(relay, relay_addr);
+                }
+            }
+        }
+    }
+
+    /// Handles the notification of a `CircuitGuard`.
+    fn on_circuit_closed(&mut self, closed: CircuitClosed) {
+        if let Some(src) = closed.counted {
+            self.num_circuits -= 1;
+            let remove = match self.circuits_per_peer.get_mut(&src) {
+                Some(num) => {
+                    *num -= 1;
+                    *num == 0
+                }
+                None => false,
+            };
+            if remove {
+                self.circuits_per_peer.remove(&src);
+            }
+        }
+
+        for peer_id in closed.peers {
+            if self.connected.contains_key(&peer_id) {
+                self.events.push_back(NetworkBehaviourAction::SendEvent {
+                    peer_id,
+                    event: RelayHandlerIn::CircuitClosed,
+                });
+            }
+        }
+    }
+
+    /// Handles a `HOP` request of `src`, which wants us to relay a connection to `dst`.
+    fn on_hop_request(&mut self, src: PeerId, substream: TSubstream, dst: PeerId) {
+        let mut guard = self.guard(src.clone());
+
+        let refusal = if !self.config.hop_enabled {
+            Some(CircuitRelay_Status::HOP_CANT_SPEAK_RELAY)
+        } else if dst == self.local_peer_id {
+            Some(CircuitRelay_Status::HOP_CANT_RELAY_TO_SELF)
+        } else if self.num_circuits >= self.config.max_circuits ||
+            self.circuits_per_peer.get(&src).map_or(false, |n| *n >= self.config.max_circuits_per_peer)
+        {
+            Some(CircuitRelay_Status::HOP_CANT_SPEAK_RELAY)
+        } else if !self.connected.contains_key(&dst) {
+            Some(CircuitRelay_Status::HOP_NO_CONN_TO_DST)
+        } else {
+            None
+        };
+
+        if let Some(status) = refusal {
+            self.refuse(substream, status, guard);
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(RelayEvent::CircuitRefused { src, dst }));
+            return;
+        }
+
+        self.num_circuits += 1;
+        *self.circuits_per_peer.entry(src.clone()).or_insert(0) += 1;
+        guard.counted = Some(src.clone());
+
+        let src_peer = RelayPeer {
+            peer_id: src.clone(),
+            addrs: self.connected.get(&src).into_iter().cloned().collect(),
+        };
+        let request_id = self.next_request_id.next();
+        self.events.push_back(NetworkBehaviourAction::SendEvent {
+            peer_id: dst.clone(),
+            event: RelayHandlerIn::OutgoingRequest {
+                request_id,
+                request: RelayOutbound::stop(src_peer, RelayPeer::new(dst.clone())),
+            },
+        });
+        self.pending_circuits.insert(request_id, PendingCircuit { src, dst, substream, guard });
+    }
+
+    /// Handles a `STOP` request of `relay`, which opens a circuit from `src` to us.
+    fn on_stop_request(&mut self, relay: PeerId, substream: TSubstream, src: PeerId) {
+        let guard = self.guard(relay.clone());
+
+        if !self.listening {
+            self.refuse(substream, CircuitRelay_Status::STOP_RELAY_REFUSED, guard);
+            return;
+        }
+
+        let mut remote_addr = self.connected.get(&relay).cloned().unwrap_or_else(Multiaddr::empty);
+        remote_addr.append(Protocol::P2p(relay.into()));
+        remote_addr.append(Protocol::P2pCircuit);
+        remote_addr.append(Protocol::P2p(src.into()));
+
+        let to_listener = self.to_listener.clone();
+        let future = protocol::send_status(substream, CircuitRelay_Status::SUCCESS)
+            .map(move |substream| {
+                let connection = Connection::new(substream, guard);
+                let _ = to_listener.unbounded_send((connection, remote_addr));
+            })
+            .map_err(|err| debug!("Failed to accept relayed connection: {:?}", err));
+        self.tasks.push(Box::new(future));
+    }
+
+    /// Handles the success of a request we sent to `peer_id`.
+    fn on_request_success(&mut self, peer_id: PeerId, request_id: RequestId, substream: TSubstream) {
+        if let Some((_, sender)) = self.outgoing_dials.remove(&request_id) {
+            let connection = Connection::new(substream, self.guard(peer_id));
+            let _ = sender.send(Ok(connection));
+            return;
+        }
+
+        let PendingCircuit { src, dst, substream: src_substream, mut guard } =
+            match self.pending_circuits.remove(&request_id) {
+                Some(circuit) => circuit,
+                None => {
+                    // The source is gone. Dropping the guard notifies the handler, which counted
+                    // the substream as a circuit.
+                    drop(self.guard(peer_id));
+                    return;
+                }
+            };
+
+        guard.peers.push(peer_id);
+        let future = protocol::send_status(src_substream, CircuitRelay_Status::SUCCESS)
+            .and_then(move |src_substream| splice(src_substream, substream))
+            .then(move |result| {
+                if let Err(err) = result {
+                    debug!("Relayed connection closed with an error: {:?}", err);
+                }
+                drop(guard);
+                Ok::<_, ()>(())
+            });
+        self.tasks.push(Box::new(future));
+        self.events.push_back(NetworkBehaviourAction::GenerateEvent(RelayEvent::CircuitOpened { src, dst }));
+    }
+
+    /// Handles the failure of a request we sent.
+    fn on_request_failure(&mut self, request_id: RequestId, error: io::Error) {
+        if let Some((_, sender)) = self.outgoing_dials.remove(&request_id) {
+            let _ = sender.send(Err(error));
+            return;
+        }
+
+        if let Some(PendingCircuit { src, dst, substream, guard }) = self.pending_circuits.remove(&request_id) {
+            debug!("Destination {:?} refused relayed connection: {:?}", dst, error);
+            self.refuse(substream, CircuitRelay_Status::HOP_CANT_OPEN_DST_STREAM, guard);
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(RelayEvent::CircuitRefused { src, dst }));
+        }
+    }
+
+    /// Fails the dials waiting for a connection to `relay`.
+    fn fail_pending_dials(&mut self, relay: &PeerId) {
+        for dial in self.pending_dials.remove(relay).into_iter().flat_map(|d| d) {
+            let err = io::Error::new(io::ErrorKind::ConnectionRefused, "failed to reach the relay");
+            let _ = dial.sender.send(Err(err));
+        }
+    }
+}
+
+impl<TSubstream, TTopology> NetworkBehaviour<TTopology> for Relay<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+{
+    type ProtocolsHandler = RelayHandler<TSubstream>;
+    type OutEvent = RelayEvent;
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        RelayHandler::new(self.config.hop_enabled, self.config.connection_idle_timeout)
+    }
+
+    fn inject_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
+        let addr = match endpoint {
+            ConnectedPoint::Dialer { address } => address,
+            ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+        };
+        self.connected.insert(peer_id.clone(), addr);
+
+        if self.listening_relays.contains_key(&peer_id) {
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id: peer_id.clone(),
+                event: RelayHandlerIn::UsedForListening,
+            });
+        }
+
+        for dial in self.pending_dials.remove(&peer_id).into_iter().flat_map(|d| d) {
+            self.send_hop_request(peer_id.clone(), dial.dst, dial.sender);
+        }
+    }
+
+    fn inject_disconnected(&mut self, peer_id: &PeerId, _: ConnectedPoint) {
+        self.connected.remove(peer_id);
+
+        // The handler is gone, alongside with the requests it was processing.
+        let failed = self.outgoing_dials.iter()
+            .filter(|(_, (relay, _))| relay == peer_id)
+            .map(|(id, _)| *id)
+            .chain(self.pending_circuits.iter()
+                .filter(|(_, circuit)| &circuit.dst == peer_id)
+                .map(|(id, _)| *id))
+            .collect::<Vec<_>>();
+        for request_id in failed {
+            let err = io::Error::new(io::ErrorKind::ConnectionReset, "connection closed");
+            self.on_request_failure(request_id, err);
+        }
+
+        if let Some(addr) = self.listening_relays.get(peer_id) {
+            self.events.push_back(dial_action(peer_id.clone(), addr.clone()));
+        }
+    }
+
+    fn inject_node_event(&mut self, peer_id: PeerId, event: RelayHandlerEvent<TSubstream>) {
+        match event {
+            RelayHandlerEvent::HopRequest { substream, dst } =>
+                self.on_hop_request(peer_id, substream, dst.peer_id),
+            RelayHandlerEvent::StopRequest { substream, src } =>
+                self.on_stop_request(peer_id, substream, src.peer_id),
+            RelayHandlerEvent::OutgoingRequestSuccess { request_id, substream } =>
+                self.on_request_success(peer_id, request_id, substream),
+            RelayHandlerEvent::OutgoingRequestFailure { request_id, error } =>
+                self.on_request_failure(request_id, error),
+        }
+    }
+
+    fn inject_addr_reach_failure(&mut self, peer_id: Option<&PeerId>, addr: &Multiaddr, _: &dyn error::Error) {
+        if peer_id.is_some() {
+            return;
+        }
+
+        let relays = self.pending_dials.iter()
+            .filter(|(_, dials)| dials.iter().any(|dial| &dial.relay_addr == addr))
+            .map(|(relay, _)| relay.clone())
+            .collect::<Vec<_>>();
+        for relay in relays {
+            self.fail_pending_dials(&relay);
+        }
+    }
+
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        self.fail_pending_dials(peer_id);
+    }
+
+    fn poll(
+        &mut self,
+        _: &mut PollParameters<TTopology>,
+    ) -> Async<
+        NetworkBehaviourAction<
+            <Self::ProtocolsHandler as ProtocolsHandler>::InEvent,
+            Self::OutEvent,
+        >,
+    > {
+        while let Ok(Async::Ready(Some(message))) = self.from_transport.poll() {
+            self.on_transport_message(message);
+        }
+
+        while let Ok(Async::Ready(Some(closed))) = self.circuit_closed_rx.poll() {
+            self.on_circuit_closed(closed);
+        }
+
+        for n in (0..self.tasks.len()).rev() {
+            let mut task = self.tasks.swap_remove(n);
+            match task.poll() {
+                Ok(Async::NotReady) => self.tasks.push(task),
+                Ok(Async::Ready(())) | Err(()) => (),
+            }
+        }
+
+        // Tasks that finished above have dropped their guard.
+        while let Ok(Async::Ready(Some(closed))) = self.circuit_closed_rx.poll() {
+            self.on_circuit_closed(closed);
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Async::Ready(event);
+        }
+
+        Async::NotReady
+    }
+}
+
+/// Returns the action that dials a relay, by address if we know it and by peer id otherwise.
+fn dial_action(relay: PeerId, relay_addr: Multiaddr) -> NetworkBehaviourAction<RelayHandlerIn, RelayEvent> {
+    if relay_addr.iter().next().is_some() {
+        NetworkBehaviourAction::DialAddress { address: relay_addr }
+    } else {
+        NetworkBehaviourAction::DialPeer { peer_id: relay }
+    }
+}
+
+/// Copies the data between two substreams in both directions, until both are closed.
+fn splice<A, B>(a: A, b: B) -> impl Future<Item = (), Error = io::Error>
+where
+    A: AsyncRead + AsyncWrite,
+    B: AsyncRead + AsyncWrite,
+{
+    let (a_read, a_write) = a.split();
+    let (b_read, b_write) = b.split();
+    let a_to_b = tokio_io::io::copy(a_read, b_write)
+        .and_then(|(_, _, b_write)| tokio_io::io::shutdown(b_write));
+    let b_to_a = tokio_io::io::copy(b_read, a_write)
+        .and_then(|(_, _, a_write)| tokio_io::io::shutdown(a_write));
+    a_to_b.join(b_to_a).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Relay, RelayConfig, RelayEvent, new_transport_and_behaviour};
+    use crate::handler::RelayHandlerEvent;
+    use crate::protocol::RelayPeer;
+    use libp2p_core::PeerId;
+    use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction};
+    use std::io::Cursor;
+
+    type TestRelay = Relay<Cursor<Vec<u8>>>;
+
+    fn relay(config: RelayConfig) -> TestRelay {
+        let (_, relay) = new_transport_and_behaviour::<(), _>(PeerId::random(), config.with_hop_enabled(true), ());
+        relay
+    }
+
+    fn connect(relay: &mut TestRelay, peer_id: &PeerId) {
+        let endpoint = ConnectedPoint::Dialer { address: "/ip4/1.2.3.4/tcp/5".parse().unwrap() };
+        NetworkBehaviour::<()>::inject_connected(relay, peer_id.clone(), endpoint);
+    }
+
+    fn hop_request(relay: &mut TestRelay, src: &PeerId, dst: &PeerId) {
+        let event = RelayHandlerEvent::HopRequest {
+            substream: Cursor::new(Vec::new()),
+            dst: RelayPeer::new(dst.clone()),
+        };
+        NetworkBehaviour::<()>::inject_node_event(relay, src.clone(), event);
+    }
+
+    /// Returns the sources of the circuits that have been refused.
+    fn refused(relay: &TestRelay) -> Vec<PeerId> {
+        relay.events.iter()
+            .filter_map(|event| match event {
+                NetworkBehaviourAction::GenerateEvent(RelayEvent::CircuitRefused { src, .. }) => Some(src.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_limits() {
+        let config = RelayConfig::default();
+        assert_eq!(config.max_circuits, 64);
+        assert_eq!(config.max_circuits_per_peer, 4);
+    }
+
+    #[test]
+    fn circuits_over_per_peer_limit_are_refused() {
+        let mut relay = relay(RelayConfig::default().with_max_circuits_per_peer(2));
+        let (src, other, dst) = (PeerId::random(), PeerId::random(), PeerId::random());
+        connect(&mut relay, &dst);
+
+        for _ in 0..3 {
+            hop_request(&mut relay, &src, &dst);
+        }
+        assert_eq!(relay.num_circuits(), 2);
+        assert_eq!(refused(&relay), vec![src]);
+
+        // Other sources are not affected.
+        hop_request(&mut relay, &other, &dst);
+        assert_eq!(relay.num_circuits(), 3);
+        assert_eq!(refused(&relay).len(), 1);
+    }
+
+    #[test]
+    fn circuits_over_total_limit_are_refused() {
+        let mut relay = relay(RelayConfig::default().with_max_circuits(2));
+        let dst = PeerId::random();
+        let sources = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+        connect(&mut relay, &dst);
+
+        for src in &sources {
+            hop_request(&mut relay, src, &dst);
+        }
+        assert_eq!(relay.num_circuits(), 2);
+        assert_eq!(refused(&relay), vec![sources[2].clone()]);
+    }
+}
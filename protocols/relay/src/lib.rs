@@ -0,0 +1,55 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of the circuit relay protocol.
+//!
+//! A relay is a node that accepts to forward a connection between two other nodes that can't
+//! reach each other directly, for example because one of them is behind a NAT.
+//!
+//! # Usage
+//!
+//! Call `new_transport_and_behaviour` with the transport to wrap. This returns a `RelayTransport`
+//! and a `Relay` behaviour, which must both be part of the same `Swarm`. The transport handles
+//! the addresses that contain `/p2p-circuit`, and forwards everything else to the inner
+//! transport. The connections produced by the transport can then be upgraded with secio and a
+//! muxer, exactly like the connections of the inner transport.
+//!
+//! - Dialing `/ip4/1.2.3.4/tcp/30333/p2p/<relay>/p2p-circuit/p2p/<dest>` connects to the relay
+//!   if necessary, then asks it to open a circuit to `<dest>`.
+//! - Listening on `/ip4/1.2.3.4/tcp/30333/p2p/<relay>/p2p-circuit` connects to the relay and
+//!   keeps the connection alive, so that other nodes can reach us through it.
+//!
+//! Nodes only relay connections between other nodes if `RelayConfig::with_hop_enabled` has been
+//! called. A relay only opens circuits towards nodes it is already connected to, and limits the
+//! number of circuits it relays at the same time, both in total and per source.
+//!
+//! The circuits are opened on substreams of the existing connections of the `Swarm`, and the
+//! relay copies the data between the two substreams.
+
+pub use self::handler::RequestId;
+pub use self::layer::{Relay, RelayConfig, RelayEvent, new_transport_and_behaviour};
+pub use self::transport::{Connection, RelayListener, RelayTransport, RelayedDial};
+
+pub mod handler;
+pub mod protocol;
+
+mod layer;
+mod message_proto;
+mod transport;
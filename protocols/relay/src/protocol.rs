@@ -0,0 +1,402 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::message_proto::{CircuitRelay, CircuitRelay_Peer, CircuitRelay_Status, CircuitRelay_Type};
+use futures::{future::{self, Loop}, prelude::*};
+use libp2p_core::{Multiaddr, PeerId, upgrade::{InboundUpgrade, OutboundUpgrade, UpgradeInfo}};
+use protobuf::{self, Message};
+use std::{io, iter};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Name of the relay protocol on the wire.
+pub const PROTOCOL_NAME: &[u8] = b"/libp2p/circuit/relay/0.1.0";
+
+/// Maximum size of a relay message. Messages only contain two peers and their addresses.
+const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// A peer, alongside with the addresses it can be reached at, as found in a relay message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayPeer {
+    /// Identity of the peer.
+    pub peer_id: PeerId,
+    /// Addresses of the peer. Can be empty.
+    pub addrs: Vec<Multiaddr>,
+}
+
+impl RelayPeer {
+    /// Builds a `RelayPeer` without any address.
+    #[inline]
+    pub fn new(peer_id: PeerId) -> RelayPeer {
+        RelayPeer { peer_id, addrs: Vec::new() }
+    }
+}
+
+/// Request received from a remote on a substream negotiated with `RelayListen`.
+#[derive(Debug)]
+pub enum RelayInbound<TSubstream> {
+    /// The remote asks us to relay a connection to `dst`.
+    ///
+    /// The request must be answered with `send_status`, after which the substream carries the
+    /// data of the circuit.
+    Hop {
+        /// Substream on which the request has been received.
+        substream: TSubstream,
+        /// The peer that opens the circuit, as reported by the remote.
+        src: RelayPeer,
+        /// The peer to relay the connection to.
+        dst: RelayPeer,
+    },
+    /// The remote, acting as a relay, opens a circuit from `src` to us.
+    ///
+    /// The request must be answered with `send_status`, after which the substream carries the
+    /// data of the circuit.
+    Stop {
+        /// Substream on which the request has been received.
+        substream: TSubstream,
+        /// The peer that opened the circuit.
+        src: RelayPeer,
+    },
+    /// The remote asked whether we accept to relay connections, and we have already answered.
+    CanHop,
+}
+
+/// Upgrade that accepts relay requests from remotes.
+#[derive(Debug, Clone)]
+pub struct RelayListen {
+    /// Whether we accept to relay connections. Used to answer `CAN_HOP` requests.
+    hop_enabled: bool,
+}
+
+impl RelayListen {
+    /// Builds a new `RelayListen`.
+    #[inline]
+    pub fn new(hop_enabled: bool) -> RelayListen {
+        RelayListen { hop_enabled }
+    }
+}
+
+impl UpgradeInfo for RelayListen {
+    type Info = &'static [u8];
+    type InfoIter = iter::Once<Self::Info>;
+
+    #[inline]
+    fn protocol_info(&self) -> Self::InfoIter {
+        iter::once(PROTOCOL_NAME)
+    }
+}
+
+impl<TSocket> InboundUpgrade<TSocket> for RelayListen
+where
+    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+{
+    type Output = RelayInbound<TSocket>;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_inbound(self, socket: TSocket, _: Self::Info) -> Self::Future {
+        let hop_enabled = self.hop_enabled;
+        let future = read_message(socket).and_then(move |(socket, mut message)| -> Self::Future {
+            if !message.has_field_type() {
+                return refuse(socket, CircuitRelay_Status::MALFORMED_MESSAGE);
+            }
+
+            match message.get_field_type() {
+                CircuitRelay_Type::HOP => {
+                    let src = match peer_from_proto(message.take_srcPeer()) {
+                        Some(src) => src,
+                        None => return refuse(socket, CircuitRelay_Status::HOP_SRC_MULTIADDR_INVALID),
+                    };
+                    let dst = match peer_from_proto(message.take_dstPeer()) {
+                        Some(dst) => dst,
+                        None => return refuse(socket, CircuitRelay_Status::HOP_DST_MULTIADDR_INVALID),
+                    };
+                    Box::new(future::ok(RelayInbound::Hop { substream: socket, src, dst }))
+                }
+                CircuitRelay_Type::STOP => {
+                    let src = match peer_from_proto(message.take_srcPeer()) {
+                        Some(src) => src,
+                        None => return refuse(socket, CircuitRelay_Status::STOP_SRC_MULTIADDR_INVALID),
+                    };
+                    Box::new(future::ok(RelayInbound::Stop { substream: socket, src }))
+                }
+                CircuitRelay_Type::CAN_HOP => {
+                    let status = if hop_enabled {
+                        CircuitRelay_Status::SUCCESS
+                    } else {
+                        CircuitRelay_Status::HOP_CANT_SPEAK_RELAY
+                    };
+                    Box::new(send_status(socket, status).map(|_| RelayInbound::CanHop))
+                }
+                CircuitRelay_Type::STATUS => refuse(socket, CircuitRelay_Status::MALFORMED_MESSAGE),
+            }
+        });
+
+        Box::new(future)
+    }
+}
+
+/// Upgrade that sends a relay request to the remote and waits for it to be accepted.
+///
+/// The output of the upgrade is the substream, which then carries the data of the circuit.
+#[derive(Debug, Clone)]
+pub struct RelayOutbound {
+    /// The message to send.
+    message: CircuitRelay,
+}
+
+impl RelayOutbound {
+    /// Asks the remote to relay a connection from `src` to `dst`.
+    pub fn hop(src: RelayPeer, dst: RelayPeer) -> RelayOutbound {
+        RelayOutbound::new(CircuitRelay_Type::HOP, src, dst)
+    }
+
+    /// Tells the remote that `src` opens a circuit towards it, with `dst` being the remote.
+    pub fn stop(src: RelayPeer, dst: RelayPeer) -> RelayOutbound {
+        RelayOutbound::new(CircuitRelay_Type::STOP, src, dst)
+    }
+
+    fn new(ty: CircuitRelay_Type, src: RelayPeer, dst: RelayPeer) -> RelayOutbound {
+        let mut message = CircuitRelay::new();
+        message.set_field_type(ty);
+        message.set_srcPeer(peer_to_proto(src));
+        message.set_dstPeer(peer_to_proto(dst));
+        RelayOutbound { message }
+    }
+}
+
+impl UpgradeInfo for RelayOutbound {
+    type Info = &'static [u8];
+    type InfoIter = iter::Once<Self::Info>;
+
+    #[inline]
+    fn protocol_info(&self) -> Self::InfoIter {
+        iter::once(PROTOCOL_NAME)
+    }
+}
+
+impl<TSocket> OutboundUpgrade<TSocket> for RelayOutbound
+where
+    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+{
+    type Output = TSocket;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_outbound(self, socket: TSocket, _: Self::Info) -> Self::Future {
+        let future = write_message(socket, &self.message)
+            .and_then(read_message)
+            .and_then(|(socket, message)| {
+                if !message.has_field_type() || message.get_field_type() != CircuitRelay_Type::STATUS {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a relay STATUS message"));
+                }
+
+                match message.get_code() {
+                    CircuitRelay_Status::SUCCESS => Ok(socket),
+                    code => Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!("relay request refused: {:?}", code),
+                    )),
+                }
+            });
+
+        Box::new(future)
+    }
+}
+
+/// Sends a `STATUS` message on the substream, in answer to a `HOP` or `STOP` request.
+///
+/// If the status is `SUCCESS`, the substream can then be used to carry the data of the circuit.
+pub(crate) fn send_status<TSocket>(socket: TSocket, status: CircuitRelay_Status)
+    -> impl Future<Item = TSocket, Error = io::Error>
+where
+    TSocket: AsyncWrite,
+{
+    let mut message = CircuitRelay::new();
+    message.set_field_type(CircuitRelay_Type::STATUS);
+    message.set_code(status);
+    write_message(socket, &message)
+}
+
+/// Answers a request with an error status, then fails the upgrade.
+fn refuse<TSocket, TOut>(socket: TSocket, status: CircuitRelay_Status)
+    -> Box<dyn Future<Item = TOut, Error = io::Error> + Send>
+where
+    TSocket: AsyncWrite + Send + 'static,
+    TOut: Send + 'static,
+{
+    let future = send_status(socket, status).and_then(move |_| {
+        Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid relay request: {:?}", status)))
+    });
+    Box::new(future)
+}
+
+/// Writes a length-prefixed message on the socket and flushes it.
+fn write_message<TSocket>(socket: TSocket, message: &CircuitRelay)
+    -> impl Future<Item = TSocket, Error = io::Error>
+where
+    TSocket: AsyncWrite,
+{
+    future::result(message.write_length_delimited_to_bytes())
+        .map_err(io::Error::from)
+        .and_then(move |bytes| tokio_io::io::write_all(socket, bytes))
+        .and_then(|(socket, _)| tokio_io::io::flush(socket))
+}
+
+/// Reads a length-prefixed message from the socket.
+///
+/// Contrary to a `Framed`, this never reads past the end of the message. Once a circuit is
+/// established, the rest of the data belongs to the relayed connection and mustn't be lost in a
+/// buffer.
+fn read_message<TSocket>(socket: TSocket) -> impl Future<Item = (TSocket, CircuitRelay), Error = io::Error>
+where
+    TSocket: AsyncRead,
+{
+    // The length prefix is read one byte at a time.
+    let length = future::loop_fn((socket, 0usize, 0u32), |(socket, len, shift)| {
+        tokio_io::io::read_exact(socket, [0u8; 1]).and_then(move |(socket, byte)| {
+            let len = len | (usize::from(byte[0] & 0x7f) << shift);
+            if byte[0] & 0x80 == 0 {
+                Ok(Loop::Break((socket, len)))
+            } else if shift >= 14 {
+                Err(io::Error::new(io::ErrorKind::InvalidData, "relay message too large"))
+            } else {
+                Ok(Loop::Continue((socket, len, shift + 7)))
+            }
+        })
+    });
+
+    length
+        .and_then(|(socket, len)| {
+            if len > MAX_MESSAGE_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "relay message too large"));
+            }
+            Ok((socket, len))
+        })
+        .and_then(|(socket, len)| tokio_io::io::read_exact(socket, vec![0; len]))
+        .and_then(|(socket, bytes)| {
+            let message = protobuf::parse_from_bytes::<CircuitRelay>(&bytes)?;
+            Ok((socket, message))
+        })
+}
+
+fn peer_to_proto(peer: RelayPeer) -> CircuitRelay_Peer {
+    let mut out = CircuitRelay_Peer::new();
+    out.set_id(peer.peer_id.into_bytes());
+    for addr in peer.addrs {
+        out.mut_addrs().push(addr.into_bytes());
+    }
+    out
+}
+
+/// Turns a peer found in a message into a `RelayPeer`. Returns `None` if the peer id is invalid.
+///
+/// Invalid addresses are ignored.
+fn peer_from_proto(mut peer: CircuitRelay_Peer) -> Option<RelayPeer> {
+    let peer_id = PeerId::from_bytes(peer.take_id()).ok()?;
+    let addrs = peer.take_addrs()
+        .into_iter()
+        .filter_map(|addr| Multiaddr::from_bytes(addr).ok())
+        .collect();
+    Some(RelayPeer { peer_id, addrs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RelayInbound, RelayListen, RelayOutbound, RelayPeer, send_status};
+    use crate::message_proto::CircuitRelay_Status;
+    use futures::prelude::*;
+    use libp2p_core::{PeerId, upgrade::{InboundUpgrade, OutboundUpgrade}};
+    use std::io;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::runtime::current_thread::Runtime;
+
+    #[test]
+    fn hop_request_then_relayed_data() {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        let src = RelayPeer {
+            peer_id: PeerId::random(),
+            addrs: vec!["/ip4/1.2.3.4/tcp/5".parse().unwrap()],
+        };
+        let dst = RelayPeer::new(PeerId::random());
+        let (expected_src, expected_dst) = (src.clone(), dst.clone());
+
+        let server = listener.incoming()
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(|(socket, _)| {
+                RelayListen::new(true).upgrade_inbound(socket.unwrap(), super::PROTOCOL_NAME)
+            })
+            .and_then(move |request| match request {
+                RelayInbound::Hop { substream, src, dst } => {
+                    assert_eq!(src, expected_src);
+                    assert_eq!(dst, expected_dst);
+                    send_status(substream, CircuitRelay_Status::SUCCESS)
+                }
+                _ => panic!("expected a HOP request"),
+            })
+            .and_then(|substream| tokio::io::read_to_end(substream, Vec::new()))
+            .map(|(_, data)| assert_eq!(data, b"hello world"));
+
+        let client = TcpStream::connect(&listener_addr)
+            .and_then(move |socket| {
+                RelayOutbound::hop(src, dst).upgrade_outbound(socket, super::PROTOCOL_NAME)
+            })
+            .and_then(|substream| tokio::io::write_all(substream, b"hello world"))
+            .map(|_| ());
+
+        let mut runtime = Runtime::new().unwrap();
+        runtime.block_on(server.join(client)).unwrap();
+    }
+
+    #[test]
+    fn refused_stop_request() {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        let server = listener.incoming()
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(|(socket, _)| {
+                RelayListen::new(false).upgrade_inbound(socket.unwrap(), super::PROTOCOL_NAME)
+            })
+            .and_then(|request| match request {
+                RelayInbound::Stop { substream, .. } =>
+                    send_status(substream, CircuitRelay_Status::STOP_RELAY_REFUSED),
+                _ => panic!("expected a STOP request"),
+            })
+            .map(|_| ());
+
+        let client = TcpStream::connect(&listener_addr)
+            .and_then(|socket| {
+                let src = RelayPeer::new(PeerId::random());
+                let dst = RelayPeer::new(PeerId::random());
+                RelayOutbound::stop(src, dst).upgrade_outbound(socket, super::PROTOCOL_NAME)
+            })
+            .then(|result| {
+                assert!(result.is_err());
+                Ok::<_, io::Error>(())
+            });
+
+        let mut runtime = Runtime::new().unwrap();
+        runtime.block_on(server.join(client)).unwrap();
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::layer::CircuitGuard;
+use futures::{future, prelude::*, sync::{mpsc, oneshot}};
+use libp2p_core::{
+    Multiaddr, PeerId, Transport,
+    either::{EitherFuture, EitherListenStream, EitherOutput},
+    multiaddr::Protocol,
+};
+use parking_lot::Mutex;
+use std::{fmt, io, io::{Read, Write}, sync::Arc};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Transport that supports the `/p2p-circuit` addresses, and delegates all the other addresses
+/// to an inner transport.
+///
+/// Dialing `<relay-addr>/p2p/<relay-id>/p2p-circuit/p2p/<dest-id>` asks the relay to open a
+/// circuit to `dest-id`. Listening on `<relay-addr>/p2p/<relay-id>/p2p-circuit` connects to the
+/// relay and accepts the circuits it opens towards us. Listening on `/p2p-circuit` accepts the
+/// circuits opened by any relay we happen to be connected to.
+///
+/// The circuits go through the connections of the `Swarm`, and are therefore handled by the
+/// `Relay` behaviour created alongside with this transport by `new_transport_and_behaviour`. The
+/// behaviour must be part of the `Swarm` for the circuits to work.
+///
+/// The output of the transport is a raw stream, which can be upgraded like the output of any
+/// other transport (for example with secio then with a muxer).
+///
+/// > **Note**: All the relayed connections are reported by the first listener on a
+/// >           `/p2p-circuit` address. The listeners that are created afterwards only make sure
+/// >           that we stay connected to their relay.
+#[derive(Debug, Clone)]
+pub struct RelayTransport<TTransport> {
+    /// Transport used for all the addresses that don't contain `/p2p-circuit`.
+    inner: TTransport,
+    /// Channel to the `Relay` behaviour.
+    to_behaviour: mpsc::UnboundedSender<TransportToBehaviourMsg>,
+    /// Relayed connections accepted by the behaviour. Taken by the first listener.
+    incoming: Arc<Mutex<Option<mpsc::UnboundedReceiver<(Connection, Multiaddr)>>>>,
+}
+
+/// Message sent by the transport to the behaviour.
+#[derive(Debug)]
+pub(crate) enum TransportToBehaviourMsg {
+    /// Open a circuit to `dst` through `relay`.
+    Dial {
+        /// The relay to go through.
+        relay: PeerId,
+        /// Address of the relay. Can be empty, in which case the relay is dialed by its peer id.
+        relay_addr: Multiaddr,
+        /// The peer to open a circuit with.
+        dst: PeerId,
+        /// Where to send the outcome.
+        sender: oneshot::Sender<Result<Connection, io::Error>>,
+    },
+    /// Start accepting relayed connections, and stay connected to `relay` if any.
+    Listen {
+        /// Relay to stay connected to, and its address. The address can be empty.
+        relay: Option<(PeerId, Multiaddr)>,
+    },
+}
+
+impl<TTransport> RelayTransport<TTransport> {
+    /// Builds a new `RelayTransport`. The channels are connected to the behaviour.
+    pub(crate) fn new(
+        inner: TTransport,
+        to_behaviour: mpsc::UnboundedSender<TransportToBehaviourMsg>,
+        incoming: mpsc::UnboundedReceiver<(Connection, Multiaddr)>,
+    ) -> Self {
+        RelayTransport {
+            inner,
+            to_behaviour,
+            incoming: Arc::new(Mutex::new(Some(incoming))),
+        }
+    }
+}
+
+impl<TTransport> Transport for RelayTransport<TTransport>
+where
+    TTransport: Transport,
+{
+    type Output = EitherOutput<TTransport::Output, Connection>;
+    type Listener = EitherListenStream<TTransport::Listener, RelayListener>;
+    type ListenerUpgrade = EitherFuture<TTransport::ListenerUpgrade, future::FutureResult<Connection, io::Error>>;
+    type Dial = EitherFuture<TTransport::Dial, RelayedDial>;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        let relay_addr = match split_circuit_addr(&addr) {
+            Some((relay_addr, dst_addr)) => {
+                // We can only listen for circuits that end with us.
+                if dst_addr.iter().next().is_some() {
+                    return Err((self, addr));
+                }
+                relay_addr
+            }
+            None => {
+                let RelayTransport { inner, to_behaviour, incoming } = self;
+                return match inner.listen_on(addr) {
+                    Ok((listener, addr)) => Ok((EitherListenStream::First(listener), addr)),
+                    Err((inner, addr)) => Err((RelayTransport { inner, to_behaviour, incoming }, addr)),
+                };
+            }
+        };
+
+        let relay = if relay_addr.iter().next().is_none() {
+            None
+        } else {
+            match split_peer_id(relay_addr) {
+                Some(relay) => Some(relay),
+                None => return Err((self, addr)),
+            }
+        };
+
+        if self.to_behaviour.unbounded_send(TransportToBehaviourMsg::Listen { relay }).is_err() {
+            return Err((self, addr));
+        }
+
+        let listener = RelayListener {
+            incoming: self.incoming.lock().take(),
+        };
+
+        Ok((EitherListenStream::Second(listener), addr))
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        let (relay_addr, dst_addr) = match split_circuit_addr(&addr) {
+            Some(split) => split,
+            None => {
+                let RelayTransport { inner, to_behaviour, incoming } = self;
+                return match inner.dial(addr) {
+                    Ok(dial) => Ok(EitherFuture::First(dial)),
+                    Err((inner, addr)) => Err((RelayTransport { inner, to_behaviour, incoming }, addr)),
+                };
+            }
+        };
+
+        let (relay, relay_addr) = match split_peer_id(relay_addr) {
+            Some(relay) => relay,
+            None => return Err((self, addr)),
+        };
+
+        let dst = match split_peer_id(dst_addr) {
+            Some((dst, rest)) => {
+                if rest.iter().next().is_some() {
+                    return Err((self, addr));
+                }
+                dst
+            }
+            None => return Err((self, addr)),
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        let message = TransportToBehaviourMsg::Dial { relay, relay_addr, dst, sender };
+        if self.to_behaviour.unbounded_send(message).is_err() {
+            return Err((self, addr));
+        }
+
+        Ok(EitherFuture::Second(RelayedDial { receiver }))
+    }
+
+    #[inline]
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.nat_traversal(server, observed)
+    }
+}
+
+/// Splits an address around its `/p2p-circuit` component.
+///
+/// Returns `None` if the address doesn't contain `/p2p-circuit`.
+fn split_circuit_addr(addr: &Multiaddr) -> Option<(Multiaddr, Multiaddr)> {
+    let mut before = Multiaddr::empty();
+    let mut after = Multiaddr::empty();
+    let mut found = false;
+
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::P2pCircuit if !found => found = true,
+            protocol => if found { after.append(protocol) } else { before.append(protocol) },
+        }
+    }
+
+    if found {
+        Some((before, after))
+    } else {
+        None
+    }
+}
+
+/// Removes the trailing `/p2p/<peer-id>` component of an address.
+///
+/// Returns `None` if the address doesn't end with `/p2p/<peer-id>`.
+fn split_peer_id(mut addr: Multiaddr) -> Option<(PeerId, Multiaddr)> {
+    match addr.pop()? {
+        Protocol::P2p(hash) => PeerId::from_multihash(hash).ok().map(|peer_id| (peer_id, addr)),
+        _ => None,
+    }
+}
+
+/// Stream of the relayed connections, returned by `RelayTransport::listen_on`.
+#[derive(Debug)]
+pub struct RelayListener {
+    /// Connections accepted by the behaviour. `None` if another listener already reports them.
+    incoming: Option<mpsc::UnboundedReceiver<(Connection, Multiaddr)>>,
+}
+
+impl Stream for RelayListener {
+    type Item = (future::FutureResult<Connection, io::Error>, Multiaddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let incoming = match self.incoming {
+            Some(ref mut incoming) => incoming,
+            None => return Ok(Async::NotReady),
+        };
+
+        match incoming.poll() {
+            Ok(Async::Ready(Some((connection, addr)))) => Ok(Async::Ready(Some((future::ok(connection), addr)))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(None)) | Err(()) => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+/// Future that resolves to a relayed connection, returned by `RelayTransport::dial`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct RelayedDial {
+    /// Receives the outcome from the behaviour.
+    receiver: oneshot::Receiver<Result<Connection, io::Error>>,
+}
+
+impl Future for RelayedDial {
+    type Item = Connection;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.receiver.poll() {
+            Ok(Async::Ready(Ok(connection))) => Ok(Async::Ready(connection)),
+            Ok(Async::Ready(Err(err))) => Err(err),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(oneshot::Canceled) =>
+                Err(io::Error::new(io::ErrorKind::Other, "the relay behaviour has been dropped")),
+        }
+    }
+}
+
+/// Stream that can be used as a relayed connection. Implemented on all the substreams.
+trait RelayedStream: AsyncRead + AsyncWrite + Send {}
+impl<T> RelayedStream for T where T: AsyncRead + AsyncWrite + Send {}
+
+/// A connection relayed through another peer.
+///
+/// The connection to the relay is kept alive for as long as this object exists.
+pub struct Connection {
+    /// The substream on the connection to the relay.
+    stream: Box<dyn RelayedStream>,
+    /// Informs the behaviour when the connection is dropped.
+    _guard: CircuitGuard,
+}
+
+impl Connection {
+    /// Builds a new `Connection` from a substream on which a circuit has been established.
+    pub(crate) fn new<TSubstream>(stream: TSubstream, guard: CircuitGuard) -> Self
+    where
+        TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        Connection {
+            stream: Box::new(stream),
+            _guard: guard,
+        }
+    }
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Connection").finish()
+    }
+}
+
+impl io::Read for Connection {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl AsyncRead for Connection {
+    #[inline]
+    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
+        self.stream.prepare_uninitialized_buffer(buf)
+    }
+}
+
+impl io::Write for Connection {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl AsyncWrite for Connection {
+    #[inline]
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.stream.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_circuit_addr, split_peer_id};
+    use libp2p_core::{Multiaddr, PeerId};
+
+    #[test]
+    fn split_circuit_address() {
+        let relay = PeerId::random();
+        let dst = PeerId::random();
+        let addr: Multiaddr = format!("/ip4/1.2.3.4/tcp/30333/p2p/{}/p2p-circuit/p2p/{}",
+            relay.to_base58(), dst.to_base58()).parse().unwrap();
+
+        let (relay_addr, dst_addr) = split_circuit_addr(&addr).unwrap();
+        let (relay_id, relay_addr) = split_peer_id(relay_addr).unwrap();
+        assert_eq!(relay_id, relay);
+        assert_eq!(relay_addr, "/ip4/1.2.3.4/tcp/30333".parse::<Multiaddr>().unwrap());
+        let (dst_id, rest) = split_peer_id(dst_addr).unwrap();
+        assert_eq!(dst_id, dst);
+        assert!(rest.iter().next().is_none());
+
+        assert!(split_circuit_addr(&"/ip4/1.2.3.4/tcp/30333".parse().unwrap()).is_none());
+        assert!(split_peer_id("/ip4/1.2.3.4/tcp/30333".parse().unwrap()).is_none());
+    }
+}
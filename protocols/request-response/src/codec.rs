@@ -0,0 +1,52 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use bytes::BytesMut;
+use libp2p_core::ProtocolName;
+use std::io;
+
+/// Turns requests and responses into bytes and back, for the protocols of a `RequestResponse`.
+///
+/// Messages are length-prefixed on the wire by this crate, therefore the codec only has to deal
+/// with the content of a single message. The codec is cloned for each connection.
+pub trait RequestResponseCodec: Clone {
+    /// Name of a protocol that the codec supports.
+    type Protocol: ProtocolName + Clone;
+    /// Request sent to a remote.
+    type Request;
+    /// Response sent back by the remote.
+    type Response;
+
+    /// Decodes a request received from a remote on a substream that uses `protocol`.
+    fn read_request(&mut self, protocol: &Self::Protocol, bytes: BytesMut)
+        -> Result<Self::Request, io::Error>;
+
+    /// Decodes a response received from a remote on a substream that uses `protocol`.
+    fn read_response(&mut self, protocol: &Self::Protocol, bytes: BytesMut)
+        -> Result<Self::Response, io::Error>;
+
+    /// Encodes a request to send to a remote on a substream that uses `protocol`.
+    fn write_request(&mut self, protocol: &Self::Protocol, request: Self::Request)
+        -> Result<Vec<u8>, io::Error>;
+
+    /// Encodes a response to send back to a remote on a substream that uses `protocol`.
+    fn write_response(&mut self, protocol: &Self::Protocol, response: Self::Response)
+        -> Result<Vec<u8>, io::Error>;
+}
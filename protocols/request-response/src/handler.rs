@@ -0,0 +1,766 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::codec::RequestResponseCodec;
+use crate::protocol::{RequestResponseProtocol, RequestResponseSubstream};
+use futures::{prelude::*, sync::oneshot};
+use libp2p_core::{
+    ProtocolsHandler, ProtocolsHandlerEvent,
+    protocols_handler::{KeepAlive, ProtocolsHandlerUpgrErr},
+    upgrade::{InboundUpgrade, OutboundUpgrade, UpgradeError}
+};
+use smallvec::SmallVec;
+use std::{collections::VecDeque, fmt, io, sync::Arc, sync::atomic::{AtomicUsize, Ordering}};
+use std::time::{Duration, Instant};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Delay;
+
+/// Identifier of a request, either sent by us or received from a remote.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestId(usize);
+
+impl RequestId {
+    /// Generates a new identifier from the given counter.
+    #[inline]
+    pub(crate) fn next(counter: &AtomicUsize) -> RequestId {
+        RequestId(counter.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Channel through which the response to an inbound request is sent back to the remote.
+///
+/// Dropping the channel without sending a response closes the substream, and the remote will
+/// report a failure.
+pub struct ResponseChannel<TResponse> {
+    request_id: RequestId,
+    sender: oneshot::Sender<TResponse>,
+}
+
+impl<TResponse> ResponseChannel<TResponse> {
+    /// Returns the identifier of the request this channel answers.
+    #[inline]
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    /// Returns false if the response can no longer be delivered, for example because the
+    /// connection has been closed or because the request has timed out.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        !self.sender.is_canceled()
+    }
+
+    /// Sends the response to the remote.
+    ///
+    /// Returns back the response if it can no longer be delivered.
+    #[inline]
+    pub fn send(self, response: TResponse) -> Result<(), TResponse> {
+        self.sender.send(response)
+    }
+}
+
+impl<TResponse> fmt::Debug for ResponseChannel<TResponse> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ResponseChannel")
+            .field("request_id", &self.request_id)
+            .finish()
+    }
+}
+
+/// Reason why a request that we sent has failed.
+#[derive(Debug)]
+pub enum OutboundFailure {
+    /// We couldn't reach the remote.
+    DialFailure,
+    /// The remote didn't answer before the timeout.
+    Timeout,
+    /// The connection closed before the remote answered.
+    ConnectionClosed,
+    /// The remote supports none of the protocols of the request.
+    UnsupportedProtocols,
+    /// Error while sending the request or decoding the response.
+    Io(io::Error),
+}
+
+impl fmt::Display for OutboundFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutboundFailure::DialFailure => write!(f, "failed to dial the remote"),
+            OutboundFailure::Timeout => write!(f, "timeout while waiting for a response"),
+            OutboundFailure::ConnectionClosed => write!(f, "connection closed before a response"),
+            OutboundFailure::UnsupportedProtocols => write!(f, "the remote supports none of the protocols"),
+            OutboundFailure::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+/// Reason why we couldn't answer a request received from a remote.
+#[derive(Debug)]
+pub enum InboundFailure {
+    /// No response was sent before the timeout.
+    Timeout,
+    /// The `ResponseChannel` has been dropped without sending a response.
+    ResponseOmission,
+    /// Error while decoding the request or sending the response.
+    Io(io::Error),
+}
+
+impl fmt::Display for InboundFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InboundFailure::Timeout => write!(f, "timeout while answering a request"),
+            InboundFailure::ResponseOmission => write!(f, "no response was sent"),
+            InboundFailure::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+/// Event that can be sent to the handler.
+#[derive(Debug)]
+pub enum RequestResponseHandlerIn<TRequest> {
+    /// Opens a substream and sends a request on it.
+    Request {
+        /// Identifier to report back with the response.
+        request_id: RequestId,
+        /// The request to send.
+        request: TRequest,
+    },
+}
+
+/// Event produced by the handler.
+pub enum RequestResponseHandlerEvent<TCodec>
+where
+    TCodec: RequestResponseCodec,
+{
+    /// The remote sent us a request.
+    Request {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// The request.
+        request: TCodec::Request,
+        /// Channel to use to send the response.
+        channel: ResponseChannel<TCodec::Response>,
+    },
+    /// The remote answered one of our requests.
+    Response {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// The response.
+        response: TCodec::Response,
+    },
+    /// One of our requests has failed.
+    OutboundFailure {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// What went wrong.
+        error: OutboundFailure,
+    },
+    /// We failed to answer a request of the remote.
+    InboundFailure {
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// What went wrong.
+        error: InboundFailure,
+    },
+}
+
+/// Protocol handler that sends requests and answers the requests of the remote.
+///
+/// Each request is sent on a new substream. The substream is closed as soon as the response has
+/// been received.
+pub struct RequestResponseHandler<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    /// Turns requests and responses into bytes.
+    codec: TCodec,
+
+    /// Upgrade used to negotiate the substreams.
+    protocol: RequestResponseProtocol<TCodec::Protocol>,
+
+    /// How long a request is allowed to last before we report a timeout.
+    request_timeout: Duration,
+
+    /// How long to keep the connection alive when there's no request in progress.
+    keep_alive_timeout: Duration,
+
+    /// Until when to keep the connection alive.
+    keep_alive: KeepAlive,
+
+    /// Generates the identifiers of inbound requests. Shared with the behaviour and the other
+    /// handlers.
+    request_id_counter: Arc<AtomicUsize>,
+
+    /// If true, we are shutting down the existing substreams and should refuse any incoming
+    /// request.
+    shutting_down: bool,
+
+    /// Requests for which we still have to open a substream.
+    pending_requests: VecDeque<(RequestId, TCodec::Request)>,
+
+    /// Events to report to the behaviour.
+    pending_events: VecDeque<RequestResponseHandlerEvent<TCodec>>,
+
+    /// List of active substreams with the state they are in.
+    substreams: Vec<SubstreamState<TSubstream, TCodec>>,
+}
+
+/// State of an active substream, opened either by us or by the remote.
+enum SubstreamState<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    /// Waiting to send a request to the remote.
+    OutPendingSend {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        protocol: TCodec::Protocol,
+        message: Vec<u8>,
+        timeout: Delay,
+    },
+    /// Waiting to flush the substream so that the request arrives to the remote.
+    OutPendingFlush {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        protocol: TCodec::Protocol,
+        timeout: Delay,
+    },
+    /// Waiting for the response of the remote.
+    OutWaitingResponse {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        protocol: TCodec::Protocol,
+        timeout: Delay,
+    },
+    /// Waiting for the remote to send a request.
+    InWaitingRequest {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        protocol: TCodec::Protocol,
+        timeout: Delay,
+    },
+    /// Waiting for the user to send the response through the `ResponseChannel`.
+    InWaitingUser {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        protocol: TCodec::Protocol,
+        response: oneshot::Receiver<TCodec::Response>,
+        timeout: Delay,
+    },
+    /// Waiting to send the response to the remote.
+    InPendingSend {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        message: Vec<u8>,
+        timeout: Delay,
+    },
+    /// Waiting to flush the substream so that the response arrives to the remote.
+    InPendingFlush {
+        request_id: RequestId,
+        substream: RequestResponseSubstream<TSubstream>,
+        timeout: Delay,
+    },
+    /// The substream is being closed.
+    Closing(RequestResponseSubstream<TSubstream>),
+}
+
+impl<TSubstream, TCodec> RequestResponseHandler<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    /// Builds a new `RequestResponseHandler`.
+    pub fn new(
+        codec: TCodec,
+        protocols: SmallVec<[TCodec::Protocol; 2]>,
+        max_message_size: usize,
+        request_timeout: Duration,
+        keep_alive_timeout: Duration,
+        request_id_counter: Arc<AtomicUsize>,
+    ) -> Self {
+        RequestResponseHandler {
+            codec,
+            protocol: RequestResponseProtocol::new(protocols, max_message_size),
+            request_timeout,
+            keep_alive_timeout,
+            keep_alive: KeepAlive::Until(Instant::now() + keep_alive_timeout),
+            request_id_counter,
+            shutting_down: false,
+            pending_requests: VecDeque::new(),
+            pending_events: VecDeque::new(),
+            substreams: Vec::new(),
+        }
+    }
+}
+
+impl<TSubstream, TCodec> ProtocolsHandler for RequestResponseHandler<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    type InEvent = RequestResponseHandlerIn<TCodec::Request>;
+    type OutEvent = RequestResponseHandlerEvent<TCodec>;
+    type Substream = TSubstream;
+    type InboundProtocol = RequestResponseProtocol<TCodec::Protocol>;
+    type OutboundProtocol = RequestResponseProtocol<TCodec::Protocol>;
+    type OutboundOpenInfo = (RequestId, TCodec::Request);
+
+    #[inline]
+    fn listen_protocol(&self) -> Self::InboundProtocol {
+        self.protocol.clone()
+    }
+
+    fn inject_fully_negotiated_inbound(
+        &mut self,
+        (substream, protocol): <Self::InboundProtocol as InboundUpgrade<TSubstream>>::Output,
+    ) {
+        if self.shutting_down {
+            return;
+        }
+
+        self.substreams.push(SubstreamState::InWaitingRequest {
+            request_id: RequestId::next(&self.request_id_counter),
+            substream,
+            protocol,
+            timeout: Delay::new(Instant::now() + self.request_timeout),
+        });
+    }
+
+    fn inject_fully_negotiated_outbound(
+        &mut self,
+        (substream, protocol): <Self::OutboundProtocol as OutboundUpgrade<TSubstream>>::Output,
+        (request_id, request): Self::OutboundOpenInfo,
+    ) {
+        if self.shutting_down {
+            self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+                request_id,
+                error: OutboundFailure::ConnectionClosed,
+            });
+            self.substreams.push(SubstreamState::Closing(substream));
+            return;
+        }
+
+        match self.codec.write_request(&protocol, request) {
+            Ok(message) => {
+                self.substreams.push(SubstreamState::OutPendingSend {
+                    request_id,
+                    substream,
+                    protocol,
+                    message,
+                    timeout: Delay::new(Instant::now() + self.request_timeout),
+                });
+            }
+            Err(err) => {
+                self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+                    request_id,
+                    error: OutboundFailure::Io(err),
+                });
+            }
+        }
+    }
+
+    #[inline]
+    fn inject_event(&mut self, event: Self::InEvent) {
+        match event {
+            RequestResponseHandlerIn::Request { request_id, .. } if self.shutting_down => {
+                self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+                    request_id,
+                    error: OutboundFailure::ConnectionClosed,
+                });
+            }
+            RequestResponseHandlerIn::Request { request_id, request } => {
+                self.pending_requests.push_back((request_id, request));
+            }
+        }
+    }
+
+    #[inline]
+    fn inject_inbound_closed(&mut self) {}
+
+    fn inject_dial_upgrade_error(
+        &mut self,
+        (request_id, _): Self::OutboundOpenInfo,
+        error: ProtocolsHandlerUpgrErr<io::Error>,
+    ) {
+        let error = match error {
+            ProtocolsHandlerUpgrErr::Timeout => OutboundFailure::Timeout,
+            ProtocolsHandlerUpgrErr::Upgrade(UpgradeError::Select(_)) =>
+                OutboundFailure::UnsupportedProtocols,
+            ProtocolsHandlerUpgrErr::Upgrade(UpgradeError::Apply(err)) => OutboundFailure::Io(err),
+            err => OutboundFailure::Io(io::Error::new(io::ErrorKind::Other, err.to_string())),
+        };
+
+        self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+            request_id,
+            error,
+        });
+    }
+
+    #[inline]
+    fn connection_keep_alive(&self) -> KeepAlive {
+        self.keep_alive
+    }
+
+    #[inline]
+    fn shutdown(&mut self) {
+        self.shutting_down = true;
+
+        // The requests that haven't been answered yet will never be.
+        for (request_id, _) in self.pending_requests.drain(..) {
+            self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+                request_id,
+                error: OutboundFailure::ConnectionClosed,
+            });
+        }
+
+        for n in (0..self.substreams.len()).rev() {
+            let substream = self.substreams.swap_remove(n);
+            if let Some(request_id) = substream.outbound_request_id() {
+                self.pending_events.push_back(RequestResponseHandlerEvent::OutboundFailure {
+                    request_id,
+                    error: OutboundFailure::ConnectionClosed,
+                });
+            }
+            self.substreams.push(SubstreamState::Closing(substream.into_substream()));
+        }
+    }
+
+    fn poll(
+        &mut self,
+    ) -> Poll<
+        Option<ProtocolsHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::OutEvent>>,
+        io::Error,
+    > {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
+        }
+
+        // Special case if shutting down.
+        if self.shutting_down {
+            for n in (0..self.substreams.len()).rev() {
+                match self.substreams.swap_remove(n) {
+                    SubstreamState::Closing(mut substream) => match substream.close() {
+                        Ok(Async::NotReady) =>
+                            self.substreams.push(SubstreamState::Closing(substream)),
+                        Ok(Async::Ready(())) | Err(_) => (),
+                    },
+                    _ => unreachable!("All the substreams are set to Closing in shutdown(); QED"),
+                }
+            }
+
+            if self.substreams.is_empty() {
+                return Ok(Async::Ready(None));
+            } else {
+                return Ok(Async::NotReady);
+            }
+        }
+
+        if let Some((request_id, request)) = self.pending_requests.pop_front() {
+            return Ok(Async::Ready(Some(ProtocolsHandlerEvent::OutboundSubstreamRequest {
+                upgrade: self.protocol.clone(),
+                info: (request_id, request),
+            })));
+        }
+
+        // We remove each element from `substreams` one by one and add them back.
+        for n in (0..self.substreams.len()).rev() {
+            let mut substream = self.substreams.swap_remove(n);
+
+            loop {
+                match advance_substream(substream, &mut self.codec) {
+                    (Some(new_state), Some(event), _) => {
+                        self.substreams.push(new_state);
+                        return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
+                    }
+                    (None, Some(event), _) => {
+                        return Ok(Async::Ready(Some(ProtocolsHandlerEvent::Custom(event))));
+                    }
+                    (Some(new_state), None, false) => {
+                        self.substreams.push(new_state);
+                        break;
+                    }
+                    (Some(new_state), None, true) => {
+                        substream = new_state;
+                        continue;
+                    }
+                    (None, None, _) => {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if self.substreams.is_empty() {
+            if self.keep_alive.is_forever() {
+                self.keep_alive = KeepAlive::Until(Instant::now() + self.keep_alive_timeout);
+            }
+        } else {
+            self.keep_alive = KeepAlive::Forever;
+        }
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<TSubstream, TCodec> SubstreamState<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    /// If this is a substream on which we sent a request, returns the identifier of that request.
+    fn outbound_request_id(&self) -> Option<RequestId> {
+        match self {
+            SubstreamState::OutPendingSend { request_id, .. } => Some(*request_id),
+            SubstreamState::OutPendingFlush { request_id, .. } => Some(*request_id),
+            SubstreamState::OutWaitingResponse { request_id, .. } => Some(*request_id),
+            _ => None,
+        }
+    }
+
+    /// Consumes this state and produces the substream.
+    fn into_substream(self) -> RequestResponseSubstream<TSubstream> {
+        match self {
+            SubstreamState::OutPendingSend { substream, .. } => substream,
+            SubstreamState::OutPendingFlush { substream, .. } => substream,
+            SubstreamState::OutWaitingResponse { substream, .. } => substream,
+            SubstreamState::InWaitingRequest { substream, .. } => substream,
+            SubstreamState::InWaitingUser { substream, .. } => substream,
+            SubstreamState::InPendingSend { substream, .. } => substream,
+            SubstreamState::InPendingFlush { substream, .. } => substream,
+            SubstreamState::Closing(substream) => substream,
+        }
+    }
+}
+
+/// Returns true if the timeout has elapsed, or if the timer has errored.
+fn has_expired(timeout: &mut Delay) -> bool {
+    match timeout.poll() {
+        Ok(Async::NotReady) => false,
+        Ok(Async::Ready(())) | Err(_) => true,
+    }
+}
+
+/// Advances one substream.
+///
+/// Returns the new state for that substream, an event to generate, and whether the substream
+/// should be polled again.
+fn advance_substream<TSubstream, TCodec>(
+    state: SubstreamState<TSubstream, TCodec>,
+    codec: &mut TCodec,
+) -> (
+    Option<SubstreamState<TSubstream, TCodec>>,
+    Option<RequestResponseHandlerEvent<TCodec>>,
+    bool,
+)
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    let out_failure = |substream, request_id, error| (
+        Some(SubstreamState::Closing(substream)),
+        Some(RequestResponseHandlerEvent::OutboundFailure { request_id, error }),
+        true,
+    );
+    let in_failure = |substream, request_id, error| (
+        Some(SubstreamState::Closing(substream)),
+        Some(RequestResponseHandlerEvent::InboundFailure { request_id, error }),
+        true,
+    );
+
+    match state {
+        SubstreamState::OutPendingSend { request_id, mut substream, protocol, message, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return out_failure(substream, request_id, OutboundFailure::Timeout);
+            }
+            match substream.start_send(message) {
+                Ok(AsyncSink::Ready) => (
+                    Some(SubstreamState::OutPendingFlush { request_id, substream, protocol, timeout }),
+                    None,
+                    true,
+                ),
+                Ok(AsyncSink::NotReady(message)) => (
+                    Some(SubstreamState::OutPendingSend { request_id, substream, protocol, message, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => out_failure(substream, request_id, OutboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::OutPendingFlush { request_id, mut substream, protocol, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return out_failure(substream, request_id, OutboundFailure::Timeout);
+            }
+            match substream.poll_complete() {
+                Ok(Async::Ready(())) => (
+                    Some(SubstreamState::OutWaitingResponse { request_id, substream, protocol, timeout }),
+                    None,
+                    true,
+                ),
+                Ok(Async::NotReady) => (
+                    Some(SubstreamState::OutPendingFlush { request_id, substream, protocol, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => out_failure(substream, request_id, OutboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::OutWaitingResponse { request_id, mut substream, protocol, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return out_failure(substream, request_id, OutboundFailure::Timeout);
+            }
+            match substream.poll() {
+                Ok(Async::Ready(Some(bytes))) => match codec.read_response(&protocol, bytes) {
+                    Ok(response) => (
+                        Some(SubstreamState::Closing(substream)),
+                        Some(RequestResponseHandlerEvent::Response { request_id, response }),
+                        true,
+                    ),
+                    Err(err) => out_failure(substream, request_id, OutboundFailure::Io(err)),
+                },
+                Ok(Async::Ready(None)) => {
+                    let err = io::Error::new(io::ErrorKind::UnexpectedEof,
+                        "substream closed before the response");
+                    out_failure(substream, request_id, OutboundFailure::Io(err))
+                }
+                Ok(Async::NotReady) => (
+                    Some(SubstreamState::OutWaitingResponse { request_id, substream, protocol, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => out_failure(substream, request_id, OutboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::InWaitingRequest { request_id, mut substream, protocol, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return in_failure(substream, request_id, InboundFailure::Timeout);
+            }
+            match substream.poll() {
+                Ok(Async::Ready(Some(bytes))) => match codec.read_request(&protocol, bytes) {
+                    Ok(request) => {
+                        let (sender, response) = oneshot::channel();
+                        let channel = ResponseChannel { request_id, sender };
+                        let new_state = SubstreamState::InWaitingUser {
+                            request_id,
+                            substream,
+                            protocol,
+                            response,
+                            timeout,
+                        };
+                        let event = RequestResponseHandlerEvent::Request {
+                            request_id,
+                            request,
+                            channel,
+                        };
+                        (Some(new_state), Some(event), true)
+                    }
+                    Err(err) => in_failure(substream, request_id, InboundFailure::Io(err)),
+                },
+                // The remote closed the substream without sending anything.
+                Ok(Async::Ready(None)) => (Some(SubstreamState::Closing(substream)), None, true),
+                Ok(Async::NotReady) => (
+                    Some(SubstreamState::InWaitingRequest { request_id, substream, protocol, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => in_failure(substream, request_id, InboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::InWaitingUser { request_id, substream, protocol, mut response, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return in_failure(substream, request_id, InboundFailure::Timeout);
+            }
+            match response.poll() {
+                Ok(Async::Ready(response)) => match codec.write_response(&protocol, response) {
+                    Ok(message) => (
+                        Some(SubstreamState::InPendingSend { request_id, substream, message, timeout }),
+                        None,
+                        true,
+                    ),
+                    Err(err) => in_failure(substream, request_id, InboundFailure::Io(err)),
+                },
+                Ok(Async::NotReady) => (
+                    Some(SubstreamState::InWaitingUser { request_id, substream, protocol, response, timeout }),
+                    None,
+                    false,
+                ),
+                Err(oneshot::Canceled) =>
+                    in_failure(substream, request_id, InboundFailure::ResponseOmission),
+            }
+        }
+        SubstreamState::InPendingSend { request_id, mut substream, message, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return in_failure(substream, request_id, InboundFailure::Timeout);
+            }
+            match substream.start_send(message) {
+                Ok(AsyncSink::Ready) => (
+                    Some(SubstreamState::InPendingFlush { request_id, substream, timeout }),
+                    None,
+                    true,
+                ),
+                Ok(AsyncSink::NotReady(message)) => (
+                    Some(SubstreamState::InPendingSend { request_id, substream, message, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => in_failure(substream, request_id, InboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::InPendingFlush { request_id, mut substream, mut timeout } => {
+            if has_expired(&mut timeout) {
+                return in_failure(substream, request_id, InboundFailure::Timeout);
+            }
+            match substream.poll_complete() {
+                Ok(Async::Ready(())) => (Some(SubstreamState::Closing(substream)), None, true),
+                Ok(Async::NotReady) => (
+                    Some(SubstreamState::InPendingFlush { request_id, substream, timeout }),
+                    None,
+                    false,
+                ),
+                Err(err) => in_failure(substream, request_id, InboundFailure::Io(err)),
+            }
+        }
+        SubstreamState::Closing(mut substream) => match substream.close() {
+            Ok(Async::Ready(())) | Err(_) => (None, None, false),
+            Ok(Async::NotReady) => (Some(SubstreamState::Closing(substream)), None, false),
+        },
+    }
+}
+
+impl<TSubstream, TCodec> fmt::Debug for RequestResponseHandler<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("RequestResponseHandler")
+            .field("shutting_down", &self.shutting_down)
+            .field("pending_requests", &self.pending_requests.len())
+            .field("substreams", &self.substreams.len())
+            .finish()
+    }
+}
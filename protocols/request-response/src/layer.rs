@@ -0,0 +1,584 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::codec::RequestResponseCodec;
+use crate::handler::{
+    InboundFailure, OutboundFailure, RequestId, RequestResponseHandler,
+    RequestResponseHandlerEvent, RequestResponseHandlerIn, ResponseChannel,
+};
+use fnv::FnvHashMap;
+use futures::prelude::*;
+use libp2p_core::swarm::{ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters};
+use libp2p_core::{protocols_handler::ProtocolsHandler, PeerId};
+use smallvec::SmallVec;
+use std::{collections::VecDeque, marker::PhantomData, sync::Arc, sync::atomic::AtomicUsize};
+use std::time::{Duration, Instant};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Delay;
+
+/// Configuration of a `RequestResponse` behaviour.
+#[derive(Debug, Clone)]
+pub struct RequestResponseConfig {
+    request_timeout: Duration,
+    connection_keep_alive: Duration,
+    max_message_size: usize,
+}
+
+impl Default for RequestResponseConfig {
+    #[inline]
+    fn default() -> Self {
+        RequestResponseConfig {
+            request_timeout: Duration::from_secs(10),
+            connection_keep_alive: Duration::from_secs(10),
+            max_message_size: 1024 * 1024,
+        }
+    }
+}
+
+impl RequestResponseConfig {
+    /// Sets how long we wait for the response to a request, and how long the user has to answer
+    /// an inbound request.
+    ///
+    /// A request to a peer we're not connected to also fails if we can't connect to that peer
+    /// within this duration.
+    #[inline]
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets how long a connection is kept open when no request is in progress.
+    #[inline]
+    pub fn with_connection_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.connection_keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a request or a response.
+    #[inline]
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+}
+
+/// Event produced by the `RequestResponse` behaviour.
+#[derive(Debug)]
+pub enum RequestResponseEvent<TRequest, TResponse> {
+    /// A remote sent us a request. The response must be sent through `channel`.
+    Request {
+        /// The remote that sent the request.
+        peer_id: PeerId,
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// The request.
+        request: TRequest,
+        /// Channel to use to send the response.
+        channel: ResponseChannel<TResponse>,
+    },
+    /// A remote answered one of our requests.
+    Response {
+        /// The remote that answered.
+        peer_id: PeerId,
+        /// Identifier of the request, as returned by `send_request`.
+        request_id: RequestId,
+        /// The response.
+        response: TResponse,
+    },
+    /// One of our requests has failed.
+    OutboundFailure {
+        /// The remote the request was sent to.
+        peer_id: PeerId,
+        /// Identifier of the request, as returned by `send_request`.
+        request_id: RequestId,
+        /// What went wrong.
+        error: OutboundFailure,
+    },
+    /// We failed to answer a request of a remote.
+    InboundFailure {
+        /// The remote that sent the request.
+        peer_id: PeerId,
+        /// Identifier of the request.
+        request_id: RequestId,
+        /// What went wrong.
+        error: InboundFailure,
+    },
+}
+
+/// Network behaviour that sends requests to remotes and lets the user answer the requests of
+/// remotes, using the protocols and the encoding provided by a `RequestResponseCodec`.
+pub struct RequestResponse<TSubstream, TCodec>
+where
+    TCodec: RequestResponseCodec,
+{
+    /// Turns requests and responses into bytes. Cloned for each connection.
+    codec: TCodec,
+
+    /// Protocols to negotiate, by order of preference.
+    protocols: SmallVec<[TCodec::Protocol; 2]>,
+
+    /// Configuration of the behaviour.
+    config: RequestResponseConfig,
+
+    /// Generates the identifiers of requests. Shared with the handlers.
+    request_id_counter: Arc<AtomicUsize>,
+
+    /// For each peer we're connected to, the requests sent to it and not answered yet.
+    connected: FnvHashMap<PeerId, SmallVec<[RequestId; 8]>>,
+
+    /// Requests to send to peers we're not connected to yet, with the moment when they time out.
+    pending_requests: FnvHashMap<PeerId, SmallVec<[(RequestId, TCodec::Request, Instant); 4]>>,
+
+    /// Fires when the earliest of the `pending_requests` times out. `None` if there's no pending
+    /// request.
+    pending_timeout: Option<Delay>,
+
+    /// Events to produce from `poll()`.
+    events: VecDeque<NetworkBehaviourAction<RequestResponseHandlerIn<TCodec::Request>, RequestResponseEvent<TCodec::Request, TCodec::Response>>>,
+
+    /// Marker to pin the generics.
+    marker: PhantomData<TSubstream>,
+}
+
+impl<TSubstream, TCodec> RequestResponse<TSubstream, TCodec>
+where
+    TCodec: RequestResponseCodec,
+{
+    /// Creates a `RequestResponse` that supports the given protocols, by order of preference.
+    pub fn new<I>(codec: TCodec, protocols: I, config: RequestResponseConfig) -> Self
+    where
+        I: IntoIterator<Item = TCodec::Protocol>,
+    {
+        RequestResponse {
+            codec,
+            protocols: protocols.into_iter().collect(),
+            config,
+            request_id_counter: Arc::new(AtomicUsize::new(0)),
+            connected: FnvHashMap::default(),
+            pending_requests: FnvHashMap::default(),
+            pending_timeout: None,
+            events: VecDeque::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sends a request to a peer. Dials the peer if we're not connected to it.
+    ///
+    /// The response, or the failure, is reported later as an event carrying the returned
+    /// `RequestId`.
+    pub fn send_request(&mut self, peer_id: &PeerId, request: TCodec::Request) -> RequestId {
+        let request_id = RequestId::next(&self.request_id_counter);
+
+        if let Some(in_progress) = self.connected.get_mut(peer_id) {
+            in_progress.push(request_id);
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id: peer_id.clone(),
+                event: RequestResponseHandlerIn::Request { request_id, request },
+            });
+            return request_id;
+        }
+
+        let pending = self.pending_requests.entry(peer_id.clone()).or_insert_with(SmallVec::new);
+        if pending.is_empty() {
+            self.events.push_back(NetworkBehaviourAction::DialPeer {
+                peer_id: peer_id.clone(),
+            });
+        }
+        let deadline = Instant::now() + self.config.request_timeout;
+        pending.push((request_id, request, deadline));
+        // All the requests have the same timeout, therefore an existing timer always fires first.
+        if self.pending_timeout.is_none() {
+            self.pending_timeout = Some(Delay::new(deadline));
+        }
+        request_id
+    }
+
+    /// Sends the response to a request received from a remote.
+    ///
+    /// This is equivalent to `channel.send(response)`. Returns back the response if the request
+    /// can no longer be answered, for example because the connection has been closed.
+    #[inline]
+    pub fn send_response(&mut self, channel: ResponseChannel<TCodec::Response>, response: TCodec::Response)
+        -> Result<(), TCodec::Response>
+    {
+        channel.send(response)
+    }
+
+    /// Returns true if we're connected to the given peer.
+    #[inline]
+    pub fn is_connected(&self, peer_id: &PeerId) -> bool {
+        self.connected.contains_key(peer_id)
+    }
+
+    /// Reports a failure for the pending requests whose peer didn't connect in time, and resets
+    /// `pending_timeout` for the remaining ones.
+    fn expire_pending_requests(&mut self) {
+        let now = Instant::now();
+        let mut next_deadline: Option<Instant> = None;
+        let events = &mut self.events;
+
+        for (peer_id, pending) in self.pending_requests.iter_mut() {
+            pending.retain(|&mut (request_id, _, deadline)| {
+                if deadline > now {
+                    next_deadline = Some(next_deadline.map_or(deadline, |d| d.min(deadline)));
+                    return true;
+                }
+
+                events.push_back(NetworkBehaviourAction::GenerateEvent(
+                    RequestResponseEvent::OutboundFailure {
+                        peer_id: peer_id.clone(),
+                        request_id,
+                        error: OutboundFailure::Timeout,
+                    }
+                ));
+                false
+            });
+        }
+
+        self.pending_requests.retain(|_, pending| !pending.is_empty());
+        self.pending_timeout = next_deadline.map(Delay::new);
+    }
+
+    /// Removes a request from the list of requests in progress.
+    fn finish_request(&mut self, peer_id: &PeerId, request_id: RequestId) {
+        if let Some(in_progress) = self.connected.get_mut(peer_id) {
+            if let Some(pos) = in_progress.iter().position(|id| *id == request_id) {
+                in_progress.remove(pos);
+            }
+        }
+    }
+}
+
+impl<TSubstream, TCodec, TTopology> NetworkBehaviour<TTopology> for RequestResponse<TSubstream, TCodec>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+    TCodec: RequestResponseCodec,
+{
+    type ProtocolsHandler = RequestResponseHandler<TSubstream, TCodec>;
+    type OutEvent = RequestResponseEvent<TCodec::Request, TCodec::Response>;
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        RequestResponseHandler::new(
+            self.codec.clone(),
+            self.protocols.clone(),
+            self.config.max_message_size,
+            self.config.request_timeout,
+            self.config.connection_keep_alive,
+            self.request_id_counter.clone(),
+        )
+    }
+
+    fn inject_connected(&mut self, peer_id: PeerId, _: ConnectedPoint) {
+        let mut in_progress = SmallVec::new();
+
+        for (request_id, request, _) in self.pending_requests.remove(&peer_id).into_iter().flat_map(|p| p) {
+            in_progress.push(request_id);
+            self.events.push_back(NetworkBehaviourAction::SendEvent {
+                peer_id: peer_id.clone(),
+                event: RequestResponseHandlerIn::Request { request_id, request },
+            });
+        }
+
+        self.connected.insert(peer_id, in_progress);
+    }
+
+    fn inject_disconnected(&mut self, peer_id: &PeerId, _: ConnectedPoint) {
+        let in_progress = match self.connected.remove(peer_id) {
+            Some(in_progress) => in_progress,
+            None => return,
+        };
+
+        for request_id in in_progress {
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(
+                RequestResponseEvent::OutboundFailure {
+                    peer_id: peer_id.clone(),
+                    request_id,
+                    error: OutboundFailure::ConnectionClosed,
+                }
+            ));
+        }
+    }
+
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        for (request_id, _, _) in self.pending_requests.remove(peer_id).into_iter().flat_map(|p| p) {
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(
+                RequestResponseEvent::OutboundFailure {
+                    peer_id: peer_id.clone(),
+                    request_id,
+                    error: OutboundFailure::DialFailure,
+                }
+            ));
+        }
+    }
+
+    fn inject_node_event(&mut self, peer_id: PeerId, event: RequestResponseHandlerEvent<TCodec>) {
+        let event = match event {
+            RequestResponseHandlerEvent::Request { request_id, request, channel } =>
+                RequestResponseEvent::Request { peer_id, request_id, request, channel },
+            RequestResponseHandlerEvent::Response { request_id, response } => {
+                self.finish_request(&peer_id, request_id);
+                RequestResponseEvent::Response { peer_id, request_id, response }
+            }
+            RequestResponseHandlerEvent::OutboundFailure { request_id, error } => {
+                self.finish_request(&peer_id, request_id);
+                RequestResponseEvent::OutboundFailure { peer_id, request_id, error }
+            }
+            RequestResponseHandlerEvent::InboundFailure { request_id, error } =>
+                RequestResponseEvent::InboundFailure { peer_id, request_id, error },
+        };
+
+        self.events.push_back(NetworkBehaviourAction::GenerateEvent(event));
+    }
+
+    fn poll(
+        &mut self,
+        _: &mut PollParameters<TTopology>,
+    ) -> Async<
+        NetworkBehaviourAction<
+            <Self::ProtocolsHandler as ProtocolsHandler>::InEvent,
+            Self::OutEvent,
+        >,
+    > {
+        loop {
+            match self.pending_timeout.as_mut().map(|timeout| timeout.poll()) {
+                Some(Ok(Async::Ready(()))) => self.expire_pending_requests(),
+                // If the timer is broken, we still want to report the expired requests but
+                // shouldn't loop forever on the error.
+                Some(Err(_)) => {
+                    self.expire_pending_requests();
+                    break;
+                }
+                Some(Ok(Async::NotReady)) | None => break,
+            }
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Async::Ready(event);
+        }
+
+        Async::NotReady
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{Bytes, BytesMut};
+    use futures::{future, try_ready};
+    use libp2p_core::{PublicKey, Swarm, Transport};
+    use libp2p_core::nodes::Substream;
+    use libp2p_core::topology::MemoryTopology;
+    use libp2p_core::transport::{boxed::Boxed, memory};
+    use libp2p_mplex::{MplexConfig, Multiplex};
+    use std::io;
+    use tokio::runtime::current_thread::Runtime;
+
+    /// Codec that sends requests and responses as they are.
+    #[derive(Debug, Clone)]
+    struct RawCodec;
+
+    impl RequestResponseCodec for RawCodec {
+        type Protocol = &'static [u8];
+        type Request = Vec<u8>;
+        type Response = Vec<u8>;
+
+        fn read_request(&mut self, _: &Self::Protocol, bytes: BytesMut) -> Result<Vec<u8>, io::Error> {
+            Ok(bytes.to_vec())
+        }
+
+        fn read_response(&mut self, _: &Self::Protocol, bytes: BytesMut) -> Result<Vec<u8>, io::Error> {
+            Ok(bytes.to_vec())
+        }
+
+        fn write_request(&mut self, _: &Self::Protocol, request: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+            Ok(request)
+        }
+
+        fn write_response(&mut self, _: &Self::Protocol, response: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+            Ok(response)
+        }
+    }
+
+    type TestMuxer = Multiplex<memory::Channel<Bytes>>;
+    type TestSwarm = Swarm<
+        Boxed<(PeerId, TestMuxer)>,
+        RequestResponse<Substream<TestMuxer>, RawCodec>,
+        MemoryTopology
+    >;
+
+    /// Builds a swarm on top of one end of the memory transport. All the connections are
+    /// considered to be with `remote_id`.
+    fn build_swarm<T>(transport: T, local_key: PublicKey, remote_id: PeerId, config: RequestResponseConfig)
+        -> TestSwarm
+    where
+        T: Transport<Output = memory::Channel<Bytes>> + Clone + Send + Sync + 'static,
+        T::Dial: Send + 'static,
+        T::Listener: Send + 'static,
+        T::ListenerUpgrade: Send + 'static,
+    {
+        let transport = transport
+            .with_upgrade(MplexConfig::new())
+            .map(move |muxer, _| (remote_id, muxer))
+            .boxed();
+        let behaviour = RequestResponse::new(RawCodec, vec![&b"/test/1.0.0"[..]], config);
+        Swarm::new(transport, behaviour, MemoryTopology::empty(local_key))
+    }
+
+    fn keys() -> (PublicKey, PublicKey) {
+        (PublicKey::Ed25519(vec![1; 32]), PublicKey::Ed25519(vec![2; 32]))
+    }
+
+    #[test]
+    fn request_and_response() {
+        let (listener_key, dialer_key) = keys();
+        let (listener_id, dialer_id) = (listener_key.clone().into_peer_id(), dialer_key.clone().into_peer_id());
+        let (dialer, listener) = memory::connector();
+
+        let mut listener = build_swarm(listener, listener_key, dialer_id.clone(), Default::default());
+        Swarm::listen_on(&mut listener, "/memory".parse().unwrap()).unwrap();
+        let mut dialer = build_swarm(dialer, dialer_key, listener_id.clone(), Default::default());
+        Swarm::topology_mut(&mut dialer).add_address(listener_id.clone(), "/memory".parse().unwrap());
+        let sent_id = dialer.send_request(&listener_id, b"ping".to_vec());
+
+        let mut rt = Runtime::new().unwrap();
+        let response = rt.block_on(future::poll_fn(move || -> Poll<_, io::Error> {
+            while let Async::Ready(Some(event)) = listener.poll()? {
+                if let RequestResponseEvent::Request { peer_id, request, channel, .. } = event {
+                    assert_eq!(peer_id, dialer_id);
+                    assert_eq!(request, b"ping".to_vec());
+                    assert!(listener.send_response(channel, b"pong".to_vec()).is_ok());
+                }
+            }
+
+            while let Async::Ready(Some(event)) = dialer.poll()? {
+                match event {
+                    RequestResponseEvent::Response { peer_id, request_id, response } => {
+                        assert_eq!(peer_id, listener_id);
+                        assert_eq!(request_id, sent_id);
+                        return Ok(Async::Ready(response));
+                    }
+                    event => panic!("unexpected event: {:?}", event),
+                }
+            }
+
+            Ok(Async::NotReady)
+        })).unwrap();
+
+        assert_eq!(response, b"pong".to_vec());
+    }
+
+    #[test]
+    fn unanswered_request_times_out() {
+        let (listener_key, dialer_key) = keys();
+        let (listener_id, dialer_id) = (listener_key.clone().into_peer_id(), dialer_key.clone().into_peer_id());
+        let (dialer, listener) = memory::connector();
+        let config = RequestResponseConfig::default().with_request_timeout(Duration::from_millis(200));
+
+        let mut listener = build_swarm(listener, listener_key, dialer_id, config.clone());
+        Swarm::listen_on(&mut listener, "/memory".parse().unwrap()).unwrap();
+        let mut dialer = build_swarm(dialer, dialer_key, listener_id.clone(), config);
+        Swarm::topology_mut(&mut dialer).add_address(listener_id.clone(), "/memory".parse().unwrap());
+        let sent_id = dialer.send_request(&listener_id, b"ping".to_vec());
+
+        // Keep the channels alive without answering, so that the request is neither answered nor
+        // refused.
+        let mut channels = Vec::new();
+        let mut rt = Runtime::new().unwrap();
+        let error = rt.block_on(future::poll_fn(move || -> Poll<_, io::Error> {
+            while let Async::Ready(Some(event)) = listener.poll()? {
+                if let RequestResponseEvent::Request { channel, .. } = event {
+                    channels.push(channel);
+                }
+            }
+
+            while let Async::Ready(Some(event)) = dialer.poll()? {
+                match event {
+                    RequestResponseEvent::OutboundFailure { request_id, error, .. } => {
+                        assert_eq!(request_id, sent_id);
+                        return Ok(Async::Ready(error));
+                    }
+                    event => panic!("unexpected event: {:?}", event),
+                }
+            }
+
+            Ok(Async::NotReady)
+        })).unwrap();
+
+        match error {
+            OutboundFailure::Timeout => {}
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unreachable_peer_reports_dial_failure() {
+        let (listener_key, dialer_key) = keys();
+        let listener_id = listener_key.into_peer_id();
+        // Nobody listens on the other end of the connector.
+        let (dialer, _) = memory::connector();
+
+        let mut dialer = build_swarm(dialer, dialer_key, listener_id.clone(), Default::default());
+        Swarm::topology_mut(&mut dialer).add_address(listener_id.clone(), "/memory".parse().unwrap());
+        let sent_id = dialer.send_request(&listener_id, b"ping".to_vec());
+
+        let mut rt = Runtime::new().unwrap();
+        let (request_id, error) = rt.block_on(future::poll_fn(move || -> Poll<_, io::Error> {
+            match try_ready!(dialer.poll()) {
+                Some(RequestResponseEvent::OutboundFailure { request_id, error, .. }) =>
+                    Ok(Async::Ready((request_id, error))),
+                event => panic!("unexpected event: {:?}", event),
+            }
+        })).unwrap();
+
+        assert_eq!(request_id, sent_id);
+        match error {
+            OutboundFailure::DialFailure => {}
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn request_to_peer_that_never_connects_times_out() {
+        let (listener_key, dialer_key) = keys();
+        let listener_id = listener_key.into_peer_id();
+        // The listening end is kept alive but never polled, so the connection is never negotiated.
+        let (dialer, _listener) = memory::connector();
+        let config = RequestResponseConfig::default().with_request_timeout(Duration::from_millis(200));
+
+        let mut dialer = build_swarm(dialer, dialer_key, listener_id.clone(), config);
+        Swarm::topology_mut(&mut dialer).add_address(listener_id.clone(), "/memory".parse().unwrap());
+        let sent_id = dialer.send_request(&listener_id, b"ping".to_vec());
+
+        let mut rt = Runtime::new().unwrap();
+        let (request_id, error) = rt.block_on(future::poll_fn(move || -> Poll<_, io::Error> {
+            match try_ready!(dialer.poll()) {
+                Some(RequestResponseEvent::OutboundFailure { request_id, error, .. }) =>
+                    Ok(Async::Ready((request_id, error))),
+                event => panic!("unexpected event: {:?}", event),
+            }
+        })).unwrap();
+
+        assert_eq!(request_id, sent_id);
+        match error {
+            OutboundFailure::Timeout => {}
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Generic request/response protocols.
+//!
+//! Many protocols consist in opening a substream, sending a single request on it, and reading a
+//! single response back. This crate implements this pattern once and for all.
+//!
+//! # Usage
+//!
+//! Implement the `RequestResponseCodec` trait, which indicates the names of the protocols and
+//! how to turn requests and responses into bytes, then create a `RequestResponse` and plug it into
+//! the `Swarm`.
+//!
+//! Call `send_request` to send a request to a remote. The returned `RequestId` is later reported
+//! alongside with the response or with the reason of the failure.
+//!
+//! Requests received from remotes are reported as `RequestResponseEvent::Request` events. They
+//! contain a `ResponseChannel` through which the response is sent, which can be done at any time
+//! before the request times out.
+//!
+//! Each request is sent on its own substream. Messages are prefixed with their length.
+
+pub use self::codec::RequestResponseCodec;
+pub use self::handler::{InboundFailure, OutboundFailure, RequestId, ResponseChannel};
+pub use self::layer::{RequestResponse, RequestResponseConfig, RequestResponseEvent};
+
+pub mod handler;
+pub mod protocol;
+
+mod codec;
+mod layer;
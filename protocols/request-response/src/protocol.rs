@@ -0,0 +1,106 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::future;
+use libp2p_core::{InboundUpgrade, OutboundUpgrade, ProtocolName, UpgradeInfo};
+use smallvec::SmallVec;
+use std::io;
+use tokio_codec::Framed;
+use tokio_io::{AsyncRead, AsyncWrite};
+use unsigned_varint::codec::UviBytes;
+
+/// Substream on which requests and responses are exchanged, once negotiated.
+pub type RequestResponseSubstream<TSocket> = Framed<TSocket, UviBytes<Vec<u8>>>;
+
+/// Implementation of `ConnectionUpgrade` that negotiates one of the protocols of a
+/// `RequestResponse`.
+///
+/// The output of the upgrade is the substream, plus the protocol that has been negotiated.
+#[derive(Debug, Clone)]
+pub struct RequestResponseProtocol<TProtocol> {
+    /// Protocols to negotiate, by order of preference.
+    protocols: SmallVec<[TProtocol; 2]>,
+    /// Maximum size in bytes of a message.
+    max_message_size: usize,
+}
+
+impl<TProtocol> RequestResponseProtocol<TProtocol> {
+    /// Builds a new `RequestResponseProtocol`.
+    #[inline]
+    pub fn new(protocols: SmallVec<[TProtocol; 2]>, max_message_size: usize) -> Self {
+        RequestResponseProtocol {
+            protocols,
+            max_message_size,
+        }
+    }
+
+    /// Wraps the socket in a length-prefixed codec.
+    fn framed<TSocket>(&self, socket: TSocket) -> RequestResponseSubstream<TSocket>
+    where
+        TSocket: AsyncRead + AsyncWrite,
+    {
+        let mut codec = UviBytes::default();
+        codec.set_max_len(self.max_message_size);
+        Framed::new(socket, codec)
+    }
+}
+
+impl<TProtocol> UpgradeInfo for RequestResponseProtocol<TProtocol>
+where
+    TProtocol: ProtocolName + Clone,
+{
+    type Info = TProtocol;
+    type InfoIter = smallvec::IntoIter<[TProtocol; 2]>;
+
+    #[inline]
+    fn protocol_info(&self) -> Self::InfoIter {
+        self.protocols.clone().into_iter()
+    }
+}
+
+impl<TSocket, TProtocol> InboundUpgrade<TSocket> for RequestResponseProtocol<TProtocol>
+where
+    TSocket: AsyncRead + AsyncWrite,
+    TProtocol: ProtocolName + Clone,
+{
+    type Output = (RequestResponseSubstream<TSocket>, TProtocol);
+    type Error = io::Error;
+    type Future = future::FutureResult<Self::Output, Self::Error>;
+
+    #[inline]
+    fn upgrade_inbound(self, socket: TSocket, protocol: Self::Info) -> Self::Future {
+        future::ok((self.framed(socket), protocol))
+    }
+}
+
+impl<TSocket, TProtocol> OutboundUpgrade<TSocket> for RequestResponseProtocol<TProtocol>
+where
+    TSocket: AsyncRead + AsyncWrite,
+    TProtocol: ProtocolName + Clone,
+{
+    type Output = (RequestResponseSubstream<TSocket>, TProtocol);
+    type Error = io::Error;
+    type Future = future::FutureResult<Self::Output, Self::Error>;
+
+    #[inline]
+    fn upgrade_outbound(self, socket: TSocket, protocol: Self::Info) -> Self::Future {
+        future::ok((self.framed(socket), protocol))
+    }
+}
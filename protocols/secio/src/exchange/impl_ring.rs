@@ -25,7 +25,6 @@ use futures::{future, prelude::*};
 use log::debug;
 use ring::agreement as ring_agreement;
 use ring::rand as ring_rand;
-use untrusted::Input as UntrustedInput;
 
 impl Into<&'static ring_agreement::Algorithm> for KeyAgreement {
     #[inline]
@@ -48,8 +47,10 @@ pub fn generate_agreement(algorithm: KeyAgreement) -> impl Future<Item = (Agreem
 
     match ring_agreement::EphemeralPrivateKey::generate(algorithm.into(), &rng) {
         Ok(tmp_priv_key) => {
-            let mut tmp_pub_key: Vec<u8> = (0 .. tmp_priv_key.public_key_len()).map(|_| 0).collect();
-            tmp_priv_key.compute_public_key(&mut tmp_pub_key).unwrap();
+            let tmp_pub_key = tmp_priv_key.compute_public_key()
+                .expect("computing the public key of a freshly generated private key never fails")
+                .as_ref()
+                .to_vec();
             future::ok((tmp_priv_key, tmp_pub_key))
         },
         Err(_) => {
@@ -63,8 +64,8 @@ pub fn generate_agreement(algorithm: KeyAgreement) -> impl Future<Item = (Agreem
 pub fn agree(algorithm: KeyAgreement, my_private_key: AgreementPrivateKey, other_public_key: &[u8], _out_size: usize)
     -> impl Future<Item = Vec<u8>, Error = SecioError>
 {
-    ring_agreement::agree_ephemeral(my_private_key, algorithm.into(),
-                                    UntrustedInput::from(other_public_key),
+    let other_public_key = ring_agreement::UnparsedPublicKey::new(algorithm.into(), other_public_key);
+    ring_agreement::agree_ephemeral(my_private_key, &other_public_key,
                                     SecioError::SecretGenerationFailed,
                                     |key_material| Ok(key_material.to_vec()))
         .into_future()
@@ -22,7 +22,6 @@ use crate::algo_support;
 use bytes::BytesMut;
 use crate::codec::{full_codec, FullCodec, Hmac};
 use crate::stream_cipher::{Cipher, ctr};
-use ed25519_dalek::{PublicKey as Ed25519PublicKey, Signature as Ed25519Signature};
 use crate::error::SecioError;
 use crate::exchange;
 use futures::future;
@@ -34,21 +33,13 @@ use log::{debug, trace};
 use protobuf::parse_from_bytes as protobuf_parse_from_bytes;
 use protobuf::Message as ProtobufMessage;
 use rand::{self, RngCore};
-#[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-use ring::signature::{RSASigningState, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256, verify as ring_verify};
-#[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-use ring::rand::SystemRandom;
-#[cfg(feature = "secp256k1")]
-use secp256k1;
-use sha2::{Digest as ShaDigestTrait, Sha256, Sha512};
+use sha2::{Digest as ShaDigestTrait, Sha256};
 use std::cmp::{self, Ordering};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use crate::structs_proto::{Exchange, Propose};
 use tokio_io::codec::length_delimited;
 use tokio_io::{AsyncRead, AsyncWrite};
-#[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-use untrusted::Input as UntrustedInput;
-use crate::{KeyAgreement, SecioConfig, SecioKeyPairInner};
+use crate::{KeyAgreement, SecioConfig, verify_signature};
 
 // This struct contains the whole context of a handshake, and is filled progressively
 // throughout the various parts of the handshake.
@@ -366,45 +357,7 @@ where
 
                 let mut exchange = Exchange::new();
                 exchange.set_epubkey(tmp_pub_key);
-                exchange.set_signature({
-                    match context.config.key.inner {
-                        #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-                        SecioKeyPairInner::Rsa { ref private, .. } => {
-                            let mut state = match RSASigningState::new(private.clone()) {
-                                Ok(s) => s,
-                                Err(_) => {
-                                    debug!("failed to sign local exchange");
-                                    return Err(SecioError::SigningFailure);
-                                },
-                            };
-                            let mut signature = vec![0; private.public_modulus_len()];
-                            let rng = SystemRandom::new();
-                            match state.sign(&RSA_PKCS1_SHA256, &rng, &data_to_sign, &mut signature) {
-                                Ok(_) => (),
-                                Err(_) => {
-                                    debug!("failed to sign local exchange");
-                                    return Err(SecioError::SigningFailure);
-                                },
-                            };
-
-                            signature
-                        },
-                        SecioKeyPairInner::Ed25519 { ref key_pair } => {
-                            let signature = key_pair.sign::<Sha512>(&data_to_sign);
-                            signature.to_bytes().to_vec()
-                        },
-                        #[cfg(feature = "secp256k1")]
-                        SecioKeyPairInner::Secp256k1 { ref private } => {
-                            let data_to_sign = Sha256::digest(&data_to_sign);
-                            let message = secp256k1::Message::from_slice(data_to_sign.as_ref())
-                                .expect("digest output length doesn't match secp256k1 input length");
-                            let secp256k1 = secp256k1::Secp256k1::signing_only();
-                            secp256k1
-                                .sign(&message, private)
-                                .serialize_der(&secp256k1)
-                        },
-                    }
-                });
+                exchange.set_signature(context.config.key.sign(&data_to_sign)?);
                 exchange
             };
             let local_exch = exchange.write_to_bytes()?;
@@ -451,73 +404,7 @@ where
             data_to_verify.extend_from_slice(&context.state.remote.local.proposition_bytes);
             data_to_verify.extend_from_slice(remote_exch.get_epubkey());
 
-            match context.state.remote.public_key {
-                #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-                PublicKey::Rsa(ref remote_public_key) => {
-                    // TODO: The ring library doesn't like some stuff in our DER public key,
-                    //       therefore we scrap the first 24 bytes of the key. A proper fix would
-                    //       be to write a DER parser, but that's not trivial.
-                    match ring_verify(&RSA_PKCS1_2048_8192_SHA256,
-                                      UntrustedInput::from(&remote_public_key[24..]),
-                                      UntrustedInput::from(&data_to_verify),
-                                      UntrustedInput::from(remote_exch.get_signature()))
-                    {
-                        Ok(()) => (),
-                        Err(_) => {
-                            debug!("failed to verify the remote's signature");
-                            return Err(SecioError::SignatureVerificationFailed)
-                        },
-                    }
-                },
-                PublicKey::Ed25519(ref remote_public_key) => {
-                    let signature = Ed25519Signature::from_bytes(remote_exch.get_signature());
-                    let pubkey = Ed25519PublicKey::from_bytes(remote_public_key);
-
-                    if let (Ok(signature), Ok(pubkey)) = (signature, pubkey) {
-                        match pubkey.verify::<Sha512>(&data_to_verify, &signature) {
-                            Ok(()) => (),
-                            Err(_) => {
-                                debug!("failed to verify the remote's signature");
-                                return Err(SecioError::SignatureVerificationFailed)
-                            }
-                        }
-                    } else {
-                        debug!("the remote's signature or publickey are in the wrong format");
-                        return Err(SecioError::SignatureVerificationFailed)
-                    }
-                },
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(ref remote_public_key) => {
-                    let data_to_verify = Sha256::digest(&data_to_verify);
-                    let message = secp256k1::Message::from_slice(data_to_verify.as_ref())
-                        .expect("digest output length doesn't match secp256k1 input length");
-                    let secp256k1 = secp256k1::Secp256k1::verification_only();
-                    let signature = secp256k1::Signature::from_der(&secp256k1, remote_exch.get_signature());
-                    let remote_public_key = secp256k1::key::PublicKey::from_slice(&secp256k1, remote_public_key);
-                    if let (Ok(signature), Ok(remote_public_key)) = (signature, remote_public_key) {
-                        match secp256k1.verify(&message, &signature, &remote_public_key) {
-                            Ok(()) => (),
-                            Err(_) => {
-                                debug!("failed to verify the remote's signature");
-                                return Err(SecioError::SignatureVerificationFailed)
-                            },
-                        }
-                    } else {
-                        debug!("remote's secp256k1 signature has wrong format");
-                        return Err(SecioError::SignatureVerificationFailed)
-                    }
-                },
-                #[cfg(not(all(feature = "ring", not(target_os = "emscripten"))))]
-                PublicKey::Rsa(_) => {
-                    debug!("support for RSA was disabled at compile-time");
-                    return Err(SecioError::SignatureVerificationFailed);
-                },
-                #[cfg(not(feature = "secp256k1"))]
-                PublicKey::Secp256k1(_) => {
-                    debug!("support for secp256k1 was disabled at compile-time");
-                    return Err(SecioError::SignatureVerificationFailed);
-                }
-            };
+            verify_signature(&context.state.remote.public_key, &data_to_verify, remote_exch.get_signature())?;
 
             trace!("successfully verified the remote's signature");
             Ok((remote_exch, socket, context))
@@ -640,7 +527,7 @@ mod tests {
     use crate::{SecioConfig, SecioKeyPair};
 
     #[test]
-    #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
+    #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
     fn handshake_with_self_succeeds_rsa() {
         let key1 = {
             let private = include_bytes!("../tests/test-rsa-private-key.pk8");
@@ -86,21 +86,18 @@ pub use self::error::SecioError;
 #[cfg(feature = "secp256k1")]
 use asn1_der::{traits::FromDerEncoded, traits::FromDerObject, DerObject};
 use bytes::BytesMut;
-use ed25519_dalek::Keypair as Ed25519KeyPair;
 use futures::stream::MapErr as StreamMapErr;
 use futures::{Future, Poll, Sink, StartSend, Stream};
-use libp2p_core::{PeerId, PublicKey, upgrade::{UpgradeInfo, InboundUpgrade, OutboundUpgrade}};
+#[cfg(feature = "secp256k1")]
+use libp2p_core::identity::secp256k1;
+use libp2p_core::{PeerId, PublicKey, upgrade::{AuthenticatedOutput, UpgradeInfo, InboundUpgrade, OutboundUpgrade}};
+use libp2p_core::identity::{ed25519, Keypair};
 use log::debug;
-#[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
-use ring::signature::RSAKeyPair;
 use rw_stream_sink::RwStreamSink;
 use std::error::Error;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::iter;
-use std::sync::Arc;
 use tokio_io::{AsyncRead, AsyncWrite};
-#[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
-use untrusted::Input;
 
 mod algo_support;
 mod codec;
@@ -182,6 +179,9 @@ impl SecioConfig {
 
 /// Private and public keys of the local node.
 ///
+/// This is a thin wrapper around `libp2p_core::identity::Keypair`, which can be used to sign
+/// data with the identity of the node outside of secio.
+///
 /// # Generating offline keys with OpenSSL
 ///
 /// ## RSA
@@ -202,14 +202,31 @@ impl SecioConfig {
 ///                                                include_bytes!("public.der"));
 /// ```
 ///
+/// ## Ed25519
+///
+/// Generating the key:
+///
+/// ```text
+/// openssl genpkey -algorithm ed25519 -outform DER -out private.pk8
+/// ```
+///
+/// Loading the key:
+///
+/// ```ignore
+/// let key_pair = SecioKeyPair::ed25519_from_pkcs8(include_bytes!("private.pk8"));
+/// ```
+///
 #[derive(Clone)]
 pub struct SecioKeyPair {
-    inner: SecioKeyPairInner,
+    inner: Keypair,
 }
 
 impl SecioKeyPair {
     /// Builds a `SecioKeyPair` from a PKCS8 private key and public key.
-    #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
+    ///
+    /// The public key must be the DER encoding of the `SubjectPublicKeyInfo` matching the private
+    /// key.
+    #[cfg(all(feature = "rsa", not(target_os = "emscripten")))]
     pub fn rsa_from_pkcs8<P>(
         private: &[u8],
         public: P,
@@ -217,40 +234,48 @@ impl SecioKeyPair {
     where
         P: Into<Vec<u8>>,
     {
-        let private = RSAKeyPair::from_pkcs8(Input::from(&private[..])).map_err(Box::new)?;
+        let inner = Keypair::rsa_from_pkcs8(private)?;
+        if inner.public() != PublicKey::Rsa(public.into()) {
+            return Err("the RSA public key doesn't match the private key".into());
+        }
 
-        Ok(SecioKeyPair {
-            inner: SecioKeyPairInner::Rsa {
-                public: public.into(),
-                private: Arc::new(private),
-            },
-        })
+        Ok(SecioKeyPair { inner })
     }
 
     /// Generates a new Ed25519 key pair and uses it.
     pub fn ed25519_generated() -> Result<SecioKeyPair, Box<Error + Send + Sync>> {
-        let mut csprng = rand::rngs::OsRng::new()?;
-        let keypair: Ed25519KeyPair = Ed25519KeyPair::generate::<sha2::Sha512, _>(&mut csprng);
-        Ok(SecioKeyPair {
-            inner: SecioKeyPairInner::Ed25519 {
-                key_pair: Arc::new(keypair),
-            }
-        })
+        Ok(SecioKeyPair { inner: Keypair::generate_ed25519() })
+    }
+
+    /// Builds a `SecioKeyPair` from a raw ed25519 private key.
+    ///
+    /// The key is either the 32 bytes secret defined in RFC 8032, or these 32 bytes followed by
+    /// the 32 bytes of the public key, which is the format used by the other implementations of
+    /// libp2p. In the latter case, the public key must match the secret.
+    pub fn ed25519_raw_key<K>(key: K) -> Result<SecioKeyPair, Box<Error + Send + Sync>>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_pair = ed25519::Keypair::decode(key.as_ref())?;
+        Ok(SecioKeyPair { inner: Keypair::Ed25519(key_pair) })
+    }
+
+    /// Builds a `SecioKeyPair` from an ed25519 private key in the PKCS#8 format of RFC 8410.
+    ///
+    /// Both the version 1 of the format, produced by OpenSSL, and the version 2, which also
+    /// contains the public key and is produced by ring, are supported.
+    pub fn ed25519_from_pkcs8<K>(key: K) -> Result<SecioKeyPair, Box<Error + Send + Sync>>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_pair = ed25519::Keypair::from_pkcs8(key.as_ref())?;
+        Ok(SecioKeyPair { inner: Keypair::Ed25519(key_pair) })
     }
 
     /// Generates a new random sec256k1 key pair.
     #[cfg(feature = "secp256k1")]
     pub fn secp256k1_generated() -> Result<SecioKeyPair, Box<Error + Send + Sync>> {
-        let secp = secp256k1::Secp256k1::new();
-        // TODO: This will work once 0.11.5 is released. See https://github.com/rust-bitcoin/rust-secp256k1/pull/80#pullrequestreview-172681778
-        // let private = secp256k1::key::SecretKey::new(&secp, &mut secp256k1::rand::thread_rng());
-        use rand::Rng;
-        let mut random_slice= [0u8; secp256k1::constants::SECRET_KEY_SIZE];
-        rand::thread_rng().fill(&mut random_slice[..]);
-        let private = secp256k1::key::SecretKey::from_slice(&secp, &random_slice).expect("slice has the right size");
-        Ok(SecioKeyPair {
-            inner: SecioKeyPairInner::Secp256k1 { private },
-        })
+        Ok(SecioKeyPair { inner: Keypair::generate_secp256k1() })
     }
 
     /// Builds a `SecioKeyPair` from a raw secp256k1 32 bytes private key.
@@ -259,12 +284,8 @@ impl SecioKeyPair {
     where
         K: AsRef<[u8]>,
     {
-        let secp = secp256k1::Secp256k1::without_caps();
-        let private = secp256k1::key::SecretKey::from_slice(&secp, key.as_ref())?;
-
-        Ok(SecioKeyPair {
-            inner: SecioKeyPairInner::Secp256k1 { private },
-        })
+        let key_pair = secp256k1::Keypair::decode(key.as_ref())?;
+        Ok(SecioKeyPair { inner: Keypair::Secp256k1(key_pair) })
     }
 
     /// Builds a `SecioKeyPair` from a secp256k1 private key in DER format.
@@ -284,21 +305,16 @@ impl SecioKeyPair {
         SecioKeyPair::secp256k1_raw_key(&private_key)
     }
 
+    /// Returns the identity keypair wrapped by this key pair.
+    #[inline]
+    pub fn identity(&self) -> &Keypair {
+        &self.inner
+    }
+
     /// Returns the public key corresponding to this key pair.
+    #[inline]
     pub fn to_public_key(&self) -> PublicKey {
-        match self.inner {
-            #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-            SecioKeyPairInner::Rsa { ref public, .. } => PublicKey::Rsa(public.clone()),
-            SecioKeyPairInner::Ed25519 { ref key_pair } => {
-                PublicKey::Ed25519(key_pair.public.as_bytes().to_vec())
-            }
-            #[cfg(feature = "secp256k1")]
-            SecioKeyPairInner::Secp256k1 { ref private } => {
-                let secp = secp256k1::Secp256k1::signing_only();
-                let pubkey = secp256k1::key::PublicKey::from_secret_key(&secp, private);
-                PublicKey::Secp256k1(pubkey.serialize().to_vec())
-            }
-        }
+        self.inner.public()
     }
 
     /// Builds a `PeerId` corresponding to the public key of this key pair.
@@ -307,24 +323,34 @@ impl SecioKeyPair {
         self.to_public_key().into_peer_id()
     }
 
-    // TODO: method to save generated key on disk?
+    /// Signs `data` with the private key of this key pair.
+    ///
+    /// The signature can be checked against the public key with `verify_signature`.
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SecioError> {
+        self.inner.sign(data).map_err(|err| {
+            debug!("failed to sign: {}", err);
+            SecioError::SigningFailure
+        })
+    }
 }
 
-// Inner content of `SecioKeyPair`.
-#[derive(Clone)]
-enum SecioKeyPairInner {
-    #[cfg(all(feature = "ring", not(target_os = "emscripten")))]
-    Rsa {
-        public: Vec<u8>,
-        // We use an `Arc` so that we can clone the enum.
-        private: Arc<RSAKeyPair>,
-    },
-    Ed25519 {
-        // We use an `Arc` so that we can clone the enum.
-        key_pair: Arc<Ed25519KeyPair>,
-    },
-    #[cfg(feature = "secp256k1")]
-    Secp256k1 { private: secp256k1::key::SecretKey },
+impl From<Keypair> for SecioKeyPair {
+    #[inline]
+    fn from(inner: Keypair) -> SecioKeyPair {
+        SecioKeyPair { inner }
+    }
+}
+
+/// Verifies that `signature` is a signature of `data` produced by the private key corresponding
+/// to `public_key`.
+///
+/// This is the counterpart of `SecioKeyPair::sign`.
+pub fn verify_signature(public_key: &PublicKey, data: &[u8], signature: &[u8]) -> Result<(), SecioError> {
+    if public_key.verify(data, signature) {
+        Ok(())
+    } else {
+        Err(SecioError::SignatureVerificationFailed)
+    }
 }
 
 /// Output of the secio protocol.
@@ -340,6 +366,18 @@ where
     pub ephemeral_public_key: Vec<u8>,
 }
 
+impl<S> AuthenticatedOutput for SecioOutput<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    type Stream = RwStreamSink<StreamMapErr<SecioMiddleware<S>, fn(SecioError) -> IoError>>;
+
+    #[inline]
+    fn into_authenticated(self) -> (PeerId, Self::Stream) {
+        (self.remote_key.into_peer_id(), self.stream)
+    }
+}
+
 impl UpgradeInfo for SecioConfig {
     type Info = &'static [u8];
     type InfoIter = iter::Once<Self::Info>;
@@ -442,3 +480,77 @@ where
         self.inner.poll()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{verify_signature, SecioKeyPair};
+    use libp2p_core::PublicKey;
+
+    // Test vector 1 of RFC 8032.
+    const SECRET: &[u8] = &[
+        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
+        0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
+        0x7f, 0x60,
+    ];
+    const PUBLIC: &[u8] = &[
+        0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64, 0x07,
+        0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68, 0xf7, 0x07,
+        0x51, 0x1a,
+    ];
+    const SIGNATURE: &[u8] = &[
+        0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e, 0x82,
+        0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65, 0x22, 0x49,
+        0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e, 0x39, 0x70, 0x1c,
+        0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24, 0x65, 0x51, 0x41, 0x43,
+        0x8e, 0x7a, 0x10, 0x0b,
+    ];
+
+    #[test]
+    fn ed25519_raw_key_matches_rfc8032() {
+        let key = SecioKeyPair::ed25519_raw_key(SECRET).unwrap();
+        assert_eq!(key.to_public_key(), PublicKey::Ed25519(PUBLIC.to_vec()));
+        assert_eq!(key.sign(b"").unwrap(), SIGNATURE);
+        assert!(verify_signature(&key.to_public_key(), b"", SIGNATURE).is_ok());
+        assert!(verify_signature(&key.to_public_key(), b"hello", SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn ed25519_raw_key_with_public_key() {
+        let mut raw = SECRET.to_vec();
+        raw.extend_from_slice(PUBLIC);
+        let key = SecioKeyPair::ed25519_raw_key(&raw).unwrap();
+        assert_eq!(key.to_public_key(), PublicKey::Ed25519(PUBLIC.to_vec()));
+
+        raw[40] ^= 1;
+        assert!(SecioKeyPair::ed25519_raw_key(&raw).is_err());
+        assert!(SecioKeyPair::ed25519_raw_key(&SECRET[1..]).is_err());
+    }
+
+    #[test]
+    fn ed25519_from_pkcs8() {
+        let mut v1 = vec![
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        v1.extend_from_slice(SECRET);
+        let key = SecioKeyPair::ed25519_from_pkcs8(&v1).unwrap();
+        assert_eq!(key.to_public_key(), PublicKey::Ed25519(PUBLIC.to_vec()));
+
+        let mut v2 = vec![
+            0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        v2.extend_from_slice(SECRET);
+        v2.extend_from_slice(&[0xa1, 0x23, 0x03, 0x21, 0x00]);
+        v2.extend_from_slice(PUBLIC);
+        let key = SecioKeyPair::ed25519_from_pkcs8(&v2).unwrap();
+        assert_eq!(key.to_public_key(), PublicKey::Ed25519(PUBLIC.to_vec()));
+        assert_eq!(
+            key.to_peer_id().to_base58(),
+            "12D3KooWQK1wnefoLrcVHbbnf5tLzbopUd3K3bFAoJpA7YJgL5pV"
+        );
+
+        v1[9] ^= 1;
+        assert!(SecioKeyPair::ed25519_from_pkcs8(&v1).is_err());
+    }
+}
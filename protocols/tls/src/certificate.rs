@@ -0,0 +1,269 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Generation and verification of the certificates used to authenticate TLS connections.
+//!
+//! Each node generates a self-signed certificate whose key is a freshly generated ECDSA P-256
+//! key. The certificate carries the libp2p public key extension, which contains the public key
+//! of the node and a signature, made with the node's key, of the public key of the certificate.
+//! Verifying this signature proves that the owner of the certificate is the owner of the node's
+//! key, without requiring any certificate authority.
+
+use libp2p_core::PublicKey;
+use libp2p_secio::SecioKeyPair;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use rustls::TLSError;
+use std::error::Error;
+use yasna::models::ObjectIdentifier;
+use yasna::tags::{TAG_BITSTRING, TAG_GENERALIZEDTIME, TAG_UTCTIME};
+use yasna::{ASN1Error, ASN1ErrorKind, ASN1Result, DERWriter, Tag};
+
+/// OID of the libp2p public key extension.
+const LIBP2P_EXTENSION_OID: &[u64] = &[1, 3, 6, 1, 4, 1, 53594, 1, 1];
+/// Prefix of the message signed by the node's key.
+const LIBP2P_SIGNING_PREFIX: &[u8] = b"libp2p-tls-handshake:";
+
+const EC_PUBLIC_KEY_OID: &[u64] = &[1, 2, 840, 10045, 2, 1];
+const PRIME256V1_OID: &[u64] = &[1, 2, 840, 10045, 3, 1, 7];
+const ECDSA_WITH_SHA256_OID: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+const COMMON_NAME_OID: &[u64] = &[2, 5, 4, 3];
+
+/// Generates a self-signed certificate for the node whose key is `keypair`.
+///
+/// Returns the certificate and its private key, both DER-encoded.
+pub fn make_certificate(keypair: &SecioKeyPair)
+    -> Result<(rustls::Certificate, rustls::PrivateKey), Box<dyn Error + Send + Sync>>
+{
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
+        .map_err(|_| "failed to generate the certificate key")?;
+    let certificate_key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref())
+        .map_err(|_| "failed to parse the certificate key")?;
+
+    let spki = yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_sequence(|writer| {
+                writer.next().write_oid(&ObjectIdentifier::from_slice(EC_PUBLIC_KEY_OID));
+                writer.next().write_oid(&ObjectIdentifier::from_slice(PRIME256V1_OID));
+            });
+            write_bit_string(writer.next(), certificate_key.public_key().as_ref());
+        })
+    });
+
+    let extension = {
+        let mut message = LIBP2P_SIGNING_PREFIX.to_vec();
+        message.extend_from_slice(&spki);
+        let signature = keypair.sign(&message)?;
+        yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_bytes(&keypair.to_public_key().into_protobuf_encoding());
+                writer.next().write_bytes(&signature);
+            })
+        })
+    };
+
+    // Serial numbers must be positive.
+    let serial_number = (rand::random::<u64>() >> 1) | 1;
+
+    let tbs_certificate = yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            // Version 3.
+            writer.next().write_tagged(Tag::context(0), |writer| writer.write_u8(2));
+            writer.next().write_u64(serial_number);
+            write_algorithm(writer.next(), ECDSA_WITH_SHA256_OID);
+            write_name(writer.next());
+            // The certificate is valid forever, as its authenticity doesn't depend on its age.
+            writer.next().write_sequence(|writer| {
+                writer.next().write_tagged_implicit(TAG_UTCTIME, |writer| {
+                    writer.write_bytes(b"750101000000Z")
+                });
+                writer.next().write_tagged_implicit(TAG_GENERALIZEDTIME, |writer| {
+                    writer.write_bytes(b"40960101000000Z")
+                });
+            });
+            write_name(writer.next());
+            writer.next().write_der(&spki);
+            writer.next().write_tagged(Tag::context(3), |writer| {
+                writer.write_sequence(|writer| {
+                    writer.next().write_sequence(|writer| {
+                        writer.next().write_oid(&ObjectIdentifier::from_slice(LIBP2P_EXTENSION_OID));
+                        writer.next().write_bytes(&extension);
+                    })
+                })
+            });
+        })
+    });
+
+    let signature = certificate_key.sign(&rng, &tbs_certificate)
+        .map_err(|_| "failed to sign the certificate")?;
+
+    let certificate = yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_der(&tbs_certificate);
+            write_algorithm(writer.next(), ECDSA_WITH_SHA256_OID);
+            write_bit_string(writer.next(), signature.as_ref());
+        })
+    });
+
+    Ok((rustls::Certificate(certificate), rustls::PrivateKey(pkcs8.as_ref().to_vec())))
+}
+
+/// Extracts the public key of the node from a certificate, and verifies that the certificate's
+/// key has been signed by it.
+///
+/// This doesn't verify the signature of the certificate itself.
+pub fn extract_public_key(certificate: &[u8]) -> Result<PublicKey, TLSError> {
+    let (spki, extension) = parse_certificate(certificate)
+        .map_err(|_| TLSError::General("malformed certificate".to_owned()))?;
+    let extension = extension.ok_or_else(|| {
+        TLSError::General("certificate doesn't contain the libp2p public key extension".to_owned())
+    })?;
+
+    let (public_key, signature) = yasna::parse_der(&extension, |reader| {
+        reader.read_sequence(|reader| {
+            let public_key = reader.next().read_bytes()?;
+            let signature = reader.next().read_bytes()?;
+            Ok((public_key, signature))
+        })
+    }).map_err(|_| TLSError::General("malformed libp2p public key extension".to_owned()))?;
+
+    let public_key = PublicKey::from_protobuf_encoding(&public_key)
+        .map_err(|_| TLSError::General("invalid public key in certificate".to_owned()))?;
+
+    let mut message = LIBP2P_SIGNING_PREFIX.to_vec();
+    message.extend_from_slice(&spki);
+    libp2p_secio::verify_signature(&public_key, &message, &signature)
+        .map_err(|_| TLSError::General("invalid signature of the certificate key".to_owned()))?;
+
+    Ok(public_key)
+}
+
+/// Parses a DER-encoded certificate. Returns the DER encoding of its `SubjectPublicKeyInfo`,
+/// and the value of the libp2p public key extension if any.
+fn parse_certificate(certificate: &[u8]) -> ASN1Result<(Vec<u8>, Option<Vec<u8>>)> {
+    yasna::parse_der(certificate, |reader| {
+        reader.read_sequence(|reader| {
+            let parsed = reader.next().read_sequence(|reader| {
+                let version = reader.next().read_tagged(Tag::context(0), |reader| reader.read_u8())?;
+                if version != 2 {
+                    return Err(ASN1Error::new(ASN1ErrorKind::Invalid));
+                }
+                // Serial number, signature algorithm, issuer, validity and subject.
+                for _ in 0..5 {
+                    reader.next().read_der()?;
+                }
+                let spki = reader.next().read_der()?;
+
+                let mut extension = None;
+                reader.read_optional(|reader| {
+                    reader.read_tagged(Tag::context(3), |reader| {
+                        reader.read_sequence_of(|reader| {
+                            reader.read_sequence(|reader| {
+                                let oid = reader.next().read_oid()?;
+                                let _critical = reader.read_default(false, |reader| reader.read_bool())?;
+                                let value = reader.next().read_bytes()?;
+                                if oid == ObjectIdentifier::from_slice(LIBP2P_EXTENSION_OID) {
+                                    if extension.is_some() {
+                                        return Err(ASN1Error::new(ASN1ErrorKind::Invalid));
+                                    }
+                                    extension = Some(value);
+                                }
+                                Ok(())
+                            })
+                        })
+                    })
+                })?;
+
+                Ok((spki, extension))
+            })?;
+
+            // Signature algorithm and signature.
+            reader.next().read_der()?;
+            reader.next().read_der()?;
+            Ok(parsed)
+        })
+    })
+}
+
+/// Writes an `AlgorithmIdentifier` without parameters.
+fn write_algorithm(writer: DERWriter, oid: &[u64]) {
+    writer.write_sequence(|writer| {
+        writer.next().write_oid(&ObjectIdentifier::from_slice(oid));
+    })
+}
+
+/// Writes the `Name` used as both the issuer and the subject of our certificates.
+fn write_name(writer: DERWriter) {
+    writer.write_sequence(|writer| {
+        writer.next().write_set(|writer| {
+            writer.next().write_sequence(|writer| {
+                writer.next().write_oid(&ObjectIdentifier::from_slice(COMMON_NAME_OID));
+                writer.next().write_utf8_string("libp2p");
+            })
+        })
+    })
+}
+
+/// Writes a `BIT STRING` whose length is a multiple of eight.
+fn write_bit_string(writer: DERWriter, bytes: &[u8]) {
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    // Number of unused bits in the last byte.
+    content.push(0);
+    content.extend_from_slice(bytes);
+    writer.write_tagged_implicit(TAG_BITSTRING, |writer| writer.write_bytes(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_public_key, make_certificate};
+    use libp2p_secio::SecioKeyPair;
+
+    #[test]
+    fn certificate_contains_public_key() {
+        let keypair = SecioKeyPair::ed25519_generated().unwrap();
+        let (certificate, _) = make_certificate(&keypair).unwrap();
+        let public_key = extract_public_key(&certificate.0).unwrap();
+        assert_eq!(public_key, keypair.to_public_key());
+    }
+
+    #[test]
+    fn certificate_of_other_key_is_rejected() {
+        let keypair = SecioKeyPair::ed25519_generated().unwrap();
+        let other = SecioKeyPair::ed25519_generated().unwrap();
+        let (certificate, _) = make_certificate(&keypair).unwrap();
+
+        // Replace the public key in the extension with another one of the same length.
+        let public_key = keypair.to_public_key().into_protobuf_encoding();
+        let other_public_key = other.to_public_key().into_protobuf_encoding();
+        let mut der = certificate.0.clone();
+        let pos = der.windows(public_key.len())
+            .position(|window| window == &public_key[..])
+            .unwrap();
+        der[pos..pos + public_key.len()].copy_from_slice(&other_public_key);
+
+        assert!(extract_public_key(&der).is_err());
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(extract_public_key(b"definitely not a certificate").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of TLS 1.3 as a libp2p connection upgrade.
+//!
+//! TLS is an alternative to secio that can be audited with standard tooling. Each node generates
+//! a self-signed certificate that carries the libp2p public key extension, which binds the
+//! certificate to the node's identity key. Both parties present their certificate, and the
+//! handshake fails if the certificate of the remote isn't correctly signed by the key it embeds.
+//!
+//! # Usage
+//!
+//! The `TlsConfig` struct implements the `InboundUpgrade` and `OutboundUpgrade` traits. Its
+//! output is a tuple of the `PeerId` of the remote and the encrypted stream, which means that it
+//! can be used in place of `SecioConfig`.
+//!
+//! ```no_run
+//! # fn main() {
+//! use futures::Future;
+//! use libp2p_core::{Multiaddr, Transport};
+//! use libp2p_secio::SecioKeyPair;
+//! use libp2p_tcp::TcpConfig;
+//! use libp2p_tls::TlsConfig;
+//! use tokio_io::io::write_all;
+//!
+//! let keypair = SecioKeyPair::ed25519_generated().unwrap();
+//! let dialer = TcpConfig::new()
+//!     .with_upgrade(TlsConfig::new(&keypair).unwrap())
+//!     .map(|(_peer_id, stream), _| stream);
+//!
+//! let future = dialer.dial("/ip4/127.0.0.1/tcp/12345".parse::<Multiaddr>().unwrap())
+//!     .unwrap_or_else(|_| panic!("Unable to dial node"))
+//!     .and_then(|connection| write_all(connection, "hello world"))
+//!     .map_err(|e| panic!("error: {:?}", e));
+//!
+//! tokio::run(future.map(|_| ()));
+//! # }
+//! ```
+
+mod certificate;
+mod verifier;
+
+pub use crate::certificate::extract_public_key;
+
+use futures::prelude::*;
+use libp2p_core::{PeerId, upgrade::{UpgradeInfo, InboundUpgrade, OutboundUpgrade}};
+use libp2p_secio::SecioKeyPair;
+use log::debug;
+use rustls::Session;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Error as IoError, ErrorKind as IoErrorKind, Read, Write};
+use std::iter;
+use std::sync::Arc;
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Name of the server passed to the TLS client. The name isn't verified, as we authenticate the
+/// remote through the libp2p extension of its certificate.
+const SERVER_NAME: &str = "libp2p";
+
+/// Implementation of the `InboundUpgrade` and `OutboundUpgrade` traits of `libp2p_core`.
+/// Automatically applies TLS on any connection.
+#[derive(Clone)]
+pub struct TlsConfig {
+    /// TLS configuration used when we are the server of a connection.
+    server_config: Arc<rustls::ServerConfig>,
+    /// TLS configuration used when we are the client of a connection.
+    client_config: Arc<rustls::ClientConfig>,
+}
+
+impl TlsConfig {
+    /// Creates a new configuration object for TLS. The connections are authenticated with
+    /// `keypair`.
+    pub fn new(keypair: &SecioKeyPair) -> Result<TlsConfig, Box<dyn Error + Send + Sync>> {
+        let (client_config, server_config) = make_configs(keypair)?;
+        Ok(TlsConfig {
+            server_config: Arc::new(server_config),
+            client_config: Arc::new(client_config),
+        })
+    }
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsConfig").finish()
+    }
+}
+
+/// Builds the rustls client and server configurations that authenticate the local node with
+/// `keypair`, and that only accept remotes presenting a valid libp2p certificate.
+///
+/// Only TLS 1.3 is enabled. This is meant for transports, such as QUIC, that handle TLS
+/// themselves and only need the configuration.
+pub fn make_configs(keypair: &SecioKeyPair)
+    -> Result<(rustls::ClientConfig, rustls::ServerConfig), Box<dyn Error + Send + Sync>>
+{
+    let (certificate, key) = certificate::make_certificate(keypair)?;
+    let verifier = Arc::new(verifier::Libp2pCertificateVerifier);
+
+    let mut server_config = rustls::ServerConfig::new(verifier.clone());
+    server_config.versions = vec![rustls::ProtocolVersion::TLSv1_3];
+    server_config.set_single_cert(vec![certificate.clone()], key.clone())?;
+
+    let mut client_config = rustls::ClientConfig::new();
+    client_config.versions = vec![rustls::ProtocolVersion::TLSv1_3];
+    client_config.dangerous().set_certificate_verifier(verifier);
+    client_config.set_single_client_cert(vec![certificate], key);
+
+    Ok((client_config, server_config))
+}
+
+impl UpgradeInfo for TlsConfig {
+    type Info = &'static [u8];
+    type InfoIter = iter::Once<Self::Info>;
+
+    fn protocol_info(&self) -> Self::InfoIter {
+        iter::once(b"/tls/1.0.0")
+    }
+}
+
+impl<T> InboundUpgrade<T> for TlsConfig
+where
+    T: AsyncRead + AsyncWrite + Send + 'static
+{
+    type Output = (PeerId, TlsStream<T>);
+    type Error = IoError;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_inbound(self, socket: T, _: Self::Info) -> Self::Future {
+        let future = TlsAcceptor::from(self.server_config)
+            .accept(socket)
+            .and_then(|stream| {
+                let peer_id = remote_peer_id(stream.get_ref().1)?;
+                debug!("TLS handshake with {:?} finished", peer_id);
+                Ok((peer_id, TlsStream::Server(stream)))
+            });
+        Box::new(future)
+    }
+}
+
+impl<T> OutboundUpgrade<T> for TlsConfig
+where
+    T: AsyncRead + AsyncWrite + Send + 'static
+{
+    type Output = (PeerId, TlsStream<T>);
+    type Error = IoError;
+    type Future = Box<dyn Future<Item = Self::Output, Error = Self::Error> + Send>;
+
+    fn upgrade_outbound(self, socket: T, _: Self::Info) -> Self::Future {
+        let server_name = webpki::DNSNameRef::try_from_ascii_str(SERVER_NAME)
+            .expect("SERVER_NAME is a valid DNS name");
+        let future = TlsConnector::from(self.client_config)
+            .connect(server_name, socket)
+            .and_then(|stream| {
+                let peer_id = remote_peer_id(stream.get_ref().1)?;
+                debug!("TLS handshake with {:?} finished", peer_id);
+                Ok((peer_id, TlsStream::Client(stream)))
+            });
+        Box::new(future)
+    }
+}
+
+/// Returns the `PeerId` of the remote, extracted from the certificate it presented.
+///
+/// The certificate has already been verified during the handshake.
+fn remote_peer_id<S: Session>(session: &S) -> Result<PeerId, IoError> {
+    let certificates = session.get_peer_certificates()
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "remote didn't present a certificate"))?;
+    let certificate = certificates.first()
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "remote didn't present a certificate"))?;
+    let public_key = extract_public_key(&certificate.0)
+        .map_err(|err| IoError::new(IoErrorKind::InvalidData, err))?;
+    Ok(public_key.into_peer_id())
+}
+
+/// Stream encrypted with TLS.
+pub enum TlsStream<T> {
+    /// We are the client of the TLS session.
+    Client(tokio_rustls::client::TlsStream<T>),
+    /// We are the server of the TLS session.
+    Server(tokio_rustls::server::TlsStream<T>),
+}
+
+impl<T> Read for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            TlsStream::Client(stream) => stream.read(buf),
+            TlsStream::Server(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl<T> AsyncRead for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite
+{
+}
+
+impl<T> Write for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TlsStream::Client(stream) => stream.write(buf),
+            TlsStream::Server(stream) => stream.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TlsStream::Client(stream) => stream.flush(),
+            TlsStream::Server(stream) => stream.flush(),
+        }
+    }
+}
+
+impl<T> AsyncWrite for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite
+{
+    #[inline]
+    fn shutdown(&mut self) -> Poll<(), IoError> {
+        match self {
+            TlsStream::Client(stream) => stream.shutdown(),
+            TlsStream::Server(stream) => stream.shutdown(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for TlsStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TlsStream::Client(_) => f.debug_tuple("TlsStream::Client").finish(),
+            TlsStream::Server(_) => f.debug_tuple("TlsStream::Server").finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TlsConfig;
+    use futures::prelude::*;
+    use libp2p_core::upgrade::{InboundUpgrade, OutboundUpgrade};
+    use libp2p_secio::SecioKeyPair;
+    use tokio::runtime::current_thread::Runtime;
+    use tokio_tcp::{TcpListener, TcpStream};
+
+    #[test]
+    fn handshake_with_self_succeeds() {
+        let listener_key = SecioKeyPair::ed25519_generated().unwrap();
+        let listener_id = listener_key.to_peer_id();
+        let dialer_key = SecioKeyPair::ed25519_generated().unwrap();
+        let dialer_id = dialer_key.to_peer_id();
+
+        let listener_config = TlsConfig::new(&listener_key).unwrap();
+        let dialer_config = TlsConfig::new(&dialer_key).unwrap();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener_addr = listener.local_addr().unwrap();
+
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(e, _)| e)
+            .and_then(move |(connec, _)| listener_config.upgrade_inbound(connec.unwrap(), &b"/tls/1.0.0"[..]))
+            .and_then(|(peer_id, stream)| {
+                tokio_io::io::read_exact(stream, [0; 5])
+                    .and_then(|(stream, buf)| tokio_io::io::write_all(stream, buf))
+                    .and_then(|(stream, _)| tokio_io::io::flush(stream))
+                    .map(move |_| peer_id)
+            });
+
+        let client = TcpStream::connect(&listener_addr)
+            .and_then(move |stream| dialer_config.upgrade_outbound(stream, &b"/tls/1.0.0"[..]))
+            .and_then(|(peer_id, stream)| {
+                tokio_io::io::write_all(stream, b"hello")
+                    .and_then(|(stream, _)| tokio_io::io::flush(stream))
+                    .and_then(|stream| tokio_io::io::read_exact(stream, [0; 5]))
+                    .map(move |(_, buf)| (peer_id, buf))
+            });
+
+        let mut rt = Runtime::new().unwrap();
+        let (seen_by_listener, (seen_by_dialer, echo)) = rt.block_on(server.join(client)).unwrap();
+        assert_eq!(seen_by_listener, dialer_id);
+        assert_eq!(seen_by_dialer, listener_id);
+        assert_eq!(&echo, b"hello");
+    }
+}
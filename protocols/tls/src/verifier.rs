@@ -0,0 +1,126 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Verification of the certificates presented by remotes.
+//!
+//! The certificates are self-signed, so the usual verification against a set of certificate
+//! authorities doesn't apply. Instead, we check that the certificate is correctly self-signed
+//! and that it contains a valid libp2p public key extension.
+
+use crate::certificate;
+use rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, DistinguishedNames, RootCertStore,
+    ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use std::time::SystemTime;
+
+/// Signature algorithms accepted for the self-signature of the certificates.
+static SIGNATURE_ALGORITHMS: &[&webpki::SignatureAlgorithm] = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::ED25519,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+];
+
+/// Implementation of the rustls certificate verification traits for libp2p certificates.
+///
+/// Both sides of a connection are required to present a certificate.
+pub struct Libp2pCertificateVerifier;
+
+impl ServerCertVerifier for Libp2pCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        let certificate = single_certificate(presented_certs)?;
+        let (end_entity, trust_anchor) = parse_self_signed(certificate)?;
+        end_entity
+            .verify_is_valid_tls_server_cert(
+                SIGNATURE_ALGORITHMS,
+                &webpki::TLSServerTrustAnchors(&[trust_anchor]),
+                &[],
+                now()?,
+            )
+            .map_err(TLSError::WebPKIError)?;
+        certificate::extract_public_key(&certificate.0)?;
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+impl ClientCertVerifier for Libp2pCertificateVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        true
+    }
+
+    fn client_auth_root_subjects(&self) -> DistinguishedNames {
+        DistinguishedNames::new()
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[Certificate],
+    ) -> Result<ClientCertVerified, TLSError> {
+        let certificate = single_certificate(presented_certs)?;
+        let (end_entity, trust_anchor) = parse_self_signed(certificate)?;
+        end_entity
+            .verify_is_valid_tls_client_cert(
+                SIGNATURE_ALGORITHMS,
+                &webpki::TLSClientTrustAnchors(&[trust_anchor]),
+                &[],
+                now()?,
+            )
+            .map_err(TLSError::WebPKIError)?;
+        certificate::extract_public_key(&certificate.0)?;
+        Ok(ClientCertVerified::assertion())
+    }
+}
+
+/// Returns the only certificate of the chain. Our certificates are self-signed, so chains of
+/// more than one certificate are refused.
+fn single_certificate(presented_certs: &[Certificate]) -> Result<&Certificate, TLSError> {
+    match presented_certs {
+        [certificate] => Ok(certificate),
+        [] => Err(TLSError::NoCertificatesPresented),
+        _ => Err(TLSError::General("expected a single certificate".to_owned())),
+    }
+}
+
+/// Parses the certificate, and builds a trust anchor out of it so that its self-signature can
+/// be verified.
+fn parse_self_signed(
+    certificate: &Certificate,
+) -> Result<(webpki::EndEntityCert, webpki::TrustAnchor), TLSError> {
+    let end_entity =
+        webpki::EndEntityCert::from(&certificate.0).map_err(TLSError::WebPKIError)?;
+    let trust_anchor = webpki::trust_anchor_util::cert_der_as_trust_anchor(&certificate.0)
+        .map_err(TLSError::WebPKIError)?;
+    Ok((end_entity, trust_anchor))
+}
+
+fn now() -> Result<webpki::Time, TLSError> {
+    webpki::Time::try_from(SystemTime::now()).map_err(|_| TLSError::FailedToGetCurrentTime)
+}
@@ -137,23 +137,34 @@ pub extern crate tokio_io;
 pub extern crate tokio_codec;
 
 extern crate libp2p_core_derive;
-extern crate tokio_executor;
+#[cfg(test)]
+extern crate tokio;
 
+pub extern crate libp2p_bandwidth as bandwidth;
 pub extern crate libp2p_core as core;
 #[cfg(not(target_os = "emscripten"))]
 pub extern crate libp2p_dns as dns;
 pub extern crate libp2p_identify as identify;
 pub extern crate libp2p_kad as kad;
 pub extern crate libp2p_floodsub as floodsub;
+pub extern crate libp2p_gossipsub as gossipsub;
 pub extern crate libp2p_mplex as mplex;
 #[cfg(not(target_os = "emscripten"))]
 pub extern crate libp2p_mdns as mdns;
+pub extern crate libp2p_noise as noise;
 pub extern crate libp2p_ping as ping;
 pub extern crate libp2p_plaintext as plaintext;
+pub extern crate libp2p_pnet as pnet;
+#[cfg(not(target_os = "emscripten"))]
+pub extern crate libp2p_quic as quic;
 pub extern crate libp2p_ratelimit as ratelimit;
+pub extern crate libp2p_relay as relay;
+pub extern crate libp2p_request_response as request_response;
 pub extern crate libp2p_secio as secio;
 #[cfg(not(target_os = "emscripten"))]
 pub extern crate libp2p_tcp as tcp;
+#[cfg(not(target_os = "emscripten"))]
+pub extern crate libp2p_tls as tls;
 pub extern crate libp2p_uds as uds;
 #[cfg(feature = "libp2p-websocket")]
 pub extern crate libp2p_websocket as websocket;
@@ -161,6 +172,8 @@ pub extern crate libp2p_yamux as yamux;
 
 mod transport_ext;
 
+pub mod transport_builder;
+
 pub mod simple;
 
 pub use self::core::{
@@ -172,7 +185,6 @@ pub use self::multiaddr::Multiaddr;
 pub use self::simple::SimpleProtocol;
 pub use self::transport_ext::TransportExt;
 
-use futures::prelude::*;
 use std::time::Duration;
 
 /// Builds a `Transport` that supports the most commonly-used protocols that libp2p supports.
@@ -196,20 +208,9 @@ pub fn build_tcp_ws_secio_mplex_yamux(local_private_key: secio::SecioKeyPair)
     -> impl Transport<Output = (PeerId, impl core::muxing::StreamMuxer<OutboundSubstream = impl Send, Substream = impl Send> + Send + Sync), Listener = impl Send, Dial = impl Send, ListenerUpgrade = impl Send> + Clone
 {
     CommonTransport::new()
-        .with_upgrade(secio::SecioConfig::new(local_private_key))
-        .and_then(move |out, endpoint| {
-            let peer_id = out.remote_key.into_peer_id();
-            let peer_id2 = peer_id.clone();
-            let upgrade = core::upgrade::SelectUpgrade::new(yamux::Config::default(), mplex::MplexConfig::new())
-                // TODO: use a single `.map` instead of two maps
-                .map_inbound(move |muxer| (peer_id, muxer))
-                .map_outbound(move |muxer| (peer_id2, muxer));
-
-            core::upgrade::apply(out.stream, upgrade, endpoint)
-                .map(|(id, muxer)| (id, core::muxing::StreamMuxerBox::new(muxer)))
-                .map_err(|e| e.into_io_error())
-        })
-        .with_timeout(Duration::from_secs(20))
+        .authenticate(secio::SecioConfig::new(local_private_key))
+        .multiplex(core::upgrade::SelectUpgrade::new(yamux::Config::default(), mplex::MplexConfig::new()))
+        .timeout(Duration::from_secs(20))
 }
 
 /// Implementation of `Transport` that supports the most common protocols.
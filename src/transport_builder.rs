@@ -0,0 +1,372 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Builder that assembles a transport suitable for the `Swarm`.
+//!
+//! Starting from a raw transport, `TransportExt::authenticate` applies an upgrade that identifies
+//! the remote, and `Authenticated::multiplex` then applies a stream multiplexing upgrade. The
+//! resulting transport produces a `(PeerId, StreamMuxerBox)`, which is what the `Swarm` expects.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(not(target_os = "emscripten"))] {
+//! use libp2p::{TransportExt, core::upgrade::SelectUpgrade, mplex, secio, tcp, yamux};
+//! use std::time::Duration;
+//!
+//! let key = secio::SecioKeyPair::ed25519_generated().unwrap();
+//! let transport = tcp::TcpConfig::new()
+//!     .authenticate(secio::SecioConfig::new(key))
+//!     .multiplex(SelectUpgrade::new(yamux::Config::default(), mplex::MplexConfig::new()))
+//!     .timeout(Duration::from_secs(20))
+//!     .boxed();
+//! # }
+//! ```
+
+use core::{
+    PeerId,
+    muxing::{StreamMuxer, StreamMuxerBox},
+    nodes::ConnectedPoint,
+    transport::{boxed::Boxed, map::Map, timeout::TransportTimeout, upgrade::Upgrade},
+    upgrade::{AuthenticatedOutput, InboundUpgrade, OutboundUpgrade, UpgradeInfo},
+};
+use futures::prelude::*;
+use std::{io, time::Duration};
+use tokio_io::{AsyncRead, AsyncWrite};
+use {Multiaddr, Transport};
+
+/// Transport whose output is the `PeerId` of the remote and a stream to communicate with it.
+///
+/// Created with `TransportExt::authenticate`.
+#[derive(Debug, Clone)]
+pub struct Authenticated<T> {
+    inner: T,
+}
+
+/// Function that turns the output of an authentication upgrade into a `(PeerId, stream)` tuple.
+pub type IntoAuthenticated<O> = fn(O, ConnectedPoint) -> (PeerId, <O as AuthenticatedOutput>::Stream);
+
+fn into_authenticated<O>(output: O, _: ConnectedPoint) -> (PeerId, O::Stream)
+where
+    O: AuthenticatedOutput
+{
+    output.into_authenticated()
+}
+
+/// Applies `upgrade` to the connections of `transport`. See `TransportExt::authenticate`.
+pub(crate) fn authenticate<T, U, O, E>(transport: T, upgrade: U)
+    -> Authenticated<Map<Upgrade<T, U>, IntoAuthenticated<O>>>
+where
+    T: Transport,
+    T::Output: AsyncRead + AsyncWrite,
+    U: InboundUpgrade<T::Output, Output = O, Error = E>,
+    U: OutboundUpgrade<T::Output, Output = O, Error = E>,
+    O: AuthenticatedOutput
+{
+    let map: IntoAuthenticated<O> = into_authenticated::<O>;
+    Authenticated {
+        inner: transport.with_upgrade(upgrade).map(map),
+    }
+}
+
+impl<T> Authenticated<T> {
+    /// Applies a stream multiplexing upgrade on top of the authenticated stream.
+    ///
+    /// The resulting muxer is boxed, so that the output of the transport is always
+    /// `(PeerId, StreamMuxerBox)` whatever the upgrade.
+    #[inline]
+    pub fn multiplex<S, U>(self, upgrade: U) -> Multiplexed<Multiplex<T, S, U>>
+    where
+        T: Transport<Output = (PeerId, S)>,
+        S: AsyncRead + AsyncWrite,
+    {
+        let map: IntoWithPeerId<S> = into_with_peer_id::<S>;
+        Multiplexed {
+            inner: Upgrade::new(self.inner.map(map), MultiplexUpgrade { inner: upgrade }),
+        }
+    }
+}
+
+impl<T> Transport for Authenticated<T>
+where
+    T: Transport
+{
+    type Output = T::Output;
+    type Listener = T::Listener;
+    type ListenerUpgrade = T::ListenerUpgrade;
+    type Dial = T::Dial;
+
+    #[inline]
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        self.inner.listen_on(addr)
+            .map_err(|(inner, addr)| (Authenticated { inner }, addr))
+    }
+
+    #[inline]
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        self.inner.dial(addr)
+            .map_err(|(inner, addr)| (Authenticated { inner }, addr))
+    }
+
+    #[inline]
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.nat_traversal(server, observed)
+    }
+}
+
+/// Transport whose output is the `PeerId` of the remote and a stream muxer, and that is
+/// therefore suitable for the `Swarm`.
+///
+/// Created with `Authenticated::multiplex`.
+#[derive(Debug, Clone)]
+pub struct Multiplexed<T> {
+    inner: T,
+}
+
+impl<T> Multiplexed<T>
+where
+    T: Transport<Output = (PeerId, StreamMuxerBox)>
+{
+    /// Adds a timeout to the connection, authentication and multiplexing steps.
+    #[inline]
+    pub fn timeout(self, timeout: Duration) -> Multiplexed<TransportTimeout<T>> {
+        Multiplexed {
+            inner: self.inner.with_timeout(timeout),
+        }
+    }
+
+    /// Turns the transport into a `Boxed`, erasing its type.
+    #[inline]
+    pub fn boxed(self) -> Boxed<(PeerId, StreamMuxerBox)>
+    where
+        T: Clone + Send + Sync + 'static,
+        T::Dial: Send + 'static,
+        T::Listener: Send + 'static,
+        T::ListenerUpgrade: Send + 'static,
+    {
+        self.inner.boxed()
+    }
+}
+
+impl<T> Transport for Multiplexed<T>
+where
+    T: Transport
+{
+    type Output = T::Output;
+    type Listener = T::Listener;
+    type ListenerUpgrade = T::ListenerUpgrade;
+    type Dial = T::Dial;
+
+    #[inline]
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        self.inner.listen_on(addr)
+            .map_err(|(inner, addr)| (Multiplexed { inner }, addr))
+    }
+
+    #[inline]
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        self.inner.dial(addr)
+            .map_err(|(inner, addr)| (Multiplexed { inner }, addr))
+    }
+
+    #[inline]
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.nat_traversal(server, observed)
+    }
+}
+
+/// Transport that applies a stream multiplexing upgrade to the output of an authenticated
+/// transport.
+///
+/// Created with `Authenticated::multiplex`.
+pub type Multiplex<T, S, U> = Upgrade<Map<T, IntoWithPeerId<S>>, MultiplexUpgrade<U>>;
+
+/// Function that moves the `PeerId` of an authenticated connection into the stream, so that
+/// the multiplexing upgrade can be applied by the `Upgrade` transport.
+pub type IntoWithPeerId<S> = fn((PeerId, S), ConnectedPoint) -> WithPeerId<S>;
+
+fn into_with_peer_id<S>((peer_id, stream): (PeerId, S), _: ConnectedPoint) -> WithPeerId<S> {
+    WithPeerId { peer_id, stream }
+}
+
+/// Stream of an authenticated connection, together with the `PeerId` of the remote.
+///
+/// Reading and writing are delegated to the inner stream.
+#[derive(Debug)]
+pub struct WithPeerId<S> {
+    peer_id: PeerId,
+    stream: S,
+}
+
+impl<S: io::Read> io::Read for WithPeerId<S> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for WithPeerId<S> {}
+
+impl<S: io::Write> io::Write for WithPeerId<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for WithPeerId<S> {
+    #[inline]
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.stream.shutdown()
+    }
+}
+
+/// Upgrade that applies a stream multiplexing upgrade to a `WithPeerId`, and produces the
+/// `PeerId` of the remote and the boxed muxer.
+#[derive(Debug, Clone)]
+pub struct MultiplexUpgrade<U> {
+    inner: U,
+}
+
+impl<U> UpgradeInfo for MultiplexUpgrade<U>
+where
+    U: UpgradeInfo
+{
+    type Info = U::Info;
+    type InfoIter = U::InfoIter;
+
+    #[inline]
+    fn protocol_info(&self) -> Self::InfoIter {
+        self.inner.protocol_info()
+    }
+}
+
+impl<S, U, M> InboundUpgrade<WithPeerId<S>> for MultiplexUpgrade<U>
+where
+    U: InboundUpgrade<S, Output = M>,
+    M: StreamMuxer + Send + Sync + 'static,
+    M::OutboundSubstream: Send,
+    M::Substream: Send,
+{
+    type Output = (PeerId, StreamMuxerBox);
+    type Error = U::Error;
+    type Future = MultiplexFuture<U::Future>;
+
+    #[inline]
+    fn upgrade_inbound(self, socket: WithPeerId<S>, info: Self::Info) -> Self::Future {
+        MultiplexFuture {
+            peer_id: Some(socket.peer_id),
+            future: self.inner.upgrade_inbound(socket.stream, info),
+        }
+    }
+}
+
+impl<S, U, M> OutboundUpgrade<WithPeerId<S>> for MultiplexUpgrade<U>
+where
+    U: OutboundUpgrade<S, Output = M>,
+    M: StreamMuxer + Send + Sync + 'static,
+    M::OutboundSubstream: Send,
+    M::Substream: Send,
+{
+    type Output = (PeerId, StreamMuxerBox);
+    type Error = U::Error;
+    type Future = MultiplexFuture<U::Future>;
+
+    #[inline]
+    fn upgrade_outbound(self, socket: WithPeerId<S>, info: Self::Info) -> Self::Future {
+        MultiplexFuture {
+            peer_id: Some(socket.peer_id),
+            future: self.inner.upgrade_outbound(socket.stream, info),
+        }
+    }
+}
+
+/// Future that performs the multiplexing handshake, then produces the `PeerId` of the remote
+/// and the boxed muxer.
+#[must_use = "futures do nothing unless polled"]
+pub struct MultiplexFuture<F> {
+    peer_id: Option<PeerId>,
+    future: F,
+}
+
+impl<F> Future for MultiplexFuture<F>
+where
+    F: Future,
+    F::Item: StreamMuxer + Send + Sync + 'static,
+    <F::Item as StreamMuxer>::OutboundSubstream: Send,
+    <F::Item as StreamMuxer>::Substream: Send,
+{
+    type Item = (PeerId, StreamMuxerBox);
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let muxer = match self.future.poll()? {
+            Async::Ready(muxer) => muxer,
+            Async::NotReady => return Ok(Async::NotReady),
+        };
+        let peer_id = self.peer_id.take().expect("MultiplexFuture has already finished.");
+        Ok(Async::Ready((peer_id, StreamMuxerBox::new(muxer))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::transport::memory;
+    use futures::prelude::*;
+    use mplex::MplexConfig;
+    use secio::{SecioConfig, SecioKeyPair};
+    use tokio::runtime::current_thread::Runtime;
+    use {Transport, TransportExt};
+
+    #[test]
+    fn dial_and_listen() {
+        let listener_key = SecioKeyPair::ed25519_generated().unwrap();
+        let dialer_key = SecioKeyPair::ed25519_generated().unwrap();
+        let (listener_id, dialer_id) = (listener_key.to_peer_id(), dialer_key.to_peer_id());
+        let (dialer, listener) = memory::connector();
+
+        let listener = listener
+            .authenticate(SecioConfig::new(listener_key))
+            .multiplex(MplexConfig::new());
+        let dialer = dialer
+            .authenticate(SecioConfig::new(dialer_key))
+            .multiplex(MplexConfig::new());
+
+        let (listener, addr) = listener.listen_on("/memory".parse().unwrap())
+            .unwrap_or_else(|_| panic!("listening on the memory transport never fails"));
+        let inbound = listener
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(|(upgrade, _)| upgrade.expect("the listener has closed").0);
+        let outbound = dialer.dial(addr)
+            .unwrap_or_else(|_| panic!("dialing the memory transport never fails"));
+
+        let mut rt = Runtime::new().unwrap();
+        let ((inbound_id, _inbound_muxer), (outbound_id, _outbound_muxer)) =
+            rt.block_on(inbound.join(outbound)).unwrap();
+        assert_eq!(inbound_id, dialer_id);
+        assert_eq!(outbound_id, listener_id);
+    }
+}
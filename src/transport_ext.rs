@@ -20,9 +20,13 @@
 
 //! Provides the `TransportExt` trait.
 
+use bandwidth::{BandwidthLogging, BandwidthSinks};
+use core::{transport::{map::Map, upgrade::Upgrade}, upgrade::{AuthenticatedOutput, InboundUpgrade, OutboundUpgrade}};
+use pnet::{PnetConfig, PnetTransport, PreSharedKey};
 use ratelimit::RateLimited;
-use std::io;
-use tokio_executor::DefaultExecutor;
+use std::{io, sync::Arc, time::Duration};
+use tokio_io::{AsyncRead, AsyncWrite};
+use transport_builder::{self, Authenticated, IntoAuthenticated};
 use Transport;
 
 /// Trait automatically implemented on all objects that implement `Transport`. Provides some
@@ -36,12 +40,15 @@ use Transport;
 /// use std::time::Duration;
 ///
 /// let _transport = TcpConfig::new()
-///     .with_rate_limit(1024 * 1024, 1024 * 1024);
+///     .with_max_rate(1024 * 1024, 1024 * 1024);
 /// ```
 ///
 pub trait TransportExt: Transport {
     /// Adds a maximum transfer rate to the sockets created with the transport.
+    ///
+    /// This never fails.
     #[inline]
+    #[deprecated(note = "Use `TransportExt::with_max_rate` instead")]
     fn with_rate_limit(
         self,
         max_read_bytes_per_sec: usize,
@@ -50,15 +57,65 @@ pub trait TransportExt: Transport {
     where
         Self: Sized,
     {
-        RateLimited::new(
-            &mut DefaultExecutor::current(),
-            self,
-            max_read_bytes_per_sec,
-            max_write_bytes_per_sec,
-        )
+        Ok(self.with_max_rate(max_read_bytes_per_sec, max_write_bytes_per_sec))
     }
 
-    // TODO: add methods to easily upgrade for secio/mplex/yamux
+    /// Adds a maximum transfer rate to the sockets created with the transport.
+    ///
+    /// The limit is shared by all the sockets. Finer-grained limits can be configured at runtime
+    /// through `RateLimited::limits`.
+    #[inline]
+    fn with_max_rate(
+        self,
+        max_read_bytes_per_sec: usize,
+        max_write_bytes_per_sec: usize,
+    ) -> RateLimited<Self>
+    where
+        Self: Sized,
+    {
+        RateLimited::with_max_rate(self, max_read_bytes_per_sec, max_write_bytes_per_sec)
+    }
+
+    /// Restricts the transport to the members of the private network whose key is `key`.
+    ///
+    /// The protection is applied on the raw connections, before any upgrade. It must therefore
+    /// be applied before `with_upgrade`.
+    #[inline]
+    fn with_pnet(self, key: PreSharedKey) -> PnetTransport<Self>
+    where
+        Self: Sized,
+    {
+        PnetTransport::new(self, PnetConfig::new(key))
+    }
+
+    /// Counts the bytes transferred on the sockets created with the transport.
+    ///
+    /// The returned `BandwidthSinks` exposes the counters. The rates are computed over a sliding
+    /// window of duration `window`.
+    #[inline]
+    fn with_bandwidth_logging(self, window: Duration) -> (BandwidthLogging<Self>, Arc<BandwidthSinks>)
+    where
+        Self: Sized,
+    {
+        BandwidthLogging::new(self, window)
+    }
+
+    /// Applies an upgrade that authenticates the remote, such as secio, to the connections
+    /// created with the transport.
+    ///
+    /// This is the first step of building a transport suitable for the `Swarm`. The returned
+    /// value must then be multiplexed with `Authenticated::multiplex`.
+    #[inline]
+    fn authenticate<U, O, E>(self, upgrade: U) -> Authenticated<Map<Upgrade<Self, U>, IntoAuthenticated<O>>>
+    where
+        Self: Sized,
+        Self::Output: AsyncRead + AsyncWrite,
+        U: InboundUpgrade<Self::Output, Output = O, Error = E>,
+        U: OutboundUpgrade<Self::Output, Output = O, Error = E>,
+        O: AuthenticatedOutput,
+    {
+        transport_builder::authenticate(self, upgrade)
+    }
 }
 
 impl<TTransport> TransportExt for TTransport where TTransport: Transport {}
@@ -0,0 +1,370 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Counters of the bytes transferred, globally and per connection.
+
+use libp2p_core::PeerId;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// Number of buckets a sliding window is divided into.
+const BUCKETS_PER_WINDOW: u32 = 10;
+
+/// Counts the bytes transferred in one direction, in total and over a sliding window.
+#[derive(Debug)]
+struct Meter {
+    /// Total number of bytes since the creation of the meter.
+    total: u64,
+    /// Duration of the sliding window.
+    window: Duration,
+    /// Duration covered by a bucket.
+    bucket_duration: Duration,
+    /// Start of each bucket and number of bytes in it, oldest first.
+    buckets: VecDeque<(Instant, u64)>,
+}
+
+impl Meter {
+    fn new(window: Duration) -> Meter {
+        Meter {
+            total: 0,
+            window,
+            bucket_duration: window / BUCKETS_PER_WINDOW,
+            buckets: VecDeque::with_capacity(BUCKETS_PER_WINDOW as usize + 1),
+        }
+    }
+
+    fn record(&mut self, now: Instant, bytes: u64) {
+        self.total += bytes;
+        let bucket_duration = self.bucket_duration;
+        let in_last_bucket = self.buckets.back()
+            .map(|(start, _)| now.duration_since(*start) < bucket_duration)
+            .unwrap_or(false);
+        if in_last_bucket {
+            self.buckets.back_mut().expect("in_last_bucket is only true if there is a bucket").1 += bytes;
+        } else {
+            self.buckets.push_back((now, bytes));
+        }
+
+        let window = self.window;
+        while let Some(&(start, _)) = self.buckets.front() {
+            if now.duration_since(start) <= window {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Returns the average number of bytes per second over the last window.
+    fn rate(&self, now: Instant) -> u64 {
+        let bytes: u64 = self.buckets.iter()
+            .filter(|(start, _)| now.duration_since(*start) <= self.window)
+            .map(|(_, count)| count)
+            .sum();
+        let window_ms = self.window.as_secs() * 1000 + u64::from(self.window.subsec_millis());
+        bytes * 1000 / window_ms
+    }
+}
+
+/// Bandwidth used by all the connections of a `BandwidthLogging` transport.
+///
+/// Shared between the transport and the application, which can read the counters at any time.
+///
+/// The totals of a peer are kept after all its connections have been closed, so that they can
+/// still be read. They must be removed with `remove_peer` once they are no longer needed.
+#[derive(Debug)]
+pub struct BandwidthSinks {
+    window: Duration,
+    inbound: Mutex<Meter>,
+    outbound: Mutex<Meter>,
+    peers: Mutex<HashMap<PeerId, PeerEntry>>,
+}
+
+/// Bandwidth attributed to a peer.
+#[derive(Debug, Default)]
+struct PeerEntry {
+    total_inbound: u64,
+    total_outbound: u64,
+    /// Connections attributed to this peer. Closed connections are removed lazily.
+    connections: Vec<Weak<ConnectionCounters>>,
+}
+
+/// Bandwidth used with a peer, as returned by `BandwidthSinks::peer`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PeerBandwidth {
+    /// Total number of bytes received from the peer.
+    pub total_inbound: u64,
+    /// Total number of bytes sent to the peer.
+    pub total_outbound: u64,
+    /// Number of bytes per second received from the peer over the sliding window, summed over
+    /// the open connections.
+    pub inbound_rate: u64,
+    /// Number of bytes per second sent to the peer over the sliding window, summed over the open
+    /// connections.
+    pub outbound_rate: u64,
+    /// Number of open connections with the peer.
+    pub connections: usize,
+}
+
+impl BandwidthSinks {
+    /// Creates new counters whose rates are computed over a sliding window of duration `window`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `window` is shorter than one millisecond.
+    pub fn new(window: Duration) -> Arc<BandwidthSinks> {
+        assert!(window >= Duration::from_millis(1), "the window must last at least 1ms");
+        Arc::new(BandwidthSinks {
+            window,
+            inbound: Mutex::new(Meter::new(window)),
+            outbound: Mutex::new(Meter::new(window)),
+            peers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the duration of the sliding window over which the rates are computed.
+    #[inline]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns the total number of bytes received on all the connections.
+    #[inline]
+    pub fn total_inbound(&self) -> u64 {
+        self.inbound.lock().total
+    }
+
+    /// Returns the total number of bytes sent on all the connections.
+    #[inline]
+    pub fn total_outbound(&self) -> u64 {
+        self.outbound.lock().total
+    }
+
+    /// Returns the number of bytes per second received on all the connections, over the sliding
+    /// window.
+    #[inline]
+    pub fn inbound_rate(&self) -> u64 {
+        self.inbound.lock().rate(Instant::now())
+    }
+
+    /// Returns the number of bytes per second sent on all the connections, over the sliding
+    /// window.
+    #[inline]
+    pub fn outbound_rate(&self) -> u64 {
+        self.outbound.lock().rate(Instant::now())
+    }
+
+    /// Returns the bandwidth used with a peer, or `None` if no connection has been attributed to
+    /// this peer.
+    pub fn peer(&self, peer_id: &PeerId) -> Option<PeerBandwidth> {
+        let mut peers = self.peers.lock();
+        peers.get_mut(peer_id).map(PeerEntry::bandwidth)
+    }
+
+    /// Returns the bandwidth used with each peer a connection has been attributed to.
+    pub fn peers(&self) -> Vec<(PeerId, PeerBandwidth)> {
+        let mut peers = self.peers.lock();
+        peers.iter_mut()
+            .map(|(peer_id, entry)| (peer_id.clone(), entry.bandwidth()))
+            .collect()
+    }
+
+    /// Forgets the totals of a peer. Does nothing if the peer has open connections.
+    ///
+    /// Entries are never removed automatically, so this should be called for peers we're no
+    /// longer interested in, for example when the swarm reports that they are disconnected.
+    ///
+    /// Returns true if the peer has been removed.
+    pub fn remove_peer(&self, peer_id: &PeerId) -> bool {
+        let mut peers = self.peers.lock();
+        let removable = peers.get_mut(peer_id)
+            .map(|entry| entry.bandwidth().connections == 0)
+            .unwrap_or(false);
+        if removable {
+            peers.remove(peer_id);
+        }
+        removable
+    }
+}
+
+impl PeerEntry {
+    fn bandwidth(&mut self) -> PeerBandwidth {
+        self.connections.retain(|c| c.upgrade().is_some());
+        let now = Instant::now();
+        let mut bandwidth = PeerBandwidth {
+            total_inbound: self.total_inbound,
+            total_outbound: self.total_outbound,
+            inbound_rate: 0,
+            outbound_rate: 0,
+            connections: 0,
+        };
+        for connection in self.connections.iter().filter_map(Weak::upgrade) {
+            bandwidth.inbound_rate += connection.inbound.lock().rate(now);
+            bandwidth.outbound_rate += connection.outbound.lock().rate(now);
+            bandwidth.connections += 1;
+        }
+        bandwidth
+    }
+}
+
+/// Bandwidth used by a single connection.
+#[derive(Debug)]
+pub struct ConnectionCounters {
+    sinks: Arc<BandwidthSinks>,
+    inbound: Mutex<Meter>,
+    outbound: Mutex<Meter>,
+    /// Peer the connection has been attributed to. Locked before the meters when both are
+    /// needed.
+    peer_id: Mutex<Option<PeerId>>,
+}
+
+impl ConnectionCounters {
+    pub(crate) fn new(sinks: Arc<BandwidthSinks>) -> Arc<ConnectionCounters> {
+        let window = sinks.window;
+        Arc::new(ConnectionCounters {
+            sinks,
+            inbound: Mutex::new(Meter::new(window)),
+            outbound: Mutex::new(Meter::new(window)),
+            peer_id: Mutex::new(None),
+        })
+    }
+
+    /// Attributes the bandwidth of the connection to a peer, including the bytes that have
+    /// already been transferred.
+    ///
+    /// This is meant to be called once the upgrade that authenticates the remote has completed.
+    /// Does nothing if the connection has already been attributed to a peer.
+    pub fn attribute(this: &Arc<ConnectionCounters>, peer_id: PeerId) {
+        let mut attributed = this.peer_id.lock();
+        if attributed.is_some() {
+            return;
+        }
+
+        let mut peers = this.sinks.peers.lock();
+        let entry = peers.entry(peer_id.clone()).or_insert_with(PeerEntry::default);
+        entry.total_inbound += this.inbound.lock().total;
+        entry.total_outbound += this.outbound.lock().total;
+        entry.connections.push(Arc::downgrade(this));
+        *attributed = Some(peer_id);
+    }
+
+    /// Returns the peer the connection has been attributed to, if any.
+    #[inline]
+    pub fn peer_id(&self) -> Option<PeerId> {
+        self.peer_id.lock().clone()
+    }
+
+    /// Returns the total number of bytes received on this connection.
+    #[inline]
+    pub fn total_inbound(&self) -> u64 {
+        self.inbound.lock().total
+    }
+
+    /// Returns the total number of bytes sent on this connection.
+    #[inline]
+    pub fn total_outbound(&self) -> u64 {
+        self.outbound.lock().total
+    }
+
+    /// Returns the number of bytes per second received on this connection, over the sliding
+    /// window.
+    #[inline]
+    pub fn inbound_rate(&self) -> u64 {
+        self.inbound.lock().rate(Instant::now())
+    }
+
+    /// Returns the number of bytes per second sent on this connection, over the sliding window.
+    #[inline]
+    pub fn outbound_rate(&self) -> u64 {
+        self.outbound.lock().rate(Instant::now())
+    }
+
+    pub(crate) fn record_inbound(&self, bytes: usize) {
+        let now = Instant::now();
+        let bytes = bytes as u64;
+        // Holding the lock prevents `attribute` from counting these bytes a second time.
+        let peer_id = self.peer_id.lock();
+        self.inbound.lock().record(now, bytes);
+        self.sinks.inbound.lock().record(now, bytes);
+        if let Some(ref peer_id) = *peer_id {
+            if let Some(entry) = self.sinks.peers.lock().get_mut(peer_id) {
+                entry.total_inbound += bytes;
+            }
+        }
+    }
+
+    pub(crate) fn record_outbound(&self, bytes: usize) {
+        let now = Instant::now();
+        let bytes = bytes as u64;
+        let peer_id = self.peer_id.lock();
+        self.outbound.lock().record(now, bytes);
+        self.sinks.outbound.lock().record(now, bytes);
+        if let Some(ref peer_id) = *peer_id {
+            if let Some(entry) = self.sinks.peers.lock().get_mut(peer_id) {
+                entry.total_outbound += bytes;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandwidthSinks, ConnectionCounters, Meter};
+    use libp2p_core::PeerId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn meter_forgets_old_buckets() {
+        let mut meter = Meter::new(Duration::from_secs(1));
+        let start = Instant::now();
+        meter.record(start, 1000);
+        assert_eq!(meter.rate(start), 1000);
+        meter.record(start + Duration::from_millis(500), 500);
+        assert_eq!(meter.rate(start + Duration::from_millis(500)), 1500);
+        assert_eq!(meter.rate(start + Duration::from_millis(1200)), 500);
+        assert_eq!(meter.rate(start + Duration::from_secs(2)), 0);
+        assert_eq!(meter.total, 1500);
+    }
+
+    #[test]
+    fn attribution_includes_past_traffic() {
+        let sinks = BandwidthSinks::new(Duration::from_secs(1));
+        let counters = ConnectionCounters::new(sinks.clone());
+        counters.record_inbound(10);
+        counters.record_outbound(20);
+
+        let peer_id = PeerId::random();
+        assert!(sinks.peer(&peer_id).is_none());
+        ConnectionCounters::attribute(&counters, peer_id.clone());
+        counters.record_inbound(5);
+
+        let bandwidth = sinks.peer(&peer_id).unwrap();
+        assert_eq!(bandwidth.total_inbound, 15);
+        assert_eq!(bandwidth.total_outbound, 20);
+        assert_eq!(bandwidth.connections, 1);
+        assert_eq!(sinks.total_inbound(), 15);
+        assert!(!sinks.remove_peer(&peer_id));
+
+        drop(counters);
+        assert_eq!(sinks.peer(&peer_id).unwrap().connections, 0);
+        assert!(sinks.remove_peer(&peer_id));
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transport adapter that measures the bandwidth used by the connections of the underlying
+//! transport.
+//!
+//! The `BandwidthLogging` struct wraps around a transport and counts the bytes received and sent
+//! on each of its connections. The counters are available through the `BandwidthSinks` returned
+//! when creating the transport, which exposes the totals and the current rates over a sliding
+//! window, globally and per peer.
+//!
+//! The identity of the remote isn't known when a raw connection is opened. Each connection
+//! exposes its `ConnectionCounters`, which must be attributed to the `PeerId` of the remote once
+//! the upgrade that authenticates it has completed.
+//!
+//! # Example
+//!
+//! ```
+//! use futures::prelude::*;
+//! use libp2p_bandwidth::{BandwidthLogging, ConnectionCounters};
+//! use libp2p_core::{Transport, upgrade};
+//! use libp2p_secio::{SecioConfig, SecioKeyPair};
+//! use libp2p_tcp::TcpConfig;
+//! use std::time::Duration;
+//!
+//! let keypair = SecioKeyPair::ed25519_generated().unwrap();
+//! let (transport, sinks) = BandwidthLogging::new(TcpConfig::new(), Duration::from_secs(5));
+//! let transport = transport.and_then(move |conn, endpoint| {
+//!     let counters = conn.counters().clone();
+//!     upgrade::apply(conn, SecioConfig::new(keypair.clone()), endpoint)
+//!         .map(move |out| {
+//!             let peer_id = out.remote_key.into_peer_id();
+//!             ConnectionCounters::attribute(&counters, peer_id.clone());
+//!             (peer_id, out.stream)
+//!         })
+//!         .map_err(|err| err.into_io_error())
+//! });
+//!
+//! // The counters can be read at any time, for example to display them.
+//! println!("Receiving {} bytes per second", sinks.inbound_rate());
+//! ```
+
+mod counters;
+
+pub use crate::counters::{BandwidthSinks, ConnectionCounters, PeerBandwidth};
+
+use futures::{prelude::*, try_ready};
+use libp2p_core::{Multiaddr, Transport};
+use std::{io, sync::Arc, time::Duration};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Wraps around a `Transport` and counts the bytes that go through all the connections it opens
+/// or accepts.
+#[derive(Debug, Clone)]
+pub struct BandwidthLogging<TInner> {
+    inner: TInner,
+    sinks: Arc<BandwidthSinks>,
+}
+
+impl<TInner> BandwidthLogging<TInner> {
+    /// Wraps around a transport. The rates are computed over a sliding window of duration
+    /// `window`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `window` is shorter than one millisecond.
+    pub fn new(inner: TInner, window: Duration) -> (Self, Arc<BandwidthSinks>) {
+        let sinks = BandwidthSinks::new(window);
+        let transport = BandwidthLogging { inner, sinks: sinks.clone() };
+        (transport, sinks)
+    }
+}
+
+impl<TInner> Transport for BandwidthLogging<TInner>
+where
+    TInner: Transport,
+    TInner::Output: AsyncRead + AsyncWrite,
+{
+    type Output = BandwidthConnecLogging<TInner::Output>;
+    type Listener = BandwidthListener<TInner::Listener>;
+    type ListenerUpgrade = BandwidthFuture<TInner::ListenerUpgrade>;
+    type Dial = BandwidthFuture<TInner::Dial>;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        let sinks = self.sinks;
+        match self.inner.listen_on(addr) {
+            Ok((inner, addr)) => Ok((BandwidthListener { inner, sinks }, addr)),
+            Err((inner, addr)) => Err((BandwidthLogging { inner, sinks }, addr)),
+        }
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        let sinks = self.sinks;
+        match self.inner.dial(addr) {
+            Ok(inner) => Ok(BandwidthFuture { inner, sinks }),
+            Err((inner, addr)) => Err((BandwidthLogging { inner, sinks }, addr)),
+        }
+    }
+
+    #[inline]
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.nat_traversal(server, observed)
+    }
+}
+
+/// Listener of a `BandwidthLogging` transport.
+pub struct BandwidthListener<TInner> {
+    inner: TInner,
+    sinks: Arc<BandwidthSinks>,
+}
+
+impl<TInner, TUpgr> Stream for BandwidthListener<TInner>
+where
+    TInner: Stream<Item = (TUpgr, Multiaddr)>,
+{
+    type Item = (BandwidthFuture<TUpgr>, Multiaddr);
+    type Error = TInner::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        match try_ready!(self.inner.poll()) {
+            Some((inner, addr)) => {
+                let future = BandwidthFuture { inner, sinks: self.sinks.clone() };
+                Ok(Async::Ready(Some((future, addr))))
+            },
+            None => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+/// Future that produces a connection of a `BandwidthLogging` transport.
+#[must_use = "futures do nothing unless polled"]
+pub struct BandwidthFuture<TInner> {
+    inner: TInner,
+    sinks: Arc<BandwidthSinks>,
+}
+
+impl<TInner> Future for BandwidthFuture<TInner>
+where
+    TInner: Future,
+{
+    type Item = BandwidthConnecLogging<TInner::Item>;
+    type Error = TInner::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let inner = try_ready!(self.inner.poll());
+        let counters = ConnectionCounters::new(self.sinks.clone());
+        Ok(Async::Ready(BandwidthConnecLogging { inner, counters }))
+    }
+}
+
+/// Connection of a `BandwidthLogging` transport. Counts the bytes read from and written to the
+/// underlying connection.
+pub struct BandwidthConnecLogging<TInner> {
+    inner: TInner,
+    counters: Arc<ConnectionCounters>,
+}
+
+impl<TInner> BandwidthConnecLogging<TInner> {
+    /// Returns the counters of this connection.
+    #[inline]
+    pub fn counters(&self) -> &Arc<ConnectionCounters> {
+        &self.counters
+    }
+}
+
+impl<TInner> io::Read for BandwidthConnecLogging<TInner>
+where
+    TInner: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_bytes = self.inner.read(buf)?;
+        self.counters.record_inbound(num_bytes);
+        Ok(num_bytes)
+    }
+}
+
+impl<TInner> AsyncRead for BandwidthConnecLogging<TInner>
+where
+    TInner: AsyncRead,
+{
+    #[inline]
+    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
+        self.inner.prepare_uninitialized_buffer(buf)
+    }
+}
+
+impl<TInner> io::Write for BandwidthConnecLogging<TInner>
+where
+    TInner: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = self.inner.write(buf)?;
+        self.counters.record_outbound(num_bytes);
+        Ok(num_bytes)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<TInner> AsyncWrite for BandwidthConnecLogging<TInner>
+where
+    TInner: AsyncWrite,
+{
+    #[inline]
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BandwidthLogging;
+    use futures::prelude::*;
+    use libp2p_core::Transport;
+    use libp2p_tcp::TcpConfig;
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+
+    #[test]
+    fn counts_both_directions() {
+        let (transport, sinks) = BandwidthLogging::new(TcpConfig::new(), Duration::from_secs(10));
+        let (listener, addr) = transport.clone()
+            .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .unwrap();
+
+        let server = listener
+            .into_future()
+            .map_err(|(e, _)| e)
+            .and_then(|(upgrade, _)| upgrade.unwrap().0)
+            .and_then(|socket| tokio_io::io::read_exact(socket, vec![0; 5]))
+            .and_then(|(socket, buf)| tokio_io::io::write_all(socket, buf))
+            .and_then(|(socket, _)| tokio_io::io::flush(socket));
+
+        let client = transport.dial(addr).unwrap()
+            .and_then(|socket| tokio_io::io::write_all(socket, b"hello"))
+            .and_then(|(socket, _)| tokio_io::io::read_exact(socket, vec![0; 5]));
+
+        let mut rt = Runtime::new().unwrap();
+        let (_, (socket, received)) = rt.block_on(server.join(client)).unwrap();
+        assert_eq!(received, b"hello");
+        assert_eq!(socket.counters().total_inbound(), 5);
+        assert_eq!(socket.counters().total_outbound(), 5);
+
+        assert_eq!(sinks.total_inbound(), 10);
+        assert_eq!(sinks.total_outbound(), 10);
+        assert!(sinks.inbound_rate() > 0);
+    }
+}
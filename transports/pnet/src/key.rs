@@ -0,0 +1,167 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Pre-shared keys of private networks.
+
+use std::{error, fmt, str, str::FromStr};
+
+/// Length of a pre-shared key, in bytes.
+pub const KEY_SIZE: usize = 32;
+
+const KEY_CODEC: &str = "/key/swarm/psk/1.0.0/";
+const KEY_ENCODING: &str = "/base16/";
+
+/// Key shared by all the members of a private network.
+///
+/// Can be parsed from and formatted to the standard `/key/swarm/psk/1.0.0/` file format, which
+/// consists of three lines: the codec, the encoding (only `/base16/` is supported), and the key.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct PreSharedKey([u8; KEY_SIZE]);
+
+impl PreSharedKey {
+    /// Builds a pre-shared key from its raw bytes.
+    #[inline]
+    pub fn new(key: [u8; KEY_SIZE]) -> PreSharedKey {
+        PreSharedKey(key)
+    }
+
+    /// Returns the raw bytes of the key.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.0
+    }
+}
+
+impl FromStr for PreSharedKey {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<PreSharedKey, KeyParseError> {
+        let mut lines = s.lines().map(str::trim);
+
+        if lines.next() != Some(KEY_CODEC) {
+            return Err(KeyParseError::InvalidKeyFile);
+        }
+        if lines.next() != Some(KEY_ENCODING) {
+            return Err(KeyParseError::InvalidKeyEncoding);
+        }
+
+        let hex = lines.next().ok_or(KeyParseError::InvalidKeyFile)?;
+        if lines.any(|line| !line.is_empty()) {
+            return Err(KeyParseError::InvalidKeyFile);
+        }
+        if hex.len() != KEY_SIZE * 2 {
+            return Err(KeyParseError::InvalidKeyLength);
+        }
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(KeyParseError::InvalidHex);
+        }
+
+        let mut key = [0; KEY_SIZE];
+        for (byte, chunk) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let chunk = str::from_utf8(chunk).expect("hexadecimal digits are ASCII");
+            *byte = u8::from_str_radix(chunk, 16).expect("two hexadecimal digits fit in a u8");
+        }
+
+        Ok(PreSharedKey(key))
+    }
+}
+
+impl fmt::Display for PreSharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", KEY_CODEC)?;
+        writeln!(f, "{}", KEY_ENCODING)?;
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PreSharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Don't leak the key in the logs.
+        f.debug_tuple("PreSharedKey").field(&"..").finish()
+    }
+}
+
+/// Error while parsing a `PreSharedKey`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyParseError {
+    /// The text doesn't follow the `/key/swarm/psk/1.0.0/` format.
+    InvalidKeyFile,
+    /// The key is encoded with something else than `/base16/`.
+    InvalidKeyEncoding,
+    /// The key doesn't have the right length.
+    InvalidKeyLength,
+    /// The key contains characters that aren't hexadecimal digits.
+    InvalidHex,
+}
+
+impl error::Error for KeyParseError {}
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyParseError::InvalidKeyFile => write!(f, "Invalid pre-shared key file"),
+            KeyParseError::InvalidKeyEncoding => write!(f, "Unsupported pre-shared key encoding"),
+            KeyParseError::InvalidKeyLength => write!(f, "Pre-shared key must be {} bytes long", KEY_SIZE),
+            KeyParseError::InvalidHex => write!(f, "Pre-shared key isn't valid hexadecimal"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyParseError, PreSharedKey};
+
+    const KEY: &str = "/key/swarm/psk/1.0.0/\n/base16/\n6189c5cf0b87fb800c1a9feeda73c6ab5e998db48fb9e6a978575c770ceef683";
+
+    #[test]
+    fn parse_and_format() {
+        let key: PreSharedKey = KEY.parse().unwrap();
+        assert_eq!(key.as_bytes()[0], 0x61);
+        assert_eq!(key.as_bytes()[31], 0x83);
+        assert_eq!(key.to_string(), KEY);
+        assert_eq!(key.to_string().parse::<PreSharedKey>().unwrap(), key);
+    }
+
+    #[test]
+    fn trailing_newline_and_whitespace() {
+        let text = format!("{}\n", KEY.replace("\n", " \r\n"));
+        assert!(text.parse::<PreSharedKey>().is_ok());
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert_eq!("".parse::<PreSharedKey>(), Err(KeyParseError::InvalidKeyFile));
+        assert_eq!(
+            KEY.replace("/base16/", "/base64/").parse::<PreSharedKey>(),
+            Err(KeyParseError::InvalidKeyEncoding)
+        );
+        assert_eq!(
+            KEY.replace("f683", "f6").parse::<PreSharedKey>(),
+            Err(KeyParseError::InvalidKeyLength)
+        );
+        assert_eq!(
+            KEY.replace("f683", "f6zz").parse::<PreSharedKey>(),
+            Err(KeyParseError::InvalidHex)
+        );
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of private networks, as described in the
+//! [libp2p pnet specification](https://github.com/libp2p/specs/blob/master/pnet/Private-Networks-PSK-V1.md).
+//!
+//! All the members of a private network share a `PreSharedKey`. Every connection starts with
+//! each side sending a random 24-byte nonce, after which everything that goes through the
+//! connection is encrypted with XSalsa20, using the pre-shared key and the nonce of the sending
+//! side. A node that doesn't know the key can't decrypt anything, and the next protocols (such
+//! as multistream-select or secio) fail to negotiate.
+//!
+//! This encryption doesn't authenticate the remote and doesn't protect the integrity of the data.
+//! It is meant to be applied on the raw connections, and secio must still be used on top of it.
+//!
+//! # Usage
+//!
+//! The `PnetTransport` struct wraps around a transport and applies the protection on every
+//! connection it opens or accepts, before any upgrade.
+//!
+//! ```
+//! use libp2p_core::Transport;
+//! use libp2p_pnet::{PnetConfig, PnetTransport, PreSharedKey};
+//! use libp2p_tcp::TcpConfig;
+//!
+//! let key: PreSharedKey = "/key/swarm/psk/1.0.0/\n/base16/\n\
+//!     6189c5cf0b87fb800c1a9feeda73c6ab5e998db48fb9e6a978575c770ceef683".parse().unwrap();
+//! let transport = PnetTransport::new(TcpConfig::new(), PnetConfig::new(key));
+//! ```
+
+mod key;
+mod transport;
+
+pub use crate::key::{KeyParseError, PreSharedKey, KEY_SIZE};
+pub use crate::transport::{PnetListener, PnetTransport, PnetUpgrade};
+
+use futures::{prelude::*, try_ready};
+use log::{debug, trace};
+use salsa20::XSalsa20;
+use std::{fmt, io, io::{Read, Write}};
+use stream_cipher::{generic_array::GenericArray, NewStreamCipher, SyncStreamCipher};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Length of the nonce sent by each side at the start of a connection.
+const NONCE_SIZE: usize = 24;
+
+/// Configuration of a private network.
+#[derive(Debug, Copy, Clone)]
+pub struct PnetConfig {
+    key: PreSharedKey,
+}
+
+impl PnetConfig {
+    /// Builds a configuration for the private network whose key is `key`.
+    #[inline]
+    pub fn new(key: PreSharedKey) -> PnetConfig {
+        PnetConfig { key }
+    }
+
+    /// Applies the private network protection on a raw connection.
+    pub fn handshake<S>(self, socket: S) -> PnetHandshake<S>
+    where
+        S: AsyncRead + AsyncWrite,
+    {
+        PnetHandshake {
+            socket: Some(socket),
+            key: self.key,
+            local_nonce: rand::random(),
+            written: 0,
+            flushed: false,
+            remote_nonce: [0; NONCE_SIZE],
+            read: 0,
+        }
+    }
+}
+
+/// Future that exchanges the nonces, and produces a `PnetOutput`.
+#[must_use = "futures do nothing unless polled"]
+pub struct PnetHandshake<S> {
+    socket: Option<S>,
+    key: PreSharedKey,
+    local_nonce: [u8; NONCE_SIZE],
+    /// Number of bytes of `local_nonce` that have been written.
+    written: usize,
+    flushed: bool,
+    remote_nonce: [u8; NONCE_SIZE],
+    /// Number of bytes of `remote_nonce` that have been read.
+    read: usize,
+}
+
+impl<S> Future for PnetHandshake<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    type Item = PnetOutput<S>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        {
+            let socket = self.socket.as_mut().expect("future polled after completion");
+
+            while self.written < NONCE_SIZE {
+                let n = try_ready!(socket.poll_write(&self.local_nonce[self.written..]));
+                if n == 0 {
+                    return Err(io::ErrorKind::WriteZero.into());
+                }
+                self.written += n;
+            }
+
+            if !self.flushed {
+                try_ready!(socket.poll_flush());
+                self.flushed = true;
+            }
+
+            while self.read < NONCE_SIZE {
+                let n = try_ready!(socket.poll_read(&mut self.remote_nonce[self.read..]));
+                if n == 0 {
+                    debug!("connection closed during the pnet handshake");
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.read += n;
+            }
+        }
+
+        trace!("pnet nonces exchanged");
+        let socket = self.socket.take().expect("future polled after completion");
+        Ok(Async::Ready(PnetOutput::new(socket, &self.key, &self.local_nonce, &self.remote_nonce)))
+    }
+}
+
+impl<S> fmt::Debug for PnetHandshake<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PnetHandshake").finish()
+    }
+}
+
+/// Connection protected by the pre-shared key.
+pub struct PnetOutput<S> {
+    socket: S,
+    /// Cipher used to encrypt the data that we send.
+    write_cipher: XSalsa20,
+    /// Cipher used to decrypt the data that we receive.
+    read_cipher: XSalsa20,
+    /// Encrypted data that hasn't been written to the socket yet. The keystream can't be
+    /// rewound, so encrypted data must be kept until it has been entirely written.
+    write_buffer: Vec<u8>,
+}
+
+impl<S> PnetOutput<S> {
+    fn new(socket: S, key: &PreSharedKey, local_nonce: &[u8], remote_nonce: &[u8]) -> Self {
+        let key = GenericArray::from_slice(key.as_bytes());
+        PnetOutput {
+            socket,
+            write_cipher: XSalsa20::new(key, GenericArray::from_slice(local_nonce)),
+            read_cipher: XSalsa20::new(key, GenericArray::from_slice(remote_nonce)),
+            write_buffer: Vec::new(),
+        }
+    }
+}
+
+impl<S> PnetOutput<S>
+where
+    S: AsyncWrite,
+{
+    /// Tries to write the content of `write_buffer` to the socket.
+    fn poll_write_buffer(&mut self) -> Poll<(), io::Error> {
+        while !self.write_buffer.is_empty() {
+            let n = try_ready!(self.socket.poll_write(&self.write_buffer));
+            if n == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.write_buffer.drain(..n);
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<S> Read for PnetOutput<S>
+where
+    S: AsyncRead,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.socket.read(buf)?;
+        self.read_cipher.apply_keystream(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<S> AsyncRead for PnetOutput<S>
+where
+    S: AsyncRead,
+{
+}
+
+impl<S> Write for PnetOutput<S>
+where
+    S: AsyncWrite,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Async::NotReady = self.poll_write_buffer()? {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        self.write_buffer.extend_from_slice(buf);
+        self.write_cipher.apply_keystream(&mut self.write_buffer);
+        // The data is now our responsibility. Errors will be reported on the next write or flush.
+        match self.poll_write_buffer() {
+            Ok(_) => Ok(buf.len()),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(buf.len()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Async::NotReady = self.poll_write_buffer()? {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.socket.flush()
+    }
+}
+
+impl<S> AsyncWrite for PnetOutput<S>
+where
+    S: AsyncWrite,
+{
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_write_buffer());
+        self.socket.shutdown()
+    }
+}
+
+impl<S> fmt::Debug for PnetOutput<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PnetOutput").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PnetConfig, PnetTransport, PreSharedKey};
+    use futures::prelude::*;
+    use libp2p_core::Transport;
+    use libp2p_tcp::TcpConfig;
+    use tokio::runtime::current_thread::Runtime;
+
+    fn exchange(listener_key: PreSharedKey, dialer_key: PreSharedKey) -> Vec<u8> {
+        let listener = PnetTransport::new(TcpConfig::new(), PnetConfig::new(listener_key));
+        let (listener, addr) = listener
+            .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .unwrap();
+
+        let server = listener
+            .into_future()
+            .map_err(|(e, _)| e)
+            .and_then(|(upgrade, _)| upgrade.unwrap().0)
+            .and_then(|socket| tokio_io::io::read_exact(socket, vec![0; 11]))
+            .map(|(_, buf)| buf);
+
+        let dialer = PnetTransport::new(TcpConfig::new(), PnetConfig::new(dialer_key));
+        let client = dialer.dial(addr).unwrap()
+            .and_then(|socket| tokio_io::io::write_all(socket, b"hello world"))
+            .and_then(|(socket, _)| tokio_io::io::flush(socket));
+
+        let mut rt = Runtime::new().unwrap();
+        let (received, _) = rt.block_on(server.join(client)).unwrap();
+        received
+    }
+
+    #[test]
+    fn same_key_communicates() {
+        let key = PreSharedKey::new(rand::random());
+        assert_eq!(exchange(key, key), b"hello world");
+    }
+
+    #[test]
+    fn different_keys_garble_data() {
+        let key1 = PreSharedKey::new(rand::random());
+        let key2 = PreSharedKey::new(rand::random());
+        assert_ne!(exchange(key1, key2), b"hello world");
+    }
+}
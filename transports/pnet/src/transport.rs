@@ -0,0 +1,167 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Wrapper around a transport that applies the private network protection on all the
+//! connections.
+
+use crate::{PnetConfig, PnetHandshake, PnetOutput};
+use futures::{prelude::*, try_ready};
+use libp2p_core::{Multiaddr, Transport};
+use std::io;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Transport that protects all the connections of the underlying transport with a
+/// pre-shared key.
+#[derive(Debug, Clone)]
+pub struct PnetTransport<T> {
+    inner: T,
+    config: PnetConfig,
+}
+
+impl<T> PnetTransport<T> {
+    /// Wraps around `inner`.
+    #[inline]
+    pub fn new(inner: T, config: PnetConfig) -> Self {
+        PnetTransport { inner, config }
+    }
+}
+
+impl<T> Transport for PnetTransport<T>
+where
+    T: Transport,
+    T::Output: AsyncRead + AsyncWrite,
+{
+    type Output = PnetOutput<T::Output>;
+    type Listener = PnetListener<T::Listener>;
+    type ListenerUpgrade = PnetUpgrade<T::ListenerUpgrade>;
+    type Dial = PnetUpgrade<T::Dial>;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        let config = self.config;
+        match self.inner.listen_on(addr) {
+            Ok((inner, addr)) => Ok((PnetListener { inner, config }, addr)),
+            Err((inner, addr)) => Err((PnetTransport { inner, config }, addr)),
+        }
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        let config = self.config;
+        match self.inner.dial(addr) {
+            Ok(inner) => Ok(PnetUpgrade::new(inner, config)),
+            Err((inner, addr)) => Err((PnetTransport { inner, config }, addr)),
+        }
+    }
+
+    #[inline]
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.nat_traversal(server, observed)
+    }
+}
+
+/// Listener of a `PnetTransport`.
+pub struct PnetListener<L> {
+    inner: L,
+    config: PnetConfig,
+}
+
+impl<L, F> Stream for PnetListener<L>
+where
+    L: Stream<Item = (F, Multiaddr), Error = io::Error>,
+    F: Future<Error = io::Error>,
+    F::Item: AsyncRead + AsyncWrite,
+{
+    type Item = (PnetUpgrade<F>, Multiaddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        match try_ready!(self.inner.poll()) {
+            Some((upgrade, addr)) => {
+                let upgrade = PnetUpgrade::new(upgrade, self.config);
+                Ok(Async::Ready(Some((upgrade, addr))))
+            },
+            None => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+/// Future that opens a connection with the underlying transport, then applies the private
+/// network protection on it.
+#[must_use = "futures do nothing unless polled"]
+pub struct PnetUpgrade<F>
+where
+    F: Future,
+{
+    state: PnetUpgradeState<F>,
+}
+
+enum PnetUpgradeState<F>
+where
+    F: Future,
+{
+    Connecting(F, PnetConfig),
+    Handshaking(PnetHandshake<F::Item>),
+    Poisoned,
+}
+
+impl<F> PnetUpgrade<F>
+where
+    F: Future,
+{
+    fn new(inner: F, config: PnetConfig) -> Self {
+        PnetUpgrade { state: PnetUpgradeState::Connecting(inner, config) }
+    }
+}
+
+impl<F> Future for PnetUpgrade<F>
+where
+    F: Future<Error = io::Error>,
+    F::Item: AsyncRead + AsyncWrite,
+{
+    type Item = PnetOutput<F::Item>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            match std::mem::replace(&mut self.state, PnetUpgradeState::Poisoned) {
+                PnetUpgradeState::Connecting(mut inner, config) => {
+                    match inner.poll()? {
+                        Async::Ready(socket) => {
+                            self.state = PnetUpgradeState::Handshaking(config.handshake(socket));
+                        },
+                        Async::NotReady => {
+                            self.state = PnetUpgradeState::Connecting(inner, config);
+                            return Ok(Async::NotReady);
+                        },
+                    }
+                },
+                PnetUpgradeState::Handshaking(mut handshake) => {
+                    match handshake.poll()? {
+                        Async::Ready(output) => return Ok(Async::Ready(output)),
+                        Async::NotReady => {
+                            self.state = PnetUpgradeState::Handshaking(handshake);
+                            return Ok(Async::NotReady);
+                        },
+                    }
+                },
+                PnetUpgradeState::Poisoned => panic!("future polled after completion"),
+            }
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Implementation of the libp2p `Transport` trait for QUIC.
+//!
+//! QUIC provides both the encryption and the multiplexing of connections. Connections are
+//! authenticated with TLS 1.3, using self-signed certificates that carry the libp2p public key
+//! of each node. As a consequence, the `QuicConfig` transport directly produces the `PeerId` of
+//! the remote and a `StreamMuxer` whose substreams are QUIC streams. There is no need to upgrade
+//! its connections with secio, mplex or yamux.
+//!
+//! Addresses have the form `/ip4/1.2.3.4/udp/30333/quic`.
+//!
+//! # Usage
+//!
+//! ```
+//! use libp2p_quic::QuicConfig;
+//! use libp2p_secio::SecioKeyPair;
+//!
+//! let keypair = SecioKeyPair::ed25519_generated().unwrap();
+//! let quic = QuicConfig::new(&keypair).unwrap();
+//! ```
+//!
+//! > **Note**: The endpoint of a listener is driven by the listener stream. All the connections
+//! >           accepted through a listener stop making progress if the listener is destroyed.
+
+mod muxer;
+
+pub use crate::muxer::{QuicMuxer, QuicOutboundSubstream, QuicSubstream};
+
+use futures::{prelude::*, try_ready};
+use libp2p_core::{PeerId, Transport};
+use libp2p_secio::SecioKeyPair;
+use log::debug;
+use multiaddr::{Multiaddr, Protocol};
+use std::error::Error;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+/// Protocol negotiated with ALPN during the TLS handshake.
+const ALPN_PROTOCOL: &[u8] = b"libp2p";
+/// Server name used when connecting. It isn't verified by the remote.
+const SERVER_NAME: &str = "libp2p";
+
+/// Represents the configuration for a QUIC transport capability for libp2p.
+#[derive(Clone)]
+pub struct QuicConfig {
+    /// TLS configuration used when we are the server of a connection.
+    server_config: Arc<rustls::ServerConfig>,
+    /// TLS configuration used when we are the client of a connection.
+    client_config: Arc<rustls::ClientConfig>,
+}
+
+impl QuicConfig {
+    /// Creates a new configuration object for QUIC. The connections are authenticated with
+    /// `keypair`.
+    pub fn new(keypair: &SecioKeyPair) -> Result<QuicConfig, Box<dyn Error + Send + Sync>> {
+        let (mut client_config, mut server_config) = libp2p_tls::make_configs(keypair)?;
+        server_config.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        client_config.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+
+        Ok(QuicConfig {
+            server_config: Arc::new(server_config),
+            client_config: Arc::new(client_config),
+        })
+    }
+
+    /// Builds an endpoint that can open connections, and accept them if `listen` is true.
+    fn endpoint_builder(&self, listen: bool) -> quinn::EndpointBuilder {
+        let mut builder = quinn::Endpoint::builder();
+
+        let mut client_config = quinn::ClientConfig::default();
+        client_config.crypto = self.client_config.clone();
+        builder.default_client_config(client_config);
+
+        if listen {
+            let mut server_config = quinn::ServerConfig::default();
+            server_config.crypto = self.server_config.clone();
+            builder.listen(server_config);
+        }
+
+        builder
+    }
+}
+
+impl fmt::Debug for QuicConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicConfig").finish()
+    }
+}
+
+impl Transport for QuicConfig {
+    type Output = (PeerId, QuicMuxer);
+    type Listener = QuicListenStream;
+    type ListenerUpgrade = QuicUpgrade;
+    type Dial = QuicUpgrade;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        let socket_addr = match multiaddr_to_socketaddr(&addr) {
+            Ok(socket_addr) => socket_addr,
+            Err(()) => return Err((self, addr)),
+        };
+
+        let bound = self.endpoint_builder(true)
+            .bind(&socket_addr)
+            .map_err(|err| IoError::new(IoErrorKind::Other, err))
+            .and_then(|(driver, endpoint, incoming)| {
+                let local_addr = endpoint.local_addr()?;
+                Ok((driver, incoming, local_addr))
+            });
+
+        match bound {
+            Ok((driver, incoming, local_addr)) => {
+                let new_addr = socketaddr_to_multiaddr(&local_addr);
+                debug!("Now listening on {}", new_addr);
+                let listener = QuicListenStream {
+                    inner: Ok((driver, incoming)),
+                };
+                Ok((listener, new_addr))
+            },
+            Err(err) => {
+                debug!("Failed to listen on {}: {:?}", addr, err);
+                let listener = QuicListenStream {
+                    inner: Err(Some(err)),
+                };
+                Ok((listener, addr))
+            },
+        }
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        let socket_addr = match multiaddr_to_socketaddr(&addr) {
+            // As an optimization, we check that the address is not of the form `0.0.0.0`.
+            // If so, we instantly refuse dialing instead of going through the kernel.
+            Ok(socket_addr) if socket_addr.port() != 0 && !socket_addr.ip().is_unspecified() => {
+                socket_addr
+            },
+            _ => return Err((self, addr)),
+        };
+
+        debug!("Dialing {}", addr);
+
+        // Each dialed connection uses its own endpoint, bound to a random port.
+        let bind_addr = match socket_addr.ip() {
+            IpAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+            IpAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+        };
+
+        let dial = self.endpoint_builder(false)
+            .bind(&bind_addr)
+            .map_err(|err| IoError::new(IoErrorKind::Other, err))
+            .and_then(|(driver, endpoint, _)| {
+                let connecting = endpoint.connect(&socket_addr, SERVER_NAME)
+                    .map_err(|err| IoError::new(IoErrorKind::Other, err))?;
+                Ok((driver, connecting))
+            });
+
+        Ok(match dial {
+            Ok((driver, connecting)) => QuicUpgrade {
+                inner: Ok(connecting),
+                endpoint_driver: Some(driver),
+            },
+            Err(err) => QuicUpgrade {
+                inner: Err(Some(err)),
+                endpoint_driver: None,
+            },
+        })
+    }
+
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        let mut address = Multiaddr::empty();
+
+        // Use the observed IP address.
+        match server.iter().zip(observed.iter()).next() {
+            Some((Protocol::Ip4(_), x@Protocol::Ip4(_))) => address.append(x),
+            Some((Protocol::Ip6(_), x@Protocol::Ip6(_))) => address.append(x),
+            _ => return None
+        }
+
+        // Carry over everything else from the server address.
+        for proto in server.iter().skip(1) {
+            address.append(proto)
+        }
+
+        Some(address)
+    }
+}
+
+/// Stream of the connections received by a QUIC endpoint.
+pub struct QuicListenStream {
+    /// The driver of the endpoint and the stream of incoming connections, or the error that
+    /// happened when creating the endpoint.
+    inner: Result<(quinn::EndpointDriver, quinn::Incoming), Option<IoError>>,
+}
+
+impl Stream for QuicListenStream {
+    type Item = (QuicUpgrade, Multiaddr);
+    type Error = IoError;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, IoError> {
+        let (driver, incoming) = match self.inner {
+            Ok(ref mut inner) => (&mut inner.0, &mut inner.1),
+            Err(ref mut err) => {
+                return Err(err.take().expect("poll called again after error"));
+            }
+        };
+
+        // The driver processes the packets of all the connections accepted by this listener.
+        if let Async::Ready(()) = driver.poll()? {
+            return Ok(Async::Ready(None));
+        }
+
+        match incoming.poll() {
+            Ok(Async::Ready(Some(connecting))) => {
+                let remote_addr = socketaddr_to_multiaddr(&connecting.remote_address());
+                debug!("Incoming connection from {}", remote_addr);
+                let upgrade = QuicUpgrade {
+                    inner: Ok(connecting),
+                    endpoint_driver: None,
+                };
+                Ok(Async::Ready(Some((upgrade, remote_addr))))
+            },
+            Ok(Async::Ready(None)) | Err(()) => Ok(Async::Ready(None)),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl fmt::Debug for QuicListenStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicListenStream").finish()
+    }
+}
+
+/// Future that performs the QUIC handshake with a remote.
+#[must_use = "futures do nothing unless polled"]
+pub struct QuicUpgrade {
+    /// The connection being established, or the error that happened when starting to
+    /// establish it.
+    inner: Result<quinn::Connecting, Option<IoError>>,
+    /// Driver of the endpoint, if the endpoint has been created for this connection only.
+    endpoint_driver: Option<quinn::EndpointDriver>,
+}
+
+impl Future for QuicUpgrade {
+    type Item = (PeerId, QuicMuxer);
+    type Error = IoError;
+
+    fn poll(&mut self) -> Poll<Self::Item, IoError> {
+        if let Some(mut driver) = self.endpoint_driver.take() {
+            if let Async::NotReady = driver.poll()? {
+                self.endpoint_driver = Some(driver);
+            }
+        }
+
+        let connecting = match self.inner {
+            Ok(ref mut connecting) => connecting,
+            Err(ref mut err) => {
+                return Err(err.take().expect("poll called again after error"));
+            }
+        };
+
+        let (driver, connection, incoming) = try_ready!(connecting.poll().map_err(muxer::connection_error));
+        let peer_id = remote_peer_id(&connection)?;
+        debug!("Connection with {:?} established", peer_id);
+        let muxer = QuicMuxer::new(driver, self.endpoint_driver.take(), connection, incoming);
+        Ok(Async::Ready((peer_id, muxer)))
+    }
+}
+
+impl fmt::Debug for QuicUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicUpgrade").finish()
+    }
+}
+
+/// Returns the `PeerId` of the remote of an established connection. The certificate of the
+/// remote has already been verified during the handshake.
+fn remote_peer_id(connection: &quinn::Connection) -> Result<PeerId, IoError> {
+    let certificates = connection.authentication_data().peer_certificates
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "remote didn't present a certificate"))?;
+    let certificate = certificates.iter().next()
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "remote didn't present a certificate"))?;
+    let public_key = libp2p_tls::extract_public_key(&certificate.0)
+        .map_err(|err| IoError::new(IoErrorKind::InvalidData, err))?;
+    Ok(public_key.into_peer_id())
+}
+
+// This type of logic should probably be moved into the multiaddr package
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> Result<SocketAddr, ()> {
+    let mut iter = addr.iter();
+    let proto1 = iter.next().ok_or(())?;
+    let proto2 = iter.next().ok_or(())?;
+    let proto3 = iter.next().ok_or(())?;
+
+    if iter.next().is_some() {
+        return Err(());
+    }
+
+    match (proto1, proto2, proto3) {
+        (Protocol::Ip4(ip), Protocol::Udp(port), Protocol::Quic) => Ok(SocketAddr::new(ip.into(), port)),
+        (Protocol::Ip6(ip), Protocol::Udp(port), Protocol::Quic) => Ok(SocketAddr::new(ip.into(), port)),
+        _ => Err(()),
+    }
+}
+
+fn socketaddr_to_multiaddr(addr: &SocketAddr) -> Multiaddr {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) => Protocol::Ip4(ip),
+        IpAddr::V6(ip) => Protocol::Ip6(ip),
+    };
+
+    let mut multiaddr = Multiaddr::empty();
+    multiaddr.append(ip);
+    multiaddr.append(Protocol::Udp(addr.port()));
+    multiaddr.append(Protocol::Quic);
+    multiaddr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{multiaddr_to_socketaddr, QuicConfig};
+    use futures::{future, prelude::*, sync::oneshot};
+    use libp2p_core::{muxing, StreamMuxer, Transport};
+    use libp2p_secio::SecioKeyPair;
+    use multiaddr::Multiaddr;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn multiaddr_to_udp_conversion() {
+        assert!(multiaddr_to_socketaddr(&"/ip4/127.0.0.1/udp/1234".parse::<Multiaddr>().unwrap()).is_err());
+        assert!(multiaddr_to_socketaddr(&"/ip4/127.0.0.1/tcp/1234/quic".parse::<Multiaddr>().unwrap()).is_err());
+
+        assert_eq!(
+            multiaddr_to_socketaddr(&"/ip4/127.0.0.1/udp/12345/quic".parse::<Multiaddr>().unwrap()),
+            Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 12345))
+        );
+        assert_eq!(
+            multiaddr_to_socketaddr(&"/ip6/::1/udp/12345/quic".parse::<Multiaddr>().unwrap()),
+            Ok(SocketAddr::new("::1".parse().unwrap(), 12345))
+        );
+    }
+
+    #[test]
+    fn refuses_tcp_address() {
+        let keypair = SecioKeyPair::ed25519_generated().unwrap();
+        let quic = QuicConfig::new(&keypair).unwrap();
+        assert!(quic.dial("/ip4/127.0.0.1/tcp/12345".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn communicating_between_dialer_and_listener() {
+        let listener_key = SecioKeyPair::ed25519_generated().unwrap();
+        let listener_id = listener_key.to_peer_id();
+        let dialer_key = SecioKeyPair::ed25519_generated().unwrap();
+        let dialer_id = dialer_key.to_peer_id();
+
+        let (listener, addr) = QuicConfig::new(&listener_key).unwrap()
+            .listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+            .unwrap();
+        assert!(!addr.to_string().contains("udp/0"));
+
+        let mut rt = Runtime::new().unwrap();
+
+        // The listener stream must keep being polled, as it drives the connections it accepts.
+        let (tx, rx) = oneshot::channel();
+        let mut tx = Some(tx);
+        rt.spawn(listener
+            .for_each(move |(upgrade, _)| {
+                let tx = tx.take().expect("a single connection is opened");
+                let dialer_id = dialer_id.clone();
+                tokio::spawn(upgrade
+                    .and_then(move |(peer_id, muxer)| {
+                        assert_eq!(peer_id, dialer_id);
+                        muxing::inbound_from_ref_and_wrap(Arc::new(muxer))
+                    })
+                    .and_then(|substream| {
+                        tokio_io::io::read_exact(substream.expect("connection closed"), [0; 5])
+                    })
+                    .map(move |(_, buf)| { let _ = tx.send(buf); })
+                    .map_err(|err| panic!("{:?}", err)));
+                Ok(())
+            })
+            .map_err(|err| panic!("{:?}", err)));
+
+        let dialer = QuicConfig::new(&dialer_key).unwrap()
+            .dial(addr)
+            .unwrap()
+            .and_then(move |(peer_id, muxer)| {
+                assert_eq!(peer_id, listener_id);
+                let muxer = Arc::new(muxer);
+                // The connection only makes progress while the muxer is being polled.
+                let keep_alive = muxer.clone();
+                tokio::spawn(future::poll_fn(move || -> Poll<(), ()> {
+                    keep_alive.flush_all().map_err(|_| ())?;
+                    Ok(Async::NotReady)
+                }));
+                muxing::outbound_from_ref_and_wrap(muxer)
+            })
+            .and_then(|substream| {
+                tokio_io::io::write_all(substream.expect("connection closed"), b"hello")
+            })
+            .and_then(|(substream, _)| tokio_io::io::flush(substream));
+
+        let _substream = rt.block_on(dialer).unwrap();
+        let received = rt.block_on(rx).unwrap();
+        assert_eq!(&received, b"hello");
+    }
+}
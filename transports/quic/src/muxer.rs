@@ -0,0 +1,218 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::prelude::*;
+use libp2p_core::muxing::{Shutdown, StreamMuxer};
+use log::debug;
+use parking_lot::Mutex;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Implementation of `StreamMuxer` for a QUIC connection.
+///
+/// Each substream is a bidirectional QUIC stream. Unidirectional streams opened by the remote
+/// are ignored.
+pub struct QuicMuxer {
+    inner: Mutex<QuicMuxerInner>,
+}
+
+struct QuicMuxerInner {
+    /// Processes the packets of the connection. The connection only makes progress while it is
+    /// being polled. `None` if it has finished.
+    driver: Option<quinn::ConnectionDriver>,
+    /// Processes the packets of the endpoint, if the endpoint has been created for this
+    /// connection only. This is the case for dialed connections, while the endpoint of listened
+    /// connections is driven by the listener.
+    endpoint_driver: Option<quinn::EndpointDriver>,
+    /// Handle to the connection.
+    connection: quinn::Connection,
+    /// Streams opened by the remote.
+    incoming: quinn::IncomingStreams,
+}
+
+impl QuicMuxer {
+    /// Builds a new `QuicMuxer` from an established connection.
+    pub(crate) fn new(
+        driver: quinn::ConnectionDriver,
+        endpoint_driver: Option<quinn::EndpointDriver>,
+        connection: quinn::Connection,
+        incoming: quinn::IncomingStreams,
+    ) -> QuicMuxer {
+        QuicMuxer {
+            inner: Mutex::new(QuicMuxerInner {
+                driver: Some(driver),
+                endpoint_driver,
+                connection,
+                incoming,
+            }),
+        }
+    }
+}
+
+impl QuicMuxerInner {
+    /// Polls the drivers of the connection and of the endpoint, so that the current task gets
+    /// notified when packets arrive. Must be called by every operation on the muxer.
+    fn poll_drivers(&mut self) -> Result<(), IoError> {
+        if let Some(mut driver) = self.endpoint_driver.take() {
+            if let Async::NotReady = driver.poll()? {
+                self.endpoint_driver = Some(driver);
+            }
+        }
+
+        if let Some(mut driver) = self.driver.take() {
+            match driver.poll() {
+                Ok(Async::NotReady) => self.driver = Some(driver),
+                Ok(Async::Ready(())) => (),
+                Err(err) => return Err(connection_error(err)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for QuicMuxer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicMuxer")
+            .field("remote_address", &self.inner.lock().connection.remote_address())
+            .finish()
+    }
+}
+
+/// Substream of a `QuicMuxer`.
+pub struct QuicSubstream {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+/// Outbound substream of a `QuicMuxer` being opened.
+pub struct QuicOutboundSubstream {
+    inner: Box<dyn Future<Item = (quinn::SendStream, quinn::RecvStream), Error = quinn::ConnectionError> + Send>,
+}
+
+impl StreamMuxer for QuicMuxer {
+    type Substream = QuicSubstream;
+    type OutboundSubstream = QuicOutboundSubstream;
+
+    fn poll_inbound(&self) -> Poll<Option<Self::Substream>, IoError> {
+        let mut inner = self.inner.lock();
+        inner.poll_drivers()?;
+
+        loop {
+            match inner.incoming.poll() {
+                Ok(Async::Ready(Some(quinn::NewStream::Bi(send, recv)))) => {
+                    return Ok(Async::Ready(Some(QuicSubstream { send, recv })));
+                },
+                Ok(Async::Ready(Some(quinn::NewStream::Uni(_)))) => {
+                    debug!("Ignoring unidirectional stream opened by the remote");
+                },
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => return closed_or_error(err),
+            }
+        }
+    }
+
+    fn open_outbound(&self) -> Self::OutboundSubstream {
+        let inner = self.inner.lock();
+        QuicOutboundSubstream {
+            inner: Box::new(inner.connection.open_bi()),
+        }
+    }
+
+    fn poll_outbound(&self, substream: &mut Self::OutboundSubstream) -> Poll<Option<Self::Substream>, IoError> {
+        self.inner.lock().poll_drivers()?;
+
+        match substream.inner.poll() {
+            Ok(Async::Ready((send, recv))) => Ok(Async::Ready(Some(QuicSubstream { send, recv }))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => closed_or_error(err),
+        }
+    }
+
+    #[inline]
+    fn destroy_outbound(&self, _: Self::OutboundSubstream) {
+    }
+
+    fn read_substream(&self, substream: &mut Self::Substream, buf: &mut [u8]) -> Poll<usize, IoError> {
+        self.inner.lock().poll_drivers()?;
+        substream.recv.poll_read(buf)
+    }
+
+    fn write_substream(&self, substream: &mut Self::Substream, buf: &[u8]) -> Poll<usize, IoError> {
+        self.inner.lock().poll_drivers()?;
+        substream.send.poll_write(buf)
+    }
+
+    fn flush_substream(&self, substream: &mut Self::Substream) -> Poll<(), IoError> {
+        self.inner.lock().poll_drivers()?;
+        substream.send.poll_flush()
+    }
+
+    fn shutdown_substream(&self, substream: &mut Self::Substream, kind: Shutdown) -> Poll<(), IoError> {
+        self.inner.lock().poll_drivers()?;
+        match kind {
+            // QUIC can't close the receiving side of a stream without discarding the data that
+            // is in flight, so we just stop reading.
+            Shutdown::Inbound => Ok(Async::Ready(())),
+            Shutdown::Outbound | Shutdown::All => substream.send.shutdown(),
+        }
+    }
+
+    #[inline]
+    fn destroy_substream(&self, _: Self::Substream) {
+    }
+
+    fn shutdown(&self, kind: Shutdown) -> Poll<(), IoError> {
+        let mut inner = self.inner.lock();
+        match kind {
+            Shutdown::Inbound => (),
+            Shutdown::Outbound | Shutdown::All => inner.connection.close(0u32.into(), b""),
+        }
+        inner.poll_drivers()?;
+        Ok(Async::Ready(()))
+    }
+
+    fn flush_all(&self) -> Poll<(), IoError> {
+        self.inner.lock().poll_drivers()?;
+        Ok(Async::Ready(()))
+    }
+}
+
+/// Converts an error on the connection into the result of a poll. A connection closed by either
+/// side is treated as the end of the stream rather than as an error.
+fn closed_or_error<T>(err: quinn::ConnectionError) -> Poll<Option<T>, IoError> {
+    match err {
+        quinn::ConnectionError::ApplicationClosed { .. } |
+        quinn::ConnectionError::LocallyClosed => Ok(Async::Ready(None)),
+        err => Err(connection_error(err)),
+    }
+}
+
+/// Converts an error on the connection into an `IoError`.
+pub(crate) fn connection_error(err: quinn::ConnectionError) -> IoError {
+    let kind = match err {
+        quinn::ConnectionError::TimedOut => IoErrorKind::TimedOut,
+        quinn::ConnectionError::Reset => IoErrorKind::ConnectionReset,
+        _ => IoErrorKind::Other,
+    };
+    IoError::new(kind, err)
+}
@@ -18,93 +18,187 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-extern crate aio_limited;
+//! Transport adapter that limits the transfer rate of the connections it creates.
+//!
+//! The limits are described by a `RateLimits`. A connection can be limited globally, on its
+//! own, by the IP address of the remote and by the `PeerId` of the remote. The limits can be
+//! adjusted at any time and apply immediately to the existing connections.
+//!
+//! Since the `PeerId` of the remote is only known after the connection has been upgraded, it
+//! must be reported through the `ConnectionHandle` returned by `Connection::handle`.
+//!
+//! # Migrating from 0.1
+//!
+//! `RateLimited::new` is still available as a deprecated wrapper around
+//! `RateLimited::with_max_rate`. `Connection::new`, however, now takes the `RateLimits` and the
+//! IP address of the remote instead of a pair of `Limiter`s, and no longer returns a `Result`.
+//! Code that wraps connections manually must be updated accordingly.
+
 #[macro_use]
 extern crate futures;
 extern crate libp2p_core;
 #[macro_use]
 extern crate log;
+extern crate parking_lot;
 extern crate tokio_executor;
+#[cfg(test)]
+extern crate tokio;
 extern crate tokio_io;
+extern crate tokio_timer;
+
+mod limits;
+
+pub use limits::{ConnectionHandle, Limit, RateLimits};
 
-use aio_limited::{Limited, Limiter};
 use futures::prelude::*;
-use libp2p_core::{Multiaddr, Transport};
-use std::io;
+use libp2p_core::{Multiaddr, Transport, multiaddr::Protocol};
+use limits::Direction;
+use std::{cmp, io, net::IpAddr, sync::Arc, time::Instant};
 use tokio_executor::Executor;
-use tokio_io::{AsyncRead, AsyncWrite, io::{ReadHalf, WriteHalf}};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Delay;
 
 #[derive(Clone)]
 pub struct RateLimited<T> {
     value: T,
-    rlimiter: Limiter,
-    wlimiter: Limiter,
+    limits: RateLimits,
 }
 
 impl<T> RateLimited<T> {
+    /// Limits all the connections together to `max_read` and `max_write` bytes per second.
+    ///
+    /// The executor isn't used anymore, and this never fails.
+    #[deprecated(note = "Use `RateLimited::with_max_rate` instead")]
     pub fn new<E: Executor>(
-        e: &mut E,
+        _: &mut E,
         value: T,
         max_read: usize,
         max_write: usize,
     ) -> io::Result<RateLimited<T>> {
-        Ok(RateLimited {
-            value,
-            rlimiter: Limiter::new(e, max_read).map_err(|e| {
-                error!("failed to create read limiter: {}", e);
-                io::Error::new(io::ErrorKind::Other, e)
-            })?,
-            wlimiter: Limiter::new(e, max_write).map_err(|e| {
-                error!("failed to create write limiter: {}", e);
-                io::Error::new(io::ErrorKind::Other, e)
-            })?,
-        })
-    }
-
-    fn from_parts(value: T, r: Limiter, w: Limiter) -> RateLimited<T> {
-        RateLimited {
-            value,
-            rlimiter: r,
-            wlimiter: w,
-        }
+        Ok(RateLimited::with_max_rate(value, max_read, max_write))
+    }
+
+    /// Limits all the connections together to `max_read` and `max_write` bytes per second.
+    pub fn with_max_rate(value: T, max_read: usize, max_write: usize) -> RateLimited<T> {
+        let limits = RateLimits::new();
+        limits.set_global(Some(Limit::new(max_read, max_write)));
+        RateLimited::with_limits(value, limits)
+    }
+
+    /// Applies the given limits to the connections.
+    #[inline]
+    pub fn with_limits(value: T, limits: RateLimits) -> RateLimited<T> {
+        RateLimited { value, limits }
+    }
+
+    /// Returns the limits applied to the connections, which can be modified at runtime.
+    #[inline]
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+}
+
+/// Extracts the IP address from a multiaddress, if it starts with one.
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    match addr.iter().next() {
+        Some(Protocol::Ip4(ip)) => Some(IpAddr::V4(ip)),
+        Some(Protocol::Ip6(ip)) => Some(IpAddr::V6(ip)),
+        _ => None,
     }
 }
 
 /// A rate-limited connection.
 pub struct Connection<C: AsyncRead + AsyncWrite> {
-    reader: Limited<ReadHalf<C>>,
-    writer: Limited<WriteHalf<C>>,
+    inner: C,
+    handle: Arc<ConnectionHandle>,
+    read_delay: Option<Delay>,
+    write_delay: Option<Delay>,
 }
 
 impl<C: AsyncRead + AsyncWrite> Connection<C> {
-    pub fn new(c: C, rlimiter: Limiter, wlimiter: Limiter) -> io::Result<Connection<C>> {
-        let (r, w) = c.split();
-        Ok(Connection {
-            reader: Limited::new(r, rlimiter).map_err(|e| {
-                error!("failed to create limited reader: {}", e);
-                io::Error::new(io::ErrorKind::Other, e)
-            })?,
-            writer: Limited::new(w, wlimiter).map_err(|e| {
-                error!("failed to create limited writer: {}", e);
-                io::Error::new(io::ErrorKind::Other, e)
-            })?,
-        })
+    /// Wraps around `c`. `ip` is the IP address of the remote, if known.
+    pub fn new(c: C, limits: &RateLimits, ip: Option<IpAddr>) -> Connection<C> {
+        Connection {
+            inner: c,
+            handle: Arc::new(limits.register(ip)),
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+
+    /// Returns a handle that allows reporting the `PeerId` of the remote.
+    ///
+    /// The handle keeps the connection registered in the limits even after the connection has
+    /// been dropped, so it shouldn't be kept around longer than necessary.
+    #[inline]
+    pub fn handle(&self) -> Arc<ConnectionHandle> {
+        self.handle.clone()
+    }
+}
+
+/// Returns the number of bytes that can be transferred now in the given direction, or `None`
+/// if unlimited. Returns `WouldBlock` and schedules a wake-up if a limit has been reached.
+fn allowance(handle: &ConnectionHandle, direction: Direction, delay: &mut Option<Delay>)
+    -> io::Result<Option<u64>>
+{
+    loop {
+        if let Some(mut d) = delay.take() {
+            match d.poll() {
+                Ok(Async::Ready(())) => {},
+                Ok(Async::NotReady) => {
+                    *delay = Some(d);
+                    return Err(io::ErrorKind::WouldBlock.into());
+                },
+                Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
+            }
+        }
+
+        let now = Instant::now();
+        match handle.limits().available(handle.id(), direction, now) {
+            Ok(available) => return Ok(available),
+            Err(wait) => {
+                trace!("Rate limit reached for {:?}, retrying in {:?}", direction, wait);
+                *delay = Some(Delay::new(now + wait));
+            },
+        }
+    }
+}
+
+/// Returns the length of the buffer to pass to the underlying connection.
+fn capped_len(len: usize, available: Option<u64>) -> usize {
+    match available {
+        Some(available) => cmp::min(len as u64, available) as usize,
+        None => len,
     }
 }
 
 impl<C: AsyncRead + AsyncWrite> io::Read for Connection<C> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.reader.read(buf)
+        if buf.is_empty() {
+            return self.inner.read(buf);
+        }
+        let available = allowance(&self.handle, Direction::Read, &mut self.read_delay)?;
+        let len = capped_len(buf.len(), available);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.handle.limits().consume(self.handle.id(), Direction::Read, n as u64);
+        Ok(n)
     }
 }
 
 impl<C: AsyncRead + AsyncWrite> io::Write for Connection<C> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.writer.write(buf)
+        if buf.is_empty() {
+            return self.inner.write(buf);
+        }
+        let available = allowance(&self.handle, Direction::Write, &mut self.write_delay)?;
+        let len = capped_len(buf.len(), available);
+        let n = self.inner.write(&buf[..len])?;
+        self.handle.limits().consume(self.handle.id(), Direction::Write, n as u64);
+        Ok(n)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.writer.flush()
+        self.inner.flush()
     }
 }
 
@@ -112,7 +206,7 @@ impl<C: AsyncRead + AsyncWrite> AsyncRead for Connection<C> {}
 
 impl<C: AsyncRead + AsyncWrite> AsyncWrite for Connection<C> {
     fn shutdown(&mut self) -> Poll<(), io::Error> {
-        self.writer.shutdown()
+        self.inner.shutdown()
     }
 }
 
@@ -125,9 +219,10 @@ impl<T: Transport> Stream for Listener<T> {
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
         match try_ready!(self.0.value.poll()) {
             Some((upgrade, addr)) => {
-                let r = self.0.rlimiter.clone();
-                let w = self.0.wlimiter.clone();
-                let u = ListenerUpgrade(RateLimited::from_parts(upgrade, r, w));
+                let u = ListenerUpgrade {
+                    upgrade: RateLimited::with_limits(upgrade, self.0.limits.clone()),
+                    ip: ip_of(&addr),
+                };
                 Ok(Async::Ready(Some((u, addr))))
             }
             None => Ok(Async::Ready(None)),
@@ -136,7 +231,10 @@ impl<T: Transport> Stream for Listener<T> {
 }
 
 #[must_use = "futures do nothing unless polled"]
-pub struct ListenerUpgrade<T: Transport>(RateLimited<T::ListenerUpgrade>);
+pub struct ListenerUpgrade<T: Transport> {
+    upgrade: RateLimited<T::ListenerUpgrade>,
+    ip: Option<IpAddr>,
+}
 
 impl<T> Future for ListenerUpgrade<T>
 where
@@ -147,10 +245,8 @@ where
     type Error = io::Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        let conn = try_ready!(self.0.value.poll());
-        let r = self.0.rlimiter.clone();
-        let w = self.0.wlimiter.clone();
-        Ok(Async::Ready(Connection::new(conn, r, w)?))
+        let conn = try_ready!(self.upgrade.value.poll());
+        Ok(Async::Ready(Connection::new(conn, &self.upgrade.limits, self.ip)))
     }
 }
 
@@ -165,25 +261,19 @@ where
     type Dial = DialFuture<T::Dial>;
 
     fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
-        let r = self.rlimiter;
-        let w = self.wlimiter;
-        self.value
-            .listen_on(addr)
-            .map(|(listener, a)| {
-                (
-                    Listener(RateLimited::from_parts(listener, r.clone(), w.clone())),
-                    a,
-                )
-            })
-            .map_err(|(transport, a)| (RateLimited::from_parts(transport, r, w), a))
+        let limits = self.limits;
+        match self.value.listen_on(addr) {
+            Ok((listener, a)) => Ok((Listener(RateLimited::with_limits(listener, limits)), a)),
+            Err((transport, a)) => Err((RateLimited::with_limits(transport, limits), a)),
+        }
     }
 
     fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
-        let r = self.rlimiter;
-        let w = self.wlimiter;
+        let limits = self.limits;
+        let ip = ip_of(&addr);
         match self.value.dial(addr) {
-            Ok(dial) => Ok(DialFuture { r, w, f: dial }),
-            Err((t, a)) => Err((RateLimited::from_parts(t, r, w), a))
+            Ok(dial) => Ok(DialFuture { limits, ip, f: dial }),
+            Err((t, a)) => Err((RateLimited::with_limits(t, limits), a))
         }
     }
 
@@ -194,8 +284,8 @@ where
 
 /// Future to avoid boxing.
 pub struct DialFuture<T> {
-    r: Limiter,
-    w: Limiter,
+    limits: RateLimits,
+    ip: Option<IpAddr>,
     f: T
 }
 
@@ -203,13 +293,43 @@ impl<T> Future for DialFuture<T>
 where
     T: Future,
     T::Item: AsyncRead + AsyncWrite,
-    T::Error: From<io::Error>
 {
     type Item = Connection<T::Item>;
     type Error = T::Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         let item = try_ready!(self.f.poll());
-        Ok(Async::Ready(Connection::new(item, self.r.clone(), self.w.clone())?))
+        Ok(Async::Ready(Connection::new(item, &self.limits, self.ip)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use std::{io::{self, Cursor, Write}, time::{Duration, Instant}};
+    use super::{Connection, Limit, RateLimits};
+    use tokio::runtime::current_thread::Runtime;
+
+    #[test]
+    fn connection_is_throttled_until_delay_fires() {
+        let limits = RateLimits::new();
+        limits.set_per_connection(Some(Limit::new(100, 100)));
+        let mut conn = Connection::new(Cursor::new(Vec::new()), &limits, None);
+
+        let mut rt = Runtime::new().unwrap();
+        let start = Instant::now();
+        let conn = rt.block_on(future::lazy(move || {
+            // The first write is capped to the content of the bucket, the second one has to
+            // wait for it to refill.
+            assert_eq!(conn.write(&[0; 150]).unwrap(), 100);
+            let err = conn.write(&[0; 50]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+            assert!(conn.write_delay.is_some());
+            tokio::io::write_all(conn, vec![0; 50])
+        })).unwrap().0;
+
+        // Resuming required about half a second worth of tokens.
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        assert_eq!(conn.inner.into_inner().len(), 150);
     }
 }
@@ -0,0 +1,516 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Registry of the transfer rate limits and of the connections they apply to.
+
+use libp2p_core::PeerId;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Minimum delay in milliseconds before retrying a transfer once a limit has been reached.
+/// Avoids waking up the task for every byte.
+const MIN_RETRY_DELAY_MS: u64 = 10;
+
+/// Maximum transfer rates, in bytes per second.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Limit {
+    /// Maximum number of bytes read per second.
+    pub max_read: usize,
+    /// Maximum number of bytes written per second.
+    pub max_write: usize,
+}
+
+impl Limit {
+    /// Builds a `Limit`.
+    #[inline]
+    pub fn new(max_read: usize, max_write: usize) -> Limit {
+        Limit { max_read, max_write }
+    }
+}
+
+/// Direction of a transfer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Read,
+    Write,
+}
+
+/// Set of limits applied to the connections of a `RateLimited` transport.
+///
+/// Each connection is subject to the global limit, shared by all the connections, to its own
+/// limit, to the limit of the IP address of the remote, and to the limit of the remote `PeerId`
+/// once it is known. Each of these limits is optional.
+///
+/// The limits can be modified at any time through any clone of the `RateLimits`, and apply
+/// immediately to the existing connections.
+#[derive(Clone)]
+pub struct RateLimits {
+    inner: Arc<Mutex<Registry>>,
+}
+
+struct Registry {
+    next_connection_id: u64,
+    global: Option<Buckets>,
+    per_connection: Option<Limit>,
+    connections: HashMap<u64, ConnectionEntry>,
+    per_ip: Option<Limit>,
+    ips: HashMap<IpAddr, Group>,
+    per_peer: Option<Limit>,
+    peers: HashMap<PeerId, Group>,
+}
+
+/// A connection subject to the limits.
+struct ConnectionEntry {
+    ip: Option<IpAddr>,
+    peer_id: Option<PeerId>,
+    buckets: Option<Buckets>,
+}
+
+/// Connections sharing the same IP address or the same `PeerId`.
+struct Group {
+    /// Limit specific to this group. If `None`, the default limit applies.
+    specific: Option<Limit>,
+    buckets: Option<Buckets>,
+    connections: usize,
+}
+
+impl Group {
+    fn new() -> Group {
+        Group { specific: None, buckets: None, connections: 0 }
+    }
+
+    fn update(&mut self, default: Option<Limit>) {
+        update_buckets(&mut self.buckets, self.specific.or(default));
+    }
+}
+
+/// Token buckets of both directions.
+struct Buckets {
+    read: Bucket,
+    write: Bucket,
+}
+
+impl Buckets {
+    fn new(limit: Limit) -> Buckets {
+        Buckets {
+            read: Bucket::new(limit.max_read as u64),
+            write: Bucket::new(limit.max_write as u64),
+        }
+    }
+
+    fn get(&mut self, direction: Direction) -> &mut Bucket {
+        match direction {
+            Direction::Read => &mut self.read,
+            Direction::Write => &mut self.write,
+        }
+    }
+}
+
+/// Applies a new limit to some buckets, creating or destroying them if necessary.
+fn update_buckets(buckets: &mut Option<Buckets>, limit: Option<Limit>) {
+    match (buckets.as_mut(), limit) {
+        (Some(b), Some(limit)) => {
+            b.read.set_rate(limit.max_read as u64);
+            b.write.set_rate(limit.max_write as u64);
+        },
+        (None, Some(limit)) => *buckets = Some(Buckets::new(limit)),
+        (_, None) => *buckets = None,
+    }
+}
+
+/// Token bucket that refills at a constant rate, and can hold up to one second of transfer.
+struct Bucket {
+    /// Number of bytes per second.
+    rate: u64,
+    /// Number of bytes that can be transferred. Can be negative if more bytes than available
+    /// have been transferred concurrently.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u64) -> Bucket {
+        Bucket { rate, tokens: rate as f64, last_refill: Instant::now() }
+    }
+
+    fn set_rate(&mut self, rate: u64) {
+        self.refill(Instant::now());
+        self.rate = rate;
+        self.tokens = self.tokens.min(rate as f64);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if now <= self.last_refill {
+            return;
+        }
+        let elapsed = now - self.last_refill;
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        self.last_refill = now;
+    }
+
+    /// Returns the number of bytes that can be transferred now, or the delay after which to try
+    /// again if the bucket is empty.
+    fn available(&mut self, now: Instant) -> Result<u64, Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            return Ok(self.tokens as u64);
+        }
+
+        if self.rate == 0 {
+            // The rate may be raised later, so we still try again regularly.
+            return Err(Duration::from_secs(1));
+        }
+        let secs = (1.0 - self.tokens) / self.rate as f64;
+        let delay = Duration::new(secs as u64, (secs.fract() * 1e9) as u32);
+        Err(delay.max(Duration::from_millis(MIN_RETRY_DELAY_MS)))
+    }
+
+    fn consume(&mut self, bytes: u64) {
+        self.tokens -= bytes as f64;
+    }
+}
+
+impl RateLimits {
+    /// Builds a set of limits that doesn't limit anything.
+    pub fn new() -> RateLimits {
+        RateLimits {
+            inner: Arc::new(Mutex::new(Registry {
+                next_connection_id: 0,
+                global: None,
+                per_connection: None,
+                connections: HashMap::new(),
+                per_ip: None,
+                ips: HashMap::new(),
+                per_peer: None,
+                peers: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Sets the limit shared by all the connections.
+    pub fn set_global(&self, limit: Option<Limit>) {
+        update_buckets(&mut self.inner.lock().global, limit);
+    }
+
+    /// Sets the limit applied to each connection individually.
+    pub fn set_per_connection(&self, limit: Option<Limit>) {
+        let mut registry = self.inner.lock();
+        registry.per_connection = limit;
+        for connection in registry.connections.values_mut() {
+            update_buckets(&mut connection.buckets, limit);
+        }
+    }
+
+    /// Sets the limit shared by the connections with the same remote IP address, for the IP
+    /// addresses that don't have a specific limit.
+    pub fn set_per_ip(&self, limit: Option<Limit>) {
+        let mut registry = self.inner.lock();
+        registry.per_ip = limit;
+        for group in registry.ips.values_mut() {
+            group.update(limit);
+        }
+    }
+
+    /// Sets the limit shared by the connections with the given remote IP address, overriding the
+    /// one set with `set_per_ip`.
+    pub fn set_ip_limit(&self, ip: IpAddr, limit: Limit) {
+        let mut registry = self.inner.lock();
+        let default = registry.per_ip;
+        let group = registry.ips.entry(ip).or_insert_with(Group::new);
+        group.specific = Some(limit);
+        group.update(default);
+    }
+
+    /// Removes the limit specific to an IP address. The one set with `set_per_ip` applies again.
+    pub fn remove_ip_limit(&self, ip: &IpAddr) {
+        let mut registry = self.inner.lock();
+        let default = registry.per_ip;
+        let remove = match registry.ips.get_mut(ip) {
+            Some(group) => {
+                group.specific = None;
+                group.update(default);
+                group.connections == 0
+            },
+            None => false,
+        };
+        if remove {
+            registry.ips.remove(ip);
+        }
+    }
+
+    /// Sets the limit shared by the connections with the same remote `PeerId`, for the peers
+    /// that don't have a specific limit.
+    pub fn set_per_peer(&self, limit: Option<Limit>) {
+        let mut registry = self.inner.lock();
+        registry.per_peer = limit;
+        for group in registry.peers.values_mut() {
+            group.update(limit);
+        }
+    }
+
+    /// Sets the limit shared by the connections with the given peer, overriding the one set
+    /// with `set_per_peer`.
+    pub fn set_peer_limit(&self, peer_id: PeerId, limit: Limit) {
+        let mut registry = self.inner.lock();
+        let default = registry.per_peer;
+        let group = registry.peers.entry(peer_id).or_insert_with(Group::new);
+        group.specific = Some(limit);
+        group.update(default);
+    }
+
+    /// Removes the limit specific to a peer. The one set with `set_per_peer` applies again.
+    pub fn remove_peer_limit(&self, peer_id: &PeerId) {
+        let mut registry = self.inner.lock();
+        let default = registry.per_peer;
+        let remove = match registry.peers.get_mut(peer_id) {
+            Some(group) => {
+                group.specific = None;
+                group.update(default);
+                group.connections == 0
+            },
+            None => false,
+        };
+        if remove {
+            registry.peers.remove(peer_id);
+        }
+    }
+
+    /// Registers a new connection with a remote whose IP address is `ip`.
+    pub(crate) fn register(&self, ip: Option<IpAddr>) -> ConnectionHandle {
+        let mut registry = self.inner.lock();
+        let id = registry.next_connection_id;
+        registry.next_connection_id += 1;
+
+        if let Some(ip) = ip {
+            let default = registry.per_ip;
+            let group = registry.ips.entry(ip).or_insert_with(Group::new);
+            group.connections += 1;
+            group.update(default);
+        }
+
+        let buckets = registry.per_connection.map(Buckets::new);
+        registry.connections.insert(id, ConnectionEntry { ip, peer_id: None, buckets });
+        ConnectionHandle { limits: self.clone(), id }
+    }
+
+    /// Returns the number of bytes that a connection can transfer now, or `None` if it isn't
+    /// limited. If one of the limits has been reached, returns the delay after which to try
+    /// again.
+    pub(crate) fn available(&self, id: u64, direction: Direction, now: Instant)
+        -> Result<Option<u64>, Duration>
+    {
+        let mut registry = self.inner.lock();
+        let registry = &mut *registry;
+        let connection = registry.connections.get_mut(&id)
+            .expect("connections are unregistered only when their handle is dropped");
+
+        let mut available: Option<u64> = None;
+        let mut retry: Option<Duration> = None;
+        {
+            let mut check = |buckets: Option<&mut Buckets>| {
+                if let Some(buckets) = buckets {
+                    match buckets.get(direction).available(now) {
+                        Ok(n) => available = Some(available.map_or(n, |a| a.min(n))),
+                        Err(delay) => retry = Some(retry.map_or(delay, |r| r.max(delay))),
+                    }
+                }
+            };
+
+            check(registry.global.as_mut());
+            check(connection.buckets.as_mut());
+            if let Some(ref ip) = connection.ip {
+                check(registry.ips.get_mut(ip).and_then(|g| g.buckets.as_mut()));
+            }
+            if let Some(ref peer_id) = connection.peer_id {
+                check(registry.peers.get_mut(peer_id).and_then(|g| g.buckets.as_mut()));
+            }
+        }
+
+        match retry {
+            Some(delay) => Err(delay),
+            None => Ok(available),
+        }
+    }
+
+    /// Records that a connection has transferred `bytes` bytes.
+    pub(crate) fn consume(&self, id: u64, direction: Direction, bytes: u64) {
+        let mut registry = self.inner.lock();
+        let registry = &mut *registry;
+        let connection = registry.connections.get_mut(&id)
+            .expect("connections are unregistered only when their handle is dropped");
+
+        if let Some(ref mut buckets) = registry.global {
+            buckets.get(direction).consume(bytes);
+        }
+        if let Some(ref mut buckets) = connection.buckets {
+            buckets.get(direction).consume(bytes);
+        }
+        if let Some(ref ip) = connection.ip {
+            if let Some(buckets) = registry.ips.get_mut(ip).and_then(|g| g.buckets.as_mut()) {
+                buckets.get(direction).consume(bytes);
+            }
+        }
+        if let Some(ref peer_id) = connection.peer_id {
+            if let Some(buckets) = registry.peers.get_mut(peer_id).and_then(|g| g.buckets.as_mut()) {
+                buckets.get(direction).consume(bytes);
+            }
+        }
+    }
+}
+
+impl Default for RateLimits {
+    #[inline]
+    fn default() -> Self {
+        RateLimits::new()
+    }
+}
+
+/// Handle to a connection subject to a `RateLimits`. Unregisters the connection when dropped.
+pub struct ConnectionHandle {
+    limits: RateLimits,
+    id: u64,
+}
+
+impl ConnectionHandle {
+    /// Subjects the connection to the limits of a peer.
+    ///
+    /// This is meant to be called once the upgrade that authenticates the remote has completed.
+    /// Does nothing if the `PeerId` of the connection is already known.
+    pub fn set_peer_id(&self, peer_id: PeerId) {
+        let mut registry = self.limits.inner.lock();
+        let default = registry.per_peer;
+        match registry.connections.get_mut(&self.id) {
+            Some(connection) => {
+                if connection.peer_id.is_some() {
+                    return;
+                }
+                connection.peer_id = Some(peer_id.clone());
+            },
+            None => return,
+        }
+        let group = registry.peers.entry(peer_id).or_insert_with(Group::new);
+        group.connections += 1;
+        group.update(default);
+    }
+
+    #[inline]
+    pub(crate) fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    #[inline]
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for ConnectionHandle {
+    fn drop(&mut self) {
+        let mut registry = self.limits.inner.lock();
+        let connection = match registry.connections.remove(&self.id) {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        if let Some(ip) = connection.ip {
+            let remove = registry.ips.get_mut(&ip).map_or(false, |group| {
+                group.connections -= 1;
+                group.connections == 0 && group.specific.is_none()
+            });
+            if remove {
+                registry.ips.remove(&ip);
+            }
+        }
+
+        if let Some(peer_id) = connection.peer_id {
+            let remove = registry.peers.get_mut(&peer_id).map_or(false, |group| {
+                group.connections -= 1;
+                group.connections == 0 && group.specific.is_none()
+            });
+            if remove {
+                registry.peers.remove(&peer_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p_core::PeerId;
+    use std::time::{Duration, Instant};
+    use super::{Direction, Limit, RateLimits};
+
+    #[test]
+    fn unlimited_by_default() {
+        let limits = RateLimits::new();
+        let handle = limits.register(Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(limits.available(handle.id(), Direction::Read, Instant::now()), Ok(None));
+    }
+
+    #[test]
+    fn smallest_limit_applies() {
+        let limits = RateLimits::new();
+        limits.set_global(Some(Limit::new(1000, 1000)));
+        limits.set_per_ip(Some(Limit::new(500, 1000)));
+        let ip = "127.0.0.1".parse().unwrap();
+        let handle = limits.register(Some(ip));
+        let now = Instant::now();
+        assert_eq!(limits.available(handle.id(), Direction::Read, now), Ok(Some(500)));
+        assert_eq!(limits.available(handle.id(), Direction::Write, now), Ok(Some(1000)));
+
+        let peer_id = PeerId::random();
+        limits.set_peer_limit(peer_id.clone(), Limit::new(100, 100));
+        handle.set_peer_id(peer_id);
+        assert_eq!(limits.available(handle.id(), Direction::Write, now), Ok(Some(100)));
+
+        // Limits can be adjusted at runtime.
+        limits.set_ip_limit(ip, Limit::new(10, 10));
+        assert_eq!(limits.available(handle.id(), Direction::Read, now), Ok(Some(10)));
+    }
+
+    #[test]
+    fn exhausted_limit_delays() {
+        let limits = RateLimits::new();
+        limits.set_per_connection(Some(Limit::new(100, 100)));
+        let first = limits.register(None);
+        let second = limits.register(None);
+        let now = Instant::now();
+        limits.consume(first.id(), Direction::Read, 100);
+        let delay = limits.available(first.id(), Direction::Read, now).unwrap_err();
+        assert!(delay <= Duration::from_millis(20));
+        // Each connection has its own bucket.
+        assert_eq!(limits.available(second.id(), Direction::Read, now), Ok(Some(100)));
+    }
+
+    #[test]
+    fn groups_are_removed_with_their_connections() {
+        let limits = RateLimits::new();
+        limits.set_per_ip(Some(Limit::new(100, 100)));
+        let handle = limits.register(Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(limits.inner.lock().ips.len(), 1);
+        drop(handle);
+        assert!(limits.inner.lock().ips.is_empty());
+        assert!(limits.inner.lock().connections.is_empty());
+    }
+}